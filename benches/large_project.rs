@@ -0,0 +1,109 @@
+//! Benchmarks over a large synthetic project: hundreds of tasks and long notes
+//!
+//! Run with `cargo bench`. Each group compares the cached path a session
+//! takes per task against the uncached work it replaces.
+
+use clancy::project::{Project, ProjectMetadata, NOTE_CATEGORIES};
+use clancy::transcript::Transcript;
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
+use std::path::Path;
+
+const TASKS: u32 = 800;
+const NOTE_ENTRIES: usize = 2_000;
+const TRANSCRIPT_TURNS: usize = 400;
+
+/// Builds a project in `dir` with long notes and many task logs
+fn synthetic_project(dir: &Path) -> Project {
+    let path = dir.join("big");
+    std::fs::create_dir_all(path.join("notes")).unwrap();
+    std::fs::create_dir_all(path.join("tasks")).unwrap();
+    let project = Project::new(ProjectMetadata::new("big"), path);
+    for category in NOTE_CATEGORIES {
+        let notes: String = (0..NOTE_ENTRIES)
+            .map(|i| {
+                format!(
+                    "- [2025-01-01] {} entry {} about the request pipeline\n",
+                    category, i
+                )
+            })
+            .collect();
+        project.write_notes(category, &notes).unwrap();
+    }
+    for num in 1..=TASKS {
+        let name = format!("{:03}-synthetic-task.json", num);
+        std::fs::write(project.tasks_path().join(name), "{}").unwrap();
+    }
+    project
+}
+
+/// A stream-json transcript with many text and tool-use turns
+fn synthetic_transcript() -> String {
+    let mut output = String::new();
+    for i in 0..TRANSCRIPT_TURNS {
+        output.push_str(&format!(
+            r#"{{"type":"assistant","message":{{"content":[{{"type":"text","text":"Step {} of the refactor"}},{{"type":"tool_use","name":"Edit","id":"t{}","input":{{"file_path":"src/m{}.rs","old_string":"a","new_string":"b"}}}}]}}}}"#,
+            i, i, i
+        ));
+        output.push('\n');
+    }
+    output
+        .push_str(r#"{"type":"result","subtype":"success","result":"done","total_cost_usd":0.5}"#);
+    output
+}
+
+fn bench_notes(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let project = synthetic_project(dir.path());
+    let mut group = c.benchmark_group("read_notes");
+    group.bench_function("cached", |b| {
+        b.iter(|| {
+            for category in NOTE_CATEGORIES {
+                black_box(project.read_notes(category).unwrap());
+            }
+        })
+    });
+    group.bench_function("uncached", |b| {
+        b.iter(|| {
+            for category in NOTE_CATEGORIES {
+                black_box(std::fs::read_to_string(project.notes_path(category)).unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+fn bench_task_number(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut project = synthetic_project(dir.path());
+    let mut group = c.benchmark_group("next_task_number");
+    group.bench_function("counter", |b| {
+        b.iter(|| black_box(project.next_task_number().unwrap()))
+    });
+    group.bench_function("directory_scan", |b| {
+        b.iter(|| {
+            // A zero counter, in memory and on disk, makes the next call
+            // seed it from the tasks dir
+            project.metadata.stats.last_task_number = 0;
+            let _ = std::fs::remove_file(project.path.join("project.toml"));
+            black_box(project.next_task_number().unwrap())
+        })
+    });
+    group.finish();
+}
+
+fn bench_transcript(c: &mut Criterion) {
+    let output = synthetic_transcript();
+    let transcript = Transcript::parse(&output);
+    let mut group = c.benchmark_group("full_history_transcript");
+    group.bench_function("parsed_once", |b| {
+        b.iter(|| black_box(transcript.messages.len()))
+    });
+    group.bench_function("reparsed", |b| {
+        b.iter(|| black_box(Transcript::parse(&output).messages.len()))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_notes, bench_task_number, bench_transcript);
+criterion_main!(benches);
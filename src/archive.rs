@@ -0,0 +1,316 @@
+//! Project export and import
+//!
+//! `clancy export` bundles a project directory (metadata, notes, task logs)
+//! into a `.tar.gz` whose first entry is a manifest, and `clancy
+//! import-archive <file>` unpacks one into the projects directory. Imports are validated
+//! before anything lands: the manifest's format version must be supported,
+//! every entry must stay inside the project, and the project name must not
+//! already be taken (use `--as` to import under another name).
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+use crate::config;
+use crate::project::{self, Project, ProjectMetadata, METADATA_FILE, PENDING_SUFFIX};
+
+/// Name of the manifest entry, always first in the archive
+const MANIFEST_FILE: &str = "clancy-export.json";
+
+/// Directory the project's files live under inside the archive
+const PROJECT_PREFIX: &str = "project";
+
+/// Archive layout version written by this build; newer ones are refused
+const FORMAT_VERSION: u32 = 1;
+
+/// Describes an exported project
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    format_version: u32,
+    clancy_version: String,
+    project: String,
+    exported: DateTime<Utc>,
+    tasks: u32,
+}
+
+/// Exports a project to `out` (default `<project>.tar.gz`)
+pub fn export_project(project_name: &str, out: Option<&Path>) -> Result<()> {
+    let project = Project::open(project_name)?;
+    let out = out
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from(format!("{}.tar.gz", project_name)));
+    let manifest = write_archive(&project.path, &project.metadata, &out)?;
+    println!(
+        "Exported '{}' ({} tasks) to {}",
+        manifest.project,
+        manifest.tasks,
+        out.display()
+    );
+    Ok(())
+}
+
+/// Imports a project from an archive made by `clancy export`, optionally
+/// under a different name
+pub fn import_archive(file: &Path, rename: Option<&str>) -> Result<()> {
+    config::ensure_config_dir()?;
+    let (name, manifest) = unpack_archive(file, &config::projects_dir()?, rename)?;
+
+    let project = Project::open(&name)?;
+    if let Some(ref parent) = project.metadata.parent {
+        if Project::open(parent).is_err() {
+            println!(
+                "Note: parent project '{}' doesn't exist here; link it with `clancy link` or remove it with `clancy unlink {}`.",
+                parent, name
+            );
+        }
+    }
+    println!(
+        "Imported '{}' ({} tasks, exported {} by clancy {})",
+        name,
+        manifest.tasks,
+        manifest.exported.format("%Y-%m-%d"),
+        manifest.clancy_version
+    );
+    Ok(())
+}
+
+/// Writes the manifest and every project file into a gzipped tar at `out`
+fn write_archive(project_dir: &Path, metadata: &ProjectMetadata, out: &Path) -> Result<Manifest> {
+    let manifest = Manifest {
+        format_version: FORMAT_VERSION,
+        clancy_version: env!("CARGO_PKG_VERSION").to_string(),
+        project: metadata.name.clone(),
+        exported: Utc::now(),
+        tasks: metadata.stats.total_tasks,
+    };
+    let file = File::create(out).with_context(|| format!("Failed to create {}", out.display()))?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+    let json = serde_json::to_vec_pretty(&manifest)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(json.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(manifest.exported.timestamp().max(0) as u64);
+    header.set_cksum();
+    builder.append_data(&mut header, MANIFEST_FILE, json.as_slice())?;
+
+    for path in project_files(project_dir)? {
+        let rel = path.strip_prefix(project_dir)?;
+        builder
+            .append_path_with_name(&path, Path::new(PROJECT_PREFIX).join(rel))
+            .with_context(|| format!("Failed to add {}", path.display()))?;
+    }
+    builder.into_inner()?.finish()?;
+    Ok(manifest)
+}
+
+/// Regular files under a project directory, sorted, skipping lock files and
+/// writes in progress
+fn project_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if file_type.is_dir() {
+                pending.push(path);
+            } else if file_type.is_file()
+                && !name.ends_with(".lock")
+                && !name.ends_with(PENDING_SUFFIX)
+            {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Validates and unpacks an archive into `projects_dir`, returning the
+/// project's name there
+///
+/// Files go to a hidden staging directory first, which is renamed into
+/// place only once everything unpacked and the metadata parsed.
+fn unpack_archive(
+    file: &Path,
+    projects_dir: &Path,
+    rename: Option<&str>,
+) -> Result<(String, Manifest)> {
+    let reader = File::open(file).with_context(|| format!("Failed to open {}", file.display()))?;
+    let mut archive = tar::Archive::new(GzDecoder::new(reader));
+    let mut entries = archive
+        .entries()
+        .with_context(|| format!("{} is not a clancy export", file.display()))?;
+
+    let mut first = entries
+        .next()
+        .transpose()
+        .with_context(|| format!("{} is not a clancy export", file.display()))?
+        .with_context(|| format!("{} is empty", file.display()))?;
+    if first.path()?.as_ref() != Path::new(MANIFEST_FILE) {
+        bail!(
+            "{} is not a clancy export (no {})",
+            file.display(),
+            MANIFEST_FILE
+        );
+    }
+    let mut json = String::new();
+    first.read_to_string(&mut json)?;
+    let manifest: Manifest = serde_json::from_str(&json).context("Invalid export manifest")?;
+    check_manifest(&manifest)?;
+
+    let name = rename.unwrap_or(&manifest.project).to_string();
+    project::validate_project_name(&name)?;
+    let dest = projects_dir.join(&name);
+    if dest.exists() {
+        bail!(
+            "Project '{}' already exists; import under another name with --as <name>",
+            name
+        );
+    }
+
+    let staging = projects_dir.join(format!(".import-{}{}", name, PENDING_SUFFIX));
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging)?;
+    }
+    let result = (|| -> Result<()> {
+        for entry in entries {
+            let mut entry = entry?;
+            let path = entry.path()?.into_owned();
+            let Some(rel) = project_relative(&path) else {
+                bail!("Refusing unsafe path in archive: {}", path.display());
+            };
+            match entry.header().entry_type() {
+                tar::EntryType::Regular => {
+                    let target = staging.join(&rel);
+                    if let Some(parent) = target.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    entry.unpack(&target)?;
+                }
+                tar::EntryType::Directory => std::fs::create_dir_all(staging.join(&rel))?,
+                other => bail!("Refusing {:?} entry in archive: {}", other, path.display()),
+            }
+        }
+
+        let metadata_path = staging.join(METADATA_FILE);
+        let content = std::fs::read_to_string(&metadata_path)
+            .with_context(|| format!("Archive has no {}", METADATA_FILE))?;
+        let mut metadata: ProjectMetadata = toml::from_str(&content)
+            .with_context(|| format!("Archive's {} is invalid", METADATA_FILE))?;
+        if metadata.name != name {
+            metadata.name = name.clone();
+            project::write_atomic(&metadata_path, &toml::to_string_pretty(&metadata)?)?;
+        }
+        std::fs::create_dir_all(staging.join("notes"))?;
+        std::fs::create_dir_all(staging.join("tasks"))?;
+        std::fs::rename(&staging, &dest)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_dir_all(&staging);
+    }
+    result.with_context(|| format!("Failed to import {}", file.display()))?;
+    Ok((name, manifest))
+}
+
+/// Refuses archives written by a newer, incompatible clancy
+fn check_manifest(manifest: &Manifest) -> Result<()> {
+    if manifest.format_version > FORMAT_VERSION {
+        bail!(
+            "Export format {} (from clancy {}) is newer than this clancy supports ({}); upgrade to import it",
+            manifest.format_version,
+            manifest.clancy_version,
+            FORMAT_VERSION
+        );
+    }
+    Ok(())
+}
+
+/// Path of an archive entry inside the project, or None if it isn't under
+/// the project prefix or could escape it
+fn project_relative(path: &Path) -> Option<PathBuf> {
+    let rel = path.strip_prefix(PROJECT_PREFIX).ok()?;
+    let safe = rel.components().all(|c| matches!(c, Component::Normal(_)));
+    (safe && rel.components().next().is_some()).then(|| rel.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let source = temp_dir.path().join("src").join("api");
+        std::fs::create_dir_all(source.join("notes")).unwrap();
+        std::fs::create_dir_all(source.join("tasks")).unwrap();
+        let mut metadata = ProjectMetadata::new("api");
+        metadata.stats.total_tasks = 1;
+        std::fs::write(
+            source.join(METADATA_FILE),
+            toml::to_string_pretty(&metadata).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(source.join("notes/plan.md"), "- ship it\n").unwrap();
+        std::fs::write(source.join("tasks/001-a-k3x9q.json"), "{}").unwrap();
+        std::fs::write(source.join("task_counter.lock"), "").unwrap();
+
+        let out = temp_dir.path().join("api.tar.gz");
+        write_archive(&source, &metadata, &out).unwrap();
+
+        let projects = temp_dir.path().join("projects");
+        std::fs::create_dir_all(&projects).unwrap();
+        let (name, manifest) = unpack_archive(&out, &projects, None).unwrap();
+        assert_eq!((name.as_str(), manifest.tasks), ("api", 1));
+        assert_eq!(
+            std::fs::read_to_string(projects.join("api/notes/plan.md")).unwrap(),
+            "- ship it\n"
+        );
+        assert!(projects.join("api/tasks/001-a-k3x9q.json").exists());
+        assert!(!projects.join("api/task_counter.lock").exists());
+
+        // Taken names are refused unless renamed
+        assert!(unpack_archive(&out, &projects, None).is_err());
+        let (name, _) = unpack_archive(&out, &projects, Some("api-copy")).unwrap();
+        assert_eq!(name, "api-copy");
+        let content =
+            std::fs::read_to_string(projects.join("api-copy").join(METADATA_FILE)).unwrap();
+        let renamed: ProjectMetadata = toml::from_str(&content).unwrap();
+        assert_eq!(renamed.name, "api-copy");
+    }
+
+    #[test]
+    fn test_check_manifest_refuses_newer_format() {
+        let mut manifest = Manifest {
+            format_version: FORMAT_VERSION,
+            clancy_version: "0.1.0".to_string(),
+            project: "api".to_string(),
+            exported: Utc::now(),
+            tasks: 0,
+        };
+        assert!(check_manifest(&manifest).is_ok());
+        manifest.format_version = FORMAT_VERSION + 1;
+        assert!(check_manifest(&manifest).is_err());
+    }
+
+    #[test]
+    fn test_project_relative() {
+        assert_eq!(
+            project_relative(Path::new("project/notes/plan.md")),
+            Some(PathBuf::from("notes/plan.md"))
+        );
+        assert_eq!(project_relative(Path::new("project/../evil")), None);
+        assert_eq!(project_relative(Path::new("other/file")), None);
+        assert_eq!(project_relative(Path::new("project")), None);
+    }
+}
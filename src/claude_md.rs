@@ -0,0 +1,123 @@
+//! Exporting notes as a CLAUDE.md
+//!
+//! Renders a project's architecture, decisions, and failures notes for plain
+//! Claude Code sessions and teammates not using Clancy. The generated text
+//! sits between marker comments so regenerating replaces only that block and
+//! keeps anything written around it.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use crate::project::Project;
+use crate::repl::section_title;
+
+const BLOCK_START: &str = "<!-- clancy:begin (generated by `clancy emit-claude-md`; edits inside this block are overwritten) -->";
+const BLOCK_END: &str = "<!-- clancy:end -->";
+
+/// Note categories exported, in order
+const EXPORTED_CATEGORIES: &[&str] = &["architecture", "decisions", "failures"];
+
+/// Prints the project's CLAUDE.md block, or writes it into `output`
+pub fn emit_claude_md(project_name: &str, output: Option<&Path>) -> Result<()> {
+    let project = Project::open(project_name)?;
+    let mut sections = Vec::new();
+    for category in EXPORTED_CATEGORIES {
+        sections.push((*category, project.read_notes(category)?));
+    }
+    let block = render_block(
+        &project.metadata.name,
+        project.metadata.description.as_deref(),
+        &sections,
+    );
+
+    let Some(path) = output else {
+        print!("{}", block);
+        return Ok(());
+    };
+    let existing = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    std::fs::write(path, replace_block(&existing, &block))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    println!("Wrote project notes to {}", path.display());
+    Ok(())
+}
+
+/// Renders the managed block, skipping empty note categories
+fn render_block(name: &str, description: Option<&str>, sections: &[(&str, String)]) -> String {
+    let mut block = format!("{}\n# Project: {}\n", BLOCK_START, name);
+    if let Some(description) = description {
+        block.push_str(&format!("\n{}\n", description.trim()));
+    }
+    for (category, notes) in sections {
+        if notes.trim().is_empty() {
+            continue;
+        }
+        block.push_str(&format!(
+            "\n## {}\n\n{}\n",
+            section_title(category),
+            notes.trim()
+        ));
+    }
+    block.push_str(BLOCK_END);
+    block.push('\n');
+    block
+}
+
+/// Swaps the managed block in `content` for `block`, appending it when
+/// there's no block yet
+fn replace_block(content: &str, block: &str) -> String {
+    if let Some(start) = content.find(BLOCK_START) {
+        if let Some(end) = content[start..].find(BLOCK_END) {
+            let mut rest = &content[start + end + BLOCK_END.len()..];
+            rest = rest.strip_prefix('\n').unwrap_or(rest);
+            return format!("{}{}{}", &content[..start], block, rest);
+        }
+    }
+    if content.trim().is_empty() {
+        block.to_string()
+    } else {
+        format!("{}\n\n{}", content.trim_end(), block)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_block_skips_empty_categories() {
+        let sections = vec![
+            ("architecture", "- Axum API over Postgres\n".to_string()),
+            ("decisions", "  \n".to_string()),
+            (
+                "failures",
+                "- sqlx offline mode needs `cargo sqlx prepare`".to_string(),
+            ),
+        ];
+        let block = render_block("api", Some("Billing API"), &sections);
+        assert!(block.starts_with(BLOCK_START));
+        assert!(block.contains("# Project: api\n\nBilling API\n"));
+        assert!(block.contains("## Architectural Context\n\n- Axum API over Postgres\n"));
+        assert!(!block.contains("Key Decisions"));
+        assert!(block.contains("## Known Pitfalls\n"));
+        assert!(block.ends_with(&format!("{}\n", BLOCK_END)));
+    }
+
+    #[test]
+    fn test_replace_block() {
+        let block = format!("{}\nnew\n{}\n", BLOCK_START, BLOCK_END);
+        assert_eq!(replace_block("", &block), block);
+
+        let appended = replace_block("# Team rules\n\nUse tabs.\n", &block);
+        assert_eq!(appended, format!("# Team rules\n\nUse tabs.\n\n{}", block));
+
+        let existing = format!("intro\n{}\nold\n{}\noutro\n", BLOCK_START, BLOCK_END);
+        assert_eq!(
+            replace_block(&existing, &block),
+            format!("intro\n{}outro\n", block)
+        );
+    }
+}
@@ -0,0 +1,45 @@
+//! System clipboard access
+//!
+//! Wraps the `arboard` crate, which talks to the macOS, Windows, X11, and
+//! Wayland clipboards directly. Used by `/handoff`, `/copy`, and `/paste`.
+//!
+//! On Linux the copying process owns the clipboard contents, so one handle
+//! is kept for the life of the process and copied text stays available while
+//! clancy runs (and afterwards when a clipboard manager takes it over).
+
+use anyhow::{Context, Result};
+use arboard::Clipboard;
+use std::sync::Mutex;
+
+/// Process-wide handle, opened on first use
+static CLIPBOARD: Mutex<Option<Clipboard>> = Mutex::new(None);
+
+/// Runs `f` with the shared clipboard handle, opening it if needed
+fn with_clipboard<T>(f: impl FnOnce(&mut Clipboard) -> Result<T>) -> Result<T> {
+    let mut guard = CLIPBOARD.lock().unwrap_or_else(|e| e.into_inner());
+    let clipboard = match guard.take() {
+        Some(clipboard) => guard.insert(clipboard),
+        None => guard.insert(Clipboard::new().context("no clipboard available")?),
+    };
+    f(clipboard)
+}
+
+/// Copies text to the system clipboard
+pub fn copy(text: &str) -> Result<()> {
+    with_clipboard(|clipboard| {
+        clipboard
+            .set_text(text)
+            .context("failed to copy to the clipboard")
+    })
+}
+
+/// Reads the clipboard's text
+pub fn paste() -> Result<String> {
+    with_clipboard(|clipboard| {
+        let text = clipboard
+            .get_text()
+            .context("failed to read the clipboard")?;
+        // Windows ends lines with CRLF
+        Ok(text.replace("\r\n", "\n"))
+    })
+}
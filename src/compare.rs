@@ -0,0 +1,208 @@
+//! Side-by-side comparison of two stored tasks
+//!
+//! Backs `clancy compare`, which is handy when a task was retried with a
+//! different prompt and you want to see which attempt did better.
+
+use anyhow::Result;
+
+use crate::diff::file_diffs;
+use crate::extraction::ExtractionResult;
+use crate::project::{Project, NOTE_CATEGORIES};
+use crate::tasklog::{load_task_log, TaskLog};
+use crate::text;
+
+/// Loads two tasks from a project and prints their comparison
+pub fn compare_tasks(project_name: &str, a: u32, b: u32) -> Result<()> {
+    let project = Project::open(project_name)?;
+    let log_a = load_task_log(&project, a)?;
+    let log_b = load_task_log(&project, b)?;
+    print!("{}", render_comparison(&log_a, &log_b));
+    Ok(())
+}
+
+/// Renders two task logs side by side, followed by the notes each produced
+pub fn render_comparison(a: &TaskLog, b: &TaskLog) -> String {
+    let rows = [
+        ("Prompt", a.prompt.clone(), b.prompt.clone()),
+        ("Result", outcome(a), outcome(b)),
+        ("Duration", duration(a), duration(b)),
+        ("Cost", cost(a), cost(b)),
+        ("Tools", tools(a), tools(b)),
+        ("Files", files(a), files(b)),
+    ];
+
+    let header_a = format!("Task {}", a.task_number);
+    let header_b = format!("Task {}", b.task_number);
+    let width = rows
+        .iter()
+        .map(|(_, left, _)| left.chars().count())
+        .chain(std::iter::once(header_a.len()))
+        .max()
+        .unwrap_or(0)
+        .min(40);
+
+    let mut out = format!("{:<10} {:<width$}  {}\n", "", header_a, header_b);
+    for (label, left, right) in &rows {
+        out.push_str(&format!(
+            "{:<10} {:<width$}  {}\n",
+            label,
+            truncate(left, width),
+            right
+        ));
+    }
+
+    for log in [a, b] {
+        if !log.summary.is_empty() {
+            out.push_str(&format!(
+                "\n## Summary (task {})\n{}\n",
+                log.task_number,
+                log.summary.trim()
+            ));
+        }
+    }
+
+    out.push_str("\n## Notes Produced\n");
+    match (&a.extracted_notes, &b.extracted_notes) {
+        (None, None) => out.push_str("(no extracted notes recorded for either task)\n"),
+        (notes_a, notes_b) => {
+            let mut any = false;
+            for category in NOTE_CATEGORIES {
+                let left = category_notes(notes_a.as_ref(), category);
+                let right = category_notes(notes_b.as_ref(), category);
+                let only_a = lines_not_in(left, right);
+                let only_b = lines_not_in(right, left);
+                if only_a.is_empty() && only_b.is_empty() {
+                    continue;
+                }
+                any = true;
+                out.push_str(&format!("\n### {}\n", category));
+                for line in only_a {
+                    out.push_str(&format!("  [{}] {}\n", a.task_number, line));
+                }
+                for line in only_b {
+                    out.push_str(&format!("  [{}] {}\n", b.task_number, line));
+                }
+            }
+            if !any {
+                out.push_str("(both tasks produced the same notes)\n");
+            }
+        }
+    }
+
+    out
+}
+
+fn outcome(log: &TaskLog) -> String {
+    if log.success { "succeeded" } else { "failed" }.to_string()
+}
+
+fn duration(log: &TaskLog) -> String {
+    log.duration_ms
+        .map(|ms| format!("{:.1}s", ms as f64 / 1000.0))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn cost(log: &TaskLog) -> String {
+    log.cost_usd
+        .map(|c| format!("${:.4}", c))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn tools(log: &TaskLog) -> String {
+    let counts = log.transcript.tool_counts();
+    if counts.is_empty() {
+        return "-".to_string();
+    }
+    counts
+        .iter()
+        .map(|(name, n)| format!("{}x{}", name, n))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn files(log: &TaskLog) -> String {
+    let paths: Vec<String> = file_diffs(&log.transcript)
+        .into_iter()
+        .map(|d| d.path)
+        .collect();
+    if paths.is_empty() {
+        "-".to_string()
+    } else {
+        paths.join(", ")
+    }
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    format!("{}…", text::truncate_chars(text, width.saturating_sub(1)))
+}
+
+fn category_notes<'a>(notes: Option<&'a ExtractionResult>, category: &str) -> &'a str {
+    let Some(notes) = notes else {
+        return "";
+    };
+    let field = match category {
+        "architecture" => &notes.architecture,
+        "decisions" => &notes.decisions,
+        "failures" => &notes.failures,
+        "plan" => &notes.plan,
+        _ => &None,
+    };
+    field.as_deref().unwrap_or("")
+}
+
+/// Returns the non-blank lines of `text` that don't appear in `other`
+fn lines_not_in<'a>(text: &'a str, other: &str) -> Vec<&'a str> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !other.lines().any(|o| o.trim() == *line))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(number: u32, notes: serde_json::Value) -> TaskLog {
+        serde_json::from_value(serde_json::json!({
+            "task_number": number,
+            "prompt": format!("attempt {}", number),
+            "success": true,
+            "duration_ms": 1500,
+            "extracted_notes": notes,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_lines_not_in() {
+        assert_eq!(lines_not_in("- a\n- b\n\n", "- b\n"), vec!["- a"]);
+        assert!(lines_not_in("", "- a").is_empty());
+    }
+
+    #[test]
+    fn test_render_comparison_notes_diff() {
+        let a = log(
+            1,
+            serde_json::json!({"decisions": "- Use sqlx\n- Keep tokio"}),
+        );
+        let b = log(
+            2,
+            serde_json::json!({"decisions": "- Keep tokio\n- Use diesel"}),
+        );
+        let out = render_comparison(&a, &b);
+        assert!(out.contains("Prompt     attempt 1  attempt 2\n"));
+        assert!(out.contains("Duration   1.5s       1.5s\n"));
+        assert!(out.contains("### decisions\n  [1] - Use sqlx\n  [2] - Use diesel\n"));
+        assert!(!out.contains("Keep tokio"));
+    }
+
+    #[test]
+    fn test_render_comparison_without_notes() {
+        let a = log(1, serde_json::Value::Null);
+        let b = log(2, serde_json::Value::Null);
+        assert!(render_comparison(&a, &b).contains("(no extracted notes recorded"));
+    }
+}
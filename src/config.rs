@@ -1,6 +1,7 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use std::sync::Mutex;
 
 /// Global Clancy configuration
 #[derive(Debug, Default, Serialize, Deserialize)]
@@ -13,6 +14,22 @@ pub struct Config {
     pub context: ContextConfig,
     #[serde(default)]
     pub repl: ReplConfig,
+    #[serde(default)]
+    pub review: ReviewConfig,
+    #[serde(default)]
+    pub sandbox: SandboxConfig,
+    #[serde(default)]
+    pub daemon: DaemonConfig,
+    #[serde(default)]
+    pub pricing: PricingConfig,
+    #[serde(default)]
+    pub routing: RoutingConfig,
+    #[serde(default)]
+    pub policy: PolicyConfig,
+    #[serde(default)]
+    pub processors: ProcessorsConfig,
+    #[serde(default)]
+    pub i18n: I18nConfig,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -26,6 +43,9 @@ pub struct ClaudeConfig {
     /// Base URL for Claude API (allows proxies like Vercel AI Gateway)
     #[serde(default = "default_base_url")]
     pub base_url: String,
+    /// Models to try, in order, when the extraction model is unavailable
+    #[serde(default)]
+    pub fallback_models: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -33,9 +53,31 @@ pub struct ExtractionConfig {
     /// Max tokens for transcript before truncation
     #[serde(default = "default_max_transcript_tokens")]
     pub max_transcript_tokens: usize,
-    /// Include tool outputs in transcript
+    /// Include tool outputs in transcript (error outputs are always included)
     #[serde(default = "default_true")]
     pub include_tool_outputs: bool,
+    /// Per-result cap on tool output bytes sent for extraction
+    #[serde(default = "default_max_tool_output_bytes")]
+    pub max_tool_output_bytes: usize,
+    /// Cap on all tool output bytes sent for extraction; errors are counted first
+    #[serde(default = "default_max_total_tool_output_bytes")]
+    pub max_total_tool_output_bytes: usize,
+    /// Ask which extracted categories to apply after each task
+    #[serde(default)]
+    pub confirm_categories: bool,
+    /// Output token budget for the extraction response
+    #[serde(default = "default_extraction_max_tokens")]
+    pub max_tokens: u32,
+    /// Highest budget to retry with when a response is truncated
+    #[serde(default = "default_extraction_max_tokens_limit")]
+    pub max_tokens_limit: u32,
+    /// Also extract notes from tasks cancelled with Ctrl+C
+    #[serde(default)]
+    pub extract_interrupted: bool,
+    /// Without an API key, or when the API call fails, extract rule-based
+    /// notes (errors, stated decisions) instead of none
+    #[serde(default = "default_true")]
+    pub offline_fallback: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -46,9 +88,50 @@ pub struct ContextConfig {
     /// Include notes from parent projects
     #[serde(default = "default_true")]
     pub include_parent_notes: bool,
+    /// How many ancestors up the parent chain to inherit notes from
+    #[serde(default = "default_inheritance_depth")]
+    pub inheritance_depth: usize,
+    /// List sibling projects (same parent) in a "Related Projects" section
+    #[serde(default)]
+    pub include_siblings: bool,
+    /// Add an "Environment" section with OS and toolchain versions
+    #[serde(default)]
+    pub include_environment: bool,
+    /// Add a "How to Build/Test" section detected from project manifests
+    #[serde(default = "default_true")]
+    pub include_build_commands: bool,
+    /// Log file whose tail is included in every task's context (e.g. CI failures)
+    #[serde(default)]
+    pub failure_log: Option<String>,
+    /// Lines from the end of the failure log to include
+    #[serde(default = "default_failure_log_lines")]
+    pub failure_log_lines: usize,
+    /// Bytes of a file inlined by /attach or `{{file:...}}` before it is cut off
+    #[serde(default = "default_attach_max_bytes")]
+    pub attach_max_bytes: usize,
+    /// Move decision entries older than this many months to
+    /// decisions-archive.md at session start
+    #[serde(default)]
+    pub archive_decisions_after_months: Option<u32>,
+    /// Include decisions-archive.md in context
+    #[serde(default)]
+    pub include_decisions_archive: bool,
+    /// Keep context byte-stable across a session's tasks for prompt caching:
+    /// no task counter, and session history last
+    #[serde(default)]
+    pub stable: bool,
     /// Conversation continuity mode: fresh | summary | full
     #[serde(default = "default_conversation_mode")]
     pub conversation_mode: String,
+    /// Step the conversation mode down (full → summary → fresh) before a task
+    /// whose projected context is above this many tokens, instead of
+    /// truncating it
+    #[serde(default)]
+    pub downgrade_above_tokens: Option<usize>,
+    /// Step the mode down once session spend passes this (USD): at most
+    /// summary past it, fresh past twice it
+    #[serde(default)]
+    pub downgrade_above_session_usd: Option<f64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -56,9 +139,225 @@ pub struct ReplConfig {
     /// Editor for /notes command
     #[serde(default = "default_editor")]
     pub editor: String,
-    /// Prompt style: project | minimal
+    /// Prompt style: project | minimal | template
     #[serde(default = "default_prompt_style")]
     pub prompt_style: String,
+    /// Prompt for the template style; placeholders: {project}, {mode},
+    /// {cost}, {tasks}, {branch}, {indicator}
+    #[serde(default = "default_prompt_template")]
+    pub prompt_template: String,
+    /// Session budget (USD); the prompt shows spend once 80% of it is used
+    #[serde(default)]
+    pub budget_usd: Option<f64>,
+    /// Run every task as /plan-task (plan, approve, then execute)
+    #[serde(default)]
+    pub plan_first: bool,
+    /// Max entries kept in each project's REPL history
+    #[serde(default = "default_history_size")]
+    pub history_size: usize,
+    /// Drop duplicate history entries, keeping the most recent
+    #[serde(default = "default_true")]
+    pub history_dedup: bool,
+    /// Key bindings: emacs | vi
+    #[serde(default = "default_edit_mode")]
+    pub edit_mode: String,
+    /// Ctrl-R reverse incremental history search
+    #[serde(default = "default_true")]
+    pub incremental_search: bool,
+    /// Don't record input that starts with a space
+    #[serde(default)]
+    pub history_ignore_space: bool,
+    /// Recent tasks checked for a near-identical prompt before running
+    /// (0 disables the warning)
+    #[serde(default = "default_duplicate_lookback")]
+    pub duplicate_lookback: usize,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ReviewConfig {
+    /// Run a reviewer pass after each task
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SandboxConfig {
+    /// Run tasks inside a container by default (toggle with /sandbox)
+    #[serde(default)]
+    pub enabled: bool,
+    /// Container runtime: docker | podman
+    #[serde(default = "default_sandbox_runtime")]
+    pub runtime: String,
+    /// Image with the claude CLI installed
+    #[serde(default)]
+    pub image: String,
+    /// Where the working directory is mounted inside the container
+    #[serde(default = "default_sandbox_workdir")]
+    pub workdir: String,
+    /// Extra `run` arguments, e.g. additional `-v` mounts or `--network`
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+    /// Host environment variables forwarded into the container
+    #[serde(default = "default_sandbox_env")]
+    pub env: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DaemonConfig {
+    /// Seconds between queue and schedule checks
+    #[serde(default = "default_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+    /// Stop starting tasks once today's spend reaches this (USD)
+    #[serde(default)]
+    pub daily_budget_usd: Option<f64>,
+    /// Tasks enqueued automatically once a day
+    #[serde(default)]
+    pub schedule: Vec<ScheduledTask>,
+    /// Address for the HTTP API, e.g. "127.0.0.1:7411"; off when unset
+    #[serde(default)]
+    pub listen: Option<String>,
+    /// Bearer token the HTTP API requires; the API won't start without one
+    #[serde(default)]
+    pub api_token: Option<String>,
+    /// Extra directories `POST /enqueue` may run tasks in, besides where the
+    /// daemon started and schedule entry dirs
+    #[serde(default)]
+    pub roots: Vec<PathBuf>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PricingConfig {
+    /// Model tasks run on, for cost estimates (a family name like "sonnet"
+    /// or a full model ID)
+    #[serde(default = "default_task_model")]
+    pub task_model: String,
+    /// Input price in USD per million tokens, overriding the built-in table
+    #[serde(default)]
+    pub input_per_mtok: Option<f64>,
+    /// Ask before running REPL tasks whose estimated input cost exceeds this
+    #[serde(default)]
+    pub confirm_above_usd: Option<f64>,
+    /// Stop a task mid-stream once its running cost estimate crosses this
+    #[serde(default)]
+    pub max_task_usd: Option<f64>,
+}
+
+/// Rules choosing the model or agent per task, under `[[routing.rules]]`
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RoutingConfig {
+    /// Checked in order; the first matching rule wins
+    #[serde(default)]
+    pub rules: Vec<RouteRule>,
+}
+
+/// A routing rule; a rule with both conditions needs both to match, and one
+/// with neither matches every task
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RouteRule {
+    /// Tag the prompt must contain as a word, e.g. "#docs"
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// Match prompts of at most this many characters
+    #[serde(default)]
+    pub max_chars: Option<usize>,
+    /// Passed as `--model`
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Passed as `--agent`
+    #[serde(default)]
+    pub agent: Option<String>,
+}
+
+/// Tool calls and dependency changes to flag after each task, under
+/// `[policy]`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PolicyConfig {
+    /// Discouraged commands: "Tool: pattern" limits a pattern to one tool
+    /// (e.g. "Bash: rm -rf"), a bare pattern checks every tool's input
+    #[serde(default)]
+    pub discouraged: Vec<String>,
+    /// Diff manifests and lockfiles around each task, recording dependency
+    /// changes and flagging ones the prompt didn't ask for
+    #[serde(default = "default_true")]
+    pub track_dependencies: bool,
+}
+
+impl Default for PolicyConfig {
+    fn default() -> Self {
+        Self {
+            discouraged: Vec::new(),
+            track_dependencies: true,
+        }
+    }
+}
+
+/// Language of REPL and report output, under `[i18n]`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct I18nConfig {
+    /// Message locale; anything but "en" is read from
+    /// `locales/<locale>.toml` in the config directory
+    #[serde(default = "default_locale")]
+    pub locale: String,
+    /// Also translate the context section titles sent to Claude, which stay
+    /// English by default
+    #[serde(default)]
+    pub localize_context: bool,
+}
+
+impl Default for I18nConfig {
+    fn default() -> Self {
+        Self {
+            locale: default_locale(),
+            localize_context: false,
+        }
+    }
+}
+
+fn default_locale() -> String {
+    "en".to_string()
+}
+
+/// Transcript processors run after each task, beyond the built-ins
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProcessorsConfig {
+    /// Built-in processors to skip, e.g. "files_touched" (summary and
+    /// tools_used always run; log readers rely on them)
+    #[serde(default)]
+    pub disabled: Vec<String>,
+    /// External commands under `[[processors.commands]]`
+    #[serde(default)]
+    pub commands: Vec<CommandProcessorConfig>,
+}
+
+/// A shell command that derives an artifact from a task's transcript
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandProcessorConfig {
+    /// Key the artifact is stored under in the task log
+    pub name: String,
+    /// Run with `sh -c` in the task's directory, the transcript JSON on stdin
+    pub command: String,
+    /// Also show the artifact with the task in later tasks' session context
+    #[serde(default)]
+    pub context: bool,
+    /// Seconds before the command is killed and its artifact skipped
+    #[serde(default = "default_processor_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_processor_timeout_secs() -> u64 {
+    30
+}
+
+/// A task the daemon enqueues daily at a local time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledTask {
+    pub project: String,
+    pub prompt: String,
+    /// Local time of day, "HH:MM"
+    pub at: String,
+    /// Directory to run in (defaults to where the daemon was started)
+    #[serde(default)]
+    pub dir: Option<PathBuf>,
 }
 
 fn default_api_key_env() -> String {
@@ -77,10 +376,62 @@ fn default_max_transcript_tokens() -> usize {
     100000
 }
 
+fn default_max_tool_output_bytes() -> usize {
+    500
+}
+
+fn default_max_total_tool_output_bytes() -> usize {
+    20000
+}
+
+fn default_extraction_max_tokens() -> u32 {
+    2048
+}
+
+fn default_extraction_max_tokens_limit() -> u32 {
+    8192
+}
+
 fn default_max_context_tokens() -> usize {
     12000
 }
 
+fn default_inheritance_depth() -> usize {
+    3
+}
+
+fn default_failure_log_lines() -> usize {
+    80
+}
+
+fn default_attach_max_bytes() -> usize {
+    32_000
+}
+
+fn default_poll_interval_secs() -> u64 {
+    30
+}
+
+fn default_prompt_template() -> String {
+    "{project} ({branch}) [{mode}, ${cost}, {tasks} tasks]> ".to_string()
+}
+
+fn default_edit_mode() -> String {
+    "emacs".to_string()
+}
+
+fn default_history_size() -> usize {
+    1000
+}
+
+fn default_task_model() -> String {
+    "sonnet".to_string()
+}
+
+fn default_duplicate_lookback() -> usize {
+    20
+}
+
 fn default_true() -> bool {
     true
 }
@@ -93,16 +444,66 @@ fn default_editor() -> String {
     std::env::var("EDITOR").unwrap_or_else(|_| "vim".to_string())
 }
 
+fn default_sandbox_runtime() -> String {
+    "docker".to_string()
+}
+
+fn default_sandbox_workdir() -> String {
+    "/workspace".to_string()
+}
+
+fn default_sandbox_env() -> Vec<String> {
+    vec!["ANTHROPIC_API_KEY".to_string()]
+}
+
 fn default_prompt_style() -> String {
     "project".to_string()
 }
 
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval_secs: default_poll_interval_secs(),
+            daily_budget_usd: None,
+            schedule: Vec::new(),
+            listen: None,
+            api_token: None,
+            roots: Vec::new(),
+        }
+    }
+}
+
+impl Default for PricingConfig {
+    fn default() -> Self {
+        Self {
+            task_model: default_task_model(),
+            input_per_mtok: None,
+            confirm_above_usd: None,
+            max_task_usd: None,
+        }
+    }
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            runtime: default_sandbox_runtime(),
+            image: String::new(),
+            workdir: default_sandbox_workdir(),
+            extra_args: Vec::new(),
+            env: default_sandbox_env(),
+        }
+    }
+}
+
 impl Default for ClaudeConfig {
     fn default() -> Self {
         Self {
             api_key_env: default_api_key_env(),
             model: default_model(),
             base_url: default_base_url(),
+            fallback_models: Vec::new(),
         }
     }
 }
@@ -112,6 +513,13 @@ impl Default for ExtractionConfig {
         Self {
             max_transcript_tokens: default_max_transcript_tokens(),
             include_tool_outputs: true,
+            max_tool_output_bytes: default_max_tool_output_bytes(),
+            max_total_tool_output_bytes: default_max_total_tool_output_bytes(),
+            confirm_categories: false,
+            max_tokens: default_extraction_max_tokens(),
+            max_tokens_limit: default_extraction_max_tokens_limit(),
+            extract_interrupted: false,
+            offline_fallback: true,
         }
     }
 }
@@ -121,7 +529,19 @@ impl Default for ContextConfig {
         Self {
             max_context_tokens: default_max_context_tokens(),
             include_parent_notes: true,
+            inheritance_depth: default_inheritance_depth(),
+            include_siblings: false,
+            include_environment: false,
+            include_build_commands: true,
+            failure_log: None,
+            failure_log_lines: default_failure_log_lines(),
+            attach_max_bytes: default_attach_max_bytes(),
+            archive_decisions_after_months: None,
+            include_decisions_archive: false,
+            stable: false,
             conversation_mode: default_conversation_mode(),
+            downgrade_above_tokens: None,
+            downgrade_above_session_usd: None,
         }
     }
 }
@@ -131,6 +551,15 @@ impl Default for ReplConfig {
         Self {
             editor: default_editor(),
             prompt_style: default_prompt_style(),
+            prompt_template: default_prompt_template(),
+            budget_usd: None,
+            plan_first: false,
+            history_size: default_history_size(),
+            history_dedup: true,
+            edit_mode: default_edit_mode(),
+            incremental_search: true,
+            history_ignore_space: false,
+            duplicate_lookback: default_duplicate_lookback(),
         }
     }
 }
@@ -148,6 +577,16 @@ pub fn projects_dir() -> Result<PathBuf> {
     Ok(config_dir()?.join("projects"))
 }
 
+/// Returns the message catalog directory (~/.config/clancy/locales/)
+pub fn locales_dir() -> Result<PathBuf> {
+    Ok(config_dir()?.join("locales"))
+}
+
+/// Returns the trash directory for deleted projects (~/.config/clancy/trash/)
+pub fn trash_dir() -> Result<PathBuf> {
+    Ok(config_dir()?.join("trash"))
+}
+
 /// Returns the config file path (~/.config/clancy/config.toml)
 pub fn config_file() -> Result<PathBuf> {
     Ok(config_dir()?.join("config.toml"))
@@ -166,18 +605,146 @@ pub fn ensure_config_dir() -> Result<()> {
     Ok(())
 }
 
+/// Settings changed with `/config set`, applied on every load for the rest
+/// of the process
+static OVERRIDES: Mutex<Vec<(String, toml::Value)>> = Mutex::new(Vec::new());
+
 /// Loads the config, creating default if it doesn't exist
 pub fn load_config() -> Result<Config> {
+    let mut table = read_config_table()?;
+    let overrides = OVERRIDES.lock().unwrap_or_else(|e| e.into_inner());
+    for (key, value) in overrides.iter() {
+        set_key(&mut table, key, value.clone())?;
+    }
+    toml::Value::Table(table)
+        .try_into()
+        .with_context(|| "Failed to parse config file")
+}
+
+/// Reads the config file as a raw table (empty if the file doesn't exist)
+fn read_config_table() -> Result<toml::Table> {
     let config_path = config_file()?;
+    if !config_path.exists() {
+        return Ok(toml::Table::new());
+    }
+    let content = std::fs::read_to_string(&config_path)
+        .with_context(|| format!("Failed to read config file: {:?}", config_path))?;
+    toml::from_str(&content).with_context(|| "Failed to parse config file")
+}
+
+/// Changes a setting such as `context.max_context_tokens` for the rest of the
+/// session, and also writes it to config.toml when `persist` is set
+pub fn set_setting(key: &str, raw: &str, persist: bool) -> Result<()> {
+    let value = parse_setting_value(raw);
+    let table = read_config_table()?;
+    validate_setting(&table, key, &value)?;
+    if persist {
+        let path = config_file()?;
+        let existing = if path.exists() {
+            std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read config file: {:?}", path))?
+        } else {
+            String::new()
+        };
+        let updated = save_setting(&existing, key, &value)?;
+        ensure_config_dir()?;
+        crate::project::write_atomic(&path, &updated)
+            .with_context(|| format!("Failed to write config file: {:?}", path))?;
+    }
+    let mut overrides = OVERRIDES.lock().unwrap_or_else(|e| e.into_inner());
+    overrides.retain(|(k, _)| k != key);
+    overrides.push((key.to_string(), value));
+    Ok(())
+}
+
+/// Parses a TOML value (`8000`, `true`, `["a"]`), treating anything else as a string
+fn parse_setting_value(raw: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("v = {}", raw))
+        .ok()
+        .and_then(|mut t| t.remove("v"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()))
+}
+
+/// Checks that `key` names a known setting and `value` has the right type
+fn validate_setting(table: &toml::Table, key: &str, value: &toml::Value) -> Result<()> {
+    let mut table = table.clone();
+    set_key(&mut table, key, value.clone())?;
+    let config: Config = toml::Value::Table(table)
+        .try_into()
+        .map_err(|e| anyhow::anyhow!("Invalid value for {}: {}", key, e))?;
+    // Unknown keys are silently dropped by serde, so look for the key after
+    // a round trip
+    let effective = toml::Value::try_from(&config)?;
+    let mut node = Some(&effective);
+    for part in key.split('.') {
+        node = node.and_then(|n| n.get(part));
+    }
+    if node.is_none() {
+        bail!("Unknown setting: {}", key);
+    }
+    Ok(())
+}
+
+/// Sets a dotted key in config file text, editing it in place so comments,
+/// layout, and every other setting are kept
+fn save_setting(existing: &str, key: &str, value: &toml::Value) -> Result<String> {
+    let mut doc: toml_edit::DocumentMut =
+        existing.parse().context("Failed to parse config file")?;
+    let (path, last) = match key.rsplit_once('.') {
+        Some((path, last)) => (path.split('.').collect::<Vec<_>>(), last),
+        None => (Vec::new(), key),
+    };
+    let mut table = doc.as_table_mut();
+    for part in path {
+        table = table
+            .entry(part)
+            .or_insert_with(toml_edit::table)
+            .as_table_mut()
+            .with_context(|| format!("{} is not a section", part))?;
+    }
+    let value: toml_edit::Value = value
+        .to_string()
+        .parse()
+        .with_context(|| format!("Invalid value for {}", key))?;
+    set_toml_string(table, last, value);
+    Ok(doc.to_string())
+}
+
+/// Sets a dotted key in a table, creating intermediate tables
+fn set_key(table: &mut toml::Table, key: &str, value: toml::Value) -> Result<()> {
+    let (path, last) = match key.rsplit_once('.') {
+        Some((path, last)) => (path.split('.').collect::<Vec<_>>(), last),
+        None => (Vec::new(), key),
+    };
+    let mut current = table;
+    for part in path {
+        let entry = current
+            .entry(part.to_string())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        current = match entry {
+            toml::Value::Table(t) => t,
+            _ => bail!("{} is not a section", part),
+        };
+    }
+    current.insert(last.to_string(), value);
+    Ok(())
+}
 
-    if config_path.exists() {
-        let content = std::fs::read_to_string(&config_path)
-            .with_context(|| format!("Failed to read config file: {:?}", config_path))?;
-        let config: Config =
-            toml::from_str(&content).with_context(|| "Failed to parse config file")?;
-        Ok(config)
-    } else {
-        Ok(Config::default())
+/// Sets a string (or other value) in a config table edited in place,
+/// keeping an existing value's spacing and trailing comment
+pub fn set_toml_string(
+    table: &mut toml_edit::Table,
+    key: &str,
+    value: impl Into<toml_edit::Value>,
+) {
+    let value = value.into();
+    match table.get_mut(key).and_then(|item| item.as_value_mut()) {
+        Some(old) => {
+            let decor = old.decor().clone();
+            *old = value;
+            *old.decor_mut() = decor;
+        }
+        None => table[key] = toml_edit::value(value),
     }
 }
 
@@ -192,6 +759,65 @@ mod tests {
         assert_eq!(config.context.conversation_mode, "summary");
     }
 
+    #[test]
+    fn test_set_key_and_validate() {
+        let mut table = toml::Table::new();
+        set_key(
+            &mut table,
+            "context.max_context_tokens",
+            parse_setting_value("8000"),
+        )
+        .unwrap();
+        let config: Config = toml::Value::Table(table.clone()).try_into().unwrap();
+        assert_eq!(config.context.max_context_tokens, 8000);
+
+        assert!(validate_setting(&table, "repl.budget_usd", &parse_setting_value("4.5")).is_ok());
+        assert!(validate_setting(&table, "context.bogus", &parse_setting_value("1")).is_err());
+        assert!(validate_setting(
+            &table,
+            "context.max_context_tokens",
+            &parse_setting_value("lots")
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_save_setting_keeps_comments() {
+        let existing = "# my settings\n[context]\nmax_context_tokens = 4000 # tuned\n\n[repl]\neditor = \"vim\"\n";
+        let updated = save_setting(
+            existing,
+            "context.max_context_tokens",
+            &parse_setting_value("8000"),
+        )
+        .unwrap();
+        assert_eq!(
+            updated,
+            "# my settings\n[context]\nmax_context_tokens = 8000 # tuned\n\n[repl]\neditor = \"vim\"\n"
+        );
+
+        let updated = save_setting(
+            &updated,
+            "daemon.roots",
+            &parse_setting_value(r#"["/srv"]"#),
+        )
+        .unwrap();
+        assert!(updated.starts_with("# my settings\n"));
+        assert!(updated.contains("[daemon]\nroots = [\"/srv\"]\n"));
+    }
+
+    #[test]
+    fn test_parse_setting_value() {
+        assert_eq!(parse_setting_value("true"), toml::Value::Boolean(true));
+        assert_eq!(
+            parse_setting_value("vi"),
+            toml::Value::String("vi".to_string())
+        );
+        assert_eq!(
+            parse_setting_value("[\"a\"]"),
+            toml::Value::Array(vec![toml::Value::String("a".to_string())])
+        );
+    }
+
     #[test]
     fn test_config_roundtrip() {
         let config = Config::default();
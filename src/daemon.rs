@@ -0,0 +1,849 @@
+//! Daemon mode: a persistent task queue with daily schedules
+//!
+//! `clancy enqueue` (or a `POST /enqueue` to the daemon's HTTP API, when
+//! `daemon.listen` is set) appends to `queue.json` in the config dir;
+//! `clancy daemon` polls it, enqueues scheduled tasks when they come due,
+//! and runs pending tasks one at a time, stopping for the day once the
+//! spend budget is reached. Every change to the queue holds `queue.lock`,
+//! so the CLI, the API, and the runner never overwrite each other.
+//!
+//! The API requires `Authorization: Bearer <daemon.api_token>`, only takes
+//! `application/json` bodies, and refuses any request with an `Origin`
+//! header, so a web page open in the user's browser can't queue tasks.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::config::{self, load_config, ScheduledTask};
+use crate::project::{self, Project};
+use crate::repl::Session;
+
+/// Lock file held while the queue is read, changed, and saved
+const QUEUE_LOCK: &str = "queue.lock";
+
+/// Largest request body the HTTP API accepts
+const MAX_REQUEST_BYTES: usize = 64 * 1024;
+
+/// How long the HTTP API waits on a slow or silent client
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Persistent queue state, stored as `queue.json` in the config dir
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Queue {
+    #[serde(default)]
+    pub tasks: Vec<QueuedTask>,
+    /// Last date each schedule entry was enqueued, keyed by `schedule_key`
+    #[serde(default)]
+    pub last_scheduled: BTreeMap<String, NaiveDate>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QueuedTask {
+    pub id: u32,
+    pub project: String,
+    pub prompt: String,
+    pub dir: PathBuf,
+    pub enqueued_at: DateTime<Utc>,
+    pub status: QueueStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueueStatus {
+    Pending,
+    /// Taken by the daemon and not finished yet
+    Running,
+    Done,
+    Failed,
+}
+
+impl Queue {
+    fn path() -> Result<PathBuf> {
+        Ok(config::config_dir()?.join("queue.json"))
+    }
+
+    fn load() -> Result<Self> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+        project::write_atomic(&path, &serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Loads the queue, applies `change`, and saves it, holding the queue
+    /// lock throughout
+    pub fn update<T>(change: impl FnOnce(&mut Queue) -> T) -> Result<T> {
+        config::ensure_config_dir()?;
+        let _lock = project::lock_file(config::config_dir()?.join(QUEUE_LOCK))?;
+        let mut queue = Self::load()?;
+        let result = change(&mut queue);
+        queue.save()?;
+        Ok(result)
+    }
+
+    /// Appends a pending task and returns its ID
+    pub fn push(&mut self, project: &str, prompt: &str, dir: PathBuf) -> u32 {
+        let id = self.tasks.iter().map(|t| t.id).max().unwrap_or(0) + 1;
+        self.tasks.push(QueuedTask {
+            id,
+            project: project.to_string(),
+            prompt: prompt.to_string(),
+            dir,
+            enqueued_at: Utc::now(),
+            status: QueueStatus::Pending,
+            started_at: None,
+            finished_at: None,
+            cost_usd: None,
+            error: None,
+        });
+        id
+    }
+
+    fn pending_count(&self) -> usize {
+        self.tasks
+            .iter()
+            .filter(|t| t.status == QueueStatus::Pending)
+            .count()
+    }
+
+    /// Marks the first pending task running and returns its ID, project,
+    /// prompt, and directory
+    fn take_next(&mut self) -> Option<(u32, String, String, PathBuf)> {
+        let task = self
+            .tasks
+            .iter_mut()
+            .find(|t| t.status == QueueStatus::Pending)?;
+        task.status = QueueStatus::Running;
+        task.started_at = Some(Utc::now());
+        Some((
+            task.id,
+            task.project.clone(),
+            task.prompt.clone(),
+            task.dir.clone(),
+        ))
+    }
+
+    /// Fails tasks left running by a daemon that stopped mid-task; they are
+    /// not retried, since they may have made changes already
+    fn fail_abandoned(&mut self) -> usize {
+        let mut count = 0;
+        for task in &mut self.tasks {
+            if task.status == QueueStatus::Running {
+                task.status = QueueStatus::Failed;
+                task.finished_at = Some(Utc::now());
+                task.error = Some("the daemon stopped while this task ran".to_string());
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Points tasks queued for `old` at `new`, and carries over the dates
+    /// its schedule entries last ran so they don't run twice today
+    fn rename_project(&mut self, old: &str, new: &str) {
+        for task in self.tasks.iter_mut().filter(|t| t.project == old) {
+            task.project = new.to_string();
+        }
+        let prefix = format!("{}@", old);
+        let renamed: Vec<String> = self
+            .last_scheduled
+            .keys()
+            .filter(|key| key.starts_with(&prefix))
+            .cloned()
+            .collect();
+        for key in renamed {
+            if let Some(date) = self.last_scheduled.remove(&key) {
+                let entry = &key[prefix.len()..];
+                self.last_scheduled
+                    .insert(format!("{}@{}", new, entry), date);
+            }
+        }
+    }
+
+    /// Total cost of tasks finished on the given local date
+    fn spent_on(&self, date: NaiveDate) -> f64 {
+        self.tasks
+            .iter()
+            .filter(|t| {
+                t.finished_at
+                    .is_some_and(|at| at.with_timezone(&Local).date_naive() == date)
+            })
+            .filter_map(|t| t.cost_usd)
+            .sum()
+    }
+}
+
+/// Queues a task after checking the project exists; returns its ID and the
+/// number of pending tasks
+fn enqueue_task(project_name: &str, prompt: &str, dir: PathBuf) -> Result<(u32, usize)> {
+    // Fail early rather than when the daemon picks it up
+    Project::open(project_name)?;
+    Queue::update(|queue| {
+        let id = queue.push(project_name, prompt, dir);
+        (id, queue.pending_count())
+    })
+}
+
+/// Adds a task to the queue for `clancy daemon` to run
+pub fn enqueue(project_name: &str, prompt: &str) -> Result<()> {
+    let (id, pending) = enqueue_task(project_name, prompt, std::env::current_dir()?)?;
+    println!(
+        "Queued task #{} for '{}' ({} pending)",
+        id, project_name, pending
+    );
+    Ok(())
+}
+
+/// What one poll of the queue decided
+enum Step {
+    Run(u32, String, String, PathBuf),
+    OverBudget { spent: f64, pending: usize },
+    Idle,
+}
+
+/// Runs queued and scheduled tasks sequentially until interrupted
+pub fn run_daemon() -> Result<()> {
+    let config = load_config()?;
+    let daemon = &config.daemon;
+    let home_dir = std::env::current_dir()?;
+    let interval = Duration::from_secs(daemon.poll_interval_secs.max(1));
+
+    let abandoned = Queue::update(Queue::fail_abandoned)?;
+    if abandoned > 0 {
+        println!(
+            "[daemon] Marked {} task(s) left running by a previous daemon as failed",
+            abandoned
+        );
+    }
+    if let Some(addr) = &daemon.listen {
+        let Some(token) = daemon.api_token.clone().filter(|t| !t.is_empty()) else {
+            bail!("daemon.listen requires daemon.api_token to be set");
+        };
+        let listener =
+            TcpListener::bind(addr).with_context(|| format!("Failed to listen on {}", addr))?;
+        println!("[daemon] HTTP API on http://{}", addr);
+        let mut roots = vec![home_dir.clone()];
+        roots.extend(daemon.schedule.iter().filter_map(|entry| entry.dir.clone()));
+        roots.extend(daemon.roots.iter().cloned());
+        let api = Api {
+            token,
+            default_dir: home_dir.clone(),
+            roots,
+        };
+        std::thread::spawn(move || serve(listener, &api));
+    }
+    println!(
+        "Daemon started ({} scheduled tasks, polling every {}s). Ctrl+C to stop.",
+        daemon.schedule.len(),
+        interval.as_secs()
+    );
+
+    // Only announce the budget stop once per day
+    let mut budget_notice = None;
+    loop {
+        let now = Local::now().naive_local();
+        let step = Queue::update(|queue| {
+            for index in due_schedules(&daemon.schedule, &queue.last_scheduled, now) {
+                let entry = &daemon.schedule[index];
+                let dir = entry.dir.clone().unwrap_or_else(|| home_dir.clone());
+                let id = queue.push(&entry.project, &entry.prompt, dir);
+                queue.last_scheduled.insert(schedule_key(entry), now.date());
+                println!("[daemon] Scheduled task #{} for '{}'", id, entry.project);
+            }
+
+            let spent = queue.spent_on(now.date());
+            let over_budget = daemon
+                .daily_budget_usd
+                .is_some_and(|budget| spent >= budget);
+            if queue.pending_count() == 0 {
+                Step::Idle
+            } else if over_budget {
+                Step::OverBudget {
+                    spent,
+                    pending: queue.pending_count(),
+                }
+            } else {
+                match queue.take_next() {
+                    Some((id, project, prompt, dir)) => Step::Run(id, project, prompt, dir),
+                    None => Step::Idle,
+                }
+            }
+        })?;
+
+        match step {
+            Step::OverBudget { spent, pending } => {
+                if budget_notice != Some(now.date()) {
+                    println!(
+                        "[daemon] Daily budget reached (${:.2}); {} tasks wait for tomorrow",
+                        spent, pending
+                    );
+                    budget_notice = Some(now.date());
+                }
+                std::thread::sleep(interval);
+            }
+            Step::Run(id, project, prompt, dir) => {
+                println!("[daemon] Running task #{} for '{}'", id, project);
+                let result = run_queued_task(&project, &prompt, &dir);
+                if let Err(e) = &result {
+                    eprintln!("[daemon] Task #{} failed: {:#}", id, e);
+                }
+                Queue::update(|queue| {
+                    if let Some(task) = queue.tasks.iter_mut().find(|t| t.id == id) {
+                        task.finished_at = Some(Utc::now());
+                        match result {
+                            Ok(cost) => {
+                                task.status = QueueStatus::Done;
+                                task.cost_usd = cost;
+                            }
+                            Err(e) => {
+                                task.status = QueueStatus::Failed;
+                                task.error = Some(format!("{:#}", e));
+                            }
+                        }
+                    }
+                })?;
+            }
+            Step::Idle => std::thread::sleep(interval),
+        }
+    }
+}
+
+/// Runs one queued task in its directory and returns its cost
+fn run_queued_task(project_name: &str, prompt: &str, dir: &Path) -> Result<Option<f64>> {
+    // Sessions use the current directory as their working dir
+    std::env::set_current_dir(dir).with_context(|| format!("Failed to enter {}", dir.display()))?;
+    let mut project = Project::open(project_name)?;
+    project.record_session_start()?;
+    let mut session = Session::new(project)?;
+    session.recover_interrupted_tasks()?;
+    session.run_task(prompt)?;
+    Ok(session.last_task_cost())
+}
+
+/// Body of `POST /enqueue`
+#[derive(Debug, Deserialize)]
+struct EnqueueRequest {
+    project: String,
+    prompt: String,
+    /// Directory to run in; defaults to where the daemon started
+    #[serde(default)]
+    dir: Option<PathBuf>,
+}
+
+/// What the HTTP API needs to check and route requests
+struct Api {
+    /// Bearer token every request must carry
+    token: String,
+    /// Directory tasks run in when the request names none
+    default_dir: PathBuf,
+    /// Directories a request's `dir` must resolve inside: where the daemon
+    /// started, schedule entry dirs, and `daemon.roots`
+    roots: Vec<PathBuf>,
+}
+
+impl Api {
+    /// Resolves a requested task directory, refusing one outside every root
+    fn task_dir(&self, dir: Option<PathBuf>) -> Result<PathBuf> {
+        let dir = dir.unwrap_or_else(|| self.default_dir.clone());
+        let resolved = dir
+            .canonicalize()
+            .with_context(|| format!("No such directory: {}", dir.display()))?;
+        let inside = self
+            .roots
+            .iter()
+            .filter_map(|root| root.canonicalize().ok())
+            .any(|root| resolved.starts_with(root));
+        if !inside {
+            bail!(
+                "{} is outside the daemon's project roots (add it to daemon.roots)",
+                dir.display()
+            );
+        }
+        Ok(resolved)
+    }
+}
+
+/// An HTTP API request
+struct Request {
+    method: String,
+    path: String,
+    /// Header names lowercased
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Answers HTTP API requests one connection at a time; a client that stalls
+/// is dropped after `CLIENT_TIMEOUT`
+fn serve(listener: TcpListener, api: &Api) {
+    for stream in listener.incoming() {
+        let Ok(mut stream) = stream else {
+            continue;
+        };
+        if stream.set_read_timeout(Some(CLIENT_TIMEOUT)).is_err()
+            || stream.set_write_timeout(Some(CLIENT_TIMEOUT)).is_err()
+        {
+            continue;
+        }
+        let (status, body) = match read_request(&mut stream) {
+            Ok(request) => handle_request(&request, api),
+            Err(e) => (400, error_json(&e)),
+        };
+        let reason = match status {
+            200 => "OK",
+            201 => "Created",
+            401 => "Unauthorized",
+            403 => "Forbidden",
+            404 => "Not Found",
+            415 => "Unsupported Media Type",
+            _ => "Bad Request",
+        };
+        let _ = write!(
+            stream,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            reason,
+            body.len(),
+            body
+        );
+    }
+}
+
+/// Reads a request's method, path, headers, and body
+fn read_request(stream: &mut impl Read) -> Result<Request> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        bail!("Malformed request line");
+    };
+    let (method, path) = (method.to_string(), path.to_string());
+
+    let mut headers = Vec::new();
+    let mut length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            let (name, value) = (name.trim().to_ascii_lowercase(), value.trim());
+            if name == "content-length" {
+                length = value.parse().context("Invalid Content-Length")?;
+            }
+            headers.push((name, value.to_string()));
+        }
+    }
+    if length > MAX_REQUEST_BYTES {
+        bail!("Request body over {} bytes", MAX_REQUEST_BYTES);
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok(Request {
+        method,
+        path,
+        headers,
+        body: String::from_utf8(body)?,
+    })
+}
+
+/// Whether the request's bearer token matches, compared in constant time
+fn authorized(request: &Request, token: &str) -> bool {
+    let Some(given) = request
+        .header("authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Whether the Content-Type is JSON; a missing one is only allowed without
+/// a body to describe
+fn is_json(request: &Request) -> bool {
+    match request.header("content-type") {
+        Some(value) => value
+            .split(';')
+            .next()
+            .is_some_and(|media| media.trim().eq_ignore_ascii_case("application/json")),
+        None => request.body.is_empty(),
+    }
+}
+
+/// Checks and routes a request: `POST /enqueue` queues a task, `GET /queue`
+/// lists the queue; returns the status code and JSON body
+fn handle_request(request: &Request, api: &Api) -> (u16, String) {
+    if request.header("origin").is_some() {
+        return (
+            403,
+            error_json(&anyhow::anyhow!("Browser requests are not accepted")),
+        );
+    }
+    if !authorized(request, &api.token) {
+        return (
+            401,
+            error_json(&anyhow::anyhow!(
+                "Send Authorization: Bearer <daemon.api_token>"
+            )),
+        );
+    }
+    if !is_json(request) {
+        return (
+            415,
+            error_json(&anyhow::anyhow!("Send Content-Type: application/json")),
+        );
+    }
+    match (request.method.as_str(), request.path.as_str()) {
+        ("POST", "/enqueue") => {
+            let parsed = serde_json::from_str::<EnqueueRequest>(&request.body)
+                .context("Expected {\"project\": ..., \"prompt\": ...}");
+            let enqueue = match parsed {
+                Ok(enqueue) => enqueue,
+                Err(e) => return (400, error_json(&e)),
+            };
+            let dir = match api.task_dir(enqueue.dir) {
+                Ok(dir) => dir,
+                Err(e) => return (403, error_json(&e)),
+            };
+            match enqueue_task(&enqueue.project, &enqueue.prompt, dir) {
+                Ok((id, pending)) => (
+                    201,
+                    serde_json::json!({ "id": id, "pending": pending }).to_string(),
+                ),
+                Err(e) => (400, error_json(&e)),
+            }
+        }
+        ("GET", "/queue") => match Queue::load().and_then(|q| Ok(serde_json::to_string(&q)?)) {
+            Ok(json) => (200, json),
+            Err(e) => (400, error_json(&e)),
+        },
+        _ => (
+            404,
+            error_json(&anyhow::anyhow!("Use POST /enqueue or GET /queue")),
+        ),
+    }
+}
+
+fn error_json(e: &anyhow::Error) -> String {
+    serde_json::json!({ "error": format!("{:#}", e) }).to_string()
+}
+
+/// Follows a project rename in the queue and in `[[daemon.schedule]]`
+/// entries in config.toml (edited in place, so its comments are kept);
+/// returns how many schedule entries changed
+pub fn rename_project(old: &str, new: &str) -> Result<usize> {
+    if Queue::path()?.exists() {
+        Queue::update(|queue| queue.rename_project(old, new))?;
+    }
+    let path = config::config_file()?;
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        return Ok(0);
+    };
+    let (updated, count) = rename_in_schedule(&content, old, new)?;
+    if count > 0 {
+        std::fs::write(&path, updated)
+            .with_context(|| format!("Failed to write config file: {:?}", path))?;
+    }
+    Ok(count)
+}
+
+/// Renames the project in matching `[[daemon.schedule]]` entries of a
+/// config file; returns the new text and how many entries changed
+fn rename_in_schedule(content: &str, old: &str, new: &str) -> Result<(String, usize)> {
+    let mut doc: toml_edit::DocumentMut = content.parse().context("Failed to parse config file")?;
+    let mut count = 0;
+    if let Some(schedule) = doc
+        .get_mut("daemon")
+        .and_then(|d| d.get_mut("schedule"))
+        .and_then(|s| s.as_array_of_tables_mut())
+    {
+        for entry in schedule.iter_mut() {
+            if entry.get("project").and_then(|p| p.as_str()) == Some(old) {
+                config::set_toml_string(entry, "project", new);
+                count += 1;
+            }
+        }
+    }
+    Ok((doc.to_string(), count))
+}
+
+/// Identifies a schedule entry across restarts
+fn schedule_key(entry: &ScheduledTask) -> String {
+    format!("{}@{}: {}", entry.project, entry.at, entry.prompt)
+}
+
+/// Indices of schedule entries whose time has passed today and that have
+/// not been enqueued today yet
+fn due_schedules(
+    schedule: &[ScheduledTask],
+    last_scheduled: &BTreeMap<String, NaiveDate>,
+    now: NaiveDateTime,
+) -> Vec<usize> {
+    schedule
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| {
+            let Ok(at) = NaiveTime::parse_from_str(&entry.at, "%H:%M") else {
+                eprintln!("Warning: invalid schedule time '{}' (use HH:MM)", entry.at);
+                return false;
+            };
+            now.time() >= at
+                && last_scheduled
+                    .get(&schedule_key(entry))
+                    .is_none_or(|date| *date < now.date())
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(at: &str) -> ScheduledTask {
+        ScheduledTask {
+            project: "api".to_string(),
+            prompt: "update dependency audit notes".to_string(),
+            at: at.to_string(),
+            dir: None,
+        }
+    }
+
+    fn at(date: &str, time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_due_schedules() {
+        let schedule = vec![entry("02:00"), entry("23:30"), entry("bogus")];
+        let mut last = BTreeMap::new();
+        assert_eq!(
+            due_schedules(&schedule, &last, at("2025-01-15", "03:00")),
+            vec![0]
+        );
+
+        last.insert(
+            schedule_key(&schedule[0]),
+            NaiveDate::from_ymd_opt(2025, 1, 15).unwrap(),
+        );
+        assert!(due_schedules(&schedule, &last, at("2025-01-15", "04:00")).is_empty());
+        assert_eq!(
+            due_schedules(&schedule, &last, at("2025-01-16", "02:00")),
+            vec![0]
+        );
+    }
+
+    #[test]
+    fn test_queue_push_and_spend() {
+        let mut queue = Queue::default();
+        assert_eq!(queue.push("api", "a", PathBuf::from("/tmp")), 1);
+        assert_eq!(queue.push("api", "b", PathBuf::from("/tmp")), 2);
+        assert_eq!(queue.pending_count(), 2);
+
+        let today = Local::now().date_naive();
+        queue.tasks[0].status = QueueStatus::Done;
+        queue.tasks[0].finished_at = Some(Utc::now());
+        queue.tasks[0].cost_usd = Some(0.25);
+        assert_eq!(queue.pending_count(), 1);
+        assert_eq!(queue.spent_on(today), 0.25);
+        assert_eq!(queue.spent_on(today.pred_opt().unwrap()), 0.0);
+    }
+
+    #[test]
+    fn test_take_next_marks_running() {
+        let mut queue = Queue::default();
+        queue.push("api", "a", PathBuf::from("/tmp"));
+        queue.push("api", "b", PathBuf::from("/tmp"));
+
+        let (id, _, prompt, _) = queue.take_next().unwrap();
+        assert_eq!((id, prompt.as_str()), (1, "a"));
+        assert_eq!(queue.tasks[0].status, QueueStatus::Running);
+        assert_eq!(queue.pending_count(), 1);
+        assert_eq!(queue.take_next().unwrap().0, 2);
+        assert!(queue.take_next().is_none());
+    }
+
+    #[test]
+    fn test_fail_abandoned() {
+        let mut queue = Queue::default();
+        queue.push("api", "a", PathBuf::from("/tmp"));
+        queue.push("api", "b", PathBuf::from("/tmp"));
+        queue.take_next();
+
+        assert_eq!(queue.fail_abandoned(), 1);
+        assert_eq!(queue.tasks[0].status, QueueStatus::Failed);
+        assert_eq!(queue.tasks[1].status, QueueStatus::Pending);
+    }
+
+    #[test]
+    fn test_queue_rename_project() {
+        let mut queue = Queue::default();
+        queue.push("api", "a", PathBuf::from("/tmp"));
+        queue.push("web", "b", PathBuf::from("/tmp"));
+        let today = NaiveDate::from_ymd_opt(2025, 1, 15).unwrap();
+        queue
+            .last_scheduled
+            .insert(schedule_key(&entry("02:00")), today);
+
+        queue.rename_project("api", "backend");
+        assert_eq!(queue.tasks[0].project, "backend");
+        assert_eq!(queue.tasks[1].project, "web");
+        let moved = ScheduledTask {
+            project: "backend".to_string(),
+            ..entry("02:00")
+        };
+        assert_eq!(
+            queue.last_scheduled.get(&schedule_key(&moved)),
+            Some(&today)
+        );
+        assert_eq!(queue.last_scheduled.len(), 1);
+    }
+
+    #[test]
+    fn test_rename_in_schedule_keeps_comments() {
+        let content = "[daemon]\n# nightly jobs\n[[daemon.schedule]]\nproject = \"api\"  # main\nprompt = \"audit\"\nat = \"02:00\"\n\n[[daemon.schedule]]\nproject = \"web\"\nprompt = \"x\"\nat = \"03:00\"\n";
+        let (updated, count) = rename_in_schedule(content, "api", "backend").unwrap();
+        assert_eq!(count, 1);
+        assert!(updated.contains("# nightly jobs"));
+        assert!(updated.contains("project = \"backend\"  # main"));
+        assert!(updated.contains("project = \"web\""));
+    }
+
+    fn api(root: &Path) -> Api {
+        Api {
+            token: "s3cret".to_string(),
+            default_dir: root.to_path_buf(),
+            roots: vec![root.to_path_buf()],
+        }
+    }
+
+    /// An authorized JSON request
+    fn request(method: &str, path: &str, body: &str) -> Request {
+        Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            headers: vec![
+                ("authorization".to_string(), "Bearer s3cret".to_string()),
+                ("content-type".to_string(), "application/json".to_string()),
+            ],
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_read_request() {
+        let raw = "POST /enqueue HTTP/1.1\r\nHost: x\r\nContent-Length: 7\r\n\r\n{\"a\":1}";
+        let request = read_request(&mut raw.as_bytes()).unwrap();
+        assert_eq!(
+            (
+                request.method.as_str(),
+                request.path.as_str(),
+                request.body.as_str()
+            ),
+            ("POST", "/enqueue", "{\"a\":1}")
+        );
+        assert_eq!(request.header("host"), Some("x"));
+    }
+
+    #[test]
+    fn test_handle_request_rejects_unknown_route_and_bad_body() {
+        let api = api(Path::new("/tmp"));
+        assert_eq!(handle_request(&request("GET", "/nope", ""), &api).0, 404);
+        let (status, body) = handle_request(&request("POST", "/enqueue", "{}"), &api);
+        assert_eq!(status, 400);
+        assert!(body.contains("error"));
+    }
+
+    #[test]
+    fn test_handle_request_requires_token() {
+        let api = api(Path::new("/tmp"));
+        let mut missing = request("GET", "/queue", "");
+        missing.headers.retain(|(name, _)| name != "authorization");
+        assert_eq!(handle_request(&missing, &api).0, 401);
+
+        let mut wrong = request("GET", "/queue", "");
+        wrong.headers[0].1 = "Bearer s3cres".to_string();
+        assert_eq!(handle_request(&wrong, &api).0, 401);
+    }
+
+    #[test]
+    fn test_handle_request_rejects_browser_requests() {
+        let api = api(Path::new("/tmp"));
+        let mut cross_origin = request("POST", "/enqueue", "{}");
+        cross_origin
+            .headers
+            .push(("origin".to_string(), "https://example.com".to_string()));
+        assert_eq!(handle_request(&cross_origin, &api).0, 403);
+
+        let mut plain = request("POST", "/enqueue", "{}");
+        plain.headers[1].1 = "text/plain".to_string();
+        assert_eq!(handle_request(&plain, &api).0, 415);
+        plain.headers.remove(1);
+        assert_eq!(handle_request(&plain, &api).0, 415);
+    }
+
+    #[test]
+    fn test_handle_request_refuses_dir_outside_roots() {
+        let root = tempfile::tempdir().unwrap();
+        let other = tempfile::tempdir().unwrap();
+        let body = serde_json::json!({
+            "project": "api",
+            "prompt": "x",
+            "dir": other.path(),
+        })
+        .to_string();
+        let (status, body) = handle_request(&request("POST", "/enqueue", &body), &api(root.path()));
+        assert_eq!(status, 403);
+        assert!(body.contains("outside"));
+    }
+
+    #[test]
+    fn test_task_dir_resolves_inside_roots() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir(root.path().join("api")).unwrap();
+        let api = api(root.path());
+        assert_eq!(
+            api.task_dir(Some(root.path().join("api/../api"))).unwrap(),
+            root.path().canonicalize().unwrap().join("api")
+        );
+        assert!(api.task_dir(Some(root.path().join(".."))).is_err());
+        assert!(api.task_dir(None).is_ok());
+    }
+}
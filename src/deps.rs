@@ -0,0 +1,417 @@
+//! Dependency changes made by a task
+//!
+//! Before and after each task, the manifests and lockfiles in the task's
+//! directory (Cargo.toml, Cargo.lock, package.json, package-lock.json) are
+//! read into name → version maps. The difference is recorded in the task log,
+//! and changes the prompt didn't ask for are flagged in the completion
+//! summary. Turn it off with `[policy] track_dependencies = false`.
+
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// Files snapshotted, manifests before their lockfiles
+const DEPENDENCY_FILES: &[&str] = &[
+    "Cargo.toml",
+    "Cargo.lock",
+    "package.json",
+    "package-lock.json",
+];
+
+/// Lockfiles, whose changes are counted rather than listed in summaries
+const LOCKFILES: &[&str] = &["Cargo.lock", "package-lock.json"];
+
+/// Manifest sections holding dependencies
+const CARGO_SECTIONS: &[&str] = &["dependencies", "dev-dependencies", "build-dependencies"];
+const NPM_SECTIONS: &[&str] = &[
+    "dependencies",
+    "devDependencies",
+    "peerDependencies",
+    "optionalDependencies",
+];
+
+/// Prompt words that make dependency changes expected (matched lowercase,
+/// as whole words)
+const DEPENDENCY_WORDS: &[&str] = &[
+    "depend",
+    "depends",
+    "dependency",
+    "dependencies",
+    "deps",
+    "upgrade",
+    "upgrades",
+    "upgraded",
+    "upgrading",
+    "downgrade",
+    "downgraded",
+    "bump",
+    "bumped",
+    "install",
+    "installed",
+    "installing",
+    "lockfile",
+    "cargo add",
+    "cargo update",
+    "npm i",
+    "npm install",
+];
+
+/// Changes listed per manifest before the rest are counted
+const SUMMARY_CHANGES: usize = 5;
+
+/// Dependencies per file: file name → package name → version
+pub type Snapshot = BTreeMap<String, BTreeMap<String, String>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Upgraded,
+    Downgraded,
+    /// A version that doesn't compare numerically, e.g. a git source
+    Changed,
+}
+
+/// One dependency added, removed, or moved to another version
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DependencyChange {
+    pub file: String,
+    pub name: String,
+    pub kind: ChangeKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+}
+
+impl DependencyChange {
+    /// `+ tokio 1.40`, `- rand 0.8`, or `serde 1.0.1 → 1.0.2`
+    pub fn describe(&self) -> String {
+        let from = self.from.as_deref().unwrap_or("");
+        let to = self.to.as_deref().unwrap_or("");
+        match self.kind {
+            ChangeKind::Added => format!("+ {} {}", self.name, to),
+            ChangeKind::Removed => format!("- {} {}", self.name, from),
+            _ => format!("{} {} → {}", self.name, from, to),
+        }
+    }
+}
+
+/// Reads the dependency files present in `dir`; unreadable or unparsable
+/// files are left out
+pub fn snapshot(dir: &Path) -> Snapshot {
+    DEPENDENCY_FILES
+        .iter()
+        .filter_map(|file| {
+            let content = std::fs::read_to_string(dir.join(file)).ok()?;
+            let deps = match *file {
+                "Cargo.toml" => cargo_manifest(&content),
+                "Cargo.lock" => cargo_lock(&content),
+                "package.json" => package_json(&content),
+                _ => package_lock(&content),
+            }?;
+            Some((file.to_string(), deps))
+        })
+        .collect()
+}
+
+/// Changes between two snapshots, in file then name order
+///
+/// A lockfile that only appears afterwards (first build) isn't a change; a
+/// new manifest's dependencies are.
+pub fn diff(before: &Snapshot, after: &Snapshot) -> Vec<DependencyChange> {
+    let empty = BTreeMap::new();
+    let mut changes = Vec::new();
+    for file in DEPENDENCY_FILES {
+        let (old, new) = match (before.get(*file), after.get(*file)) {
+            (None, None) => continue,
+            (None, Some(_)) | (Some(_), None) if LOCKFILES.contains(file) => continue,
+            (old, new) => (old.unwrap_or(&empty), new.unwrap_or(&empty)),
+        };
+        let names: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+        for name in names {
+            let (from, to) = (old.get(name), new.get(name));
+            let kind = match (from, to) {
+                (None, Some(_)) => ChangeKind::Added,
+                (Some(_), None) => ChangeKind::Removed,
+                (Some(a), Some(b)) if a != b => match compare_versions(a, b) {
+                    Some(Ordering::Less) => ChangeKind::Upgraded,
+                    Some(Ordering::Greater) => ChangeKind::Downgraded,
+                    _ => ChangeKind::Changed,
+                },
+                _ => continue,
+            };
+            changes.push(DependencyChange {
+                file: file.to_string(),
+                name: name.clone(),
+                kind,
+                from: from.cloned(),
+                to: to.cloned(),
+            });
+        }
+    }
+    changes
+}
+
+/// Whether the prompt asked for dependency work, or names a changed package
+pub fn expected(prompt: &str, changes: &[DependencyChange]) -> bool {
+    let prompt = prompt.to_lowercase();
+    DEPENDENCY_WORDS
+        .iter()
+        .any(|word| contains_word(&prompt, word))
+        || changes
+            .iter()
+            .any(|change| contains_word(&prompt, &change.name.to_lowercase()))
+}
+
+/// Whether `word` appears in `text` with no letter, digit, or `_` directly
+/// before or after it, so `log` doesn't match "login"
+fn contains_word(text: &str, word: &str) -> bool {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    !word.is_empty()
+        && text.match_indices(word).any(|(start, _)| {
+            let before = text[..start].chars().next_back();
+            let after = text[start + word.len()..].chars().next();
+            !before.is_some_and(is_word_char) && !after.is_some_and(is_word_char)
+        })
+}
+
+/// One line per changed file: manifest changes listed, lockfile changes
+/// counted
+pub fn summary_lines(changes: &[DependencyChange]) -> Vec<String> {
+    let mut by_file: BTreeMap<&str, Vec<&DependencyChange>> = BTreeMap::new();
+    for change in changes {
+        by_file.entry(&change.file).or_default().push(change);
+    }
+    by_file
+        .into_iter()
+        .map(|(file, changes)| {
+            if LOCKFILES.contains(&file) {
+                return format!("{}: {} packages changed", file, changes.len());
+            }
+            let mut listed: Vec<String> = changes
+                .iter()
+                .take(SUMMARY_CHANGES)
+                .map(|c| c.describe())
+                .collect();
+            if changes.len() > SUMMARY_CHANGES {
+                listed.push(format!("{} more", changes.len() - SUMMARY_CHANGES));
+            }
+            format!("{}: {}", file, listed.join(", "))
+        })
+        .collect()
+}
+
+/// Orders versions by their numeric parts, ignoring a requirement prefix
+/// like `^` or `>=`; None when either has no leading number
+fn compare_versions(a: &str, b: &str) -> Option<Ordering> {
+    let parts = |version: &str| -> Option<Vec<u64>> {
+        let version = version.trim_start_matches(|c: char| !c.is_ascii_digit());
+        let numbers: Vec<u64> = version
+            .split(['.', '-', '+'])
+            .map_while(|part| part.parse().ok())
+            .collect();
+        (!numbers.is_empty()).then_some(numbers)
+    };
+    Some(parts(a)?.cmp(&parts(b)?))
+}
+
+/// Direct dependencies of a Cargo.toml, including `[workspace.dependencies]`
+fn cargo_manifest(content: &str) -> Option<BTreeMap<String, String>> {
+    let table: toml::Table = toml::from_str(content).ok()?;
+    let workspace = table.get("workspace").and_then(|w| w.as_table());
+    let mut deps = BTreeMap::new();
+    let sections = CARGO_SECTIONS
+        .iter()
+        .filter_map(|section| table.get(*section))
+        .chain(workspace.and_then(|w| w.get("dependencies")));
+    for section in sections.filter_map(|s| s.as_table()) {
+        for (name, spec) in section {
+            let version = match spec {
+                toml::Value::String(version) => version.clone(),
+                toml::Value::Table(spec) => ["version", "path", "git"]
+                    .iter()
+                    .find_map(|key| {
+                        let value = spec.get(*key)?.as_str()?;
+                        Some(if *key == "version" {
+                            value.to_string()
+                        } else {
+                            format!("{}:{}", key, value)
+                        })
+                    })
+                    .unwrap_or_else(|| "*".to_string()),
+                _ => continue,
+            };
+            deps.insert(name.clone(), version);
+        }
+    }
+    Some(deps)
+}
+
+/// Resolved packages in a Cargo.lock; several versions of one crate are
+/// joined
+fn cargo_lock(content: &str) -> Option<BTreeMap<String, String>> {
+    let table: toml::Table = toml::from_str(content).ok()?;
+    let mut versions: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for package in table.get("package")?.as_array()? {
+        let name = package.get("name").and_then(|v| v.as_str());
+        let version = package.get("version").and_then(|v| v.as_str());
+        if let (Some(name), Some(version)) = (name, version) {
+            versions
+                .entry(name.to_string())
+                .or_default()
+                .push(version.to_string());
+        }
+    }
+    Some(
+        versions
+            .into_iter()
+            .map(|(name, mut versions)| {
+                versions.sort();
+                (name, versions.join(", "))
+            })
+            .collect(),
+    )
+}
+
+/// Declared dependencies of a package.json
+fn package_json(content: &str) -> Option<BTreeMap<String, String>> {
+    let json: serde_json::Value = serde_json::from_str(content).ok()?;
+    let mut deps = BTreeMap::new();
+    for section in NPM_SECTIONS {
+        let Some(entries) = json.get(*section).and_then(|s| s.as_object()) else {
+            continue;
+        };
+        for (name, version) in entries {
+            deps.insert(name.clone(), version.as_str().unwrap_or("*").to_string());
+        }
+    }
+    Some(deps)
+}
+
+/// Top-level installed packages in a package-lock.json (v2+ `packages`, or
+/// v1 `dependencies`)
+fn package_lock(content: &str) -> Option<BTreeMap<String, String>> {
+    let json: serde_json::Value = serde_json::from_str(content).ok()?;
+    let version = |entry: &serde_json::Value| entry.get("version")?.as_str().map(String::from);
+    if let Some(packages) = json.get("packages").and_then(|p| p.as_object()) {
+        return Some(
+            packages
+                .iter()
+                .filter_map(|(path, entry)| {
+                    let name = path.strip_prefix("node_modules/")?;
+                    // Nested copies belong to other packages
+                    (!name.contains("/node_modules/")).then_some(())?;
+                    Some((name.to_string(), version(entry)?))
+                })
+                .collect(),
+        );
+    }
+    let dependencies = json.get("dependencies")?.as_object()?;
+    Some(
+        dependencies
+            .iter()
+            .filter_map(|(name, entry)| Some((name.clone(), version(entry)?)))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_cargo_snapshots() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"app\"\n\n[dependencies]\nserde = \"1.0.1\"\nrand = { version = \"0.8\" }\nold = \"0.3\"\n",
+        )
+        .unwrap();
+        let before = snapshot(dir.path());
+
+        std::fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"app\"\n\n[dependencies]\nserde = \"1.0.2\"\nrand = { version = \"0.7\" }\nlocal = { path = \"../local\" }\n",
+        )
+        .unwrap();
+        // A lockfile that first appears now isn't reported
+        std::fs::write(
+            dir.path().join("Cargo.lock"),
+            "[[package]]\nname = \"serde\"\nversion = \"1.0.2\"\n",
+        )
+        .unwrap();
+        let changes = diff(&before, &snapshot(dir.path()));
+        let described: Vec<String> = changes.iter().map(|c| c.describe()).collect();
+        assert_eq!(
+            described,
+            [
+                "+ local path:../local",
+                "- old 0.3",
+                "rand 0.8 → 0.7",
+                "serde 1.0.1 → 1.0.2"
+            ]
+        );
+        assert_eq!(changes[2].kind, ChangeKind::Downgraded);
+        assert_eq!(changes[3].kind, ChangeKind::Upgraded);
+        assert_eq!(
+            summary_lines(&changes),
+            ["Cargo.toml: + local path:../local, - old 0.3, rand 0.8 → 0.7, serde 1.0.1 → 1.0.2"]
+        );
+    }
+
+    #[test]
+    fn test_npm_files() {
+        let deps =
+            package_json(r#"{"dependencies":{"react":"^18.2.0"},"devDependencies":{"jest":"29"}}"#)
+                .unwrap();
+        assert_eq!(deps["react"], "^18.2.0");
+        assert_eq!(deps["jest"], "29");
+
+        let lock = package_lock(
+            r#"{"packages":{"":{"name":"app"},"node_modules/react":{"version":"18.2.0"},"node_modules/a/node_modules/b":{"version":"1.0.0"}}}"#,
+        )
+        .unwrap();
+        assert_eq!(lock.len(), 1);
+        assert_eq!(lock["react"], "18.2.0");
+    }
+
+    #[test]
+    fn test_expected_matches_whole_words() {
+        let change = |name: &str| DependencyChange {
+            file: "Cargo.toml".to_string(),
+            name: name.to_string(),
+            kind: ChangeKind::Added,
+            from: None,
+            to: Some("1".to_string()),
+        };
+        assert!(!expected("fix the login page", &[change("log")]));
+        assert!(expected("add log output", &[change("log")]));
+        assert!(!expected("seed the random tests", &[change("rand")]));
+        assert!(expected("switch to serde_json", &[change("serde_json")]));
+        assert!(expected("use @types/node types", &[change("@types/node")]));
+    }
+
+    #[test]
+    fn test_expected_and_compare() {
+        let change = DependencyChange {
+            file: "Cargo.toml".to_string(),
+            name: "tokio".to_string(),
+            kind: ChangeKind::Added,
+            from: None,
+            to: Some("1".to_string()),
+        };
+        let changes = [change];
+        assert!(expected("Bump the MSRV", &changes));
+        assert!(expected("use Tokio for the server", &changes));
+        assert!(!expected("fix the login bug", &changes));
+        assert!(expected("Update dependencies", &changes));
+        assert!(expected("npm i lodash", &changes));
+        assert!(!expected("run npm init", &changes));
+
+        assert_eq!(compare_versions("^1.2.3", "1.10.0"), Some(Ordering::Less));
+        assert_eq!(compare_versions("git:https://x", "1.0"), None);
+    }
+}
@@ -0,0 +1,180 @@
+//! File diffs reconstructed from transcript tool calls
+//!
+//! Edit, MultiEdit, and Write tool inputs carry the exact text that was
+//! replaced or written, which is often a more truthful record of a task than
+//! the assistant's prose summary. Calls whose tool result was an error (e.g.
+//! `old_string` not found) are skipped since they never touched the file.
+
+use std::collections::HashSet;
+
+use crate::transcript::{Message, Transcript};
+
+/// One change made to a file by a single tool call
+#[derive(Debug, PartialEq)]
+pub enum Hunk {
+    /// `old` was replaced with `new`
+    Replace { old: String, new: String },
+    /// The whole file was written with `content`
+    Write { content: String },
+}
+
+/// All changes made to one file during a task, in order
+#[derive(Debug, PartialEq)]
+pub struct FileDiff {
+    pub path: String,
+    pub hunks: Vec<Hunk>,
+}
+
+impl FileDiff {
+    /// Counts added and removed lines across all hunks
+    pub fn line_counts(&self) -> (usize, usize) {
+        self.hunks
+            .iter()
+            .fold((0, 0), |(added, removed), hunk| match hunk {
+                Hunk::Replace { old, new } => {
+                    (added + new.lines().count(), removed + old.lines().count())
+                }
+                Hunk::Write { content } => (added + content.lines().count(), removed),
+            })
+    }
+
+    /// Renders the changes in unified-diff style
+    pub fn to_unified(&self) -> String {
+        let mut out = format!("--- {}\n+++ {}\n", self.path, self.path);
+        for hunk in &self.hunks {
+            match hunk {
+                Hunk::Replace { old, new } => {
+                    out.push_str("@@ edit @@\n");
+                    for line in old.lines() {
+                        out.push_str(&format!("-{}\n", line));
+                    }
+                    for line in new.lines() {
+                        out.push_str(&format!("+{}\n", line));
+                    }
+                }
+                Hunk::Write { content } => {
+                    out.push_str("@@ write @@\n");
+                    for line in content.lines() {
+                        out.push_str(&format!("+{}\n", line));
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Reconstructs per-file diffs from a transcript's Edit/MultiEdit/Write calls
+///
+/// Files are returned in the order they were first touched.
+pub fn file_diffs(transcript: &Transcript) -> Vec<FileDiff> {
+    let failed: HashSet<&str> = transcript
+        .messages
+        .iter()
+        .filter_map(|msg| match msg {
+            Message::ToolResult {
+                tool_id,
+                is_error: true,
+                ..
+            } => Some(tool_id.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    let mut diffs: Vec<FileDiff> = Vec::new();
+    for msg in &transcript.messages {
+        let Message::ToolUse {
+            tool_name,
+            tool_id,
+            input,
+        } = msg
+        else {
+            continue;
+        };
+        if failed.contains(tool_id.as_str()) {
+            continue;
+        }
+        let Some(path) = input.get("file_path").and_then(|p| p.as_str()) else {
+            continue;
+        };
+
+        let hunks = match tool_name.as_str() {
+            "Edit" => replace_hunk(input).into_iter().collect(),
+            "MultiEdit" => input
+                .get("edits")
+                .and_then(|e| e.as_array())
+                .map(|edits| edits.iter().filter_map(replace_hunk).collect())
+                .unwrap_or_default(),
+            "Write" => input
+                .get("content")
+                .and_then(|c| c.as_str())
+                .map(|content| {
+                    vec![Hunk::Write {
+                        content: content.to_string(),
+                    }]
+                })
+                .unwrap_or_default(),
+            _ => continue,
+        };
+
+        match diffs.iter_mut().find(|d| d.path == path) {
+            Some(diff) => diff.hunks.extend(hunks),
+            None => diffs.push(FileDiff {
+                path: path.to_string(),
+                hunks,
+            }),
+        }
+    }
+
+    diffs
+}
+
+fn replace_hunk(input: &serde_json::Value) -> Option<Hunk> {
+    Some(Hunk::Replace {
+        old: input.get("old_string")?.as_str()?.to_string(),
+        new: input.get("new_string")?.as_str()?.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_diffs_groups_edits_by_file() {
+        let output = r#"{"type":"assistant","message":{"content":[{"type":"tool_use","name":"Edit","id":"t1","input":{"file_path":"src/a.rs","old_string":"let x = 1;","new_string":"let x = 2;"}}]}}
+{"type":"assistant","message":{"content":[{"type":"tool_use","name":"Write","id":"t2","input":{"file_path":"src/b.rs","content":"fn b() {}\n"}}]}}
+{"type":"assistant","message":{"content":[{"type":"tool_use","name":"MultiEdit","id":"t3","input":{"file_path":"src/a.rs","edits":[{"old_string":"a","new_string":"b"}]}}]}}"#;
+
+        let diffs = file_diffs(&Transcript::parse(output));
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[0].path, "src/a.rs");
+        assert_eq!(diffs[0].hunks.len(), 2);
+        assert_eq!(diffs[1].path, "src/b.rs");
+        assert_eq!(diffs[1].line_counts(), (1, 0));
+    }
+
+    #[test]
+    fn test_file_diffs_skips_failed_edits() {
+        let output = r#"{"type":"assistant","message":{"content":[{"type":"tool_use","name":"Edit","id":"t1","input":{"file_path":"src/a.rs","old_string":"missing","new_string":"x"}}]}}
+{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1","content":"String not found","is_error":true}]}}"#;
+
+        assert!(file_diffs(&Transcript::parse(output)).is_empty());
+    }
+
+    #[test]
+    fn test_to_unified() {
+        let diff = FileDiff {
+            path: "src/a.rs".to_string(),
+            hunks: vec![Hunk::Replace {
+                old: "let x = 1;".to_string(),
+                new: "let x = 2;\nlet y = 3;".to_string(),
+            }],
+        };
+        assert_eq!(
+            diff.to_unified(),
+            "--- src/a.rs\n+++ src/a.rs\n@@ edit @@\n-let x = 1;\n+let x = 2;\n+let y = 3;\n"
+        );
+        assert_eq!(diff.line_counts(), (2, 1));
+    }
+}
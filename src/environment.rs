@@ -0,0 +1,300 @@
+use std::path::Path;
+use std::process::Command;
+
+/// Toolchains probed at session start, with the args that print their version
+const TOOLCHAINS: &[(&str, &[&str])] = &[
+    ("rustc", &["--version"]),
+    ("cargo", &["--version"]),
+    ("node", &["--version"]),
+    ("npm", &["--version"]),
+    ("python3", &["--version"]),
+    ("go", &["version"]),
+];
+
+/// Environment variables worth telling the agent about when set
+const ENV_FACTS: &[&str] = &["CI", "VIRTUAL_ENV", "CONDA_DEFAULT_ENV", "NODE_ENV"];
+
+/// Snapshot of the host environment, detected once per session
+#[derive(Debug, Default)]
+pub struct Environment {
+    pub os: String,
+    pub toolchains: Vec<(String, String)>,
+    pub env_facts: Vec<(String, String)>,
+}
+
+impl Environment {
+    /// Detects the OS, installed toolchain versions, and a few env vars
+    pub fn detect() -> Self {
+        let os_name = std::fs::read_to_string("/etc/os-release")
+            .ok()
+            .and_then(|content| parse_os_release(&content));
+        let os = match os_name {
+            Some(name) => format!(
+                "{} ({} {})",
+                name,
+                std::env::consts::OS,
+                std::env::consts::ARCH
+            ),
+            None => format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+        };
+
+        let toolchains = TOOLCHAINS
+            .iter()
+            .filter_map(|(tool, args)| {
+                tool_version(tool, args).map(|version| (tool.to_string(), version))
+            })
+            .collect();
+
+        let env_facts = ENV_FACTS
+            .iter()
+            .filter_map(|var| {
+                let value = std::env::var(var).ok().filter(|v| !v.is_empty())?;
+                Some((var.to_string(), value))
+            })
+            .collect();
+
+        Self {
+            os,
+            toolchains,
+            env_facts,
+        }
+    }
+
+    /// Formats the snapshot as a context section
+    pub fn format(&self) -> String {
+        let mut out = String::from("## Environment\n\n");
+        out.push_str(&format!("- OS: {}\n", self.os));
+        for (tool, version) in &self.toolchains {
+            out.push_str(&format!("- {}: {}\n", tool, version));
+        }
+        for (var, value) in &self.env_facts {
+            out.push_str(&format!("- ${}={}\n", var, value));
+        }
+        out.push('\n');
+        out
+    }
+}
+
+/// Runs `tool args` and returns the first line of its output, if it ran
+fn tool_version(tool: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(tool).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    // Older Pythons print the version to stderr
+    let text = if output.stdout.is_empty() {
+        output.stderr
+    } else {
+        output.stdout
+    };
+    let line = String::from_utf8_lossy(&text)
+        .lines()
+        .next()?
+        .trim()
+        .to_string();
+    Some(strip_tool_name(tool, &line).to_string())
+}
+
+/// Drops a leading tool name from a version line ("rustc 1.80.0" -> "1.80.0")
+fn strip_tool_name<'a>(tool: &str, line: &'a str) -> &'a str {
+    // python3 reports itself as "Python", go as "go version"
+    let prefix = match tool {
+        "python3" => "python ".to_string(),
+        "go" => "go version ".to_string(),
+        _ => format!("{} ", tool),
+    };
+    match line.get(..prefix.len()) {
+        Some(head) if head.eq_ignore_ascii_case(&prefix) => line[prefix.len()..].trim(),
+        _ => line,
+    }
+}
+
+/// Extracts PRETTY_NAME from /etc/os-release
+fn parse_os_release(content: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let value = line.strip_prefix("PRETTY_NAME=")?;
+        let value = value.trim().trim_matches('"');
+        (!value.is_empty()).then(|| value.to_string())
+    })
+}
+
+/// Build and test commands detected from a project manifest
+#[derive(Debug, PartialEq)]
+pub struct BuildCommands {
+    /// Manifest the commands were derived from, e.g. "Cargo.toml"
+    pub manifest: &'static str,
+    pub build: Option<String>,
+    pub test: Option<String>,
+}
+
+/// Detects build/test commands from the manifests present in `dir`
+pub fn detect_build_commands(dir: &Path) -> Vec<BuildCommands> {
+    let mut detected = Vec::new();
+    if dir.join("Cargo.toml").is_file() {
+        detected.push(BuildCommands {
+            manifest: "Cargo.toml",
+            build: Some("cargo build".to_string()),
+            test: Some("cargo test".to_string()),
+        });
+    }
+    if let Ok(content) = std::fs::read_to_string(dir.join("package.json")) {
+        let runner = if dir.join("pnpm-lock.yaml").is_file() {
+            "pnpm"
+        } else if dir.join("yarn.lock").is_file() {
+            "yarn"
+        } else {
+            "npm"
+        };
+        detected.push(package_json_commands(&content, runner));
+    }
+    if let Ok(content) = std::fs::read_to_string(dir.join("Makefile")) {
+        detected.push(BuildCommands {
+            manifest: "Makefile",
+            build: Some("make".to_string()),
+            test: has_make_target(&content, "test").then(|| "make test".to_string()),
+        });
+    }
+    if dir.join("go.mod").is_file() {
+        detected.push(BuildCommands {
+            manifest: "go.mod",
+            build: Some("go build ./...".to_string()),
+            test: Some("go test ./...".to_string()),
+        });
+    }
+    if dir.join("pyproject.toml").is_file() {
+        detected.push(BuildCommands {
+            manifest: "pyproject.toml",
+            build: None,
+            test: Some("pytest".to_string()),
+        });
+    }
+    detected
+}
+
+/// The first detected test command, used as the default verification command
+pub fn detected_test_command(dir: &Path) -> Option<String> {
+    detect_build_commands(dir)
+        .into_iter()
+        .find_map(|commands| commands.test)
+}
+
+/// Formats detected commands as a "How to Build/Test" context section
+pub fn format_build_commands(detected: &[BuildCommands]) -> String {
+    if detected.is_empty() {
+        return String::new();
+    }
+    let mut out = String::from("## How to Build/Test\n\n");
+    for commands in detected {
+        let mut parts = Vec::new();
+        if let Some(ref build) = commands.build {
+            parts.push(format!("build `{}`", build));
+        }
+        if let Some(ref test) = commands.test {
+            parts.push(format!("test `{}`", test));
+        }
+        if !parts.is_empty() {
+            out.push_str(&format!("- {}: {}\n", commands.manifest, parts.join(", ")));
+        }
+    }
+    out.push('\n');
+    out
+}
+
+/// Uses the `build` and `test` scripts from a package.json
+fn package_json_commands(content: &str, runner: &str) -> BuildCommands {
+    let scripts = serde_json::from_str::<serde_json::Value>(content)
+        .ok()
+        .and_then(|json| json.get("scripts").cloned());
+    let has_script = |name: &str| scripts.as_ref().is_some_and(|s| s.get(name).is_some());
+    BuildCommands {
+        manifest: "package.json",
+        build: has_script("build").then(|| format!("{} run build", runner)),
+        test: has_script("test").then(|| format!("{} test", runner)),
+    }
+}
+
+/// Whether a Makefile defines `target:` at the start of a line
+fn has_make_target(content: &str, target: &str) -> bool {
+    content.lines().any(|line| {
+        line.strip_prefix(target)
+            .is_some_and(|rest| rest.trim_start().starts_with(':'))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_build_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(detect_build_commands(dir.path()).is_empty());
+
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]\n").unwrap();
+        std::fs::write(
+            dir.path().join("Makefile"),
+            "build:\n\tcargo build\ntest : build\n\tcargo test\n",
+        )
+        .unwrap();
+        let detected = detect_build_commands(dir.path());
+        assert_eq!(detected.len(), 2);
+        assert_eq!(detected[1].test.as_deref(), Some("make test"));
+        assert_eq!(
+            detected_test_command(dir.path()).as_deref(),
+            Some("cargo test")
+        );
+        assert_eq!(
+            format_build_commands(&detected),
+            "## How to Build/Test\n\n- Cargo.toml: build `cargo build`, test `cargo test`\n\
+             - Makefile: build `make`, test `make test`\n\n"
+        );
+    }
+
+    #[test]
+    fn test_package_json_commands() {
+        let commands = package_json_commands(
+            r#"{"scripts": {"test": "vitest", "lint": "eslint ."}}"#,
+            "pnpm",
+        );
+        assert_eq!(commands.build, None);
+        assert_eq!(commands.test.as_deref(), Some("pnpm test"));
+        assert_eq!(package_json_commands("not json", "npm").test, None);
+    }
+
+    #[test]
+    fn test_parse_os_release() {
+        let content = "NAME=\"Ubuntu\"\nPRETTY_NAME=\"Ubuntu 22.04.4 LTS\"\nID=ubuntu\n";
+        assert_eq!(
+            parse_os_release(content).as_deref(),
+            Some("Ubuntu 22.04.4 LTS")
+        );
+        assert_eq!(parse_os_release("ID=alpine\n"), None);
+    }
+
+    #[test]
+    fn test_strip_tool_name() {
+        assert_eq!(
+            strip_tool_name("rustc", "rustc 1.80.0 (051478957 2024-07-21)"),
+            "1.80.0 (051478957 2024-07-21)"
+        );
+        assert_eq!(strip_tool_name("python3", "Python 3.11.4"), "3.11.4");
+        assert_eq!(
+            strip_tool_name("go", "go version go1.22.1 linux/amd64"),
+            "go1.22.1 linux/amd64"
+        );
+        assert_eq!(strip_tool_name("node", "v20.11.0"), "v20.11.0");
+    }
+
+    #[test]
+    fn test_format() {
+        let env = Environment {
+            os: "Ubuntu 22.04.4 LTS (linux x86_64)".to_string(),
+            toolchains: vec![("rustc".to_string(), "1.80.0".to_string())],
+            env_facts: vec![("CI".to_string(), "true".to_string())],
+        };
+        assert_eq!(
+            env.format(),
+            "## Environment\n\n- OS: Ubuntu 22.04.4 LTS (linux x86_64)\n- rustc: 1.80.0\n- $CI=true\n\n"
+        );
+    }
+}
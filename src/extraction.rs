@@ -1,22 +1,54 @@
 //! Automated note extraction using Claude API
 //!
 //! After each task, sends the transcript to Claude for analysis and
-//! extracts structured notes to maintain context across sessions.
+//! extracts structured notes to maintain context across sessions. Without an
+//! API key, or when the API call fails, a rule-based pass keeps notes
+//! accumulating offline: failed tool calls become failures, "I
+//! chose/decided" sentences become decisions, and a "Next steps" list in the
+//! final result is appended to the plan under a dated heading (never
+//! replacing it, as API extraction does).
 
 use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
+use std::io::Write;
 
-use crate::config::{load_config, Config};
+use crate::config::{load_config, Config, ExtractionConfig};
 use crate::project::Project;
-use crate::transcript::Transcript;
+use crate::text::{truncate, truncate_ellipsis};
+use crate::transcript::{Message, Transcript};
+
+/// Model recorded for notes from the rule-based offline extractor
+pub const HEURISTIC_MODEL: &str = "heuristic";
+
+/// Most entries the offline extractor adds to one category per task
+const HEURISTIC_MAX_ENTRIES: usize = 5;
+
+/// Bytes of an error line or decision kept by the offline extractor
+const HEURISTIC_ENTRY_BYTES: usize = 200;
+
+/// Phrases marking a sentence as a stated decision (matched lowercase)
+const DECISION_PHRASES: &[&str] = &[
+    "i chose",
+    "i decided",
+    "we decided",
+    "i went with",
+    "i opted",
+];
 
 /// Result of note extraction
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct ExtractionResult {
     pub architecture: Option<String>,
     pub decisions: Option<String>,
     pub failures: Option<String>,
     pub plan: Option<String>,
+    /// Entries appended to the plan instead of replacing it, from the
+    /// rule-based pass
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan_additions: Option<String>,
+    /// Model that produced these notes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
 }
 
 impl ExtractionResult {
@@ -26,10 +58,11 @@ impl ExtractionResult {
             || self.decisions.is_some()
             || self.failures.is_some()
             || self.plan.is_some()
+            || self.plan_additions.is_some()
     }
 
-    /// Returns a summary of what was updated
-    pub fn summary(&self) -> String {
+    /// Returns the categories that have updates, in note order
+    pub fn categories(&self) -> Vec<&'static str> {
         let mut parts = Vec::new();
         if self.architecture.is_some() {
             parts.push("architecture");
@@ -40,9 +73,43 @@ impl ExtractionResult {
         if self.failures.is_some() {
             parts.push("failures");
         }
-        if self.plan.is_some() {
+        if self.plan.is_some() || self.plan_additions.is_some() {
             parts.push("plan");
         }
+        parts
+    }
+
+    /// The extracted text for one note category
+    pub fn category(&self, name: &str) -> Option<&str> {
+        match name {
+            "architecture" => self.architecture.as_deref(),
+            "decisions" => self.decisions.as_deref(),
+            "failures" => self.failures.as_deref(),
+            "plan" => self.plan.as_deref().or(self.plan_additions.as_deref()),
+            _ => None,
+        }
+    }
+
+    /// Drops updates for every category not in `keep`
+    pub fn retain_categories(&mut self, keep: &[&str]) {
+        for (category, field) in [
+            ("architecture", &mut self.architecture),
+            ("decisions", &mut self.decisions),
+            ("failures", &mut self.failures),
+            ("plan", &mut self.plan),
+        ] {
+            if !keep.contains(&category) {
+                *field = None;
+            }
+        }
+        if !keep.contains(&"plan") {
+            self.plan_additions = None;
+        }
+    }
+
+    /// Returns a summary of what was updated
+    pub fn summary(&self) -> String {
+        let parts = self.categories();
         if parts.is_empty() {
             "no updates".to_string()
         } else {
@@ -56,6 +123,7 @@ impl ExtractionResult {
 struct ApiRequest {
     model: String,
     max_tokens: u32,
+    stream: bool,
     messages: Vec<ApiMessage>,
 }
 
@@ -65,16 +133,58 @@ struct ApiMessage {
     content: String,
 }
 
-#[derive(Debug, Deserialize)]
-struct ApiResponse {
-    content: Vec<ContentBlock>,
+/// Accumulates a streamed (server-sent events) Messages API response
+#[derive(Debug, Default)]
+struct StreamedResponse {
+    text: String,
+    stop_reason: Option<String>,
+    /// Partial line carried over between chunks (may split a UTF-8 char)
+    pending: Vec<u8>,
 }
 
-#[derive(Debug, Deserialize)]
-struct ContentBlock {
-    #[serde(rename = "type")]
-    content_type: String,
-    text: Option<String>,
+impl StreamedResponse {
+    /// Consumes a chunk of the event stream, returning how many characters
+    /// of text it added
+    fn feed(&mut self, chunk: &[u8]) -> Result<usize> {
+        self.pending.extend_from_slice(chunk);
+        let mut added = 0;
+        while let Some(newline) = self.pending.iter().position(|&b| b == b'\n') {
+            let bytes: Vec<u8> = self.pending.drain(..=newline).collect();
+            let line = String::from_utf8_lossy(&bytes);
+            let Some(data) = line.trim_end().strip_prefix("data:") else {
+                continue;
+            };
+            let Ok(event) = serde_json::from_str::<serde_json::Value>(data.trim()) else {
+                continue;
+            };
+            match event["type"].as_str() {
+                Some("content_block_delta") => {
+                    if let Some(text) = event["delta"]["text"].as_str() {
+                        self.text.push_str(text);
+                        added += text.chars().count();
+                    }
+                }
+                Some("message_delta") => {
+                    if let Some(reason) = event["delta"]["stop_reason"].as_str() {
+                        self.stop_reason = Some(reason.to_string());
+                    }
+                }
+                Some("error") => {
+                    let message = event["error"]["message"]
+                        .as_str()
+                        .unwrap_or("unknown error");
+                    bail!("Claude API stream error: {}", message);
+                }
+                _ => {}
+            }
+        }
+        Ok(added)
+    }
+
+    /// True if the response stopped because it hit max_tokens
+    fn truncated(&self) -> bool {
+        self.stop_reason.as_deref() == Some("max_tokens")
+    }
 }
 
 /// Extracts notes from a task transcript using Claude API
@@ -85,6 +195,37 @@ pub async fn extract_notes(
 ) -> Result<ExtractionResult> {
     let config = load_config()?;
 
+    let has_key = std::env::var(&config.claude.api_key_env).is_ok_and(|k| !k.trim().is_empty());
+    if !has_key && config.extraction.offline_fallback {
+        return heuristic_extraction(project, transcript);
+    }
+
+    // Build the extraction prompt
+    let extraction_prompt =
+        build_extraction_prompt(project, transcript, prompt, &config.extraction)?;
+
+    // Call Claude API and parse the response
+    let (response_text, model) = match complete(&config, &extraction_prompt).await {
+        Ok(response) => response,
+        Err(e) if config.extraction.offline_fallback => {
+            eprintln!(
+                "\n[Extraction] API call failed, using rule-based notes: {:#}",
+                e
+            );
+            return heuristic_extraction(project, transcript);
+        }
+        Err(e) => return Err(e),
+    };
+    let mut result = parse_extraction_response(&response_text)?;
+    result.model = Some(model);
+    Ok(result)
+}
+
+/// Sends a prompt to the Claude API, falling back through the configured
+/// models and retrying truncated responses
+///
+/// Returns the response text and the model that produced it.
+pub async fn complete(config: &Config, prompt: &str) -> Result<(String, String)> {
     // Get API key from environment
     let api_key = std::env::var(&config.claude.api_key_env).with_context(|| {
         format!(
@@ -93,14 +234,91 @@ pub async fn extract_notes(
         )
     })?;
 
-    // Build the extraction prompt
-    let extraction_prompt = build_extraction_prompt(project, transcript, prompt)?;
+    let mut last_error = None;
+    for model in extraction_models(config) {
+        match call_with_retry(&api_key, config, &model, prompt).await {
+            Ok(text) => return Ok((text, model)),
+            Err(e) if is_fallback_error(&e) => last_error = Some(e),
+            Err(e) => return Err(e),
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| anyhow::anyhow!("No extraction model configured")))
+}
+
+/// Calls the API, doubling max_tokens and retrying while the response is
+/// cut off, up to `extraction.max_tokens_limit`
+async fn call_with_retry(
+    api_key: &str,
+    config: &Config,
+    model: &str,
+    prompt: &str,
+) -> Result<String> {
+    let mut max_tokens = config.extraction.max_tokens;
+    loop {
+        let response = call_claude_api(api_key, config, model, max_tokens, prompt).await?;
+        if !response.truncated() {
+            return Ok(response.text);
+        }
+        match next_max_tokens(max_tokens, config.extraction.max_tokens_limit) {
+            Some(next) => {
+                print!(
+                    " truncated at {} tokens, retrying with {}...",
+                    max_tokens, next
+                );
+                std::io::stdout().flush().ok();
+                max_tokens = next;
+            }
+            None => {
+                bail!(
+                    "Extraction response was truncated at {} tokens; raise extraction.max_tokens_limit",
+                    max_tokens
+                );
+            }
+        }
+    }
+}
+
+/// The doubled token budget for a retry, or None once the limit is reached
+fn next_max_tokens(current: u32, limit: u32) -> Option<u32> {
+    (current < limit).then(|| current.saturating_mul(2).min(limit))
+}
+
+/// The configured model followed by its fallbacks, without duplicates
+fn extraction_models(config: &Config) -> Vec<String> {
+    let mut models: Vec<String> = Vec::new();
+    for model in std::iter::once(&config.claude.model).chain(&config.claude.fallback_models) {
+        if !models.contains(model) {
+            models.push(model.clone());
+        }
+    }
+    models
+}
 
-    // Call Claude API
-    let response_text = call_claude_api(&api_key, &config, &extraction_prompt).await?;
+/// An unsuccessful HTTP response from the Claude API
+#[derive(Debug)]
+struct ApiError {
+    status: u16,
+    message: String,
+}
 
-    // Parse the response
-    parse_extraction_response(&response_text)
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// Whether an API status is worth retrying with another model: the model
+/// is unknown or retired (404), or the service is overloaded (429, 5xx)
+fn should_fall_back(status: u16) -> bool {
+    matches!(status, 404 | 429 | 500..=599)
+}
+
+fn is_fallback_error(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<ApiError>()
+        .is_some_and(|e| should_fall_back(e.status))
 }
 
 /// Builds the note extraction prompt with current notes and transcript
@@ -108,6 +326,7 @@ fn build_extraction_prompt(
     project: &Project,
     transcript: &Transcript,
     task_prompt: &str,
+    config: &ExtractionConfig,
 ) -> Result<String> {
     let architecture = project.read_notes("architecture")?;
     let decisions = project.read_notes("decisions")?;
@@ -115,7 +334,7 @@ fn build_extraction_prompt(
     let plan = project.read_notes("plan")?;
 
     // Format transcript for inclusion
-    let transcript_text = format_transcript_for_extraction(transcript, task_prompt);
+    let transcript_text = format_transcript_for_extraction(transcript, task_prompt, config);
 
     Ok(format!(
         r#"You are extracting structured notes from a coding task transcript.
@@ -211,39 +430,63 @@ Output format (use exactly these headers):
 }
 
 /// Formats the transcript for inclusion in the extraction prompt
-fn format_transcript_for_extraction(transcript: &Transcript, task_prompt: &str) -> String {
-    let mut output = String::new();
+///
+/// Output is capped at roughly `max_tokens`. The task prompt, errors, and
+/// final result are always kept; other messages are sampled to fit.
+fn format_transcript_for_extraction(
+    transcript: &Transcript,
+    task_prompt: &str,
+    config: &ExtractionConfig,
+) -> String {
+    let mut header = String::new();
 
     // Include the original task prompt
-    output.push_str(&format!("Task: {}\n\n", task_prompt));
+    header.push_str(&format!("Task: {}\n\n", task_prompt));
 
     // Include model info if available
     if let Some(ref init) = transcript.init {
         if let Some(ref model) = init.model {
-            output.push_str(&format!("Model: {}\n", model));
+            header.push_str(&format!("Model: {}\n", model));
         }
     }
 
-    output.push_str("---\n\n");
+    header.push_str("---\n\n");
 
-    // Include messages
+    // Error outputs are always included and claim the shared byte cap first
+    let error_bytes: usize = transcript
+        .messages
+        .iter()
+        .filter_map(|msg| match msg {
+            crate::transcript::Message::ToolResult {
+                output,
+                is_error: true,
+                ..
+            } => Some(truncate(output, config.max_tool_output_bytes).len()),
+            _ => None,
+        })
+        .sum();
+    let mut result_budget = config
+        .max_total_tool_output_bytes
+        .saturating_sub(error_bytes);
+
+    // Format each message as a block; errors are always kept when sampling
+    let mut blocks: Vec<(String, bool)> = Vec::new();
     for msg in &transcript.messages {
         match msg {
             crate::transcript::Message::Text { text } => {
-                output.push_str("Assistant:\n");
-                output.push_str(text);
-                output.push_str("\n\n");
+                blocks.push((format!("Assistant:\n{}\n\n", text), false));
             }
             crate::transcript::Message::ToolUse {
                 tool_name, input, ..
             } => {
-                output.push_str(&format!("Tool: {}\n", tool_name));
+                let mut block = format!("Tool: {}\n", tool_name);
                 // Include relevant input for context (truncate if too long)
                 let input_str = serde_json::to_string_pretty(input).unwrap_or_default();
                 if input_str.len() < 500 {
-                    output.push_str(&format!("Input: {}\n", input_str));
+                    block.push_str(&format!("Input: {}\n", input_str));
                 }
-                output.push('\n');
+                block.push('\n');
+                blocks.push((block, false));
             }
             crate::transcript::Message::ToolResult {
                 output: result,
@@ -251,41 +494,113 @@ fn format_transcript_for_extraction(transcript: &Transcript, task_prompt: &str)
                 ..
             } => {
                 if *is_error {
-                    output.push_str(&format!("Error: {}\n\n", truncate(result, 500)));
-                } else {
-                    // Only include short tool results
-                    if result.len() < 200 {
-                        output.push_str(&format!("Result: {}\n\n", result));
-                    }
+                    blocks.push((
+                        format!(
+                            "Error: {}\n\n",
+                            cap_output(result, config.max_tool_output_bytes)
+                        ),
+                        true,
+                    ));
+                } else if config.include_tool_outputs && result_budget > 0 {
+                    let capped =
+                        cap_output(result, config.max_tool_output_bytes.min(result_budget));
+                    result_budget = result_budget.saturating_sub(capped.len());
+                    blocks.push((format!("Result: {}\n\n", capped), false));
                 }
             }
         }
     }
 
     // Include final result
+    let mut footer = String::new();
     if let Some(ref result) = transcript.result {
         if let Some(ref text) = result.result_text {
-            output.push_str("---\n\n");
-            output.push_str(&format!("Final result: {}\n", text));
+            footer.push_str("---\n\n");
+            footer.push_str(&format!("Final result: {}\n", text));
         }
         if !result.success {
-            output.push_str("(Task failed)\n");
+            footer.push_str("(Task failed)\n");
         }
     }
 
+    // Roughly 4 characters per token, as in compile_context
+    let budget = config.max_transcript_tokens.saturating_mul(4);
+    let fixed = header.len() + footer.len();
+    let body = sample_blocks(&blocks, budget.saturating_sub(fixed));
+
+    // Errors alone can overrun the budget; cut the body, never the footer
+    let output = format!("{}{}", header, body);
+    format!(
+        "{}{}",
+        truncate(&output, budget.saturating_sub(footer.len())),
+        footer
+    )
+}
+
+/// Joins message blocks, sampling evenly from the non-error blocks when they
+/// don't all fit in `budget` bytes
+///
+/// Error blocks are always kept. Each run of skipped blocks is replaced by a
+/// marker so the model knows the transcript was shortened.
+fn sample_blocks(blocks: &[(String, bool)], budget: usize) -> String {
+    let total: usize = blocks.iter().map(|(b, _)| b.len()).sum();
+    if total <= budget {
+        return blocks.iter().map(|(b, _)| b.as_str()).collect();
+    }
+
+    let errors: usize = blocks
+        .iter()
+        .filter(|(_, e)| *e)
+        .map(|(b, _)| b.len())
+        .sum();
+    let mut remaining = budget.saturating_sub(errors);
+    // Fraction of the optional content that fits, used to space out picks
+    let ratio = remaining as f64 / (total - errors).max(1) as f64;
+
+    let mut output = String::new();
+    let mut skipped = 0;
+    let mut credit = 0.0;
+    for (block, is_error) in blocks {
+        credit += ratio;
+        let keep = *is_error || (credit >= 1.0 && block.len() <= remaining);
+        if !keep {
+            skipped += 1;
+            continue;
+        }
+        if !is_error {
+            credit -= 1.0;
+            remaining -= block.len();
+        }
+        if skipped > 0 {
+            output.push_str(&format!("[... {} messages omitted ...]\n\n", skipped));
+            skipped = 0;
+        }
+        output.push_str(block);
+    }
+    if skipped > 0 {
+        output.push_str(&format!("[... {} messages omitted ...]\n\n", skipped));
+    }
     output
 }
 
-/// Calls the Claude API with the extraction prompt
-async fn call_claude_api(api_key: &str, config: &Config, prompt: &str) -> Result<String> {
+/// Sends a streaming Messages API request, printing a dot for every few
+/// hundred characters received so long extractions visibly progress
+async fn call_claude_api(
+    api_key: &str,
+    config: &Config,
+    model: &str,
+    max_tokens: u32,
+    prompt: &str,
+) -> Result<StreamedResponse> {
     let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(60))
+        .timeout(std::time::Duration::from_secs(180))
         .build()
         .context("Failed to create HTTP client")?;
 
     let request = ApiRequest {
-        model: config.claude.model.clone(),
-        max_tokens: 2048,
+        model: model.to_string(),
+        max_tokens,
+        stream: true,
         messages: vec![ApiMessage {
             role: "user".to_string(),
             content: prompt.to_string(),
@@ -293,7 +608,7 @@ async fn call_claude_api(api_key: &str, config: &Config, prompt: &str) -> Result
     };
 
     let url = format!("{}/v1/messages", config.claude.base_url);
-    let response = client
+    let mut response = client
         .post(&url)
         .header("x-api-key", api_key)
         .header("anthropic-version", "2023-06-01")
@@ -310,33 +625,43 @@ async fn call_claude_api(api_key: &str, config: &Config, prompt: &str) -> Result
         // Provide helpful error messages for common issues
         let hint = match status.as_u16() {
             401 => " (check your API key)",
+            404 => " (model not found)",
             429 => " (rate limited, try again later)",
             500..=599 => " (API server error, try again later)",
             _ => "",
         };
 
-        bail!("Claude API error ({}){}: {}", status, hint, body);
+        return Err(ApiError {
+            status: status.as_u16(),
+            message: format!(
+                "Claude API error ({}) from {}{}: {}",
+                status, model, hint, body
+            ),
+        }
+        .into());
     }
 
-    let api_response: ApiResponse = response
-        .json()
+    const CHARS_PER_DOT: usize = 400;
+    let mut streamed = StreamedResponse::default();
+    let mut since_dot = 0;
+    while let Some(chunk) = response
+        .chunk()
         .await
-        .context("Failed to parse Claude API response")?;
-
-    // Extract text from response
-    let text = api_response
-        .content
-        .iter()
-        .filter(|c| c.content_type == "text")
-        .filter_map(|c| c.text.as_deref())
-        .collect::<Vec<_>>()
-        .join("");
+        .context("Failed to read Claude API response")?
+    {
+        since_dot += streamed.feed(&chunk)?;
+        if since_dot >= CHARS_PER_DOT {
+            print!(".");
+            std::io::stdout().flush().ok();
+            since_dot %= CHARS_PER_DOT;
+        }
+    }
 
-    if text.is_empty() {
+    if streamed.text.is_empty() {
         bail!("Claude API returned empty response");
     }
 
-    Ok(text)
+    Ok(streamed)
 }
 
 /// Parses the extraction response into structured notes
@@ -385,6 +710,138 @@ fn parse_extraction_response(response: &str) -> Result<ExtractionResult> {
     Ok(result)
 }
 
+/// Rule-based notes for when the API can't be reached: lower quality, but
+/// entries already in the notes are skipped so repeats don't pile up
+fn heuristic_extraction(project: &Project, transcript: &Transcript) -> Result<ExtractionResult> {
+    let failures = project.read_notes("failures")?;
+    let decisions = project.read_notes("decisions")?;
+    let plan = project.read_notes("plan")?;
+    let today = chrono::Local::now().format("%Y-%m-%d");
+
+    let replies: Vec<&str> = transcript
+        .messages
+        .iter()
+        .filter_map(|msg| match msg {
+            Message::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    let result_text = transcript
+        .result
+        .as_ref()
+        .and_then(|r| r.result_text.as_deref());
+
+    let decision_entries: Vec<String> = unseen(
+        decision_sentences(replies.iter().copied().chain(result_text)),
+        &decisions,
+    )
+    .into_iter()
+    .map(|sentence| format!("- [{}] {}", today, sentence))
+    .collect();
+    let failure_entries: Vec<String> = unseen(error_lines(transcript), &failures)
+        .into_iter()
+        .map(|line| format!("- {}", line))
+        .collect();
+    let steps: Vec<String> = unseen(result_text.map(next_steps).unwrap_or_default(), &plan)
+        .into_iter()
+        .map(|step| format!("- [ ] {}", step))
+        .collect();
+
+    Ok(ExtractionResult {
+        architecture: None,
+        decisions: (!decision_entries.is_empty()).then(|| decision_entries.join("\n")),
+        failures: (!failure_entries.is_empty()).then(|| failure_entries.join("\n")),
+        // Appended rather than set: `plan` replaces plan.md on apply
+        plan: None,
+        plan_additions: (!steps.is_empty())
+            .then(|| format!("## Next steps ({})\n{}", today, steps.join("\n"))),
+        model: Some(HEURISTIC_MODEL.to_string()),
+    })
+}
+
+/// Candidates not already mentioned in `existing`, deduplicated and capped
+fn unseen(candidates: Vec<String>, existing: &str) -> Vec<String> {
+    let mut kept: Vec<String> = Vec::new();
+    for candidate in candidates {
+        if !existing.contains(&candidate) && !kept.contains(&candidate) {
+            kept.push(candidate);
+        }
+    }
+    kept.truncate(HEURISTIC_MAX_ENTRIES);
+    kept
+}
+
+/// The first line of each failed tool call, as `<tool> failed: <line>`
+fn error_lines(transcript: &Transcript) -> Vec<String> {
+    let tool_name = |id: &str| {
+        transcript.messages.iter().find_map(|msg| match msg {
+            Message::ToolUse {
+                tool_name, tool_id, ..
+            } if tool_id == id => Some(tool_name.as_str()),
+            _ => None,
+        })
+    };
+    transcript
+        .messages
+        .iter()
+        .filter_map(|msg| match msg {
+            Message::ToolResult {
+                tool_id,
+                output,
+                is_error: true,
+            } => {
+                let line = output.lines().map(str::trim).find(|l| !l.is_empty())?;
+                Some(format!(
+                    "{} failed: {}",
+                    tool_name(tool_id).unwrap_or("Tool"),
+                    truncate_ellipsis(line, HEURISTIC_ENTRY_BYTES)
+                ))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Sentences stating a choice ("I chose X because Y")
+fn decision_sentences<'a>(texts: impl Iterator<Item = &'a str>) -> Vec<String> {
+    texts
+        .flat_map(str::lines)
+        .flat_map(|line| line.split_inclusive(". "))
+        .map(|sentence| sentence.trim().trim_start_matches(['-', '*', ' ']))
+        .filter(|sentence| {
+            let lower = sentence.to_lowercase();
+            DECISION_PHRASES.iter().any(|phrase| lower.contains(phrase))
+        })
+        .map(|sentence| truncate_ellipsis(sentence, HEURISTIC_ENTRY_BYTES))
+        .collect()
+}
+
+/// The items listed under a "Next steps" heading
+fn next_steps(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .skip_while(|line| !(line.to_lowercase().contains("next step") && line.len() < 60))
+        .skip(1)
+        .skip_while(|line| line.is_empty())
+        .map_while(list_item)
+        .map(str::to_string)
+        .collect()
+}
+
+/// The text of a bullet (`-`, `*`) or numbered (`1.`, `1)`) list line
+fn list_item(line: &str) -> Option<&str> {
+    if let Some(item) = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")) {
+        return Some(item.trim());
+    }
+    let rest = line.trim_start_matches(|c: char| c.is_ascii_digit());
+    if rest.len() == line.len() {
+        return None;
+    }
+    rest.strip_prefix(". ")
+        .or_else(|| rest.strip_prefix(") "))
+        .map(str::trim)
+}
+
 /// Applies extraction results to project notes
 pub fn apply_extraction(project: &Project, extraction: &ExtractionResult) -> Result<()> {
     // Architecture, decisions, and failures are appended
@@ -402,16 +859,44 @@ pub fn apply_extraction(project: &Project, extraction: &ExtractionResult) -> Res
     if let Some(ref content) = extraction.plan {
         project.write_notes("plan", content)?;
     }
+    // ...unless the update only adds to it
+    if let Some(ref content) = extraction.plan_additions {
+        let existing = project.read_notes("plan")?;
+        let plan = if existing.trim().is_empty() {
+            content.clone()
+        } else {
+            format!("{}\n\n{}", existing.trim_end(), content)
+        };
+        project.write_notes("plan", &plan)?;
+    }
 
     Ok(())
 }
 
-/// Truncates a string to a maximum length
-fn truncate(s: &str, max_len: usize) -> &str {
-    if s.len() <= max_len {
-        s
+/// Parses an accept prompt answer into the categories to keep
+///
+/// Empty input, `y`, or `all` accepts everything, `n` rejects everything, and
+/// otherwise each letter selects a category by its initial (`adf`).
+pub fn parse_category_selection(input: &str, available: &[&'static str]) -> Vec<&'static str> {
+    let input = input.trim().to_lowercase();
+    match input.as_str() {
+        "" | "y" | "yes" | "all" => available.to_vec(),
+        "n" | "no" => Vec::new(),
+        _ => available
+            .iter()
+            .filter(|category| category.chars().next().is_some_and(|c| input.contains(c)))
+            .copied()
+            .collect(),
+    }
+}
+
+/// Cuts a tool output to `max_bytes`, noting how much was dropped
+fn cap_output(output: &str, max_bytes: usize) -> String {
+    let kept = truncate(output, max_bytes);
+    if kept.len() == output.len() {
+        output.to_string()
     } else {
-        &s[..max_len]
+        format!("{}... ({} more bytes)", kept, output.len() - kept.len())
     }
 }
 
@@ -419,6 +904,167 @@ fn truncate(s: &str, max_len: usize) -> &str {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_sample_blocks_keeps_errors_and_spreads_picks() {
+        let mut blocks: Vec<(String, bool)> =
+            (0..10).map(|i| (format!("msg {}\n", i), false)).collect();
+        blocks[5] = ("Error: boom\n".to_string(), true);
+
+        // Everything fits
+        assert!(sample_blocks(&blocks, 1000).contains("msg 9"));
+
+        let sampled = sample_blocks(&blocks, 40);
+        assert!(sampled.contains("Error: boom"));
+        assert!(sampled.contains("messages omitted"));
+        let kept = sampled.matches("msg ").count();
+        assert!((3..=5).contains(&kept), "kept {}", kept);
+    }
+
+    #[test]
+    fn test_format_transcript_respects_budget() {
+        let long_text = "word ".repeat(2000);
+        let output = format!(
+            r#"{{"type":"assistant","message":{{"content":[{{"type":"text","text":"{}"}}]}}}}
+{{"type":"result","subtype":"success","result":"All done","duration_ms":1,"total_cost_usd":0.0}}"#,
+            long_text
+        );
+        let config = ExtractionConfig {
+            max_transcript_tokens: 100,
+            ..Default::default()
+        };
+        let formatted =
+            format_transcript_for_extraction(&Transcript::parse(&output), "fix it", &config);
+        assert!(formatted.len() <= 400);
+        assert!(formatted.starts_with("Task: fix it"));
+        assert!(formatted.contains("Final result: All done"));
+    }
+
+    #[test]
+    fn test_format_transcript_keeps_result_when_errors_overrun() {
+        let error = "boom ".repeat(200);
+        let output = format!(
+            r#"{{"type":"user","message":{{"content":[{{"type":"tool_result","tool_use_id":"t1","content":"{}","is_error":true}}]}}}}
+{{"type":"result","subtype":"error","result":"Gave up","duration_ms":1,"total_cost_usd":0.0}}"#,
+            error
+        );
+        let config = ExtractionConfig {
+            max_transcript_tokens: 50,
+            ..Default::default()
+        };
+        let formatted =
+            format_transcript_for_extraction(&Transcript::parse(&output), "fix it", &config);
+        assert!(formatted.len() <= 200);
+        assert!(formatted.ends_with("Final result: Gave up\n(Task failed)\n"));
+    }
+
+    #[test]
+    fn test_tool_output_policies() {
+        let output = r#"{"type":"assistant","message":{"content":[{"type":"tool_use","name":"Bash","id":"t1","input":{}},{"type":"tool_use","name":"Bash","id":"t2","input":{}}]}}
+{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1","content":"0123456789abcdef","is_error":false},{"type":"tool_result","tool_use_id":"t2","content":"boom boom boom","is_error":true}]}}"#;
+        let transcript = Transcript::parse(output);
+
+        let mut config = ExtractionConfig {
+            max_tool_output_bytes: 10,
+            ..Default::default()
+        };
+        let formatted = format_transcript_for_extraction(&transcript, "x", &config);
+        assert!(formatted.contains("Result: 0123456789... (6 more bytes)"));
+        assert!(formatted.contains("Error: boom boom ... (4 more bytes)"));
+
+        config.include_tool_outputs = false;
+        let formatted = format_transcript_for_extraction(&transcript, "x", &config);
+        assert!(!formatted.contains("Result:"));
+        assert!(formatted.contains("Error: boom"));
+
+        // Errors claim the total cap before ordinary results
+        config.include_tool_outputs = true;
+        config.max_total_tool_output_bytes = 10;
+        let formatted = format_transcript_for_extraction(&transcript, "x", &config);
+        assert!(!formatted.contains("Result:"));
+        assert!(formatted.contains("Error: boom"));
+    }
+
+    #[test]
+    fn test_truncate_respects_char_boundaries() {
+        assert_eq!(truncate("héllo", 2), "h");
+    }
+
+    #[test]
+    fn test_extraction_models_dedupes_fallbacks() {
+        let mut config = Config::default();
+        config.claude.model = "sonnet".to_string();
+        config.claude.fallback_models = vec!["haiku".to_string(), "sonnet".to_string()];
+        assert_eq!(extraction_models(&config), vec!["sonnet", "haiku"]);
+    }
+
+    #[test]
+    fn test_streamed_response_across_chunks() {
+        let mut streamed = StreamedResponse::default();
+        let added = streamed
+            .feed(b"event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"delta\":{\"type\":\"text_delta\",\"text\":\"### PLAN\"}}\n\ndata: {\"type\":\"content_block_de")
+            .unwrap();
+        assert_eq!(added, 8);
+        streamed
+            .feed(b"lta\",\"delta\":{\"type\":\"text_delta\",\"text\":\"\\n- x\"}}\n\ndata: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"max_tokens\"}}\n")
+            .unwrap();
+        assert_eq!(streamed.text, "### PLAN\n- x");
+        assert!(streamed.truncated());
+
+        let mut errored = StreamedResponse::default();
+        assert!(errored
+            .feed(b"data: {\"type\":\"error\",\"error\":{\"message\":\"Overloaded\"}}\n")
+            .is_err());
+    }
+
+    #[test]
+    fn test_next_max_tokens() {
+        assert_eq!(next_max_tokens(2048, 8192), Some(4096));
+        assert_eq!(next_max_tokens(6000, 8192), Some(8192));
+        assert_eq!(next_max_tokens(8192, 8192), None);
+    }
+
+    #[test]
+    fn test_should_fall_back() {
+        assert!(should_fall_back(404));
+        assert!(should_fall_back(529));
+        assert!(!should_fall_back(401));
+        assert!(!should_fall_back(400));
+    }
+
+    #[test]
+    fn test_parse_category_selection() {
+        let available = ["architecture", "decisions", "plan"];
+        assert_eq!(parse_category_selection("", &available), available.to_vec());
+        assert_eq!(
+            parse_category_selection("n", &available),
+            Vec::<&str>::new()
+        );
+        assert_eq!(
+            parse_category_selection("P a f", &available),
+            vec!["architecture", "plan"]
+        );
+    }
+
+    #[test]
+    fn test_parse_category_selection_all() {
+        let available = ["architecture", "decisions", "plan"];
+        assert_eq!(
+            parse_category_selection("All", &available),
+            available.to_vec()
+        );
+    }
+
+    #[test]
+    fn test_retain_categories() {
+        let mut result = ExtractionResult {
+            architecture: Some("- a".to_string()),
+            plan: Some("p".to_string()),
+            ..Default::default()
+        };
+        result.retain_categories(&["plan"]);
+        assert_eq!(result.categories(), vec!["plan"]);
+    }
+
     #[test]
     fn test_parse_extraction_response() {
         let response = r#"
@@ -481,4 +1127,67 @@ NO_UPDATES
         result.plan = Some("test".to_string());
         assert_eq!(result.summary(), "architecture, plan");
     }
+
+    #[test]
+    fn test_error_lines_and_unseen() {
+        let output = r#"{"type":"assistant","message":{"content":[{"type":"tool_use","name":"Bash","id":"t1","input":{}}]}}
+{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1","content":"\nerror[E0432]: unresolved import\n  --> src/main.rs","is_error":true},{"type":"tool_result","tool_use_id":"t9","content":"ok","is_error":false}]}}"#;
+        let lines = error_lines(&Transcript::parse(output));
+        assert_eq!(lines, vec!["Bash failed: error[E0432]: unresolved import"]);
+
+        assert!(unseen(
+            lines.clone(),
+            "- Bash failed: error[E0432]: unresolved import"
+        )
+        .is_empty());
+        let repeated = vec!["a".to_string(), "a".to_string(), "b".to_string()];
+        assert_eq!(unseen(repeated, ""), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_decision_sentences() {
+        let text = "Looked around. I chose sqlx over diesel because of async. Done.\n- We decided to keep v1 routes";
+        assert_eq!(
+            decision_sentences(std::iter::once(text)),
+            vec![
+                "I chose sqlx over diesel because of async.",
+                "We decided to keep v1 routes"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_next_steps() {
+        let text = "Implemented the endpoint.\n\n**Next steps:**\n\n1. Add rate limiting\n2) Write docs\n- Deploy\n\nLet me know!";
+        assert_eq!(
+            next_steps(text),
+            vec!["Add rate limiting", "Write docs", "Deploy"]
+        );
+        assert!(next_steps("All done.").is_empty());
+        assert!(next_steps("Next steps: none").is_empty());
+    }
+
+    #[test]
+    fn test_heuristic_extraction_appends_next_steps_to_plan() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("notes")).unwrap();
+        let project = Project::new(
+            crate::project::ProjectMetadata::new("extract-test"),
+            dir.path().to_path_buf(),
+        );
+        project
+            .write_notes("plan", "## Phase 2\n- [ ] Write docs")
+            .unwrap();
+        let output = r#"{"type":"result","subtype":"success","result":"Done.\n\nNext steps:\n- Write docs\n- Add rate limiting"}"#;
+
+        let extraction = heuristic_extraction(&project, &Transcript::parse(output)).unwrap();
+        assert!(extraction.plan.is_none());
+        assert_eq!(extraction.categories(), vec!["plan"]);
+        apply_extraction(&project, &extraction).unwrap();
+
+        let plan = project.read_notes("plan").unwrap();
+        assert!(plan.starts_with("## Phase 2\n- [ ] Write docs\n\n## Next steps ("));
+        // Steps already in the plan aren't repeated
+        assert!(plan.ends_with(")\n- [ ] Add rate limiting"));
+    }
 }
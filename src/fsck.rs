@@ -0,0 +1,469 @@
+//! Project consistency checks
+//!
+//! `clancy fsck` validates each project's `project.toml`, note files, task
+//! logs and numbering, and parent link, and with `--fix` (or `--repair`)
+//! repairs what it safely can. Nothing is deleted: an unparsable task log is
+//! renamed aside, and a damaged note keeps its original. A corrupt
+//! `project.toml` is restored from a write cut off before its rename
+//! (`project.toml.<pid>.<n>-<nanos>.tmp`), then from the backup kept by each
+//! save, and as a last resort rebuilt with its counters recovered from the
+//! task logs.
+
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::config;
+use crate::project::{
+    pending_writes, write_atomic, Project, ProjectMetadata, EXTRA_NOTES, METADATA_BACKUP,
+    METADATA_FILE, NOTE_CATEGORIES, PENDING_SUFFIX,
+};
+use crate::tasklog::{self, TaskLog};
+
+/// Suffix for damaged files set aside by a repair
+const CORRUPT_SUFFIX: &str = ".corrupt";
+
+/// One problem found in a project
+#[derive(Debug, PartialEq)]
+struct Finding {
+    message: String,
+    /// Whether `--fix` repaired it (or would, in a dry run)
+    fixable: bool,
+}
+
+impl Finding {
+    fn fixable(message: String) -> Self {
+        Finding {
+            message,
+            fixable: true,
+        }
+    }
+
+    fn manual(message: String) -> Self {
+        Finding {
+            message,
+            fixable: false,
+        }
+    }
+}
+
+/// Checks one project, or every project when `project_name` is None
+pub fn fsck(project_name: Option<&str>, fix: bool) -> Result<()> {
+    let projects_dir = config::projects_dir()?;
+    let names: Vec<String> = match project_name {
+        Some(name) => vec![name.to_string()],
+        None if projects_dir.exists() => {
+            let mut names: Vec<String> = std::fs::read_dir(&projects_dir)?
+                .filter_map(|e| e.ok())
+                .filter(|e| e.path().is_dir())
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .collect();
+            names.sort();
+            names
+        }
+        None => Vec::new(),
+    };
+
+    let mut found = 0;
+    let mut fixed = 0;
+    for name in &names {
+        let path = projects_dir.join(name);
+        if !path.is_dir() {
+            anyhow::bail!("Project '{}' not found", name);
+        }
+        let findings = check_project(&path, name, fix)?;
+        if findings.is_empty() {
+            println!("{}: ok", name);
+            continue;
+        }
+        println!("{}:", name);
+        for finding in &findings {
+            let status = match (finding.fixable, fix) {
+                (true, true) => "fixed",
+                (true, false) => "fixable",
+                (false, _) => "needs attention",
+            };
+            println!("  [{}] {}", status, finding.message);
+        }
+        found += findings.len();
+        fixed += findings.iter().filter(|f| f.fixable).count();
+    }
+
+    if found > 0 {
+        let plural = if found == 1 { "" } else { "s" };
+        if fix {
+            println!("\n{} problem{} found, {} fixed.", found, plural, fixed);
+        } else {
+            println!(
+                "\n{} problem{} found, {} fixable. Run with --fix to repair.",
+                found, plural, fixed
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Checks the project in `path`, repairing fixable problems when `fix`
+fn check_project(path: &Path, name: &str, fix: bool) -> Result<Vec<Finding>> {
+    let mut findings = Vec::new();
+
+    let (metadata, mut dirty) = check_metadata(path, name, fix, &mut findings)?;
+    let mut project = Project::new(metadata, path.to_path_buf());
+
+    check_notes(&project, fix, &mut findings)?;
+
+    if let Some(parent) = project.metadata.parent.clone() {
+        let exists = path.parent().is_some_and(|dir| dir.join(&parent).is_dir());
+        if !exists {
+            findings.push(Finding::fixable(format!(
+                "parent project '{}' no longer exists; unlinking",
+                parent
+            )));
+            project.metadata.parent = None;
+            dirty = true;
+        }
+    }
+
+    let tasks_dir = project.tasks_path();
+    if !tasks_dir.is_dir() {
+        findings.push(Finding::fixable("tasks directory is missing".to_string()));
+        if fix {
+            std::fs::create_dir_all(&tasks_dir)?;
+        }
+    }
+
+    // Task numbering: unreadable logs, duplicates, gaps, and counters behind
+    // the logs. Unreadable logs are renamed so other commands skip them.
+    let mut by_number: BTreeMap<u32, Vec<String>> = BTreeMap::new();
+    for (number, log_path) in tasklog::task_log_paths(&project)? {
+        if TaskLog::load(&log_path).is_err() {
+            findings.push(Finding::fixable(format!(
+                "tasks/{} can't be parsed; moving it aside as {}{}",
+                file_name(&log_path),
+                file_name(&log_path),
+                CORRUPT_SUFFIX
+            )));
+            if fix {
+                let mut aside = log_path.clone().into_os_string();
+                aside.push(CORRUPT_SUFFIX);
+                std::fs::rename(&log_path, aside)?;
+                continue;
+            }
+        }
+        by_number
+            .entry(number)
+            .or_default()
+            .push(file_name(&log_path));
+    }
+    for (number, files) in by_number.iter().filter(|(_, files)| files.len() > 1) {
+        findings.push(Finding::manual(format!(
+            "task number {} is used by {} logs: {}",
+            number,
+            files.len(),
+            files.join(", ")
+        )));
+    }
+    let gaps = number_gaps(by_number.keys().copied());
+    if !gaps.is_empty() {
+        findings.push(Finding::manual(format!(
+            "task numbers missing from the logs: {}",
+            gaps.join(", ")
+        )));
+    }
+    let highest = by_number.keys().next_back().copied().unwrap_or(0);
+    let stats = &mut project.metadata.stats;
+    // A zero counter is seeded from the tasks dir on first use
+    if stats.last_task_number != 0 && stats.last_task_number < highest {
+        findings.push(Finding::fixable(format!(
+            "last task number is {} but task {} exists; new tasks would reuse numbers",
+            stats.last_task_number, highest
+        )));
+        stats.last_task_number = highest;
+        dirty = true;
+    }
+    let unique = by_number.len() as u32;
+    if stats.total_tasks < unique {
+        findings.push(Finding::fixable(format!(
+            "total tasks is {} but {} task logs exist",
+            stats.total_tasks, unique
+        )));
+        stats.total_tasks = unique;
+        dirty = true;
+    }
+
+    if fix && dirty {
+        project.save_metadata()?;
+    }
+    Ok(findings)
+}
+
+/// Loads `project.toml`, falling back to a pending write, the backup, or a
+/// fresh default; returns the metadata and whether it needs saving
+fn check_metadata(
+    path: &Path,
+    name: &str,
+    fix: bool,
+    findings: &mut Vec<Finding>,
+) -> Result<(ProjectMetadata, bool)> {
+    let metadata_path = path.join(METADATA_FILE);
+    let pending = pending_writes(&metadata_path);
+
+    let error = match std::fs::read_to_string(&metadata_path) {
+        Ok(content) => match toml::from_str::<ProjectMetadata>(&content) {
+            Ok(metadata) => {
+                for pending in &pending {
+                    findings.push(Finding::fixable(format!(
+                        "{} left by an interrupted write",
+                        file_name(pending)
+                    )));
+                    if fix {
+                        std::fs::remove_file(pending)?;
+                    }
+                }
+                return Ok((metadata, false));
+            }
+            Err(e) => format!("is corrupt ({})", e.message()),
+        },
+        Err(_) => "is missing".to_string(),
+    };
+
+    let backup = path.join(METADATA_BACKUP);
+    for candidate in pending.iter().chain([&backup]) {
+        let Ok(content) = std::fs::read_to_string(candidate) else {
+            continue;
+        };
+        if let Ok(metadata) = toml::from_str::<ProjectMetadata>(&content) {
+            findings.push(Finding::fixable(format!(
+                "{} {}; restoring from {}",
+                METADATA_FILE,
+                error,
+                file_name(candidate)
+            )));
+            if fix {
+                write_atomic(&metadata_path, &content)?;
+                for pending in &pending {
+                    let _ = std::fs::remove_file(pending);
+                }
+            }
+            return Ok((metadata, false));
+        }
+    }
+
+    findings.push(Finding::fixable(format!(
+        "{} {} and has no usable backup; rebuilding it from the task logs",
+        METADATA_FILE, error
+    )));
+    Ok((ProjectMetadata::new(name), true))
+}
+
+/// Checks that every note category's file exists and every note file is
+/// UTF-8
+fn check_notes(project: &Project, fix: bool, findings: &mut Vec<Finding>) -> Result<()> {
+    let notes_dir = project.path.join("notes");
+    if !notes_dir.is_dir() {
+        findings.push(Finding::fixable("notes directory is missing".to_string()));
+        if !fix {
+            return Ok(());
+        }
+        std::fs::create_dir_all(&notes_dir)?;
+    }
+
+    for category in NOTE_CATEGORIES {
+        if !project.notes_path(category).exists() {
+            findings.push(Finding::fixable(format!(
+                "notes/{}.md is missing; creating it empty",
+                category
+            )));
+            if fix {
+                project.write_notes(category, "")?;
+            }
+        }
+    }
+
+    for entry in std::fs::read_dir(&notes_dir)?.filter_map(|e| e.ok()) {
+        let entry_name = entry.file_name().to_string_lossy().to_string();
+        if entry_name.ends_with(PENDING_SUFFIX) {
+            findings.push(Finding::fixable(format!(
+                "notes/{} left by an interrupted write",
+                entry_name
+            )));
+            if fix {
+                std::fs::remove_file(entry.path())?;
+            }
+        }
+    }
+
+    for category in NOTE_CATEGORIES.iter().chain(EXTRA_NOTES) {
+        let path = project.notes_path(category);
+        let Ok(bytes) = std::fs::read(&path) else {
+            continue;
+        };
+        if std::str::from_utf8(&bytes).is_ok() {
+            continue;
+        }
+        findings.push(Finding::fixable(format!(
+            "notes/{}.md is not valid UTF-8; replacing bad bytes (original kept as {}.md{})",
+            category, category, CORRUPT_SUFFIX
+        )));
+        if fix {
+            std::fs::write(path.with_extension(format!("md{}", CORRUPT_SUFFIX)), &bytes)?;
+            project.write_notes(category, &String::from_utf8_lossy(&bytes))?;
+        }
+    }
+    Ok(())
+}
+
+/// Missing numbers below the highest, as ranges like "3" or "5-7"
+fn number_gaps(numbers: impl Iterator<Item = u32>) -> Vec<String> {
+    let mut gaps = Vec::new();
+    let mut expected = 1;
+    for number in numbers {
+        if number > expected {
+            if number - 1 == expected {
+                gaps.push(expected.to_string());
+            } else {
+                gaps.push(format!("{}-{}", expected, number - 1));
+            }
+        }
+        expected = expected.max(number + 1);
+    }
+    gaps
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project_dir(root: &Path) -> std::path::PathBuf {
+        let path = root.join("api");
+        std::fs::create_dir_all(path.join("notes")).unwrap();
+        std::fs::create_dir_all(path.join("tasks")).unwrap();
+        for category in NOTE_CATEGORIES {
+            std::fs::write(path.join(format!("notes/{}.md", category)), "").unwrap();
+        }
+        path
+    }
+
+    #[test]
+    fn test_check_project_restores_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = project_dir(dir.path());
+        let mut project = Project::new(ProjectMetadata::new("api"), path.clone());
+        project.metadata.stats.total_tasks = 2;
+        project.metadata.stats.last_task_number = 2;
+        project.save_metadata().unwrap();
+        // The second save leaves the first as the backup
+        project.save_metadata().unwrap();
+        std::fs::write(path.join(METADATA_FILE), "name = \"api\"\ncreat").unwrap();
+        for num in 1..=3 {
+            std::fs::write(path.join(format!("tasks/00{}-task.json", num)), "{}").unwrap();
+        }
+
+        let findings = check_project(&path, "api", false).unwrap();
+        assert_eq!(findings.len(), 3);
+        assert!(findings[0]
+            .message
+            .contains("restoring from project.toml.bak"));
+        assert!(findings.iter().all(|f| f.fixable));
+
+        check_project(&path, "api", true).unwrap();
+        assert!(check_project(&path, "api", false).unwrap().is_empty());
+        let metadata: ProjectMetadata =
+            toml::from_str(&std::fs::read_to_string(path.join(METADATA_FILE)).unwrap()).unwrap();
+        assert_eq!(metadata.stats.last_task_number, 3);
+        assert_eq!(metadata.stats.total_tasks, 3);
+    }
+
+    #[test]
+    fn test_check_project_restores_pending_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = project_dir(dir.path());
+        std::fs::write(path.join(METADATA_FILE), "name = \"api\"\ncreat").unwrap();
+        let pending = path.join(format!("{}.42.0-7{}", METADATA_FILE, PENDING_SUFFIX));
+        std::fs::write(
+            &pending,
+            toml::to_string(&ProjectMetadata::new("api")).unwrap(),
+        )
+        .unwrap();
+
+        let findings = check_project(&path, "api", true).unwrap();
+        assert!(findings[0]
+            .message
+            .contains("restoring from project.toml.42.0-7.tmp"));
+        assert!(!pending.exists());
+        assert!(check_project(&path, "api", false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_check_project_task_logs_and_notes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = project_dir(dir.path());
+        Project::new(ProjectMetadata::new("api"), path.clone())
+            .save_metadata()
+            .unwrap();
+        std::fs::write(path.join("tasks/001-a.json"), "{}").unwrap();
+        std::fs::write(path.join("tasks/004-a.json"), "{}").unwrap();
+        std::fs::write(path.join("tasks/004-b.json"), "{}").unwrap();
+        std::fs::write(path.join("tasks/005-a.json"), "{\"task_number\": ").unwrap();
+        std::fs::write(path.join("notes/plan.md"), b"- step \xff\n").unwrap();
+        std::fs::remove_file(path.join("notes/decisions.md")).unwrap();
+
+        let findings = check_project(&path, "api", true).unwrap();
+        let manual: Vec<&str> = findings
+            .iter()
+            .filter(|f| !f.fixable)
+            .map(|f| f.message.as_str())
+            .collect();
+        assert_eq!(
+            manual,
+            vec![
+                "task number 4 is used by 2 logs: 004-a.json, 004-b.json",
+                "task numbers missing from the logs: 2-3",
+            ]
+        );
+        assert!(path.join("tasks/005-a.json.corrupt").exists());
+        assert!(path.join("notes/decisions.md").exists());
+        assert!(std::fs::read_to_string(path.join("notes/plan.md"))
+            .unwrap()
+            .contains('\u{FFFD}'));
+        assert!(path.join("notes/plan.md.corrupt").exists());
+    }
+
+    #[test]
+    fn test_number_gaps() {
+        assert_eq!(
+            number_gaps([2, 3, 4, 8, 10].into_iter()),
+            vec!["1", "5-7", "9"]
+        );
+        assert!(number_gaps([1, 2, 2, 3].into_iter()).is_empty());
+    }
+
+    #[test]
+    fn test_check_project_unlinks_missing_parent() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = project_dir(dir.path());
+        let mut metadata = ProjectMetadata::new("api");
+        metadata.parent = Some("platform".to_string());
+        Project::new(metadata, path.clone())
+            .save_metadata()
+            .unwrap();
+
+        let findings = check_project(&path, "api", false).unwrap();
+        assert_eq!(
+            findings,
+            vec![Finding::fixable(
+                "parent project 'platform' no longer exists; unlinking".to_string()
+            )]
+        );
+        // Once the parent exists, the link is fine
+        std::fs::create_dir_all(dir.path().join("platform")).unwrap();
+        assert!(check_project(&path, "api", false).unwrap().is_empty());
+    }
+}
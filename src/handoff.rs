@@ -0,0 +1,97 @@
+//! Session handoff blurbs
+//!
+//! `/handoff` asks the extraction model for a short "state of the work"
+//! paragraph — what was done, what's next, open risks — from this session's
+//! tasks and the plan. The blurb is copied to the clipboard when a clipboard
+//! tool is available and appended to the project's `journal.md`.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use std::io::Write;
+
+use crate::config::load_config;
+use crate::extraction::complete;
+use crate::project::Project;
+
+/// Journal of handoff blurbs, in the project directory
+pub const JOURNAL_FILE: &str = "journal.md";
+
+/// Asks Claude for a handoff paragraph covering `tasks` as
+/// `(number, prompt, summary)`
+pub async fn generate_handoff(project: &Project, tasks: &[(u32, &str, &str)]) -> Result<String> {
+    let config = load_config()?;
+    let prompt = build_handoff_prompt(&project.metadata.name, tasks, &project.read_notes("plan")?);
+    let (response, _model) = complete(&config, &prompt).await?;
+    Ok(response.trim().to_string())
+}
+
+fn build_handoff_prompt(project_name: &str, tasks: &[(u32, &str, &str)], plan: &str) -> String {
+    let task_lines: String = tasks
+        .iter()
+        .map(|(number, prompt, summary)| format!("{}. {} — {}\n", number, prompt, summary))
+        .collect();
+    format!(
+        r#"You are writing a handoff note for a teammate picking up work on the project "{project}".
+
+## Tasks done this session
+{tasks}
+## Current plan
+{plan}
+
+---
+
+Write one short paragraph (at most 6 sentences) covering what was done,
+what's next, and any open risks. Plain prose, no headers or bullets."#,
+        project = project_name,
+        tasks = task_lines,
+        plan = if plan.trim().is_empty() {
+            "(no plan)"
+        } else {
+            plan.trim()
+        },
+    )
+}
+
+/// Appends a dated blurb to the project's journal
+pub fn append_journal(project: &Project, blurb: &str) -> Result<()> {
+    let path = project.path.join(JOURNAL_FILE);
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    file.write_all(journal_entry(blurb, Local::now()).as_bytes())
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn journal_entry(blurb: &str, at: DateTime<Local>) -> String {
+    format!(
+        "## {} — handoff\n\n{}\n\n",
+        at.format("%Y-%m-%d %H:%M"),
+        blurb.trim()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_build_handoff_prompt() {
+        let prompt =
+            build_handoff_prompt("api", &[(3, "add retries", "Added retry with backoff")], "");
+        assert!(prompt.contains("\"api\""));
+        assert!(prompt.contains("3. add retries — Added retry with backoff\n"));
+        assert!(prompt.contains("(no plan)"));
+    }
+
+    #[test]
+    fn test_journal_entry() {
+        let at = Local.with_ymd_and_hms(2025, 3, 4, 17, 5, 0).unwrap();
+        assert_eq!(
+            journal_entry("Done.\n", at),
+            "## 2025-03-04 17:05 — handoff\n\nDone.\n\n"
+        );
+    }
+}
@@ -0,0 +1,297 @@
+//! Offline syntax highlighting for exported HTML
+//!
+//! `clancy show --format html` pages must work without network access, so
+//! instead of a JavaScript highlighter this module colors code at render
+//! time with inline styles. The lexer is deliberately small: comments,
+//! strings, numbers, and keywords for the languages agents write most.
+//! Unknown languages are escaped and left plain.
+
+use crate::render::escape_html;
+
+const COMMENT_STYLE: &str = "color:#6e7781;font-style:italic";
+const STRING_STYLE: &str = "color:#0a3069";
+const NUMBER_STYLE: &str = "color:#0550ae";
+const KEYWORD_STYLE: &str = "color:#cf222e";
+const ADDED_STYLE: &str = "color:#116329;background:#dafbe1";
+const REMOVED_STYLE: &str = "color:#82071e;background:#ffebe9";
+const HUNK_STYLE: &str = "color:#8250df";
+
+/// How one language's tokens look
+struct Syntax {
+    /// Space-separated
+    keywords: &'static str,
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [char],
+}
+
+const RUST: Syntax = Syntax {
+    keywords: "as async await break const continue crate dyn else enum extern false fn \
+        for if impl in let loop match mod move mut pub ref return self Self \
+        static struct super trait true type unsafe use where while",
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    // A lone ' starts a lifetime more often than a char literal
+    quotes: &['"'],
+};
+
+const PYTHON: Syntax = Syntax {
+    keywords: "and as assert async await break class continue def del elif else except \
+        False finally for from global if import in is lambda None nonlocal not \
+        or pass raise return True try while with yield",
+    line_comments: &["#"],
+    block_comment: None,
+    quotes: &['"', '\''],
+};
+
+const JAVASCRIPT: Syntax = Syntax {
+    keywords: "async await break case catch class const continue default delete do else \
+        export extends false finally for from function if import in instanceof \
+        interface let new null return switch this throw true try type typeof \
+        undefined var void while yield",
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"', '\'', '`'],
+};
+
+const GO: Syntax = Syntax {
+    keywords: "break case chan const continue default defer else false for func go goto \
+        if import interface map nil package range return select struct switch \
+        true type var",
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"', '\'', '`'],
+};
+
+const C_FAMILY: Syntax = Syntax {
+    keywords: "abstract auto bool break case catch char class const continue default \
+        delete do double else enum extends extern false final float for if \
+        implements import include int interface long namespace new null nullptr \
+        package private protected public return short signed sizeof static \
+        struct switch template this throw true try typedef union unsigned using \
+        virtual void volatile while",
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"', '\''],
+};
+
+const SHELL: Syntax = Syntax {
+    keywords: "case do done elif else esac export fi for function if in local return \
+        then until while",
+    line_comments: &["#"],
+    block_comment: None,
+    quotes: &['"', '\''],
+};
+
+const JSON: Syntax = Syntax {
+    keywords: "true false null",
+    line_comments: &[],
+    block_comment: None,
+    quotes: &['"'],
+};
+
+const TOML: Syntax = Syntax {
+    keywords: "true false",
+    line_comments: &["#"],
+    block_comment: None,
+    quotes: &['"', '\''],
+};
+
+const YAML: Syntax = Syntax {
+    keywords: "true false null yes no",
+    line_comments: &["#"],
+    block_comment: None,
+    quotes: &['"', '\''],
+};
+
+/// The syntax for a fence language name or common file extension
+fn syntax_for(language: &str) -> Option<&'static Syntax> {
+    match language.to_ascii_lowercase().as_str() {
+        "rust" | "rs" => Some(&RUST),
+        "python" | "py" => Some(&PYTHON),
+        "javascript" | "js" | "jsx" | "typescript" | "ts" | "tsx" => Some(&JAVASCRIPT),
+        "go" | "golang" => Some(&GO),
+        "c" | "h" | "cpp" | "c++" | "cc" | "hpp" | "java" | "kotlin" | "kt" | "csharp" | "cs"
+        | "swift" => Some(&C_FAMILY),
+        "sh" | "bash" | "shell" | "zsh" | "console" => Some(&SHELL),
+        "json" | "jsonl" => Some(&JSON),
+        "toml" => Some(&TOML),
+        "yaml" | "yml" => Some(&YAML),
+        _ => None,
+    }
+}
+
+/// Highlights code as escaped HTML with inline-styled spans; code in a
+/// language without rules is only escaped
+pub fn highlight_html(code: &str, language: &str) -> String {
+    if language.eq_ignore_ascii_case("diff") || language.eq_ignore_ascii_case("patch") {
+        return highlight_diff(code);
+    }
+    match syntax_for(language) {
+        Some(syntax) => highlight_with(code, syntax),
+        None => escape_html(code),
+    }
+}
+
+fn span(style: &str, text: &str) -> String {
+    format!("<span style=\"{}\">{}</span>", style, escape_html(text))
+}
+
+/// Colors added, removed, and hunk header lines of a unified diff
+fn highlight_diff(code: &str) -> String {
+    let lines: Vec<String> = code
+        .split('\n')
+        .map(|line| {
+            if line.starts_with("+++") || line.starts_with("---") {
+                escape_html(line)
+            } else if line.starts_with('+') {
+                span(ADDED_STYLE, line)
+            } else if line.starts_with('-') {
+                span(REMOVED_STYLE, line)
+            } else if line.starts_with("@@") {
+                span(HUNK_STYLE, line)
+            } else {
+                escape_html(line)
+            }
+        })
+        .collect();
+    lines.join("\n")
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Byte length of the string literal starting at `rest`, which begins with
+/// `quote`; a literal left open runs to the end of the line (or of the
+/// input, for triple quotes and backticks)
+fn string_len(rest: &str, quote: char) -> usize {
+    let triple = quote.to_string().repeat(3);
+    if quote != '`' && rest.starts_with(&triple) {
+        return rest[3..].find(&triple).map_or(rest.len(), |end| end + 6);
+    }
+    let mut escaped = false;
+    for (i, c) in rest.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == quote {
+            return i + c.len_utf8();
+        } else if c == '\n' && quote != '`' {
+            return i;
+        }
+    }
+    rest.len()
+}
+
+fn highlight_with(code: &str, syntax: &Syntax) -> String {
+    let mut html = String::with_capacity(code.len() * 2);
+    let mut plain_start = 0;
+    let mut i = 0;
+    // Whether the previous char continues an identifier, so digits inside
+    // names (like `utf8`) aren't colored as numbers
+    let mut in_word = false;
+
+    while i < code.len() {
+        let rest = &code[i..];
+        let c = rest.chars().next().unwrap_or_default();
+        let token = if syntax.line_comments.iter().any(|m| rest.starts_with(m)) {
+            Some((COMMENT_STYLE, rest.find('\n').unwrap_or(rest.len())))
+        } else if let Some((open, close)) = syntax
+            .block_comment
+            .filter(|(open, _)| rest.starts_with(open))
+        {
+            let len = rest[open.len()..]
+                .find(close)
+                .map_or(rest.len(), |end| open.len() + end + close.len());
+            Some((COMMENT_STYLE, len))
+        } else if syntax.quotes.contains(&c) {
+            Some((STRING_STYLE, string_len(rest, c)))
+        } else if c.is_ascii_digit() && !in_word {
+            let len = rest
+                .find(|c: char| !(is_ident_char(c) || c == '.'))
+                .unwrap_or(rest.len());
+            Some((NUMBER_STYLE, len))
+        } else if is_ident_char(c) && !in_word {
+            let len = rest.find(|c| !is_ident_char(c)).unwrap_or(rest.len());
+            let word = &rest[..len];
+            if syntax.keywords.split_whitespace().any(|k| k == word) {
+                Some((KEYWORD_STYLE, len))
+            } else {
+                i += len;
+                in_word = true;
+                continue;
+            }
+        } else {
+            None
+        };
+
+        match token {
+            Some((style, len)) => {
+                html.push_str(&escape_html(&code[plain_start..i]));
+                html.push_str(&span(style, &rest[..len]));
+                i += len;
+                plain_start = i;
+                in_word = false;
+            }
+            None => {
+                in_word = is_ident_char(c);
+                i += c.len_utf8();
+            }
+        }
+    }
+    html.push_str(&escape_html(&code[plain_start..]));
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_rust() {
+        let html = highlight_html("let s = \"a<b\"; // done\nfn f() -> u8 { 42 }", "rust");
+        assert!(html.starts_with(&format!(
+            "<span style=\"{}\">let</span> s = ",
+            KEYWORD_STYLE
+        )));
+        assert!(html.contains(&format!(
+            "<span style=\"{}\">&quot;a&lt;b&quot;</span>",
+            STRING_STYLE
+        )));
+        assert!(html.contains(&format!(
+            "<span style=\"{}\">// done</span>\n",
+            COMMENT_STYLE
+        )));
+        assert!(html.contains(&format!("<span style=\"{}\">42</span>", NUMBER_STYLE)));
+        // Identifiers that merely contain a keyword or digits stay plain
+        assert!(html.contains(" -&gt; u8 "));
+    }
+
+    #[test]
+    fn test_highlight_python_triple_quotes() {
+        let html = highlight_html("x = \"\"\"a\n# not a comment\"\"\"\n", "py");
+        assert!(html.contains(&format!(
+            "<span style=\"{}\">&quot;&quot;&quot;a\n# not a comment&quot;&quot;&quot;</span>",
+            STRING_STYLE
+        )));
+    }
+
+    #[test]
+    fn test_highlight_diff() {
+        let html = highlight_html("--- a/x\n+++ b/x\n@@ -1 +1 @@\n-a < b\n+a > b", "diff");
+        assert!(html.starts_with("--- a/x\n+++ b/x\n"));
+        assert!(html.contains(&format!(
+            "<span style=\"{}\">-a &lt; b</span>",
+            REMOVED_STYLE
+        )));
+        assert!(html.contains(&format!("<span style=\"{}\">+a &gt; b</span>", ADDED_STYLE)));
+    }
+
+    #[test]
+    fn test_highlight_unknown_language_only_escapes() {
+        assert_eq!(highlight_html("if a < b", "cobol"), "if a &lt; b");
+        assert_eq!(highlight_html("if a < b", ""), "if a &lt; b");
+    }
+}
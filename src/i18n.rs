@@ -0,0 +1,595 @@
+//! User-facing message catalog
+//!
+//! REPL and project messages are looked up by key so they can be translated.
+//! Output of the other subcommands, and error causes from I/O or the API,
+//! stay English.
+//! English is built in. With `[i18n] locale = "de"`, messages are read from
+//! `~/.config/clancy/locales/de.toml` (keys as `"task.done" = "..."` or
+//! nested tables), and any key it lacks falls back to English. Messages use
+//! `{name}` placeholders. Context sent to Claude stays English unless
+//! `i18n.localize_context` is set.
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::config;
+
+/// Built-in English messages
+const ENGLISH: &[(&str, &str)] = &[
+    // Session lifecycle
+    (
+        "session.loading",
+        "Loading project: {name} ({sessions} prior sessions, {tasks} tasks)",
+    ),
+    ("session.context", "Injected context (~{tokens} tokens)\n"),
+    ("session.exit_hint", "Use /done or /quit to exit"),
+    ("session.ended", "Session complete. {tasks} tasks."),
+    (
+        "session.done",
+        "Session complete. {tasks} tasks, notes updated.",
+    ),
+    ("session.no_tasks", "No tasks this session."),
+    (
+        "command.unknown",
+        "Unknown command: {command}. Type /help for available commands.",
+    ),
+    (
+        "mode.full",
+        "Switched to full conversation mode. Next task will include complete prior context.",
+    ),
+    (
+        "mode.fresh",
+        "Switched to fresh mode. Next task will only include notes, no session history.",
+    ),
+    (
+        "mode.summary",
+        "Switched to summary mode (default). Next task will include task summaries.",
+    ),
+    (
+        "mode.downgraded",
+        "[Context] Switched from {from} to {to} mode: {reason}",
+    ),
+    // Tasks
+    (
+        "task.injecting",
+        "\n[Task {num}] Injecting context (~{tokens} tokens{estimate})...\n",
+    ),
+    ("task.estimate", ", est. ${cost}+ input"),
+    ("task.done", "[Task {num} {outcome}{duration}{cost}]"),
+    ("task.complete", "complete"),
+    ("task.interrupted", "interrupted"),
+    ("task.duration", " in {secs}s"),
+    ("task.cost", " (${cost}{cache})"),
+    ("task.cache_saved", ", cache saved ~${saved}"),
+    ("task.cost_limited", " (~${cost}, stopped at cost limit)"),
+    ("task.error", "Task error: {error}"),
+    ("task.extraction_skipped", "Extraction skipped."),
+    // Projects
+    ("project.none", "No projects found."),
+    ("project.name", "Project: {name}"),
+    ("project.status", "Status: {status}"),
+    (
+        "project.parent",
+        "Parent: {parent} (inherits: {categories})",
+    ),
+    ("project.parent_bare", "Parent: {parent}"),
+    ("project.load_failed", "Warning: skipping project '{name}': {error}"),
+    ("project.created", "Created: {date}"),
+    ("project.last_task", "Last task: {date}"),
+    ("project.stats", "Stats: {sessions} sessions, {tasks} tasks"),
+    ("project.children", "\nChildren:"),
+    ("project.archived", "Project '{name}' archived."),
+    ("project.unarchived", "Project '{name}' is active again."),
+    ("project.not_archived", "Project '{name}' is not archived."),
+    (
+        "project.archived_hidden",
+        "({count} archived hidden; show them with --archived or --all)",
+    ),
+    ("project.no_parent", "Project '{name}' has no parent link."),
+    ("project.unlinked", "Unlinked '{name}' from '{parent}'."),
+    ("project.deleted", "Project '{name}' deleted."),
+    ("project.trashed", "Project '{name}' moved to {path}."),
+    ("project.renamed", "Renamed '{old}' to '{new}'."),
+    ("project.relinked", "Updated parent link of: {children}"),
+    (
+        "project.schedules_renamed",
+        "Updated {count} [[daemon.schedule]] entries in config.toml",
+    ),
+    ("prompt.cancelled", "Cancelled."),
+    // Context size (/tokens)
+    ("tokens.header", "\n## Context Size\n"),
+    ("tokens.section", "  {tokens}  {title}"),
+    ("tokens.total", "  {tokens}  total"),
+    (
+        "tokens.over",
+        "\nOver max_context_tokens ({max}) by ~{over}; later sections will be truncated.",
+    ),
+    ("tokens.headroom", "\nHeadroom: ~{free} of {max} tokens"),
+    ("tokens.next_history", "Next task adds ~{tokens} tokens of history ({mode} mode)"),
+    ("tokens.next_full", "Next task adds its full transcript (full mode)"),
+    // Warnings
+    ("warn.note_usage", "Warning: could not record note usage: {error}"),
+    ("warn.ctrlc", "Warning: could not install Ctrl+C handler: {error}"),
+    ("warn.read_file", "Warning: could not read {path}: {error}"),
+    // Resuming and running tasks
+    ("resume.none", "No previous tasks to resume."),
+    ("resume.one", "Resumed task {num} from the previous session"),
+    ("resume.many", "Resumed {count} tasks from the previous session ({first}-{last})"),
+    ("sandbox.running", "[Running in {runtime} container {image}]"),
+    ("task.cost_limit_hit", "\n[Cost limit reached: ~${spent} > ${limit}; stopping task]"),
+    ("task.lossy", "[{lines} output lines contained invalid UTF-8 and were decoded lossily]"),
+    ("task.cancelled", "[Task cancelled; keeping partial output]"),
+    ("task.exit_code", "[Task failed with exit code: {code}]"),
+    // Tasks and planning
+    ("task.skipped", "Task skipped."),
+    ("task.routed", "[Routed to {target} by {reason}]"),
+    ("plan.ready", "[Plan ready (${cost})]"),
+    ("plan.confirm", "Execute this plan? [y/N]: "),
+    ("plan.discarded", "Plan discarded."),
+    (
+        "task.cost_confirm",
+        "Task {num} is estimated at ${cost} input, above your ${threshold} threshold. Run it? [y/N]: ",
+    ),
+    ("plan.injecting", "\n[Planning] Injecting context (~{tokens} tokens)...\n"),
+    ("task.recovered", "Recovered interrupted task {num} ({messages} messages captured)"),
+    // Reviewer pass
+    ("review.running", "Reviewing..."),
+    ("review.runtime_error", " error creating runtime: {error}"),
+    ("review.error", " error: {error}"),
+    ("review.verdict", " {verdict}"),
+    ("review.added", "  Added to {label}:\n{items}"),
+    ("review.apply_failed", "Warning: failed to apply review: {error}"),
+    ("review.record_failed", "Warning: failed to record review in task log: {error}"),
+    // Note extraction and compaction
+    ("extract.running", "Extracting notes..."),
+    ("extract.runtime_error", " error creating runtime: {error}"),
+    ("extract.offline", " (offline, rule-based)"),
+    ("extract.fell_back", " (fell back to {model})"),
+    ("extract.found", " found: {summary}"),
+    ("extract.applying", "Applying notes..."),
+    ("extract.apply_error", " error applying notes: {error}"),
+    ("extract.updated", " updated: {summary}"),
+    ("extract.no_updates", " no updates"),
+    ("extract.record_failed", "Warning: failed to record notes in task log: {error}"),
+    ("extract.error", " error: {error}"),
+    ("compact.none", "No tasks to compact."),
+    ("compact.running", "Compacting {count} tasks..."),
+    ("compact.done", " done. Session history compacted."),
+    // Auto mode
+    ("auto.found", "\nFound {count} phases in {file}:\n"),
+    ("auto.phase_item", "  {num}. {title}"),
+    ("auto.start_prompt", "\nPress Enter to start, or Ctrl+C to cancel..."),
+    ("auto.phase_header", "Phase {num}/{total}: {title}"),
+    ("auto.phase_skipped", "\nPhase {num} was skipped. Stopping auto mode."),
+    ("auto.phase_interrupted", "\nPhase {num} was interrupted. Stopping auto mode."),
+    ("auto.resume_later", "The next session offers to resume from this phase."),
+    ("auto.phase_failed", "\nPhase {num} failed: {error}"),
+    ("auto.stopping", "Stopping auto mode. Use /history to see completed phases."),
+    ("auto.stopped", "Stopped. {done} of {total} phases complete."),
+    ("auto.all_done", "All {count} phases complete!"),
+    ("auto.remaining", "Remaining:"),
+    ("auto.estimate", "Estimated cost: ~${cost}"),
+    ("auto.resume_prompt", "Resume now? [Y]es / [n]ot now / [d]iscard: "),
+    ("auto.discarded", "Checkpoint discarded."),
+    ("auto.kept", "Kept; you'll be asked again next session."),
+    ("auto.phase_done", "\nPhase {num} complete. Press Enter for next phase, or 'q' to stop..."),
+    ("auto.checkpoint_stale", "Discarding /auto checkpoint: {file} is missing or has changed."),
+    (
+        "auto.unfinished",
+        "\nUnfinished /auto run from {started}: {done} of {total} phases complete ({file}).",
+    ),
+    // Session commands
+    ("fork.switch_hint", "Use /fork {name} --switch to continue the session there."),
+    ("annotate.done", "Annotated task {num}."),
+    ("handoff.writing", "Writing handoff..."),
+    ("handoff.not_copied", "Added to {file} (not copied: {error})."),
+    ("pin.done", "Pinned to {category}."),
+    ("star.starred", "Starred task {num}."),
+    ("star.unstarred", "Unstarred task {num}."),
+    ("tasks.no_starred", "No starred tasks."),
+    ("tasks.none_stored", "No stored tasks."),
+    ("mode.permission", "Permission mode for next tasks: {mode}"),
+    ("sandbox.on", "Tasks will run inside the sandbox container."),
+    ("sandbox.off", "Tasks will run on the host."),
+    ("cd.current", "Tasks will run in {dir}"),
+    ("attach.added", "{file} will be included in the next task's context."),
+    ("attach.not_file", "Not a file: {path}"),
+    ("clipboard.empty", "Clipboard is empty."),
+    ("copy.missing", "Task {num} has no {what} text."),
+    ("extract.enabled", "Note extraction enabled."),
+    ("extract.disabled", "Note extraction disabled until /extract on."),
+    ("editor.failed", "Editor exited with error"),
+    ("similar.ran", "Task {num} ran this {when}."),
+    ("similar.prompt", "[v]iew it, [r]un anyway, or [c]ancel? "),
+    ("settings.updated", "Updated .claude/settings.json from project.toml"),
+    ("fork.done", "Forked '{from}' -> '{to}' (notes copied, linked as child)."),
+    ("fork.switched", "Switched session to '{name}'. Injected context (~{tokens} tokens)"),
+    ("session.complete", "Session complete for '{name}'. {tasks} tasks, notes updated."),
+    ("handoff.copied", "Copied to clipboard and added to {file}."),
+    ("pin.added", "Added to {category}. It will be in context for the next task."),
+    ("mode.usage", "Permission mode is {mode}. Usage: /mode {modes}"),
+    ("sandbox.usage", "Sandbox is {state}. Usage: /sandbox on|off"),
+    ("clipboard.pasted", "Pasted {lines} lines; edit the prompt and press Enter to run it."),
+    ("copy.done", "Copied task {num}'s {what} ({chars} chars)."),
+    ("extract.usage", "Extraction is {state}. Usage: /extract on|off"),
+    ("status.tasks", "Session tasks: {session} | Total tasks: {total}"),
+    ("notes.invalid_category", "Invalid category '{category}'. Valid: {valid}, {extra}"),
+    ("extract.accept_prompt", "{choices} — accept which? (Enter=all, n=none): "),
+    (
+        "decisions.archived",
+        "Archived {count} decision entries older than {months} months to {file}.md",
+    ),
+    // Command usage
+    ("usage.note_rm", "Usage: /note rm <category> <id>"),
+    ("usage.why", "Usage: /why <note-id>"),
+    ("usage.ask", "Usage: /ask <question>"),
+    ("usage.config", "Usage: /config [set <key> <value> [--save]]"),
+    ("usage.attach", "Usage: /attach [--head] <file>"),
+    ("usage.copy", "Usage: /copy [result|message]"),
+    // Command errors
+    ("error.auto", "Auto error: {error}"),
+    ("error.notes", "Notes error: {error}"),
+    ("error.note", "Note error: {error}"),
+    ("error.switch", "Switch error: {error}"),
+    ("error.plan", "Plan error: {error}"),
+    ("error.tasks", "Tasks error: {error}"),
+    ("error.star", "Star error: {error}"),
+    ("error.cd", "cd error: {error}"),
+    ("error.config", "Config error: {error}"),
+    ("error.verify", "Verify error: {error}"),
+    ("error.handoff", "Handoff error: {error}"),
+    ("error.pin", "Pin error: {error}"),
+    ("error.annotate", "Annotate error: {error}"),
+    ("error.generic", "Error: {error}"),
+    ("error.mcp_missing", "MCP config not found: {path}"),
+    ("sandbox.no_image", "Sandbox mode needs sandbox.image set in config.toml"),
+    ("task.dir_outside", "Task directory must be inside the project root: {dir}"),
+    ("task.no_such_dir", "No such directory: {dir}"),
+    ("usage.plan_task", "Usage: /plan-task <prompt>"),
+    ("plan.empty", "Planning run produced no plan"),
+    (
+        "auto.plan_missing",
+        "Plan file not found: {path}\nUsage: /auto [file.md]  (defaults to PLAN.md)",
+    ),
+    (
+        "auto.no_phases",
+        "No phases found in {file}.\nExpected format:\n\n## Phase 1: Title\nDescription of what to do.\n\n## Phase 2: Title\n...",
+    ),
+    ("usage.switch", "Usage: /switch <project>"),
+    ("switch.same_project", "Already in project '{name}'"),
+    ("usage.pin", "Usage: /pin <category> [text]"),
+    ("usage.note", "Usage: /note <category> <text>"),
+    ("verify.no_command", "No test command detected. Usage: /verify <command>"),
+    (
+        "template.unknown_none",
+        "Unknown template '{name}'; add one under [templates.{name}] in project.toml",
+    ),
+    ("template.unknown", "Unknown template '{name}'. Available: {available}"),
+    ("config.bad_edit_mode", "Unknown repl.edit_mode '{mode}' (use emacs or vi)"),
+    // /config
+    ("config.header", "\n## Effective Config\n"),
+    ("config.saved", "{key} = {value} (saved to config.toml)"),
+    ("config.session", "{key} = {value} for the rest of this session"),
+    // /status, /last and /history
+    ("status.project", "\n## Project: {name}"),
+    ("status.session", "\n## This Session\n{metrics}"),
+    ("status.plan", "\n## Current Plan\n{plan}"),
+    ("status.decisions", "\n## Recent Decisions"),
+    ("last.header", "\n## Task {num}: {prompt}\n"),
+    ("last.no_edits", "No file edits recorded.\n"),
+    ("history.header", "\n## Task History\n"),
+    // Verification and templates
+    ("verify.running", "[verify] {command}"),
+    ("verify.passed", "[verify] passed"),
+    ("verify.failed_code", "[verify] failed (exit {code})"),
+    ("verify.failed", "[verify] failed"),
+    (
+        "template.none",
+        "No task templates. Add one to project.toml:\n\n[templates.dependency-audit]\nprompt = \"Audit dependencies for outdated or vulnerable crates\"\nverify = \"cargo test\"",
+    ),
+    // Dependency and .gitignore checks
+    ("deps.unrequested", "!! DEPENDENCIES: changed without being asked for !!"),
+    ("deps.changed", "Dependencies changed:"),
+    (
+        "gitignore.missing",
+        "The .claude/ directory (used for context injection) is not in .gitignore.",
+    ),
+    ("gitignore.prompt", "Add '.claude/' to .gitignore? [Y/n] "),
+    ("gitignore.added", "Added '.claude/' to .gitignore\n"),
+    // Tool policy
+    ("policy.violation", "!!   {tool} `{input}` (rule: {rule})"),
+    ("policy.header_one", "!! POLICY: {count} discouraged tool call !!"),
+    ("policy.header_many", "!! POLICY: {count} discouraged tool calls !!"),
+    // /help
+    (
+        "help.text",
+        r#"
+## Clancy REPL Commands
+
+  <task description>   Run a task via Claude
+  /status              Show current notes summary
+  /notes [category]    Edit notes (architecture|decisions|failures|plan|instructions|scratch)
+  /history             Show task history this session
+  /last                Show the last task's summary and file changes
+  /notes show [category]
+                       List note entries with their IDs
+  /note <category> <text>
+                       Append a timestamped note entry
+  /note rm <category> <id>
+                       Remove a note entry by ID
+  /why <note-id>       Show the task that produced a note entry and its transcript
+  /pin <category> [text]
+                       Append text (or the last reply's final paragraph) to notes
+  /handoff             Write a handoff paragraph (copied, added to journal.md)
+  /tasks [--starred]   List stored tasks for this project
+  /star [task]         Star a task as a landmark run (/unstar to remove)
+  /annotate [task] <text>
+                       Attach a reviewer note to a task (default: last)
+  /auto [file]         Run phases from PLAN.md (or specified file)
+  /extract on|off      Toggle note extraction (or prefix a task with @noextract)
+  /plan-task <prompt>  Plan in read-only mode, approve, then execute
+  /ask <question>      Ask a read-only question (no edits, plan left alone)
+  /cd [subdir]         Run tasks in a subdirectory (no arg: project root)
+  /verify [command]    Run a check command (default: detected test command)
+  /run-template [name] Run a task template from project.toml (no name: list them)
+  /tokens              Show context size per section and remaining headroom
+  /config [set <key> <value> [--save]]
+                       Show effective settings, or change one for this session
+  /attach [--head] <file>
+                       Include a file in the next task's context (its end
+                       when capped, or its start with --head)
+  /paste [text]        Start the next prompt with the clipboard (after text)
+  /copy [result|message]
+                       Copy the last task's result or final assistant message
+  /sandbox on|off      Run tasks inside the configured container
+  /mode plan|acceptEdits|default
+                       Set Claude Code's permission mode for next tasks
+  /fork <name> [--switch]
+                       Branch a child project with copied notes
+  /switch <project>    Close this project's session and continue in another
+
+## Conversation Modes (current: {mode})
+
+  /continue            Switch to full mode (include complete prior context)
+  /compact             Summarize history and start fresh
+  /fresh               Switch to fresh mode (only notes, no history)
+  /summary             Switch to summary mode (default)
+
+## Session
+
+  /done or /quit       Exit the session
+  /help                Show this help
+"#,
+    ),
+    // Project picker and CLI
+    ("project.picker_empty", "No projects yet."),
+    ("project.picker_row", "  {num}. {name} {date}  {tasks} tasks"),
+    ("project.picker_more", "  ... {count} more; type to filter"),
+    ("project.picker_prompt", "Project (number, filter, +new-name, Enter to cancel): "),
+    ("project.no_number", "No project numbered {num}."),
+    ("project.no_match", "No project matches '{input}'. Use +{input} to create it."),
+    (
+        "project.did_you_mean",
+        "Project '{name}' doesn't exist. Did you mean '{suggestion}'? [Y]es / [c]reate '{name}' / [q]uit: ",
+    ),
+    ("project.create_cancelled", "Cancelled. Use --new to create '{name}' without asking."),
+    (
+        "project.cache_rate",
+        "Prompt cache: {rate}% of input tokens read from cache ({read} read, {written} written)",
+    ),
+    ("project.recent_tasks", "\n## Recent Tasks\n"),
+    ("project.current_plan", "\n## Current Plan\n"),
+    ("project.recent_decisions", "\n## Recent Decisions\n"),
+    ("project.note_added", "Added to {category} notes for '{project}'."),
+    ("project.new_child", "Created project '{name}' linked to parent '{parent}'."),
+    ("project.new", "Created project '{name}'."),
+    (
+        "project.linked",
+        "Linked '{child}' -> '{parent}'. Child will inherit parent's notes: {inherit}.",
+    ),
+    ("project.restore_hint", "Restore with: mv {dest} {path}"),
+    ("project.trash_confirm", "Move to trash project '{name}' ({tasks} tasks)? [y/N]: "),
+    ("project.delete_confirm", "Permanently delete project '{name}' ({tasks} tasks)? [y/N]: "),
+    // Context section titles (only translated with i18n.localize_context)
+    ("context.architecture", "Architectural Context"),
+    ("context.decisions", "Key Decisions"),
+    ("context.failures", "Known Pitfalls"),
+    ("context.plan", "Current Plan"),
+    ("context.decisions-archive", "Archived Decisions"),
+];
+
+/// Messages for the active locale, English filling any gaps
+#[derive(Debug)]
+struct Catalog {
+    messages: HashMap<String, String>,
+    localize_context: bool,
+}
+
+impl Catalog {
+    fn english() -> Self {
+        Self {
+            messages: ENGLISH
+                .iter()
+                .map(|(key, text)| (key.to_string(), text.to_string()))
+                .collect(),
+            localize_context: false,
+        }
+    }
+
+    /// English overlaid with a locale file's messages
+    fn with_overrides(content: &str, localize_context: bool) -> Result<Self> {
+        let table: toml::Table = toml::from_str(content)?;
+        let mut catalog = Self::english();
+        flatten("", &table, &mut catalog.messages);
+        catalog.localize_context = localize_context;
+        Ok(catalog)
+    }
+
+    /// The catalog selected in config; English when the locale file is
+    /// missing or broken, with a warning
+    fn from_config() -> Self {
+        let Ok(config) = config::load_config() else {
+            return Self::english();
+        };
+        let locale = config.i18n.locale;
+        if locale == "en" {
+            return Self::english();
+        }
+        match config::locales_dir().and_then(|dir| load_locale(&dir, &locale)) {
+            Ok(content) => Self::with_overrides(&content, config.i18n.localize_context)
+                .unwrap_or_else(|e| {
+                    eprintln!(
+                        "Warning: locale '{}' is invalid ({}); using English",
+                        locale, e
+                    );
+                    Self::english()
+                }),
+            Err(e) => {
+                eprintln!("Warning: {:#}; using English", e);
+                Self::english()
+            }
+        }
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.messages.get(key).map_or(key, String::as_str)
+    }
+}
+
+fn load_locale(dir: &Path, locale: &str) -> Result<String> {
+    let path = dir.join(format!("{}.toml", locale));
+    std::fs::read_to_string(&path)
+        .with_context(|| format!("could not read locale file {}", path.display()))
+}
+
+/// Collects string values of nested tables as dotted keys
+fn flatten(prefix: &str, table: &toml::Table, out: &mut HashMap<String, String>) {
+    for (key, value) in table {
+        let key = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match value {
+            toml::Value::String(text) => {
+                out.insert(key, text.clone());
+            }
+            toml::Value::Table(inner) => flatten(&key, inner, out),
+            _ => {}
+        }
+    }
+}
+
+fn catalog() -> &'static Catalog {
+    static CATALOG: OnceLock<Catalog> = OnceLock::new();
+    // Unit tests never read the user's config
+    CATALOG.get_or_init(|| {
+        if cfg!(test) {
+            Catalog::english()
+        } else {
+            Catalog::from_config()
+        }
+    })
+}
+
+/// Replaces `{name}` placeholders with their values in one pass, so braces
+/// inside a value are never substituted; unknown placeholders are kept
+pub(crate) fn fill(template: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let value = after.find('}').and_then(|end| {
+            let name = &after[..end];
+            args.iter()
+                .find(|(arg, _)| *arg == name)
+                .map(|(_, value)| (end, value.to_string()))
+        });
+        match value {
+            Some((end, value)) => {
+                out.push_str(&value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The message for `key` in the active locale
+pub fn t(key: &str) -> String {
+    catalog().get(key).to_string()
+}
+
+/// The message for `key` with its `{name}` placeholders filled in
+pub fn tf(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    fill(catalog().get(key), args)
+}
+
+/// A context section title: `english` unless `i18n.localize_context` is
+/// set and the locale has a translation for `key`
+pub fn context_text(key: &str, english: &str) -> String {
+    let catalog = catalog();
+    match catalog.messages.get(key) {
+        Some(text) if catalog.localize_context => text.clone(),
+        _ => english.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overrides_fall_back_to_english() {
+        let catalog = Catalog::with_overrides(
+            "\"session.no_tasks\" = \"Keine Aufgaben.\"\n[task]\ncomplete = \"fertig\"\n",
+            false,
+        )
+        .unwrap();
+        assert_eq!(catalog.get("session.no_tasks"), "Keine Aufgaben.");
+        assert_eq!(catalog.get("task.complete"), "fertig");
+        assert_eq!(catalog.get("task.interrupted"), "interrupted");
+        assert_eq!(catalog.get("no.such.key"), "no.such.key");
+    }
+
+    #[test]
+    fn test_fill() {
+        assert_eq!(
+            fill(
+                Catalog::english().get("project.stats"),
+                &[("sessions", &3), ("tasks", &12)]
+            ),
+            "Stats: 3 sessions, 12 tasks"
+        );
+    }
+
+    #[test]
+    fn test_fill_leaves_placeholders_in_values_alone() {
+        assert_eq!(
+            fill("{a} and {b}", &[("a", &"{b}"), ("b", &"two")]),
+            "{b} and two"
+        );
+    }
+
+    #[test]
+    fn test_fill_keeps_unknown_placeholders() {
+        assert_eq!(fill("{a} {missing}", &[("a", &1)]), "1 {missing}");
+    }
+
+    #[test]
+    fn test_english_keys_unique() {
+        let mut keys: Vec<&str> = ENGLISH.iter().map(|(key, _)| *key).collect();
+        keys.sort();
+        keys.dedup();
+        assert_eq!(keys.len(), ENGLISH.len());
+    }
+}
@@ -0,0 +1,443 @@
+//! Importing past work from other tools as tasks
+//!
+//! Each source format has an `Importer` that turns a history file into
+//! `ImportedTask`s, which are then saved as ordinary task logs (optionally
+//! with note extraction), so switching to Clancy doesn't start memory from
+//! zero. Task logs record an `import_id` so re-imports skip what's already in.
+//!
+//! Claude Code keeps each interactive session as JSONL under
+//! `~/.claude/projects/<encoded cwd>/`; its lines share the stream-json
+//! message shape, so they're stored as-is. Other formats are converted to
+//! stream-json assistant messages so `Transcript::parse` reads them too.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::notes::stable_hash;
+use crate::project::Project;
+use crate::repl::Session;
+use crate::tasklog;
+
+/// A unit of past work recovered from another tool's history
+#[derive(Debug, PartialEq)]
+struct ImportedTask {
+    /// Stable ID used to skip the task on later imports
+    id: String,
+    prompt: String,
+    started: Option<DateTime<Utc>>,
+    /// Stream-json lines for the task's transcript
+    output: String,
+}
+
+/// Converts one history file from another tool into tasks
+trait Importer {
+    fn parse(&self, content: &str) -> Vec<ImportedTask>;
+}
+
+/// A Claude Code session JSONL file, imported as one task
+struct ClaudeCodeSession;
+
+/// An aider `.aider.chat.history.md` file; each user message is a task
+struct AiderHistory;
+
+/// A Markdown work log; each `##` section is a task
+struct MarkdownLog;
+
+/// Imports Claude Code sessions for the current directory as task logs
+///
+/// `from` overrides the session directory; sessions started before `since`
+/// are skipped.
+pub fn ingest(
+    project_name: &str,
+    since: Option<NaiveDate>,
+    from: Option<&Path>,
+    extract: bool,
+) -> Result<()> {
+    let dir = match from {
+        Some(dir) => dir.to_path_buf(),
+        None => sessions_dir(&std::env::current_dir()?)?,
+    };
+    if !dir.is_dir() {
+        bail!(
+            "No Claude Code sessions found at {} (use --from to point at another directory)",
+            dir.display()
+        );
+    }
+
+    let mut tasks = Vec::new();
+    for entry in std::fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "jsonl") {
+            continue;
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        tasks.extend(ClaudeCodeSession.parse(&content));
+    }
+    tasks.retain(|task| {
+        since.is_none_or(|date| task.started.is_some_and(|t| t.date_naive() >= date))
+    });
+    import_tasks(project_name, tasks, extract, &dir.display().to_string())
+}
+
+/// Imports an aider chat history or Markdown work log as task logs
+///
+/// `format` is `aider` or `markdown`; by default `.aider.chat.history.md`
+/// files are read as aider history and anything else as a work log.
+pub fn import_file(
+    project_name: &str,
+    path: &Path,
+    format: Option<&str>,
+    extract: bool,
+) -> Result<()> {
+    let is_aider = path
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().ends_with(".aider.chat.history.md"));
+    let importer: &dyn Importer = match format {
+        Some("aider") => &AiderHistory,
+        Some("markdown") => &MarkdownLog,
+        Some(other) => bail!("Unknown import format '{}' (use aider or markdown)", other),
+        None if is_aider => &AiderHistory,
+        None => &MarkdownLog,
+    };
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let tasks = importer.parse(&content);
+    import_tasks(project_name, tasks, extract, &path.display().to_string())
+}
+
+/// Saves tasks not imported before as task logs, oldest first
+fn import_tasks(
+    project_name: &str,
+    mut tasks: Vec<ImportedTask>,
+    extract: bool,
+    source: &str,
+) -> Result<()> {
+    let project = Project::open(project_name)?;
+    let imported = imported_ids(&project)?;
+    tasks.retain(|task| !imported.contains(&task.id));
+    if tasks.is_empty() {
+        println!("Nothing new to import from {}", source);
+        return Ok(());
+    }
+    // Stable sort keeps file order for tasks without dates
+    tasks.sort_by_key(|task| task.started);
+
+    let mut session = Session::new(project)?;
+    for task in &tasks {
+        let started = task.started.unwrap_or_else(Utc::now);
+        let number = session.import_task(&task.prompt, &task.output, started, &task.id, extract)?;
+        println!(
+            "Imported task {} ({}): {}",
+            number,
+            started.format("%Y-%m-%d"),
+            task.prompt.lines().next().unwrap_or("")
+        );
+    }
+    println!("Imported {} tasks into '{}'", tasks.len(), project_name);
+    Ok(())
+}
+
+/// Claude Code's session directory for a working dir: every character
+/// other than ASCII letters and digits becomes `-`
+fn sessions_dir(cwd: &Path) -> Result<PathBuf> {
+    let home = dirs::home_dir().context("Could not determine home directory")?;
+    let encoded: String = cwd
+        .to_string_lossy()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    Ok(home.join(".claude").join("projects").join(encoded))
+}
+
+/// Import IDs already recorded in this project's task logs
+fn imported_ids(project: &Project) -> Result<HashSet<String>> {
+    let mut ids = HashSet::new();
+    for (_, path) in tasklog::task_log_paths(project)? {
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let Ok(log) = serde_json::from_str::<serde_json::Value>(&content) else {
+            continue;
+        };
+        if let Some(id) = log["import_id"].as_str() {
+            ids.insert(id.to_string());
+        }
+    }
+    Ok(ids)
+}
+
+/// Builds an ID from a format name and the task's identifying text
+///
+/// Uses the full 64-bit hash: a short note ID would let two different
+/// tasks collide and the second be skipped as already imported.
+fn content_id(format: &str, started: Option<DateTime<Utc>>, prompt: &str) -> String {
+    let started = started.map(|t| t.to_rfc3339()).unwrap_or_default();
+    format!(
+        "{}:{:016x}",
+        format,
+        stable_hash(&format!("{}\n{}", started, prompt))
+    )
+}
+
+/// Wraps plain reply text as a stream-json assistant message
+fn assistant_output(reply: &str) -> String {
+    let line = serde_json::json!({
+        "type": "assistant",
+        "message": {"content": [{"type": "text", "text": reply.trim()}]},
+    });
+    format!("{}\n", line)
+}
+
+impl Importer for ClaudeCodeSession {
+    /// Reads the session ID, first typed prompt, and start time
+    ///
+    /// Sessions without a prompt (e.g. only summaries) yield nothing.
+    fn parse(&self, content: &str) -> Vec<ImportedTask> {
+        let mut id = None;
+        let mut started = None;
+        let mut prompt = None;
+        for line in content.lines() {
+            let Ok(json) = serde_json::from_str::<serde_json::Value>(line) else {
+                continue;
+            };
+            if id.is_none() {
+                id = json["sessionId"].as_str().map(String::from);
+            }
+            if started.is_none() {
+                started = json["timestamp"]
+                    .as_str()
+                    .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+                    .map(|t| t.with_timezone(&Utc));
+            }
+            if prompt.is_none() && json["type"] == "user" && json["isMeta"] != true {
+                prompt = user_text(&json["message"]["content"]);
+            }
+        }
+        match (id, prompt) {
+            (Some(id), Some(prompt)) => vec![ImportedTask {
+                id,
+                prompt,
+                started,
+                output: content.to_string(),
+            }],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Text the user typed, skipping tool results and command records
+/// (`<command-name>...`)
+fn user_text(content: &serde_json::Value) -> Option<String> {
+    let text = match content {
+        serde_json::Value::String(text) => text.clone(),
+        serde_json::Value::Array(items) => items
+            .iter()
+            .filter(|item| item["type"] == "text")
+            .filter_map(|item| item["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => return None,
+    };
+    let text = text.trim();
+    (!text.is_empty() && !text.starts_with('<')).then(|| text.to_string())
+}
+
+impl Importer for AiderHistory {
+    /// `# aider chat started at ...` lines date what follows, `#### ` lines
+    /// are the user's message, and `> ` lines are aider's own output
+    fn parse(&self, content: &str) -> Vec<ImportedTask> {
+        let mut tasks = Vec::new();
+        let mut started = None;
+        let mut current: Option<(String, String)> = None;
+        let mut finish = |current: &mut Option<(String, String)>, started| {
+            if let Some((prompt, reply)) = current.take() {
+                tasks.push(ImportedTask {
+                    id: content_id("aider", started, &prompt),
+                    output: assistant_output(&reply),
+                    prompt,
+                    started,
+                });
+            }
+        };
+
+        for line in content.lines() {
+            if let Some(date) = line.strip_prefix("# aider chat started at ") {
+                finish(&mut current, started);
+                started = NaiveDateTime::parse_from_str(date.trim(), "%Y-%m-%d %H:%M:%S")
+                    .ok()
+                    .map(|t| t.and_utc());
+            } else if let Some(text) = line.strip_prefix("####") {
+                match &mut current {
+                    // Consecutive #### lines are one multi-line message
+                    Some((prompt, reply)) if reply.trim().is_empty() => {
+                        prompt.push('\n');
+                        prompt.push_str(text.trim());
+                    }
+                    _ => {
+                        finish(&mut current, started);
+                        current = Some((text.trim().to_string(), String::new()));
+                    }
+                }
+            } else if !line.starts_with('>') {
+                if let Some((_, reply)) = &mut current {
+                    reply.push_str(line);
+                    reply.push('\n');
+                }
+            }
+        }
+        finish(&mut current, started);
+        tasks
+    }
+}
+
+impl Importer for MarkdownLog {
+    /// A `## ` heading is the task's prompt and its section the outcome;
+    /// headings starting with a `YYYY-MM-DD` date set the task's date
+    fn parse(&self, content: &str) -> Vec<ImportedTask> {
+        let mut tasks = Vec::new();
+        let mut current: Option<(String, String)> = None;
+        let mut finish = |current: &mut Option<(String, String)>| {
+            if let Some((heading, body)) = current.take() {
+                let (started, prompt) = split_date(&heading);
+                if prompt.is_empty() && body.trim().is_empty() {
+                    return;
+                }
+                tasks.push(ImportedTask {
+                    id: content_id("markdown", started, &heading),
+                    output: assistant_output(&body),
+                    prompt: prompt.to_string(),
+                    started,
+                });
+            }
+        };
+
+        for line in content.lines() {
+            if let Some(heading) = line.strip_prefix("## ") {
+                finish(&mut current);
+                current = Some((heading.trim().to_string(), String::new()));
+            } else if let Some((_, body)) = &mut current {
+                body.push_str(line);
+                body.push('\n');
+            }
+        }
+        finish(&mut current);
+        tasks
+    }
+}
+
+/// Splits a leading `YYYY-MM-DD` date (and separator) off a heading
+fn split_date(heading: &str) -> (Option<DateTime<Utc>>, &str) {
+    let Some(date) = heading
+        .get(..10)
+        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+    else {
+        return (None, heading);
+    };
+    let rest = heading[10..].trim_start_matches([' ', ':', '-', '—']);
+    let started = date.and_hms_opt(0, 0, 0).map(|t| t.and_utc());
+    (started, if rest.is_empty() { heading } else { rest })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcript::{Message, Transcript};
+
+    #[test]
+    fn test_parse_claude_code_session() {
+        let content = r#"{"type":"summary","summary":"Auth fix"}
+{"type":"user","isMeta":true,"sessionId":"abc-123","timestamp":"2025-03-01T10:00:00Z","message":{"role":"user","content":"<local-command-caveat>"}}
+{"type":"user","sessionId":"abc-123","timestamp":"2025-03-01T10:00:05Z","message":{"role":"user","content":"<command-name>/clear</command-name>"}}
+{"type":"user","sessionId":"abc-123","timestamp":"2025-03-01T10:00:09Z","message":{"role":"user","content":[{"type":"text","text":"fix the login redirect"}]}}
+{"type":"assistant","sessionId":"abc-123","timestamp":"2025-03-01T10:00:12Z","message":{"content":[{"type":"text","text":"Looking at it."}]}}
+"#;
+        let tasks = ClaudeCodeSession.parse(content);
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].id, "abc-123");
+        assert_eq!(tasks[0].prompt, "fix the login redirect");
+        assert_eq!(
+            tasks[0].started,
+            Some("2025-03-01T10:00:00Z".parse::<DateTime<Utc>>().unwrap())
+        );
+
+        assert!(ClaudeCodeSession
+            .parse(r#"{"type":"summary","summary":"x"}"#)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_parse_aider_history() {
+        let content = "\
+# aider chat started at 2024-05-01 10:00:00
+
+> Add src/app.py to the chat? y
+
+#### add a health check endpoint
+#### return 200 with uptime
+
+I added `/health` to src/app.py.
+
+> Applied edit to src/app.py
+
+#### now add a test
+Added tests/test_health.py.
+";
+        let tasks = AiderHistory.parse(content);
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(
+            tasks[0].prompt,
+            "add a health check endpoint\nreturn 200 with uptime"
+        );
+        assert_eq!(
+            tasks[0].started.unwrap().to_rfc3339(),
+            "2024-05-01T10:00:00+00:00"
+        );
+        let transcript = Transcript::parse(&tasks[0].output);
+        assert!(matches!(
+            &transcript.messages[..],
+            [Message::Text { text }] if text == "I added `/health` to src/app.py."
+        ));
+        assert_eq!(tasks[1].prompt, "now add a test");
+        assert_ne!(tasks[0].id, tasks[1].id);
+    }
+
+    #[test]
+    fn test_parse_markdown_log() {
+        let content = "\
+# Work log
+
+## 2024-06-03: Migrate billing to Stripe v2
+Webhooks needed the new signing secret.
+
+## Flaky CI
+Pinned the postgres image.
+";
+        let tasks = MarkdownLog.parse(content);
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].prompt, "Migrate billing to Stripe v2");
+        assert_eq!(
+            tasks[0].started.unwrap().date_naive(),
+            NaiveDate::from_ymd_opt(2024, 6, 3).unwrap()
+        );
+        assert_eq!(tasks[1].prompt, "Flaky CI");
+        assert!(tasks[1].started.is_none());
+    }
+
+    #[test]
+    fn test_content_id_uses_full_hash() {
+        let id = content_id("aider", None, "fix the login bug");
+        let (format, hash) = id.split_once(':').unwrap();
+        assert_eq!(format, "aider");
+        assert_eq!(hash.len(), 16);
+        assert_ne!(id, content_id("aider", None, "fix the login bug "));
+    }
+
+    #[test]
+    fn test_sessions_dir_encoding() {
+        let dir = sessions_dir(Path::new("/home/me/code/my_app.v2")).unwrap();
+        assert!(dir.ends_with(".claude/projects/-home-me-code-my-app-v2"));
+    }
+}
@@ -0,0 +1,256 @@
+//! `clancy init`: a first-run wizard that writes a commented config.toml
+//! and checks that Claude Code and the API key work
+
+use crate::config;
+use anyhow::{bail, Context, Result};
+use std::io::Write;
+use std::process::Command;
+
+/// Conversation modes accepted by `context.conversation_mode`
+const CONVERSATION_MODES: &[&str] = &["fresh", "summary", "full"];
+
+/// Answers collected by the wizard
+#[derive(Debug, Clone, PartialEq)]
+pub struct InitAnswers {
+    pub model: String,
+    pub editor: String,
+    pub conversation_mode: String,
+    pub api_key_env: String,
+}
+
+/// Asks for the basic settings, writes config.toml, then checks that the
+/// `claude` binary runs and the API key is accepted
+///
+/// In an existing config.toml only the four answered settings change, so
+/// its comments and other settings are kept; `force` replaces it with a
+/// fresh commented file instead.
+pub fn run_init(force: bool) -> Result<()> {
+    let path = config::config_file()?;
+    let existing = if path.exists() && !force {
+        Some(
+            std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read config file: {:?}", path))?,
+        )
+    } else {
+        None
+    };
+
+    // Offer the current values, so re-running init only changes what you type
+    let current = config::load_config().unwrap_or_default();
+    let model = ask("Model for note extraction", &current.claude.model)?;
+    let editor = ask("Editor for notes", &current.repl.editor)?;
+    let conversation_mode = loop {
+        let mode = ask(
+            "Conversation mode (fresh, summary, full)",
+            &current.context.conversation_mode,
+        )?;
+        if CONVERSATION_MODES.contains(&mode.as_str()) {
+            break mode;
+        }
+        println!("  Choose one of: {}", CONVERSATION_MODES.join(", "));
+    };
+    let api_key_env = ask(
+        "Environment variable holding the API key",
+        &current.claude.api_key_env,
+    )?;
+    let answers = InitAnswers {
+        model,
+        editor,
+        conversation_mode,
+        api_key_env,
+    };
+
+    let content = match &existing {
+        Some(existing) => update_config(existing, &answers)?,
+        None => render_config(&answers),
+    };
+    config::ensure_config_dir()?;
+    std::fs::write(&path, content)
+        .with_context(|| format!("Failed to write config file: {:?}", path))?;
+    let verb = if existing.is_some() {
+        "Updated"
+    } else {
+        "Wrote"
+    };
+    println!("\n{} {}\n", verb, path.display());
+
+    check_claude_binary();
+    check_api_key(&answers, &current.claude.base_url);
+    Ok(())
+}
+
+/// Prompts with a default shown in brackets; an empty answer takes the
+/// default, and end of input stops the wizard
+fn ask(question: &str, default: &str) -> Result<String> {
+    print!("{} [{}]: ", question, default);
+    std::io::stdout().flush()?;
+    let mut input = String::new();
+    if std::io::stdin().read_line(&mut input)? == 0 {
+        bail!("Input ended before init finished; nothing was written");
+    }
+    let input = input.trim();
+    Ok(if input.is_empty() {
+        default.to_string()
+    } else {
+        input.to_string()
+    })
+}
+
+/// The config.toml written by `clancy init`, with a comment on each setting
+pub fn render_config(answers: &InitAnswers) -> String {
+    let quote = |s: &str| toml::Value::String(s.to_string()).to_string();
+    format!(
+        "# Clancy configuration, written by `clancy init`.\n\
+         # Every setting is optional; see the README for the full list.\n\
+         \n\
+         [claude]\n\
+         # Environment variable that holds your Anthropic API key\n\
+         api_key_env = {}\n\
+         # Model used for note extraction\n\
+         model = {}\n\
+         \n\
+         [context]\n\
+         # How much of earlier tasks each task sees: fresh | summary | full\n\
+         conversation_mode = {}\n\
+         \n\
+         [repl]\n\
+         # Editor opened by /notes and `clancy open-notes`\n\
+         editor = {}\n",
+        quote(&answers.api_key_env),
+        quote(&answers.model),
+        quote(&answers.conversation_mode),
+        quote(&answers.editor),
+    )
+}
+
+/// Sets the answered settings in an existing config.toml, keeping its
+/// comments, layout, and every other setting
+pub fn update_config(existing: &str, answers: &InitAnswers) -> Result<String> {
+    let mut doc: toml_edit::DocumentMut = existing
+        .parse()
+        .context("Failed to parse the existing config file")?;
+    for (section, key, value) in [
+        ("claude", "api_key_env", &answers.api_key_env),
+        ("claude", "model", &answers.model),
+        ("context", "conversation_mode", &answers.conversation_mode),
+        ("repl", "editor", &answers.editor),
+    ] {
+        let table = doc
+            .entry(section)
+            .or_insert_with(toml_edit::table)
+            .as_table_mut()
+            .with_context(|| format!("[{}] in config.toml is not a table", section))?;
+        config::set_toml_string(table, key, value);
+    }
+    Ok(doc.to_string())
+}
+
+/// Prints whether `claude --version` runs
+fn check_claude_binary() {
+    match Command::new("claude").arg("--version").output() {
+        Ok(output) if output.status.success() => {
+            let version = String::from_utf8_lossy(&output.stdout);
+            let version = version.lines().next().unwrap_or_default();
+            println!("✓ claude found ({})", version.trim());
+        }
+        Ok(output) => println!(
+            "✗ claude --version failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
+        Err(_) => println!("✗ claude not found on PATH (install Claude Code first)"),
+    }
+}
+
+/// Prints whether the API key env var is set and the API accepts the key
+fn check_api_key(answers: &InitAnswers, base_url: &str) {
+    let Ok(api_key) = std::env::var(&answers.api_key_env) else {
+        println!(
+            "✗ {} is not set (note extraction falls back to rule-based notes)",
+            answers.api_key_env
+        );
+        return;
+    };
+    let rt = match tokio::runtime::Runtime::new() {
+        Ok(rt) => rt,
+        Err(e) => {
+            println!("✗ Could not check the API key: {}", e);
+            return;
+        }
+    };
+    match rt.block_on(probe_api(&api_key, base_url)) {
+        Ok(200) => println!("✓ {} accepted by {}", answers.api_key_env, base_url),
+        Ok(401) | Ok(403) => println!("✗ {} was rejected by the API", answers.api_key_env),
+        Ok(status) => println!("✗ API returned HTTP {} for the key check", status),
+        Err(e) => println!("✗ Could not reach {}: {:#}", base_url, e),
+    }
+}
+
+/// Lists models, which costs nothing and needs a valid key
+async fn probe_api(api_key: &str, base_url: &str) -> Result<u16> {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .build()
+        .context("Failed to create HTTP client")?;
+    let response = client
+        .get(format!("{}/v1/models", base_url))
+        .header("x-api-key", api_key)
+        .header("anthropic-version", "2023-06-01")
+        .send()
+        .await?;
+    Ok(response.status().as_u16())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_render_config_round_trips() {
+        let answers = InitAnswers {
+            model: "claude-opus-4-1".to_string(),
+            editor: "code --wait".to_string(),
+            conversation_mode: "fresh".to_string(),
+            api_key_env: "MY_KEY".to_string(),
+        };
+        let config: Config = toml::from_str(&render_config(&answers)).unwrap();
+        assert_eq!(config.claude.model, "claude-opus-4-1");
+        assert_eq!(config.claude.api_key_env, "MY_KEY");
+        assert_eq!(config.context.conversation_mode, "fresh");
+        assert_eq!(config.repl.editor, "code --wait");
+    }
+
+    #[test]
+    fn test_update_config_keeps_comments_and_other_settings() {
+        let existing = "# my notes\n[claude]\nmodel = \"old\"  # pinned\nbase_url = \"http://localhost:9\"\n\n[daemon]\npoll_interval_secs = 5\n";
+        let answers = InitAnswers {
+            model: "claude-opus-4-1".to_string(),
+            editor: "vim".to_string(),
+            conversation_mode: "fresh".to_string(),
+            api_key_env: "MY_KEY".to_string(),
+        };
+        let updated = update_config(existing, &answers).unwrap();
+        assert!(
+            updated.starts_with("# my notes\n[claude]\nmodel = \"claude-opus-4-1\"  # pinned\n")
+        );
+        assert!(updated.contains("poll_interval_secs = 5"));
+
+        let config: Config = toml::from_str(&updated).unwrap();
+        assert_eq!(config.claude.base_url, "http://localhost:9");
+        assert_eq!(config.claude.api_key_env, "MY_KEY");
+        assert_eq!(config.context.conversation_mode, "fresh");
+        assert_eq!(config.repl.editor, "vim");
+    }
+
+    #[test]
+    fn test_render_config_escapes_quotes() {
+        let answers = InitAnswers {
+            model: "m".to_string(),
+            editor: r#"emacs -e "(foo)""#.to_string(),
+            conversation_mode: "summary".to_string(),
+            api_key_env: "K".to_string(),
+        };
+        let config: Config = toml::from_str(&render_config(&answers)).unwrap();
+        assert_eq!(config.repl.editor, r#"emacs -e "(foo)""#);
+    }
+}
@@ -0,0 +1,38 @@
+//! Clancy: a Claude Code session harness with cross-session memory
+//!
+//! The `clancy` binary is a thin CLI over these modules; they are a library
+//! so benchmarks can drive them directly.
+
+pub mod archive;
+pub mod claude_md;
+pub mod clipboard;
+pub mod compare;
+pub mod config;
+pub mod daemon;
+pub mod deps;
+pub mod diff;
+pub mod environment;
+pub mod extraction;
+pub mod fsck;
+pub mod handoff;
+pub mod highlight;
+pub mod i18n;
+pub mod ingest;
+pub mod init;
+pub mod notes;
+pub mod policy;
+pub mod pricing;
+pub mod processors;
+pub mod project;
+pub mod render;
+pub mod repl;
+pub mod review;
+pub mod routing;
+pub mod search;
+pub mod settings;
+pub mod stats;
+pub mod summarizer;
+pub mod tasklog;
+pub mod text;
+pub mod transcript;
+pub mod watch;
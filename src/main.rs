@@ -1,11 +1,11 @@
-mod config;
-mod extraction;
-mod project;
-mod repl;
-mod transcript;
-
 use anyhow::Result;
+use chrono::NaiveDate;
+use clancy::{
+    archive, claude_md, compare, daemon, fsck, ingest, init, notes, project, render, repl, search,
+    stats, tasklog, watch,
+};
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(name = "clancy")]
@@ -17,36 +17,318 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
+    /// Write a commented config.toml interactively and check the setup
+    Init {
+        /// Replace an existing config.toml instead of updating it in place
+        #[arg(long)]
+        force: bool,
+    },
     /// Start a session — enters the Clancy REPL
     Start {
+        /// Project name; omit to pick from existing projects
+        project_name: Option<String>,
+        /// Parent project to link for note inheritance
+        #[arg(long)]
+        parent: Option<String>,
+        /// Create the project without checking for similarly named ones
+        #[arg(long)]
+        new: bool,
+        /// Continue the previous session, restoring its task history
+        #[arg(long)]
+        resume: bool,
+    },
+    /// Continue a project's previous session with its task history restored
+    Resume {
+        /// Project name; omit to pick from existing projects
+        project_name: Option<String>,
+    },
+    /// Create a project without starting a session
+    New {
         /// Project name
         project_name: String,
+        /// Parent project to link for note inheritance
+        #[arg(long)]
+        parent: Option<String>,
+        /// Short description shown to related projects
+        #[arg(long)]
+        description: Option<String>,
     },
     /// List all projects
-    List,
+    List {
+        /// Columns to show (name, status, parent, sessions, tasks, last, cost, plan)
+        #[arg(long, value_delimiter = ',')]
+        columns: Option<Vec<String>>,
+        /// Only archived projects
+        #[arg(long, conflicts_with_all = ["active", "all"])]
+        archived: bool,
+        /// Only projects that aren't archived (the default)
+        #[arg(long, conflicts_with = "all")]
+        active: bool,
+        /// Active and archived projects
+        #[arg(long)]
+        all: bool,
+        /// Order: name, or last (most recent activity first)
+        #[arg(long, default_value = "name")]
+        sort: String,
+    },
+    /// Show the parent/child project hierarchy
+    Tree,
     /// Show project status and notes
     Status {
         /// Project name (optional, defaults to current)
         project_name: Option<String>,
+        /// Number of recent task summaries to show
+        #[arg(long, default_value_t = 5)]
+        tasks: usize,
     },
     /// View/edit notes directly
+    #[command(args_conflicts_with_subcommands = true)]
     Notes {
+        #[command(subcommand)]
+        command: Option<NotesCommands>,
         /// Project name
-        project: String,
+        project: Option<String>,
         /// Note category (architecture, decisions, failures, plan)
         category: Option<String>,
     },
+    /// Print `path:line` of a note entry or task log, for editor integrations
+    OpenNotes {
+        /// Project name
+        project: String,
+        /// What to locate: a note category (e.g. decision) or task
+        #[arg(long)]
+        at: Option<String>,
+        /// Entry ID (or unique prefix) or task number; lists all when omitted
+        id: Option<String>,
+    },
+    /// Show which task produced a note entry, then its transcript
+    Why {
+        /// Project name
+        project: String,
+        /// Entry ID (or unique prefix), as shown by `clancy note show`
+        id: String,
+    },
+    /// Add entries to a project's notes without opening an editor
+    Note {
+        #[command(subcommand)]
+        command: NoteCommands,
+    },
+    /// Show a stored task transcript
+    #[command(alias = "show-task")]
+    Show {
+        /// Project name
+        project: String,
+        /// Task number
+        task: u32,
+        /// Export as a standalone HTML page
+        #[arg(long, conflicts_with_all = ["md", "raw"])]
+        html: bool,
+        /// Export as Markdown
+        #[arg(long, alias = "markdown", conflicts_with = "raw")]
+        md: bool,
+        /// Print the original stream-json output
+        #[arg(long)]
+        raw: bool,
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// List a project's tasks, newest first
+    Log {
+        /// Project name
+        project: String,
+        /// Tasks per page
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+        /// Page to show (1 = newest)
+        #[arg(long, default_value_t = 1)]
+        page: usize,
+        /// Only failed and interrupted tasks
+        #[arg(long)]
+        failed_only: bool,
+    },
+    /// Attach a reviewer note to a stored task
+    Annotate {
+        /// Project name
+        project: String,
+        /// Task number
+        task: u32,
+        /// Note text
+        text: String,
+    },
+    /// Star a task as a landmark run
+    Star {
+        /// Project name
+        project: String,
+        /// Task number
+        task: u32,
+        /// Remove the star instead
+        #[arg(long)]
+        remove: bool,
+    },
+    /// Compare two tasks side by side
+    Compare {
+        /// Project name
+        project: String,
+        /// First task number
+        task_a: u32,
+        /// Second task number
+        task_b: u32,
+    },
+    /// Re-run a check on file changes and start repair tasks when it fails
+    Watch {
+        /// Project name
+        project: String,
+        /// Check command, run through `sh -c`
+        #[arg(long)]
+        cmd: String,
+        /// Task prompt; the failing output is appended
+        #[arg(long, default_value = "Fix the failing command.")]
+        prompt: String,
+        /// Seconds between checks for file changes
+        #[arg(long, default_value_t = 2)]
+        interval: u64,
+        /// Repair tasks to try before waiting for the next change
+        #[arg(long, default_value_t = 3)]
+        max_attempts: usize,
+    },
+    /// Run one task without the REPL; exits non-zero if the task fails
+    Run {
+        /// Project name
+        project: String,
+        /// Task prompt
+        #[arg(required_unless_present = "task", conflicts_with = "task")]
+        prompt: Option<String>,
+        /// Run a task template from project.toml instead of a prompt
+        #[arg(long)]
+        task: Option<String>,
+    },
+    /// Add a task to the queue run by `clancy daemon`
+    Enqueue {
+        /// Project name
+        project: String,
+        /// Task prompt
+        prompt: String,
+    },
+    /// Run queued and scheduled tasks one at a time
+    Daemon,
+    /// Render a project's notes as a CLAUDE.md block
+    EmitClaudeMd {
+        /// Project name
+        project: String,
+        /// File to write; only the managed block is replaced on regeneration
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Import Claude Code session logs for the current directory as tasks
+    Ingest {
+        /// Project name
+        project: String,
+        /// Skip sessions started before this date (YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<NaiveDate>,
+        /// Session directory (defaults to ~/.claude/projects/<current dir>)
+        #[arg(long)]
+        from: Option<PathBuf>,
+        /// Run note extraction on each imported session
+        #[arg(long)]
+        extract: bool,
+    },
+    /// Import an aider chat history or Markdown work log as tasks
+    Import {
+        /// Project name
+        project: String,
+        /// History file to import
+        file: PathBuf,
+        /// Source format: aider | markdown (default: aider for
+        /// .aider.chat.history.md, otherwise markdown)
+        #[arg(long)]
+        format: Option<String>,
+        /// Run note extraction on each imported task
+        #[arg(long)]
+        extract: bool,
+    },
+    /// Unpack a project archive made by `clancy export` as a new project
+    ImportArchive {
+        /// Archive path (.tar.gz)
+        archive: PathBuf,
+        /// Name to give the imported project
+        #[arg(long = "as", value_name = "NAME")]
+        rename: Option<String>,
+    },
+    /// Bundle a project's metadata, notes, and task logs into a .tar.gz
+    Export {
+        /// Project name
+        project: String,
+        /// Archive path (default: <project>.tar.gz)
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Search notes and task logs (prompts, summaries, replies) for text
+    Search {
+        /// Text to find, case-insensitive
+        #[arg(required = true)]
+        query: Vec<String>,
+        /// Only search this project (default: all projects)
+        #[arg(short, long)]
+        project: Option<String>,
+        /// Lines of context around each match
+        #[arg(short = 'C', long, default_value_t = 1)]
+        context: usize,
+        /// Maximum matches to show
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+    },
+    /// Reports across a project's history (default: usage)
+    Stats {
+        #[command(subcommand)]
+        command: Option<StatsCommands>,
+    },
+    /// Check project metadata, notes, task logs, and parent links for damage
+    Fsck {
+        /// Project name (all projects if omitted)
+        project: Option<String>,
+        /// Repair what can be repaired safely
+        #[arg(long, alias = "repair")]
+        fix: bool,
+    },
     /// Archive a completed project
     Archive {
         /// Project name
         project_name: String,
     },
+    /// Make an archived project active again
+    Unarchive {
+        /// Project name
+        project_name: String,
+    },
+    /// Delete a project (asks for confirmation unless --force)
+    Delete {
+        /// Project name
+        project_name: String,
+        /// Don't ask for confirmation
+        #[arg(long)]
+        force: bool,
+        /// Move the project to ~/.config/clancy/trash/ instead of deleting it
+        #[arg(long)]
+        trash: bool,
+    },
+    /// Rename a project, updating child projects' parent links
+    Rename {
+        /// Current project name
+        old: String,
+        /// New project name
+        new: String,
+    },
     /// Link a child project to a parent for note inheritance
     Link {
         /// Child project name
         child: String,
         /// Parent project name
         parent: String,
+        /// Note categories to inherit, comma-separated (default: architecture)
+        #[arg(long, value_delimiter = ',')]
+        inherit: Option<Vec<String>>,
     },
     /// Unlink a child project from its parent
     Unlink {
@@ -55,6 +337,86 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand)]
+enum StatsCommands {
+    /// Spend, tokens, durations, and tool use from task logs
+    Usage {
+        /// Project name (default: all projects)
+        project: Option<String>,
+        /// Group spend by day or week
+        #[arg(long, default_value = "day")]
+        by: String,
+        /// Print the full report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Runs, pass rate, spend, and recent outcomes per task template
+    Templates {
+        /// Project name (default: all projects)
+        project: Option<String>,
+    },
+    /// Group failure notes and failed-task errors into recurring problems
+    Failures {
+        /// Project name (default: all projects)
+        project: Option<String>,
+        /// Number of groups to show
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum NotesCommands {
+    /// Check notes for formatting problems
+    Lint {
+        /// Project name
+        project: String,
+        /// Apply automatic fixes
+        #[arg(long)]
+        fix: bool,
+        /// Flag entries longer than this many characters
+        #[arg(long, default_value_t = 300)]
+        max_length: usize,
+    },
+    /// List never-injected and stale entries as pruning candidates
+    Audit {
+        /// Project name
+        project: String,
+        /// Flag entries not touched in this many days
+        #[arg(long, default_value_t = 180)]
+        stale_days: i64,
+    },
+}
+
+#[derive(Subcommand)]
+enum NoteCommands {
+    /// Append an entry to a note category
+    Add {
+        /// Project name
+        project: String,
+        /// Note category (architecture, decisions, failures, plan)
+        category: String,
+        /// Entry text; read from stdin when omitted or "-"
+        text: Option<String>,
+    },
+    /// List note entries with their IDs
+    Show {
+        /// Project name
+        project: String,
+        /// Note category (defaults to all)
+        category: Option<String>,
+    },
+    /// Remove a note entry by ID
+    Rm {
+        /// Project name
+        project: String,
+        /// Note category
+        category: String,
+        /// Entry ID as shown by `clancy note show`
+        id: String,
+    },
+}
+
 fn main() -> Result<()> {
     // Load .env file if present (won't fail if missing)
     dotenvy::dotenv().ok();
@@ -62,23 +424,279 @@ fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Start { project_name } => {
-            repl::start_session(&project_name)?;
+        Commands::Init { force } => {
+            init::run_init(force)?;
+        }
+        Commands::Start {
+            project_name,
+            parent,
+            new,
+            resume,
+        } => {
+            let project_name = match project_name {
+                Some(name) if new => Some(name),
+                Some(name) => project::confirm_new_project(&name)?,
+                None => project::pick_project()?,
+            };
+            let Some(project_name) = project_name else {
+                return Ok(());
+            };
+            repl::start_session(&project_name, parent.as_deref(), resume)?;
         }
-        Commands::List => {
-            project::list_projects()?;
+        Commands::Resume { project_name } => {
+            let project_name = match project_name {
+                Some(name) => Some(name),
+                None => project::pick_project()?,
+            };
+            if let Some(project_name) = project_name {
+                repl::start_session(&project_name, None, true)?;
+            }
         }
-        Commands::Status { project_name } => {
-            project::show_status(project_name.as_deref())?;
+        Commands::New {
+            project_name,
+            parent,
+            description,
+        } => {
+            project::new_project(&project_name, parent.as_deref(), description.as_deref())?;
         }
-        Commands::Notes { project, category } => {
+        Commands::List {
+            columns,
+            archived,
+            active: _,
+            all,
+            sort,
+        } => {
+            let filter = if archived {
+                project::ListFilter::Archived
+            } else if all {
+                project::ListFilter::All
+            } else {
+                project::ListFilter::Active
+            };
+            project::list_projects(columns.as_deref(), filter, &sort)?;
+        }
+        Commands::Tree => {
+            project::show_tree()?;
+        }
+        Commands::Status {
+            project_name,
+            tasks,
+        } => {
+            project::show_status(project_name.as_deref(), tasks)?;
+        }
+        Commands::Notes {
+            command:
+                Some(NotesCommands::Lint {
+                    project,
+                    fix,
+                    max_length,
+                }),
+            ..
+        } => {
+            notes::lint_project(&project::Project::open(&project)?, fix, max_length)?;
+        }
+        Commands::Notes {
+            command:
+                Some(NotesCommands::Audit {
+                    project,
+                    stale_days,
+                }),
+            ..
+        } => {
+            notes::audit_project(&project::Project::open(&project)?, stale_days)?;
+        }
+        Commands::Notes {
+            command: None,
+            project,
+            category,
+        } => {
+            let Some(project) = project else {
+                anyhow::bail!("Usage: clancy notes <project> [category]");
+            };
             project::edit_notes(&project, category.as_deref())?;
         }
+        Commands::OpenNotes { project, at, id } => {
+            notes::open_notes(
+                &project::Project::open(&project)?,
+                at.as_deref(),
+                id.as_deref(),
+            )?;
+        }
+        Commands::Why { project, id } => {
+            notes::why(&project::Project::open(&project)?, &id)?;
+        }
+        Commands::Note { command } => match command {
+            NoteCommands::Add {
+                project,
+                category,
+                text,
+            } => {
+                project::add_note(&project, &category, text.as_deref())?;
+            }
+            NoteCommands::Show { project, category } => {
+                notes::show_entries(&project::Project::open(&project)?, category.as_deref())?;
+            }
+            NoteCommands::Rm {
+                project,
+                category,
+                id,
+            } => {
+                notes::remove_note(&project::Project::open(&project)?, &category, &id)?;
+            }
+        },
+        Commands::Show {
+            project,
+            task,
+            html,
+            md,
+            raw,
+            output,
+        } => {
+            let format = if html {
+                render::ShowFormat::Html
+            } else if raw {
+                render::ShowFormat::Raw
+            } else if md {
+                render::ShowFormat::Markdown
+            } else {
+                render::ShowFormat::Text
+            };
+            render::show_task(&project, task, format, output.as_deref())?;
+        }
+        Commands::Log {
+            project,
+            limit,
+            page,
+            failed_only,
+        } => {
+            tasklog::show_log(&project, limit, page, failed_only)?;
+        }
+        Commands::Annotate {
+            project,
+            task,
+            text,
+        } => {
+            tasklog::annotate_task(&project, task, &text)?;
+        }
+        Commands::Star {
+            project,
+            task,
+            remove,
+        } => {
+            tasklog::star_task(&project, task, !remove)?;
+        }
+        Commands::Compare {
+            project,
+            task_a,
+            task_b,
+        } => {
+            compare::compare_tasks(&project, task_a, task_b)?;
+        }
+        Commands::Watch {
+            project,
+            cmd,
+            prompt,
+            interval,
+            max_attempts,
+        } => {
+            watch::watch(&project, &cmd, &prompt, interval, max_attempts)?;
+        }
+        Commands::Run {
+            project,
+            prompt,
+            task,
+        } => {
+            // clap requires one of the prompt and --task
+            let succeeded = match task {
+                Some(template) => repl::run_template_task(&project, &template)?,
+                None => repl::run_single_task(&project, &prompt.unwrap_or_default())?,
+            };
+            if !succeeded {
+                std::process::exit(1);
+            }
+        }
+        Commands::Enqueue { project, prompt } => {
+            daemon::enqueue(&project, &prompt)?;
+        }
+        Commands::Daemon => {
+            daemon::run_daemon()?;
+        }
+        Commands::EmitClaudeMd { project, output } => {
+            claude_md::emit_claude_md(&project, output.as_deref())?;
+        }
+        Commands::Ingest {
+            project,
+            since,
+            from,
+            extract,
+        } => {
+            ingest::ingest(&project, since, from.as_deref(), extract)?;
+        }
+        Commands::Import {
+            project,
+            file,
+            format,
+            extract,
+        } => {
+            ingest::import_file(&project, &file, format.as_deref(), extract)?;
+        }
+        Commands::ImportArchive { archive, rename } => {
+            archive::import_archive(&archive, rename.as_deref())?;
+        }
+        Commands::Export { project, out } => {
+            archive::export_project(&project, out.as_deref())?;
+        }
+        Commands::Search {
+            query,
+            project,
+            context,
+            limit,
+        } => {
+            search::search(&query.join(" "), project.as_deref(), context, limit)?;
+        }
+        Commands::Stats {
+            command: Some(StatsCommands::Failures { project, limit }),
+        } => {
+            stats::failure_report(project.as_deref(), limit)?;
+        }
+        Commands::Stats {
+            command: Some(StatsCommands::Usage { project, by, json }),
+        } => {
+            stats::usage_report(project.as_deref(), &by, json)?;
+        }
+        Commands::Stats {
+            command: Some(StatsCommands::Templates { project }),
+        } => {
+            stats::template_report(project.as_deref())?;
+        }
+        Commands::Stats { command: None } => {
+            stats::usage_report(None, "day", false)?;
+        }
+        Commands::Fsck { project, fix } => {
+            fsck::fsck(project.as_deref(), fix)?;
+        }
         Commands::Archive { project_name } => {
             project::archive_project(&project_name)?;
         }
-        Commands::Link { child, parent } => {
-            project::link_projects(&child, &parent)?;
+        Commands::Unarchive { project_name } => {
+            project::unarchive_project(&project_name)?;
+        }
+        Commands::Delete {
+            project_name,
+            force,
+            trash,
+        } => {
+            project::delete_project(&project_name, force, trash)?;
+        }
+        Commands::Rename { old, new } => {
+            project::rename_project(&old, &new)?;
+        }
+        Commands::Link {
+            child,
+            parent,
+            inherit,
+        } => {
+            project::link_projects(&child, &parent, inherit.as_deref())?;
         }
         Commands::Unlink { project_name } => {
             project::unlink_project(&project_name)?;
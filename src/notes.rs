@@ -0,0 +1,965 @@
+//! Addressable note entries
+//!
+//! A note file is mostly a list of `- ` bullets, each optionally followed by
+//! indented continuation lines. Every entry gets a short ID derived from its
+//! text so it can be referred to (and removed) without hand-editing files.
+//! IDs are stable as long as the entry text doesn't change.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::project::{Project, EXTRA_NOTES, NOTE_CATEGORIES};
+use crate::render::{self, ShowFormat};
+use crate::tasklog;
+
+/// Length of the hex ID shown for each entry
+const ID_LEN: usize = 6;
+
+/// A bullet entry within a note file
+#[derive(Debug, PartialEq)]
+pub struct NoteEntry {
+    pub id: String,
+    pub text: String,
+    /// Line range `[start, end)` the entry occupies in the file
+    pub lines: std::ops::Range<usize>,
+}
+
+/// Computes the short ID for an entry's text
+pub fn entry_id(text: &str) -> String {
+    format!("{:016x}", stable_hash(text.trim()))[..ID_LEN].to_string()
+}
+
+/// 64-bit FNV-1a hash, so IDs don't change between builds or Rust versions
+pub fn stable_hash(text: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in text.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Splits note content into bullet entries
+///
+/// Headers, blank lines, and other free text are not entries.
+pub fn parse_entries(content: &str) -> Vec<NoteEntry> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut entries = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        if !is_bullet(lines[i]) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        i += 1;
+        while i < lines.len() && is_continuation(lines[i]) {
+            i += 1;
+        }
+        let text = lines[start..i]
+            .iter()
+            .map(|l| l.trim())
+            .collect::<Vec<_>>()
+            .join("\n");
+        entries.push(NoteEntry {
+            id: entry_id(&text),
+            text,
+            lines: start..i,
+        });
+    }
+    entries
+}
+
+fn is_bullet(line: &str) -> bool {
+    line.starts_with("- ") || line.starts_with("* ")
+}
+
+fn is_continuation(line: &str) -> bool {
+    !line.trim().is_empty() && line.starts_with(char::is_whitespace)
+}
+
+/// Removes every entry with the given ID, returning the new content
+pub fn remove_entry(content: &str, id: &str) -> Result<String> {
+    let id = id.to_lowercase();
+    let doomed: Vec<std::ops::Range<usize>> = parse_entries(content)
+        .into_iter()
+        .filter(|e| e.id == id)
+        .map(|e| e.lines)
+        .collect();
+    if doomed.is_empty() {
+        bail!("No note entry with ID '{}'", id);
+    }
+
+    let kept: Vec<&str> = content
+        .lines()
+        .enumerate()
+        .filter(|(i, _)| !doomed.iter().any(|r| r.contains(i)))
+        .map(|(_, line)| line)
+        .collect();
+    let mut result = kept.join("\n");
+    if content.ends_with('\n') && !result.is_empty() {
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+/// Formats a category's entries with their IDs
+pub fn format_entries(category: &str, content: &str) -> String {
+    let entries = parse_entries(content);
+    let mut out = format!("## {} ({} entries)\n", category, entries.len());
+    for entry in entries {
+        let mut lines = entry.text.lines();
+        out.push_str(&format!(
+            "  [{}] {}\n",
+            entry.id,
+            lines.next().unwrap_or("")
+        ));
+        for line in lines {
+            out.push_str(&format!("           {}\n", line));
+        }
+    }
+    out
+}
+
+/// Prints note entries with their IDs for one or all categories
+pub fn show_entries(project: &Project, category: Option<&str>) -> Result<()> {
+    let categories: Vec<&str> = match category {
+        Some(cat) => {
+            crate::project::validate_categories(&[cat.to_string()])?;
+            vec![cat]
+        }
+        None => NOTE_CATEGORIES.to_vec(),
+    };
+    for cat in categories {
+        println!("{}", format_entries(cat, &project.read_notes(cat)?));
+    }
+    Ok(())
+}
+
+/// Removes an entry from a category by ID and saves the file
+pub fn remove_note(project: &Project, category: &str, id: &str) -> Result<()> {
+    crate::project::validate_categories(&[category.to_string()])?;
+    let content = project.read_notes(category)?;
+    let updated = remove_entry(&content, id)?;
+    project.write_notes(category, &updated)?;
+    println!("Removed [{}] from {}.", id, category);
+    Ok(())
+}
+
+/// How often an entry made it into compiled context, keyed in
+/// `note_usage.json` by `category:id`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntryUsage {
+    pub first_seen: DateTime<Utc>,
+    /// Context compiles that included the entry
+    pub injected: u32,
+    /// Context compiles that cut it to fit the token budget
+    pub cut: u32,
+    /// Context compiles that left it out before any truncation, e.g. a line
+    /// repeated from another note
+    #[serde(default)]
+    pub excluded: u32,
+    pub last_injected: Option<DateTime<Utc>>,
+}
+
+fn usage_path(project: &Project) -> std::path::PathBuf {
+    project.path.join("note_usage.json")
+}
+
+fn load_usage(project: &Project) -> BTreeMap<String, EntryUsage> {
+    std::fs::read_to_string(usage_path(project))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Updates usage counts for every current entry against the compiled context
+/// before (`built`) and after truncation (`context`), dropping counts for
+/// entries that no longer exist
+pub fn record_usage(project: &Project, built: &str, context: &str) -> Result<()> {
+    let mut notes = Vec::new();
+    for category in NOTE_CATEGORIES {
+        notes.push((*category, project.read_notes(category)?));
+    }
+    let usage = update_usage(load_usage(project), &notes, built, context, Utc::now());
+    let path = usage_path(project);
+    std::fs::write(&path, serde_json::to_string_pretty(&usage)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn update_usage(
+    old: BTreeMap<String, EntryUsage>,
+    notes: &[(&str, String)],
+    built: &str,
+    context: &str,
+    now: DateTime<Utc>,
+) -> BTreeMap<String, EntryUsage> {
+    let mut usage = BTreeMap::new();
+    for (category, content) in notes {
+        for entry in parse_entries(content) {
+            let key = format!("{}:{}", category, entry.id);
+            let mut record = old.get(&key).cloned().unwrap_or(EntryUsage {
+                first_seen: now,
+                injected: 0,
+                cut: 0,
+                excluded: 0,
+                last_injected: None,
+            });
+            // The first line is enough to tell whether truncation kept it
+            let first_line = entry.text.lines().next().unwrap_or_default();
+            if context.contains(first_line) {
+                record.injected += 1;
+                record.last_injected = Some(now);
+            } else if built.contains(first_line) {
+                record.cut += 1;
+            } else {
+                record.excluded += 1;
+            }
+            usage.insert(key, record);
+        }
+    }
+    usage
+}
+
+/// Date written at the start of an entry (`- [2025-01-15 10:30] ...`)
+fn entry_date(text: &str) -> Option<NaiveDate> {
+    let rest = text.trim_start_matches(['-', '*', ' ']).strip_prefix('[')?;
+    NaiveDate::parse_from_str(rest.get(..10)?, "%Y-%m-%d").ok()
+}
+
+/// Why an entry is a pruning candidate, if it is one
+fn audit_entry(text: &str, usage: Option<&EntryUsage>, stale_before: NaiveDate) -> Option<String> {
+    if let Some(usage) = usage.filter(|u| u.injected == 0 && u.cut > 0) {
+        return Some(format!("never injected (cut {} times)", usage.cut));
+    }
+    let touched = entry_date(text).or(usage.map(|u| u.first_seen.date_naive()))?;
+    (touched < stale_before).then(|| format!("stale (last touched {})", touched))
+}
+
+/// Lists entries that are never injected or haven't been touched in
+/// `stale_days`, as candidates for `clancy note rm`
+pub fn audit_project(project: &Project, stale_days: i64) -> Result<()> {
+    let usage = load_usage(project);
+    let stale_before = (Utc::now() - chrono::Duration::days(stale_days)).date_naive();
+    let mut candidates = 0;
+    for category in NOTE_CATEGORIES {
+        let mut lines = Vec::new();
+        for entry in parse_entries(&project.read_notes(category)?) {
+            let record = usage.get(&format!("{}:{}", category, entry.id));
+            if let Some(reason) = audit_entry(&entry.text, record, stale_before) {
+                let first = entry.text.lines().next().unwrap_or_default();
+                lines.push(format!(
+                    "  [{}] {:<32} {}",
+                    entry.id,
+                    reason,
+                    crate::text::truncate_ellipsis(first, 60)
+                ));
+            }
+        }
+        if !lines.is_empty() {
+            println!("{}:\n{}\n", category, lines.join("\n"));
+            candidates += lines.len();
+        }
+    }
+
+    if candidates == 0 {
+        println!("No pruning candidates in '{}'.", project.metadata.name);
+    } else {
+        println!(
+            "{} pruning candidate(s). Remove with: clancy note rm {} <category> <id>",
+            candidates, project.metadata.name
+        );
+    }
+    if usage.is_empty() {
+        println!("(No usage recorded yet; counts start with the next session.)");
+    }
+    Ok(())
+}
+
+/// Note file (in `notes/`) that old decision entries are moved to
+pub const DECISIONS_ARCHIVE: &str = "decisions-archive";
+
+/// Moves decision entries older than `months` into the decisions archive,
+/// returning how many were moved
+///
+/// An entry's age comes from its date stamp, else from when usage tracking
+/// first saw it; entries with neither stay put.
+pub fn archive_old_decisions(project: &Project, months: u32) -> Result<usize> {
+    let Some(cutoff) = Utc::now()
+        .date_naive()
+        .checked_sub_months(chrono::Months::new(months))
+    else {
+        return Ok(0);
+    };
+    let usage = load_usage(project);
+    let content = project.read_notes("decisions")?;
+    let (kept, archived) = split_old_entries(&content, cutoff, |id| {
+        usage
+            .get(&format!("decisions:{}", id))
+            .map(|u| u.first_seen.date_naive())
+    });
+    if archived.is_empty() {
+        return Ok(0);
+    }
+
+    let existing = project.read_notes(DECISIONS_ARCHIVE)?;
+    let mut archive = existing.trim_end().to_string();
+    for entry in &archived {
+        if !archive.is_empty() {
+            archive.push('\n');
+        }
+        archive.push_str(entry);
+    }
+    archive.push('\n');
+    // Archive first so a failed write can't lose entries
+    project.write_notes(DECISIONS_ARCHIVE, &archive)?;
+    project.write_notes("decisions", &kept)?;
+    Ok(archived.len())
+}
+
+/// Splits entries dated before `cutoff` out of a note file, returning the
+/// remaining content and the removed entries' original lines
+fn split_old_entries(
+    content: &str,
+    cutoff: NaiveDate,
+    first_seen: impl Fn(&str) -> Option<NaiveDate>,
+) -> (String, Vec<String>) {
+    let lines: Vec<&str> = content.lines().collect();
+    let old: Vec<std::ops::Range<usize>> = parse_entries(content)
+        .into_iter()
+        .filter(|e| {
+            entry_date(&e.text)
+                .or_else(|| first_seen(&e.id))
+                .is_some_and(|date| date < cutoff)
+        })
+        .map(|e| e.lines)
+        .collect();
+    let archived = old.iter().map(|r| lines[r.clone()].join("\n")).collect();
+    let kept = lines
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !old.iter().any(|r| r.contains(i)))
+        .map(|(_, line)| *line)
+        .collect::<Vec<_>>()
+        .join("\n");
+    // Blank lines that separated a removed trailing entry go with it
+    let kept = kept.trim_end();
+    let kept = if kept.is_empty() {
+        String::new()
+    } else {
+        format!("{}\n", kept)
+    };
+    (kept, archived)
+}
+
+/// A problem found by `lint_notes`
+#[derive(Debug, PartialEq)]
+pub struct LintIssue {
+    /// 1-based line number in the original file
+    pub line: usize,
+    pub message: String,
+    /// Whether `--fix` resolves this issue
+    pub fixable: bool,
+}
+
+/// Checks a note file for formatting problems
+///
+/// Returns the issues found and the content with every fixable issue
+/// resolved: bullets normalized to `- `, repeated headers dropped, and runs
+/// of blank lines collapsed.
+pub fn lint_notes(category: &str, content: &str, max_len: usize) -> (Vec<LintIssue>, String) {
+    let mut issues = Vec::new();
+    let mut fixed: Vec<String> = Vec::new();
+    let mut headers: Vec<&str> = Vec::new();
+
+    for (i, raw) in content.lines().enumerate() {
+        let line = raw.trim_end();
+        let number = i + 1;
+
+        if line.starts_with('#') {
+            if headers.contains(&line) {
+                issues.push(LintIssue {
+                    line: number,
+                    message: format!("duplicate header '{}'", line),
+                    fixable: true,
+                });
+                continue;
+            }
+            headers.push(line);
+        }
+
+        if line.is_empty() && fixed.last().is_some_and(|l| l.is_empty()) {
+            issues.push(LintIssue {
+                line: number,
+                message: "repeated blank line".to_string(),
+                fixable: true,
+            });
+            continue;
+        }
+
+        let normalized = normalize_bullet(line);
+        if normalized != line {
+            issues.push(LintIssue {
+                line: number,
+                message: "non-standard bullet".to_string(),
+                fixable: true,
+            });
+        }
+        fixed.push(normalized);
+    }
+
+    for entry in parse_entries(content) {
+        let number = entry.lines.start + 1;
+        if entry.text.chars().count() > max_len {
+            issues.push(LintIssue {
+                line: number,
+                message: format!(
+                    "entry is {} chars (limit {})",
+                    entry.text.chars().count(),
+                    max_len
+                ),
+                fixable: false,
+            });
+        }
+        let body = strip_timestamp(entry.text.trim_start_matches(['-', '*']).trim_start());
+        let expected = match category {
+            "decisions"
+                if !has_date_prefix(entry.text.trim_start_matches(['-', '*']).trim_start()) =>
+            {
+                Some("decision should start with [YYYY-MM-DD]")
+            }
+            "failures" if !is_failure_phrase(body) => {
+                Some("failure should read \"Don't try X — causes Y because Z\"")
+            }
+            _ => None,
+        };
+        if let Some(message) = expected {
+            issues.push(LintIssue {
+                line: number,
+                message: message.to_string(),
+                fixable: false,
+            });
+        }
+    }
+
+    issues.sort_by_key(|issue| issue.line);
+    while fixed.last().is_some_and(|l| l.is_empty()) {
+        fixed.pop();
+    }
+    let mut fixed = fixed.join("\n");
+    if !fixed.is_empty() {
+        fixed.push('\n');
+    }
+    (issues, fixed)
+}
+
+/// Rewrites `*`, `+`, `•`, and `-text` bullets as `- text`
+///
+/// A `-` only counts as a bullet when a letter or a `[YYYY-MM-DD` timestamp
+/// follows, so text like `-5` is left alone.
+fn normalize_bullet(line: &str) -> String {
+    for marker in ["* ", "+ ", "• ", "•"] {
+        if let Some(rest) = line.strip_prefix(marker) {
+            return format!("- {}", rest.trim_start());
+        }
+    }
+    match line.strip_prefix('-') {
+        Some(rest) if rest.starts_with(char::is_alphabetic) || has_date_prefix(rest) => {
+            format!("- {}", rest)
+        }
+        _ => line.to_string(),
+    }
+}
+
+/// True if text starts with `[YYYY-MM-DD` (optionally followed by a time)
+fn has_date_prefix(text: &str) -> bool {
+    text.strip_prefix('[')
+        .and_then(|rest| rest.get(..10))
+        .is_some_and(|date| chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok())
+}
+
+/// Drops a leading `[...]` timestamp from an entry
+fn strip_timestamp(text: &str) -> &str {
+    if has_date_prefix(text) {
+        if let Some(end) = text.find(']') {
+            return text[end + 1..].trim_start();
+        }
+    }
+    text
+}
+
+fn is_failure_phrase(text: &str) -> bool {
+    let lower = text.to_lowercase();
+    ["don't", "do not", "don’t", "never", "avoid"]
+        .iter()
+        .any(|prefix| lower.starts_with(prefix))
+}
+
+/// Lints one or all note categories, optionally writing fixes
+pub fn lint_project(project: &Project, fix: bool, max_len: usize) -> Result<()> {
+    let mut remaining = 0;
+    for category in NOTE_CATEGORIES {
+        let content = project.read_notes(category)?;
+        let (issues, fixed) = lint_notes(category, &content, max_len);
+        for issue in &issues {
+            let status = if issue.fixable && fix { " (fixed)" } else { "" };
+            println!("{}:{}: {}{}", category, issue.line, issue.message, status);
+        }
+        if fix && fixed != content && issues.iter().any(|i| i.fixable) {
+            project.write_notes(category, &fixed)?;
+        }
+        remaining += issues.iter().filter(|i| !fix || !i.fixable).count();
+    }
+
+    if remaining == 0 {
+        println!("Notes for '{}' look clean.", project.metadata.name);
+    } else if !fix {
+        println!(
+            "\n{} issue(s). Run with --fix to apply automatic fixes.",
+            remaining
+        );
+    }
+    Ok(())
+}
+
+/// Prints editor jump targets as `path:line`, for editor plugins and pickers
+///
+/// `at` picks a note category (singular or plural), the decisions archive,
+/// or `task`; with an `id`
+/// (an entry ID or unique prefix, or a task number) just that location is
+/// printed, otherwise every entry as `path:line: [id] text`.
+pub fn open_notes(project: &Project, at: Option<&str>, id: Option<&str>) -> Result<()> {
+    if let Some("task" | "tasks") = at {
+        return match id {
+            Some(id) => {
+                let number: u32 = id
+                    .parse()
+                    .with_context(|| format!("Task number expected, got '{}'", id))?;
+                println!("{}:1", tasklog::find_task_log(project, number)?.display());
+                Ok(())
+            }
+            None => {
+                for (number, path) in tasklog::task_log_paths(project)? {
+                    let prompt = tasklog::TaskLog::load(&path)
+                        .map(|log| log.prompt.lines().next().unwrap_or_default().to_string())
+                        .unwrap_or_default();
+                    println!("{}:1: {:03} {}", path.display(), number, prompt);
+                }
+                Ok(())
+            }
+        };
+    }
+
+    for location in note_locations(project, at, id)? {
+        println!("{}", location);
+    }
+    Ok(())
+}
+
+/// Note files `open` searches: every category, the extra notes, and the
+/// decisions archive
+fn open_note_files() -> Vec<&'static str> {
+    NOTE_CATEGORIES
+        .iter()
+        .chain(EXTRA_NOTES)
+        .copied()
+        .chain(std::iter::once(DECISIONS_ARCHIVE))
+        .collect()
+}
+
+/// The `path:line` lines `open_notes` prints for note entries
+fn note_locations(project: &Project, at: Option<&str>, id: Option<&str>) -> Result<Vec<String>> {
+    let categories: Vec<&str> = match at {
+        Some(at) => vec![note_file_for(at).with_context(|| {
+            format!(
+                "Unknown target '{}'. Use task or one of: {}",
+                at,
+                open_note_files().join(", ")
+            )
+        })?],
+        None => open_note_files(),
+    };
+
+    let matches = matching_entries(project, &categories, id)?;
+    Ok(match id {
+        Some(id) => {
+            let (category, entry) = single_match(matches, id)?;
+            vec![format!(
+                "{}:{}",
+                project.notes_path(category).display(),
+                entry.lines.start + 1
+            )]
+        }
+        None => matches
+            .iter()
+            .map(|(category, entry)| {
+                format!(
+                    "{}:{}: [{}] {}",
+                    project.notes_path(category).display(),
+                    entry.lines.start + 1,
+                    entry.id,
+                    entry.text.lines().next().unwrap_or_default()
+                )
+            })
+            .collect(),
+    })
+}
+
+/// Entries in `categories` whose ID starts with `id` (all entries when None)
+fn matching_entries(
+    project: &Project,
+    categories: &[&'static str],
+    id: Option<&str>,
+) -> Result<Vec<(&'static str, NoteEntry)>> {
+    let mut matches = Vec::new();
+    for &category in categories {
+        for entry in parse_entries(&project.read_notes(category)?) {
+            if id.is_none_or(|id| entry.id.starts_with(&id.to_lowercase())) {
+                matches.push((category, entry));
+            }
+        }
+    }
+    Ok(matches)
+}
+
+/// The one entry an ID (or prefix) picked, or an error naming the problem
+fn single_match(
+    mut matches: Vec<(&'static str, NoteEntry)>,
+    id: &str,
+) -> Result<(&'static str, NoteEntry)> {
+    match matches.len() {
+        0 => bail!("No note entry with ID '{}'", id),
+        1 => Ok(matches.remove(0)),
+        n => bail!("ID '{}' matches {} entries; use more characters", id, n),
+    }
+}
+
+/// Shows which task's extraction added a note entry, then that task's
+/// transcript
+///
+/// Entries added by hand, by a review, or edited since extraction have no
+/// recorded source.
+pub fn why(project: &Project, id: &str) -> Result<()> {
+    let (category, entry) =
+        single_match(matching_entries(project, NOTE_CATEGORIES, Some(id))?, id)?;
+    println!(
+        "[{}] {} ({})",
+        entry.id,
+        entry.text.lines().next().unwrap_or_default(),
+        category
+    );
+
+    let mut source = None;
+    for (number, path) in tasklog::task_log_paths(project)? {
+        let Ok(log) = tasklog::TaskLog::load(&path) else {
+            continue;
+        };
+        if extracted_entry(&log, category, &entry.id) {
+            source = Some((number, log));
+            break;
+        }
+    }
+    let Some((number, log)) = source else {
+        println!("No task's extraction recorded this entry; it was added by hand or edited since.");
+        return Ok(());
+    };
+    let when = log
+        .timestamp
+        .map(|t| format!(" on {}", t.format("%Y-%m-%d %H:%M")))
+        .unwrap_or_default();
+    println!(
+        "Added by task {}{}: {}\n",
+        number,
+        when,
+        log.prompt.lines().next().unwrap_or_default()
+    );
+    render::show_task(&project.metadata.name, number, ShowFormat::Text, None)
+}
+
+/// Whether a task's recorded extraction contained the entry with `id`
+fn extracted_entry(log: &tasklog::TaskLog, category: &str, id: &str) -> bool {
+    log.extracted_notes
+        .as_ref()
+        .and_then(|notes| notes.category(category))
+        .is_some_and(|content| parse_entries(content).iter().any(|e| e.id == id))
+}
+
+/// The note file a user-typed target names, accepting singular forms like
+/// `decision`
+fn note_file_for(at: &str) -> Option<&'static str> {
+    let at = at.to_lowercase();
+    open_note_files()
+        .into_iter()
+        .find(|name| *name == at || name.strip_suffix('s') == Some(at.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTES: &str =
+        "## Decisions\n- Use sqlx\n  because of compile-time checks\n- Keep tokio\n\nFree text\n";
+
+    #[test]
+    fn test_update_usage() {
+        let now = Utc::now();
+        let notes = vec![("decisions", "- Use sqlx\n- Keep tokio\n".to_string())];
+        let usage = update_usage(
+            BTreeMap::new(),
+            &notes,
+            "## Key Decisions\n- Use sqlx\n- Keep tokio\n",
+            "## Key Decisions\n- Use sqlx\n",
+            now,
+        );
+        let kept = &usage[&format!("decisions:{}", entry_id("- Use sqlx"))];
+        assert_eq!((kept.injected, kept.cut), (1, 0));
+        let cut = &usage[&format!("decisions:{}", entry_id("- Keep tokio"))];
+        assert_eq!((cut.injected, cut.cut, cut.last_injected), (0, 1, None));
+
+        // Removed entries drop out of the usage map
+        let notes = vec![("decisions", "- Keep tokio\n".to_string())];
+        let usage = update_usage(usage, &notes, "- Keep tokio\n", "", now);
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage.values().next().unwrap().cut, 2);
+    }
+
+    #[test]
+    fn test_update_usage_counts_exclusion_apart_from_cuts() {
+        let notes = vec![("decisions", "- Keep tokio\n".to_string())];
+        let usage = update_usage(BTreeMap::new(), &notes, "", "", Utc::now());
+        let record = usage.values().next().unwrap();
+        assert_eq!((record.cut, record.excluded), (0, 1));
+    }
+
+    #[test]
+    fn test_split_old_entries() {
+        let content = "## Decisions\n- [2024-01-10] Use sqlx\n  for compile-time checks\n- [2025-03-01 09:00] Keep tokio\n- Undated choice\n";
+        let cutoff = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let (kept, archived) = split_old_entries(content, cutoff, |_| None);
+        assert_eq!(
+            kept,
+            "## Decisions\n- [2025-03-01 09:00] Keep tokio\n- Undated choice\n"
+        );
+        assert_eq!(
+            archived,
+            vec!["- [2024-01-10] Use sqlx\n  for compile-time checks"]
+        );
+
+        // Undated entries fall back to when usage tracking first saw them
+        let undated = entry_id("- Undated choice");
+        let (_, archived) = split_old_entries(content, cutoff, |id| {
+            (id == undated).then(|| NaiveDate::from_ymd_opt(2024, 5, 1).unwrap())
+        });
+        assert_eq!(archived.len(), 2);
+    }
+
+    #[test]
+    fn test_audit_entry() {
+        let cutoff = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let fresh = EntryUsage {
+            first_seen: Utc::now(),
+            injected: 3,
+            cut: 0,
+            excluded: 0,
+            last_injected: None,
+        };
+        assert_eq!(audit_entry("- Use sqlx", Some(&fresh), cutoff), None);
+        assert_eq!(
+            audit_entry("- [2024-06-01 10:00] Pin tokio", Some(&fresh), cutoff).as_deref(),
+            Some("stale (last touched 2024-06-01)")
+        );
+        let never = EntryUsage {
+            injected: 0,
+            cut: 4,
+            ..fresh
+        };
+        assert_eq!(
+            audit_entry("- Use sqlx", Some(&never), cutoff).as_deref(),
+            Some("never injected (cut 4 times)")
+        );
+        assert_eq!(audit_entry("- untracked", None, cutoff), None);
+    }
+
+    #[test]
+    fn test_parse_entries() {
+        let entries = parse_entries(NOTES);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries[0].text,
+            "- Use sqlx\nbecause of compile-time checks"
+        );
+        assert_eq!(entries[0].lines, 1..3);
+        assert_eq!(entries[1].id, entry_id("- Keep tokio"));
+        assert_eq!(entries[1].id.len(), ID_LEN);
+    }
+
+    #[test]
+    fn test_remove_entry() {
+        let id = parse_entries(NOTES)[0].id.clone();
+        assert_eq!(
+            remove_entry(NOTES, &id).unwrap(),
+            "## Decisions\n- Keep tokio\n\nFree text\n"
+        );
+        assert!(remove_entry(NOTES, "zzzzzz").is_err());
+    }
+
+    #[test]
+    fn test_lint_fixes_star_bullet() {
+        let (issues, fixed) = lint_notes("architecture", "* Uses sqlx\n", 300);
+        assert_eq!(fixed, "- Uses sqlx\n");
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].fixable);
+    }
+
+    #[test]
+    fn test_lint_fixes_dash_without_space() {
+        let (_, fixed) = lint_notes("decisions", "-[2025-01-03] Kept tokio\n", 300);
+        assert_eq!(fixed, "- [2025-01-03] Kept tokio\n");
+    }
+
+    #[test]
+    fn test_lint_drops_duplicate_header() {
+        let content = "## Decisions\n- [2025-01-02] Chose sqlx\n## Decisions\n";
+        let (issues, fixed) = lint_notes("decisions", content, 300);
+        assert_eq!(fixed, "## Decisions\n- [2025-01-02] Chose sqlx\n");
+        assert_eq!(issues[0].line, 3);
+    }
+
+    #[test]
+    fn test_lint_collapses_blank_lines() {
+        let (issues, fixed) = lint_notes("architecture", "- Uses sqlx\n\n\n- Uses tokio\n", 300);
+        assert_eq!(fixed, "- Uses sqlx\n\n- Uses tokio\n");
+        assert_eq!(issues.len(), 1);
+    }
+
+    #[test]
+    fn test_normalize_bullet_leaves_negative_numbers() {
+        assert_eq!(normalize_bullet("-5 degrees"), "-5 degrees");
+    }
+
+    #[test]
+    fn test_lint_flags_decision_without_date() {
+        let content = "- Chose sqlx\n- [2025-01-02 10:00] Chose tokio\n";
+        let (issues, _) = lint_notes("decisions", content, 300);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].line, 1);
+        assert!(!issues[0].fixable);
+    }
+
+    #[test]
+    fn test_lint_flags_long_entry() {
+        let content = "- [2025-01-02 10:00] Don't try X — causes Y because Z\n";
+        let (issues, _) = lint_notes("failures", content, 10);
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].message.starts_with("entry is 53 chars"));
+    }
+
+    #[test]
+    fn test_lint_flags_failure_phrasing() {
+        let (issues, _) = lint_notes("failures", "- X broke\n", 300);
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].message.starts_with("failure should read"));
+    }
+
+    #[test]
+    fn test_entry_id_is_stable() {
+        assert_eq!(entry_id("- Use sqlx"), entry_id("  - Use sqlx  "));
+        assert_ne!(entry_id("- Use sqlx"), entry_id("- Use diesel"));
+    }
+
+    #[test]
+    fn test_note_file_for() {
+        assert_eq!(note_file_for("decision"), Some("decisions"));
+        assert_eq!(note_file_for("Failures"), Some("failures"));
+        assert_eq!(note_file_for("architecture"), Some("architecture"));
+        assert_eq!(note_file_for("scratch"), Some("scratch"));
+        assert_eq!(note_file_for("diary"), None);
+    }
+
+    /// Builds a project rooted in a temp directory with one note file written
+    fn temp_project(dir: &std::path::Path, category: &str, content: &str) -> Project {
+        std::fs::create_dir_all(dir.join("notes")).unwrap();
+        let project = Project::new(
+            crate::project::ProjectMetadata::new("notes-test"),
+            dir.to_path_buf(),
+        );
+        project.write_notes(category, content).unwrap();
+        project
+    }
+
+    #[test]
+    fn test_note_locations_prints_path_and_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = temp_project(dir.path(), "decisions", NOTES);
+        let id = parse_entries(NOTES)[1].id.clone();
+        assert_eq!(
+            note_locations(&project, None, Some(&id)).unwrap(),
+            vec![format!("{}:4", project.notes_path("decisions").display())]
+        );
+    }
+
+    #[test]
+    fn test_note_locations_matches_id_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = temp_project(dir.path(), "decisions", NOTES);
+        let id = parse_entries(NOTES)[0].id.clone();
+        let found = note_locations(&project, Some("decision"), Some(&id[..3])).unwrap();
+        assert_eq!(
+            found,
+            vec![format!("{}:2", project.notes_path("decisions").display())]
+        );
+    }
+
+    #[test]
+    fn test_note_locations_rejects_ambiguous_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        // Add entries until two IDs share a first character
+        let mut content = String::new();
+        let mut seen = std::collections::HashSet::new();
+        let prefix = (0..)
+            .find_map(|n| {
+                let bullet = format!("- Entry {}", n);
+                content.push_str(&bullet);
+                content.push('\n');
+                let first = entry_id(&bullet)[..1].to_string();
+                (!seen.insert(first.clone())).then_some(first)
+            })
+            .unwrap();
+        let project = temp_project(dir.path(), "decisions", &content);
+        let err = note_locations(&project, None, Some(&prefix)).unwrap_err();
+        assert!(err.to_string().contains("use more characters"));
+    }
+
+    #[test]
+    fn test_note_locations_searches_decisions_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = temp_project(dir.path(), DECISIONS_ARCHIVE, NOTES);
+        let id = parse_entries(NOTES)[0].id.clone();
+        assert_eq!(
+            note_locations(&project, None, Some(&id)).unwrap(),
+            vec![format!(
+                "{}:2",
+                project.notes_path(DECISIONS_ARCHIVE).display()
+            )]
+        );
+        assert_eq!(note_file_for("decisions-archive"), Some(DECISIONS_ARCHIVE));
+    }
+
+    #[test]
+    fn test_extracted_entry_matches_recorded_extraction() {
+        let log: tasklog::TaskLog = serde_json::from_str(
+            r#"{"task_number": 3, "extracted_notes": {"decisions": "- Use sqlx\n  because of compile-time checks\n"}}"#,
+        )
+        .unwrap();
+        let id = entry_id("- Use sqlx\nbecause of compile-time checks");
+        assert!(extracted_entry(&log, "decisions", &id));
+        assert!(!extracted_entry(&log, "failures", &id));
+        assert!(!extracted_entry(
+            &log,
+            "decisions",
+            &entry_id("- Keep tokio")
+        ));
+    }
+}
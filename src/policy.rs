@@ -0,0 +1,201 @@
+//! Tool usage policy checks
+//!
+//! `[policy] discouraged` in config.toml lists commands tasks shouldn't run,
+//! like `Bash: rm -rf` or `git push --force`. A rule without a tool name
+//! only looks at commands (a tool input's `command` field), so editing a
+//! file that mentions `rm -rf` isn't flagged. Nothing is blocked; after each
+//! task the transcript's tool calls are scanned and any matches are flagged
+//! in the completion summary and recorded in the task log.
+
+use serde::Serialize;
+
+use crate::transcript::{Message, Transcript};
+
+/// A tool call that matched a discouraged rule
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Violation {
+    /// The rule as written in config
+    pub rule: String,
+    pub tool: String,
+    /// The call's input text, e.g. a Bash command
+    pub input: String,
+}
+
+/// A parsed rule: an optional tool name and a pattern to find in its input
+struct Rule<'a> {
+    raw: &'a str,
+    tool: Option<&'a str>,
+    pattern: String,
+}
+
+impl<'a> Rule<'a> {
+    /// "Bash: rm -rf" scopes the pattern to Bash; "git push --force" (or a
+    /// prefix with spaces in it) applies to any tool's command
+    fn parse(raw: &'a str) -> Rule<'a> {
+        let (tool, pattern) = match raw.split_once(':') {
+            Some((tool, pattern)) if is_tool_name(tool.trim()) => (Some(tool.trim()), pattern),
+            _ => (None, raw),
+        };
+        Rule {
+            raw,
+            tool,
+            pattern: normalize(pattern),
+        }
+    }
+
+    /// Returns the matched text: a tool-scoped rule looks at all of the
+    /// input (and with no pattern flags every use of the tool), a bare rule
+    /// only at a command
+    fn matches(&self, tool_name: &str, input: &serde_json::Value) -> Option<String> {
+        match self.tool {
+            Some(tool) if !tool.eq_ignore_ascii_case(tool_name) => None,
+            Some(_) => {
+                let text = input_text(input);
+                text.contains(&self.pattern).then_some(text)
+            }
+            None => command_text(input)
+                .filter(|text| !self.pattern.is_empty() && text.contains(&self.pattern)),
+        }
+    }
+}
+
+fn is_tool_name(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Collapses whitespace so "rm  -rf" still matches "rm -rf"
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The text a tool call's input is matched against: its top-level string
+/// fields, e.g. a Bash command or an Edit's file path and strings
+fn input_text(input: &serde_json::Value) -> String {
+    match input {
+        serde_json::Value::Object(map) => normalize(
+            &map.values()
+                .filter_map(|v| v.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+        ),
+        serde_json::Value::String(s) => normalize(s),
+        _ => String::new(),
+    }
+}
+
+/// A tool call's shell command, when it has one
+fn command_text(input: &serde_json::Value) -> Option<String> {
+    input.get("command").and_then(|c| c.as_str()).map(normalize)
+}
+
+/// Finds the tool calls in a transcript that match any discouraged rule,
+/// reporting each call once under the first rule it matches
+pub fn check_transcript(discouraged: &[String], transcript: &Transcript) -> Vec<Violation> {
+    let rules: Vec<Rule> = discouraged
+        .iter()
+        .filter(|raw| !raw.trim().is_empty())
+        .map(|raw| Rule::parse(raw))
+        .collect();
+    if rules.is_empty() {
+        return Vec::new();
+    }
+    transcript
+        .messages
+        .iter()
+        .filter_map(|msg| match msg {
+            Message::ToolUse {
+                tool_name, input, ..
+            } => rules.iter().find_map(|rule| {
+                rule.matches(tool_name, input).map(|text| Violation {
+                    rule: rule.raw.to_string(),
+                    tool: tool_name.clone(),
+                    input: text,
+                })
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool_use(tool_name: &str, input: serde_json::Value) -> Message {
+        Message::ToolUse {
+            tool_name: tool_name.to_string(),
+            tool_id: "t1".to_string(),
+            input,
+        }
+    }
+
+    #[test]
+    fn test_check_transcript() {
+        let transcript = Transcript {
+            init: None,
+            messages: vec![
+                tool_use("Bash", serde_json::json!({"command": "rm  -rf target"})),
+                tool_use(
+                    "Bash",
+                    serde_json::json!({"command": "git push --force origin"}),
+                ),
+                tool_use("Read", serde_json::json!({"file_path": "docs/rm -rf.md"})),
+                tool_use("Bash", serde_json::json!({"command": "cargo test"})),
+                tool_use(
+                    "WebFetch",
+                    serde_json::json!({"url": "https://example.com"}),
+                ),
+            ],
+            result: None,
+            lossy_lines: 0,
+        };
+        let rules = vec![
+            "Bash: rm -rf".to_string(),
+            "git push --force".to_string(),
+            "WebFetch:".to_string(),
+        ];
+        let violations = check_transcript(&rules, &transcript);
+        let found: Vec<(&str, &str)> = violations
+            .iter()
+            .map(|v| (v.rule.as_str(), v.input.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("Bash: rm -rf", "rm -rf target"),
+                ("git push --force", "git push --force origin"),
+                ("WebFetch:", "https://example.com"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bare_rule_ignores_edit_strings() {
+        let transcript = Transcript {
+            init: None,
+            messages: vec![tool_use(
+                "Edit",
+                serde_json::json!({
+                    "file_path": "docs/cleanup.md",
+                    "old_string": "Never run git push --force",
+                    "new_string": "Avoid git push --force"
+                }),
+            )],
+            result: None,
+            lossy_lines: 0,
+        };
+        let rules = vec!["git push --force".to_string()];
+        assert!(check_transcript(&rules, &transcript).is_empty());
+    }
+
+    #[test]
+    fn test_rule_parse() {
+        let rule = Rule::parse("Bash: rm -rf");
+        assert_eq!(rule.tool, Some("Bash"));
+        assert_eq!(rule.pattern, "rm -rf");
+        // A colon after a multi-word prefix is part of the pattern
+        let rule = Rule::parse("git push: --force");
+        assert_eq!(rule.tool, None);
+        assert_eq!(rule.pattern, "git push: --force");
+    }
+}
@@ -0,0 +1,209 @@
+//! Pre-task cost estimates and prompt cache savings
+//!
+//! Claude Code reports the real cost once a task finishes; this estimates the
+//! input side beforehand from the token count of what clancy sends. Claude
+//! Code's own system prompt and tool definitions aren't counted, so the
+//! estimate is a lower bound.
+//!
+//! After a task, the reported cache token counts give an estimate of what
+//! prompt caching saved compared with sending everything as fresh input.
+//!
+//! While a task streams, [`CostMeter`] adds up the usage reported on each
+//! assistant message so a per-task cost ceiling can stop it early.
+
+use std::collections::HashMap;
+
+use crate::config::PricingConfig;
+use crate::transcript::TokenUsage;
+
+/// Cache read price as a fraction of the input price
+const CACHE_READ_MULTIPLIER: f64 = 0.1;
+/// Cache write price as a fraction of the input price
+const CACHE_WRITE_MULTIPLIER: f64 = 1.25;
+/// Output price as a multiple of the input price (the same for every model
+/// in the table)
+const OUTPUT_MULTIPLIER: f64 = 5.0;
+
+/// Input prices in USD per million tokens, matched by model name substring
+/// in order
+const INPUT_PRICES: &[(&str, f64)] = &[
+    ("opus-4-5", 5.0),
+    ("opus", 15.0),
+    ("sonnet", 3.0),
+    ("haiku-4-5", 1.0),
+    ("haiku", 0.8),
+];
+
+/// Input price per million tokens for the configured task model, if known
+pub fn input_price_per_mtok(pricing: &PricingConfig) -> Option<f64> {
+    model_input_price(pricing, &pricing.task_model)
+}
+
+/// Input price per million tokens for a model, unless overridden by
+/// `pricing.input_per_mtok`
+fn model_input_price(pricing: &PricingConfig, model: &str) -> Option<f64> {
+    if let Some(price) = pricing.input_per_mtok {
+        return Some(price);
+    }
+    let model = model.to_lowercase();
+    INPUT_PRICES
+        .iter()
+        .find(|(name, _)| model.contains(name))
+        .map(|(_, price)| *price)
+}
+
+/// Estimated input cost in USD for a task sending `tokens` tokens
+pub fn estimate_input_cost(pricing: &PricingConfig, tokens: usize) -> Option<f64> {
+    input_price_per_mtok(pricing).map(|price| tokens as f64 * price / 1_000_000.0)
+}
+
+/// Estimated USD saved by prompt caching for a task run on `model` (falling
+/// back to `pricing.task_model`): cache reads at a tenth of the input price,
+/// less the premium paid for cache writes
+pub fn cache_savings(
+    pricing: &PricingConfig,
+    model: Option<&str>,
+    usage: &TokenUsage,
+) -> Option<f64> {
+    let price = model_input_price(pricing, model.unwrap_or(&pricing.task_model))?;
+    let read = usage.cache_read_tokens.unwrap_or(0) as f64;
+    let written = usage.cache_creation_tokens.unwrap_or(0) as f64;
+    let saved = read * (1.0 - CACHE_READ_MULTIPLIER) - written * (CACHE_WRITE_MULTIPLIER - 1.0);
+    Some(saved * price / 1_000_000.0)
+}
+
+/// Share of input tokens served from the prompt cache, if any input was sent
+pub fn cache_hit_rate(input_tokens: u64, cache_read: u64, cache_written: u64) -> Option<f64> {
+    let total = input_tokens + cache_read + cache_written;
+    (total > 0).then(|| cache_read as f64 / total as f64)
+}
+
+/// Running cost estimate for a streaming task
+///
+/// Claude Code repeats a message's usage on every content block it streams
+/// for that message, so usage is tracked per message ID rather than summed
+/// line by line.
+pub struct CostMeter<'a> {
+    pricing: &'a PricingConfig,
+    by_message: HashMap<String, f64>,
+}
+
+impl<'a> CostMeter<'a> {
+    pub fn new(pricing: &'a PricingConfig) -> Self {
+        CostMeter {
+            pricing,
+            by_message: HashMap::new(),
+        }
+    }
+
+    /// Folds in one stream-json event and returns the running total in USD
+    pub fn observe(&mut self, event: &serde_json::Value) -> f64 {
+        if event.get("type").and_then(|t| t.as_str()) == Some("assistant") {
+            if let Some(message) = event.get("message") {
+                if let Some(cost) = self.message_cost(message) {
+                    let id = message
+                        .get("id")
+                        .and_then(|id| id.as_str())
+                        .map(String::from)
+                        .unwrap_or_else(|| format!("#{}", self.by_message.len()));
+                    self.by_message.insert(id, cost);
+                }
+            }
+        }
+        self.total()
+    }
+
+    pub fn total(&self) -> f64 {
+        self.by_message.values().sum()
+    }
+
+    fn message_cost(&self, message: &serde_json::Value) -> Option<f64> {
+        let usage = message.get("usage")?;
+        let model = message
+            .get("model")
+            .and_then(|m| m.as_str())
+            .unwrap_or(&self.pricing.task_model);
+        let price = model_input_price(self.pricing, model)?;
+        let tokens = |key: &str| usage.get(key).and_then(|v| v.as_u64()).unwrap_or(0) as f64;
+        let weighted = tokens("input_tokens")
+            + tokens("cache_read_input_tokens") * CACHE_READ_MULTIPLIER
+            + tokens("cache_creation_input_tokens") * CACHE_WRITE_MULTIPLIER
+            + tokens("output_tokens") * OUTPUT_MULTIPLIER;
+        Some(weighted * price / 1_000_000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pricing(model: &str) -> PricingConfig {
+        PricingConfig {
+            task_model: model.to_string(),
+            ..PricingConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_input_price_lookup() {
+        assert_eq!(input_price_per_mtok(&pricing("sonnet")), Some(3.0));
+        assert_eq!(
+            input_price_per_mtok(&pricing("claude-opus-4-5-20251101")),
+            Some(5.0)
+        );
+        assert_eq!(
+            input_price_per_mtok(&pricing("claude-opus-4-1")),
+            Some(15.0)
+        );
+        assert_eq!(input_price_per_mtok(&pricing("mystery-model")), None);
+
+        let mut custom = pricing("mystery-model");
+        custom.input_per_mtok = Some(2.0);
+        assert_eq!(input_price_per_mtok(&custom), Some(2.0));
+    }
+
+    #[test]
+    fn test_cache_savings() {
+        let usage = TokenUsage {
+            input_tokens: 1_000,
+            output_tokens: 500,
+            cache_read_tokens: Some(1_000_000),
+            cache_creation_tokens: Some(100_000),
+        };
+        // 1M reads save 0.9 * $3; 100k writes cost 0.25 * $0.30 extra
+        let saved = cache_savings(&pricing("sonnet"), None, &usage).unwrap();
+        assert!((saved - 2.625).abs() < 1e-9);
+        let opus = cache_savings(&pricing("sonnet"), Some("claude-opus-4-5"), &usage).unwrap();
+        assert!((opus - 4.375).abs() < 1e-9);
+        assert_eq!(cache_savings(&pricing("unknown"), None, &usage), None);
+
+        assert_eq!(cache_hit_rate(0, 0, 0), None);
+        assert_eq!(cache_hit_rate(100, 300, 0), Some(0.75));
+    }
+
+    #[test]
+    fn test_estimate_input_cost() {
+        assert_eq!(estimate_input_cost(&pricing("sonnet"), 500_000), Some(1.5));
+        assert_eq!(estimate_input_cost(&pricing("unknown"), 500_000), None);
+    }
+
+    #[test]
+    fn test_cost_meter() {
+        let config = pricing("sonnet");
+        let mut meter = CostMeter::new(&config);
+        let event = |id: &str, output: u64| {
+            serde_json::json!({"type": "assistant", "message": {
+                "id": id,
+                "model": "claude-sonnet-4-5",
+                "usage": {"input_tokens": 100_000, "cache_read_input_tokens": 1_000_000, "output_tokens": output}
+            }})
+        };
+        // 100k input + 1M cache reads at a tenth + 20k output at 5x, at $3/MTok
+        assert!((meter.observe(&event("m1", 20_000)) - 0.9).abs() < 1e-9);
+        // A repeated block of the same message doesn't count twice
+        assert!((meter.observe(&event("m1", 20_000)) - 0.9).abs() < 1e-9);
+        assert!((meter.observe(&event("m2", 0)) - 1.5).abs() < 1e-9);
+        let result = serde_json::json!({"type": "result", "total_cost_usd": 9.0});
+        assert!((meter.observe(&result) - 1.5).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,387 @@
+//! Transcript post-processors
+//!
+//! After each task, every registered [`TranscriptProcessor`] derives an
+//! artifact from the parsed transcript. Artifacts are stored in the task
+//! log's `artifacts` object under the processor's name, and those marked for
+//! context are listed with the task in later tasks' session history. The
+//! built-ins (summary, tools_used, files_touched) go through the same
+//! interface as commands registered under `[[processors.commands]]` in
+//! config.toml.
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::config::{CommandProcessorConfig, ProcessorsConfig};
+use crate::diff::file_diffs;
+use crate::transcript::Transcript;
+
+/// Names of the built-in processors, which configured commands can't reuse
+pub const BUILTIN_PROCESSORS: &[&str] = &["summary", "tools_used", "files_touched"];
+
+/// Derives one artifact from a task's transcript
+pub trait TranscriptProcessor {
+    /// Key the artifact is stored under in the task log
+    fn name(&self) -> &str;
+
+    /// The artifact, or None when there's nothing to record
+    fn process(&self, transcript: &Transcript) -> Result<Option<Value>>;
+
+    /// Whether the artifact belongs in later tasks' session context
+    fn in_context(&self) -> bool {
+        false
+    }
+}
+
+/// The transcript's generated summary
+pub struct Summary;
+
+impl TranscriptProcessor for Summary {
+    fn name(&self) -> &str {
+        "summary"
+    }
+
+    fn process(&self, transcript: &Transcript) -> Result<Option<Value>> {
+        Ok(Some(transcript.generate_summary().into()))
+    }
+}
+
+/// Names of the tools the task used
+pub struct ToolsUsed;
+
+impl TranscriptProcessor for ToolsUsed {
+    fn name(&self) -> &str {
+        "tools_used"
+    }
+
+    fn process(&self, transcript: &Transcript) -> Result<Option<Value>> {
+        Ok(Some(transcript.tools_used().into()))
+    }
+}
+
+/// Files changed by successful edits and writes, in first-touched order
+pub struct FilesTouched;
+
+impl TranscriptProcessor for FilesTouched {
+    fn name(&self) -> &str {
+        "files_touched"
+    }
+
+    fn process(&self, transcript: &Transcript) -> Result<Option<Value>> {
+        let files: Vec<String> = file_diffs(transcript).into_iter().map(|d| d.path).collect();
+        Ok((!files.is_empty()).then(|| files.into()))
+    }
+
+    fn in_context(&self) -> bool {
+        true
+    }
+}
+
+/// A configured shell command; its trimmed stdout is the artifact, parsed
+/// as JSON when it is JSON and kept as text otherwise
+///
+/// A command still running after its `timeout_secs` is killed.
+pub struct CommandProcessor {
+    config: CommandProcessorConfig,
+    dir: PathBuf,
+}
+
+impl TranscriptProcessor for CommandProcessor {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn process(&self, transcript: &Transcript) -> Result<Option<Value>> {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(&self.config.command)
+            .current_dir(&self.dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .with_context(|| format!("Failed to run `{}`", self.config.command))?;
+        // Read stdout on its own thread so a chatty command can't fill the
+        // pipe and stall while we wait for it to exit
+        let mut stdout = child
+            .stdout
+            .take()
+            .context("Processor stdout not captured")?;
+        let reader = std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = stdout.read_to_end(&mut buf);
+            buf
+        });
+        // Write stdin on its own thread too: a command that never reads it
+        // would otherwise block us on a full pipe, past the timeout
+        if let Some(mut stdin) = child.stdin.take() {
+            let input = serde_json::to_string(transcript)?;
+            std::thread::spawn(move || {
+                // A command that ignores its input may close stdin early
+                let _ = stdin.write_all(input.as_bytes());
+            });
+        }
+
+        let deadline = Instant::now() + Duration::from_secs(self.config.timeout_secs);
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                let _ = child.kill();
+                let _ = child.wait();
+                bail!(
+                    "`{}` timed out after {}s",
+                    self.config.command,
+                    self.config.timeout_secs
+                );
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        };
+        let stdout = reader.join().unwrap_or_default();
+        if !status.success() {
+            bail!("`{}` exited with {}", self.config.command, status);
+        }
+        Ok(parse_artifact(&String::from_utf8_lossy(&stdout)))
+    }
+
+    fn in_context(&self) -> bool {
+        self.config.context
+    }
+}
+
+fn parse_artifact(stdout: &str) -> Option<Value> {
+    let text = stdout.trim();
+    if text.is_empty() {
+        return None;
+    }
+    Some(serde_json::from_str(text).unwrap_or_else(|_| text.into()))
+}
+
+/// The processors to run for a task in `dir`: enabled built-ins first, then
+/// configured commands in order
+///
+/// A command reusing a built-in's or an earlier command's name is skipped
+/// with a warning, so artifacts never overwrite each other.
+pub fn registered(config: &ProcessorsConfig, dir: &Path) -> Vec<Box<dyn TranscriptProcessor>> {
+    let mut processors: Vec<Box<dyn TranscriptProcessor>> =
+        vec![Box::new(Summary), Box::new(ToolsUsed)];
+    if !config.disabled.iter().any(|name| name == "files_touched") {
+        processors.push(Box::new(FilesTouched));
+    }
+    let mut names: Vec<&str> = BUILTIN_PROCESSORS.to_vec();
+    for command in &config.commands {
+        if names.contains(&command.name.as_str()) {
+            eprintln!(
+                "Warning: processor name '{}' is already taken; skipping `{}`",
+                command.name, command.command
+            );
+            continue;
+        }
+        names.push(&command.name);
+        processors.push(Box::new(CommandProcessor {
+            config: command.clone(),
+            dir: dir.to_path_buf(),
+        }));
+    }
+    processors
+}
+
+/// Artifacts derived from one transcript
+#[derive(Debug, Default)]
+pub struct Artifacts {
+    /// `(name, artifact)` for the task log, in processor order
+    pub values: Vec<(String, Value)>,
+    /// Lines to show with the task in later session context
+    pub context: Vec<String>,
+}
+
+/// Runs every processor over a transcript; a failing processor is reported
+/// and skipped rather than failing the task
+pub fn run_all(processors: &[Box<dyn TranscriptProcessor>], transcript: &Transcript) -> Artifacts {
+    let mut artifacts = Artifacts::default();
+    for processor in processors {
+        match processor.process(transcript) {
+            Ok(Some(value)) => {
+                if processor.in_context() {
+                    artifacts
+                        .context
+                        .push(context_line(processor.name(), &value));
+                }
+                artifacts.values.push((processor.name().to_string(), value));
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("Warning: processor {} failed: {:#}", processor.name(), e),
+        }
+    }
+    artifacts
+}
+
+/// Context lines for artifacts already stored in a task log, as `run_all`
+/// would have produced them
+pub fn stored_context(
+    processors: &[Box<dyn TranscriptProcessor>],
+    stored: &serde_json::Map<String, Value>,
+) -> Vec<String> {
+    processors
+        .iter()
+        .filter(|processor| processor.in_context())
+        .filter_map(|processor| {
+            let value = stored.get(processor.name())?;
+            Some(context_line(processor.name(), value))
+        })
+        .collect()
+}
+
+fn context_line(name: &str, value: &Value) -> String {
+    format!("{}: {}", name, context_text(value))
+}
+
+/// Renders an artifact on one line: strings as-is, lists comma-separated
+fn context_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.lines().next().unwrap_or("").to_string(),
+        Value::Array(items) => items
+            .iter()
+            .map(|item| match item {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(", "),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcript::TaskResult;
+
+    #[test]
+    fn test_run_all() {
+        let output = r#"{"type":"assistant","message":{"content":[{"type":"tool_use","name":"Write","id":"t1","input":{"file_path":"src/new.rs","content":"fn main() {}\n"}}]}}
+{"type":"result","subtype":"success","result":"Added the new entry point","total_cost_usd":0.1}"#;
+        let transcript = Transcript::parse(output);
+        let dir = tempfile::tempdir().unwrap();
+        let config = ProcessorsConfig {
+            disabled: Vec::new(),
+            commands: vec![
+                CommandProcessorConfig {
+                    name: "messages".to_string(),
+                    command: "grep -o tool_use | wc -l".to_string(),
+                    context: true,
+                    timeout_secs: 30,
+                },
+                CommandProcessorConfig {
+                    name: "broken".to_string(),
+                    command: "exit 3".to_string(),
+                    context: false,
+                    timeout_secs: 30,
+                },
+            ],
+        };
+        let artifacts = run_all(&registered(&config, dir.path()), &transcript);
+        let names: Vec<&str> = artifacts.values.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["summary", "tools_used", "files_touched", "messages"]
+        );
+        assert_eq!(artifacts.values[3].1, Value::from(1));
+        assert_eq!(
+            artifacts.context,
+            vec!["files_touched: src/new.rs", "messages: 1"]
+        );
+    }
+
+    #[test]
+    fn test_stored_context_matches_in_context_processors() {
+        let dir = tempfile::tempdir().unwrap();
+        let processors = registered(&ProcessorsConfig::default(), dir.path());
+        let stored: serde_json::Map<String, Value> = serde_json::from_str(
+            r#"{"summary": "Added it", "files_touched": ["src/a.rs", "src/b.rs"]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            stored_context(&processors, &stored),
+            vec!["files_touched: src/a.rs, src/b.rs"]
+        );
+    }
+
+    #[test]
+    fn test_parse_artifact() {
+        assert_eq!(parse_artifact("  \n"), None);
+        assert_eq!(
+            parse_artifact("{\"passed\": 12}\n"),
+            Some(serde_json::json!({"passed": 12}))
+        );
+        assert_eq!(parse_artifact("12 passed\n"), Some("12 passed".into()));
+    }
+
+    fn command(name: &str, command: &str, timeout_secs: u64) -> CommandProcessorConfig {
+        CommandProcessorConfig {
+            name: name.to_string(),
+            command: command.to_string(),
+            context: false,
+            timeout_secs,
+        }
+    }
+
+    #[test]
+    fn test_registered_skips_taken_names() {
+        let config = ProcessorsConfig {
+            disabled: Vec::new(),
+            commands: vec![
+                command("summary", "echo clobber", 30),
+                command("lint", "echo a", 30),
+                command("lint", "echo b", 30),
+            ],
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let names: Vec<String> = registered(&config, dir.path())
+            .iter()
+            .map(|p| p.name().to_string())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["summary", "tools_used", "files_touched", "lint"]
+        );
+    }
+
+    #[test]
+    fn test_command_processor_times_out() {
+        let dir = tempfile::tempdir().unwrap();
+        let processor = CommandProcessor {
+            config: command("slow", "sleep 5", 0),
+            dir: dir.path().to_path_buf(),
+        };
+        let started = Instant::now();
+        let err = processor.process(&Transcript::parse("")).unwrap_err();
+        assert!(err.to_string().contains("timed out"));
+        assert!(started.elapsed() < Duration::from_secs(4));
+    }
+
+    #[test]
+    fn test_command_processor_times_out_without_reading_large_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let processor = CommandProcessor {
+            config: command("slow", "sleep 5", 0),
+            dir: dir.path().to_path_buf(),
+        };
+        let mut transcript = Transcript::parse("");
+        transcript.result = Some(TaskResult {
+            success: true,
+            result_text: Some("x".repeat(256 * 1024)),
+            duration_ms: None,
+            total_cost_usd: None,
+            usage: None,
+        });
+        let started = Instant::now();
+        let err = processor.process(&transcript).unwrap_err();
+        assert!(err.to_string().contains("timed out"));
+        assert!(started.elapsed() < Duration::from_secs(4));
+    }
+}
@@ -1,9 +1,17 @@
 use anyhow::{bail, Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
 
 use crate::config;
+use crate::i18n;
+use crate::text;
+use crate::transcript::TokenUsage;
 
 /// Project metadata stored in project.toml
 #[derive(Debug, Serialize, Deserialize)]
@@ -18,30 +26,330 @@ pub struct ProjectMetadata {
     /// Project status: active | archived
     #[serde(default = "default_status")]
     pub status: String,
+    /// Short human description, shown to related projects
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Note categories inherited from the parent project
+    #[serde(default = "default_inherit")]
+    pub inherit: Vec<String>,
+    /// Options passed through to the claude CLI for this project's tasks
+    #[serde(default)]
+    pub claude_cli: ClaudeCliOptions,
+    /// Claude Code settings merged into `.claude/settings.json` at session
+    /// start, set in project.toml under `[claude_settings]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claude_settings: Option<toml::Table>,
+    /// Named recurring tasks, set in project.toml under `[templates.<name>]`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub templates: BTreeMap<String, TaskTemplate>,
+    /// How tasks are summarized in session history, set in project.toml
+    /// under `[summarizer]`; None uses the heuristic summarizer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summarizer: Option<SummarizerOptions>,
     #[serde(default)]
     pub stats: ProjectStats,
 }
 
+/// A recurring task run by name with `clancy run --task` or `/run-template`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskTemplate {
+    pub prompt: String,
+    /// Model for this task, overriding routing rules
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Check command run after the task; its result is recorded in the log
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verify: Option<String>,
+    /// Labels recorded in the task log
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+/// Summarizer choice for a project's session history
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SummarizerOptions {
+    /// heuristic | api | template
+    pub kind: String,
+    /// Format string for the template summarizer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    /// Model for the api summarizer, instead of the extraction model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+/// Per-project claude CLI options, set in project.toml under `[claude_cli]`
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ClaudeCliOptions {
+    /// Extra directories the agent may access, passed as `--add-dir`
+    #[serde(default)]
+    pub add_dirs: Vec<String>,
+    /// MCP server config for this project's tasks, passed as `--mcp-config`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mcp_config: Option<String>,
+}
+
+impl ProjectMetadata {
+    /// Creates metadata for a fresh, unlinked project
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            created: Utc::now(),
+            last_task: None,
+            parent: None,
+            branch: None,
+            status: default_status(),
+            description: None,
+            inherit: default_inherit(),
+            claude_cli: ClaudeCliOptions::default(),
+            claude_settings: None,
+            templates: BTreeMap::new(),
+            summarizer: None,
+            stats: ProjectStats::default(),
+        }
+    }
+}
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct ProjectStats {
     pub total_sessions: u32,
     pub total_tasks: u32,
+    /// Sum of reported task costs in USD
+    #[serde(default)]
+    pub total_cost_usd: f64,
+    /// Highest task number handed out; 0 until first needed, when it is
+    /// seeded from the tasks directory
+    #[serde(default)]
+    pub last_task_number: u32,
+    /// Uncached input tokens across all tasks
+    #[serde(default)]
+    pub input_tokens: u64,
+    /// Input tokens served from the prompt cache
+    #[serde(default)]
+    pub cache_read_tokens: u64,
+    /// Input tokens written to the prompt cache
+    #[serde(default)]
+    pub cache_creation_tokens: u64,
 }
 
 fn default_status() -> String {
     "active".to_string()
 }
 
+fn default_inherit() -> Vec<String> {
+    vec!["architecture".to_string()]
+}
+
+/// Longest allowed project name
+const MAX_PROJECT_NAME_LEN: usize = 64;
+
+/// Checks that a new project name is safe to use as a directory name
+///
+/// Allowed: ASCII letters, digits, `-`, `_`, and `.`, starting with a letter
+/// or digit, at most 64 characters.
+pub fn validate_project_name(name: &str) -> Result<()> {
+    if name.is_empty() {
+        bail!("Project name is empty");
+    }
+    if name.len() > MAX_PROJECT_NAME_LEN {
+        bail!(
+            "Project name '{}' is longer than {} characters",
+            name,
+            MAX_PROJECT_NAME_LEN
+        );
+    }
+    if !name.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+        bail!("Project name '{}' must start with a letter or digit", name);
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && !matches!(c, '-' | '_' | '.'))
+    {
+        bail!(
+            "Project name '{}' contains '{}'; use letters, digits, '-', '_', or '.'",
+            name,
+            c
+        );
+    }
+    if name.contains("..") {
+        bail!("Project name '{}' must not contain '..'", name);
+    }
+    Ok(())
+}
+
+/// Whether a name would escape the projects directory or name a hidden dir
+fn is_unsafe_path_component(name: &str) -> bool {
+    name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) || name.contains("..")
+}
+
+/// Project metadata file, in the project directory
+pub const METADATA_FILE: &str = "project.toml";
+
+/// Previous version of the metadata, kept by each save
+pub const METADATA_BACKUP: &str = "project.toml.bak";
+
+/// Suffix of a write in progress; one left behind means the write was cut off
+pub const PENDING_SUFFIX: &str = ".tmp";
+
+/// Replaces a file's content so a crash leaves either the old or the new
+/// version, never a partial one
+///
+/// The content is written and synced to `<path>.<pid>.<n>-<nanos>` plus
+/// [`PENDING_SUFFIX`], a name no concurrent writer shares, then renamed over
+/// the original, and the directory is synced so the rename itself survives
+/// a crash.
+pub fn write_atomic(path: &Path, content: &str) -> Result<()> {
+    static WRITES: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.subsec_nanos());
+    let mut pending = path.as_os_str().to_owned();
+    pending.push(format!(
+        ".{}.{}-{}{}",
+        std::process::id(),
+        WRITES.fetch_add(1, Ordering::Relaxed),
+        nanos,
+        PENDING_SUFFIX
+    ));
+    let pending = PathBuf::from(pending);
+    let result = (|| -> Result<()> {
+        let mut file = std::fs::File::create(&pending)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+        drop(file);
+        std::fs::rename(&pending, path)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&pending);
+    }
+    result?;
+    #[cfg(unix)]
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// Writes of `path` cut off before their rename, newest first
+pub fn pending_writes(path: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Vec::new();
+    };
+    let prefix = format!("{}.", name.to_string_lossy());
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut pending: Vec<(SystemTime, PathBuf)> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            name.starts_with(&prefix) && name.ends_with(PENDING_SUFFIX)
+        })
+        .map(|e| {
+            let modified = e
+                .metadata()
+                .and_then(|m| m.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            (modified, e.path())
+        })
+        .collect();
+    pending.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    pending.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Lock file held while a task number is reserved
+const COUNTER_LOCK: &str = "task_counter.lock";
+
+/// How long to wait for another process to release a lock file
+const LOCK_WAIT: Duration = Duration::from_secs(5);
+
+/// Age at which a lock file is assumed left by a crashed process
+const LOCK_STALE: Duration = Duration::from_secs(30);
+
+/// Held lock file; removed on drop
+pub(crate) struct FileLock(PathBuf);
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Takes the lock file at `path`, waiting for another process to release
+/// it; a lock left behind by a crash is broken once stale
+pub(crate) fn lock_file(path: PathBuf) -> Result<FileLock> {
+    let deadline = Instant::now() + LOCK_WAIT;
+    loop {
+        match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(_) => return Ok(FileLock(path)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                let stale = std::fs::metadata(&path)
+                    .and_then(|m| m.modified())
+                    .ok()
+                    .and_then(|modified| modified.elapsed().ok())
+                    .is_some_and(|age| age >= LOCK_STALE);
+                if stale {
+                    let _ = std::fs::remove_file(&path);
+                } else if Instant::now() >= deadline {
+                    bail!(
+                        "Timed out waiting for {} (remove it if no clancy process is running)",
+                        path.display()
+                    );
+                } else {
+                    std::thread::sleep(Duration::from_millis(10));
+                }
+            }
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to create {}", path.display()))
+            }
+        }
+    }
+}
+
 /// Note categories
 pub const NOTE_CATEGORIES: &[&str] = &["architecture", "decisions", "failures", "plan"];
 
+/// Note file holding the project's standing instructions, passed to claude
+/// with `--append-system-prompt` rather than injected as context
+pub const INSTRUCTIONS_NOTE: &str = "instructions";
+
+/// Note file for human-only thoughts; never injected into context or touched
+/// by extraction
+pub const SCRATCH_NOTE: &str = "scratch";
+
+/// Note files editable alongside the categories but kept out of context.md
+pub const EXTRA_NOTES: &[&str] = &[INSTRUCTIONS_NOTE, SCRATCH_NOTE];
+
 /// Represents a project with its directory and metadata
 pub struct Project {
     pub metadata: ProjectMetadata,
     pub path: PathBuf,
+    /// Note contents by category, reused while the file is unchanged
+    note_cache: RefCell<HashMap<String, CachedNote>>,
+}
+
+/// A note file's content with the modification time and size it was read at
+struct CachedNote {
+    modified: SystemTime,
+    len: u64,
+    content: String,
 }
 
 impl Project {
+    /// Wraps loaded metadata for the project stored at `path`
+    pub fn new(metadata: ProjectMetadata, path: PathBuf) -> Self {
+        Self {
+            metadata,
+            path,
+            note_cache: RefCell::default(),
+        }
+    }
+
     /// Opens an existing project or creates a new one
     pub fn open_or_create(name: &str) -> Result<Self> {
         config::ensure_config_dir()?;
@@ -56,37 +364,37 @@ impl Project {
 
     /// Opens an existing project
     pub fn open(name: &str) -> Result<Self> {
+        // Only the path-safety rules: projects made before validation may
+        // use other characters
+        if is_unsafe_path_component(name) {
+            bail!("Invalid project name '{}'", name);
+        }
         let project_path = config::projects_dir()?.join(name);
         if !project_path.exists() {
             bail!("Project '{}' not found", name);
         }
 
-        let metadata_path = project_path.join("project.toml");
+        let metadata_path = project_path.join(METADATA_FILE);
         let metadata = if metadata_path.exists() {
             let content = std::fs::read_to_string(&metadata_path)
                 .with_context(|| format!("Failed to read project metadata: {:?}", metadata_path))?;
-            toml::from_str(&content).with_context(|| "Failed to parse project metadata")?
+            toml::from_str(&content).with_context(|| {
+                format!(
+                    "Failed to parse project metadata (try `clancy fsck {} --fix`)",
+                    name
+                )
+            })?
         } else {
             // Metadata file missing, create default
-            ProjectMetadata {
-                name: name.to_string(),
-                created: Utc::now(),
-                last_task: None,
-                parent: None,
-                branch: None,
-                status: "active".to_string(),
-                stats: ProjectStats::default(),
-            }
+            ProjectMetadata::new(name)
         };
 
-        Ok(Self {
-            metadata,
-            path: project_path,
-        })
+        Ok(Self::new(metadata, project_path))
     }
 
     /// Creates a new project
     pub fn create(name: &str) -> Result<Self> {
+        validate_project_name(name)?;
         config::ensure_config_dir()?;
         let project_path = config::projects_dir()?.join(name);
 
@@ -103,20 +411,9 @@ impl Project {
             .context("Failed to create notes directory")?;
 
         // Create metadata
-        let metadata = ProjectMetadata {
-            name: name.to_string(),
-            created: Utc::now(),
-            last_task: None,
-            parent: None,
-            branch: None,
-            status: "active".to_string(),
-            stats: ProjectStats::default(),
-        };
+        let metadata = ProjectMetadata::new(name);
 
-        let project = Self {
-            metadata,
-            path: project_path,
-        };
+        let mut project = Self::new(metadata, project_path);
 
         // Initialize empty note files
         for category in NOTE_CATEGORIES {
@@ -133,13 +430,27 @@ impl Project {
     }
 
     /// Saves the project metadata
-    pub fn save_metadata(&self) -> Result<()> {
-        let metadata_path = self.path.join("project.toml");
+    ///
+    /// The previous version is kept as `project.toml.bak` for `clancy fsck`.
+    /// The task counter never moves backwards: another process may have
+    /// reserved numbers since this one loaded the metadata.
+    pub fn save_metadata(&mut self) -> Result<()> {
+        let saved = self.saved_stats();
+        let stats = &mut self.metadata.stats;
+        if let Some(saved) = saved {
+            stats.last_task_number = stats.last_task_number.max(saved.last_task_number);
+            stats.total_sessions = stats.total_sessions.max(saved.total_sessions);
+        }
+
+        let metadata_path = self.path.join(METADATA_FILE);
         let content = toml::to_string_pretty(&self.metadata)
             .context("Failed to serialize project metadata")?;
-        std::fs::write(&metadata_path, content)
-            .with_context(|| format!("Failed to write project metadata: {:?}", metadata_path))?;
-        Ok(())
+        if metadata_path.exists() {
+            // Best effort: a missing backup only limits what fsck can restore
+            let _ = std::fs::copy(&metadata_path, self.path.join(METADATA_BACKUP));
+        }
+        write_atomic(&metadata_path, &content)
+            .with_context(|| format!("Failed to write project metadata: {:?}", metadata_path))
     }
 
     /// Returns the path to a note file
@@ -153,22 +464,45 @@ impl Project {
     }
 
     /// Reads notes for a category
+    ///
+    /// Contents are cached and only re-read when the file's modification
+    /// time or size changes, so edits made outside Clancy are still seen.
     pub fn read_notes(&self, category: &str) -> Result<String> {
         let path = self.notes_path(category);
-        if path.exists() {
-            std::fs::read_to_string(&path)
-                .with_context(|| format!("Failed to read notes: {:?}", path))
-        } else {
-            Ok(String::new())
+        let metadata = match std::fs::metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                self.note_cache.borrow_mut().remove(category);
+                return Ok(String::new());
+            }
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read notes: {:?}", path));
+            }
+        };
+        let modified = metadata.modified()?;
+        if let Some(cached) = self.note_cache.borrow().get(category) {
+            if cached.modified == modified && cached.len == metadata.len() {
+                return Ok(cached.content.clone());
+            }
         }
+
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read notes: {:?}", path))?;
+        self.note_cache.borrow_mut().insert(
+            category.to_string(),
+            CachedNote {
+                modified,
+                len: metadata.len(),
+                content: content.clone(),
+            },
+        );
+        Ok(content)
     }
 
     /// Writes notes for a category
     pub fn write_notes(&self, category: &str, content: &str) -> Result<()> {
         let path = self.notes_path(category);
-        std::fs::write(&path, content)
-            .with_context(|| format!("Failed to write notes: {:?}", path))?;
-        Ok(())
+        write_atomic(&path, content).with_context(|| format!("Failed to write notes: {:?}", path))
     }
 
     /// Appends to notes for a category (except plan which is replaced)
@@ -187,24 +521,181 @@ impl Project {
         }
     }
 
-    /// Updates the last_task timestamp and increments task count
-    pub fn record_task(&mut self) -> Result<()> {
+    /// Appends a single hand-written entry to a note category
+    ///
+    /// Unlike `append_notes`, the plan is appended to rather than replaced.
+    pub fn add_note(&self, category: &str, text: &str) -> Result<()> {
+        validate_categories(&[category.to_string()])?;
+        let Some(entry) = note_entry(text) else {
+            bail!("Note text is empty");
+        };
+        let existing = self.read_notes(category)?;
+        let content = if existing.trim().is_empty() {
+            entry
+        } else {
+            format!("{}\n{}", existing.trim_end(), entry)
+        };
+        self.write_notes(category, &content)
+    }
+
+    /// Creates a new project with a copy of this project's notes, linked as a child
+    pub fn fork(&self, name: &str) -> Result<Project> {
+        if name == self.metadata.name {
+            bail!("Cannot fork a project into itself");
+        }
+
+        let mut child = Project::create(name)?;
+        self.copy_notes_to(&child)?;
+
+        child.metadata.parent = Some(self.metadata.name.clone());
+        child.metadata.branch = self.metadata.branch.clone();
+        child.save_metadata()?;
+
+        Ok(child)
+    }
+
+    /// Copies every note category into another project, replacing its notes
+    pub fn copy_notes_to(&self, other: &Project) -> Result<()> {
+        for category in NOTE_CATEGORIES.iter().chain(EXTRA_NOTES) {
+            let content = self.read_notes(category)?;
+            other.write_notes(category, &content)?;
+        }
+        Ok(())
+    }
+
+    /// The project's standing instructions, if any are written
+    pub fn instructions(&self) -> Result<Option<String>> {
+        let content = self.read_notes(INSTRUCTIONS_NOTE)?;
+        Ok((!content.trim().is_empty()).then(|| content.trim().to_string()))
+    }
+
+    /// Walks the parent chain, nearest first, up to `max_depth` ancestors
+    ///
+    /// Each ancestor is paired with the categories that actually flow down to
+    /// this project: the intersection of every `inherit` list along the path,
+    /// since a parent only carries what it inherits itself. Missing projects
+    /// and cycles end the walk.
+    pub fn ancestors(&self, max_depth: usize) -> Vec<(Project, Vec<String>)> {
+        let mut ancestors = Vec::new();
+        let mut visited = vec![self.metadata.name.clone()];
+        let mut categories = self.metadata.inherit.clone();
+        let mut next = self.metadata.parent.clone();
+
+        while let Some(name) = next {
+            if ancestors.len() >= max_depth || visited.contains(&name) {
+                break;
+            }
+            let Ok(ancestor) = Project::open(&name) else {
+                break;
+            };
+            visited.push(name);
+            next = ancestor.metadata.parent.clone();
+            let inherited = categories.clone();
+            categories.retain(|c| ancestor.metadata.inherit.contains(c));
+            ancestors.push((ancestor, inherited));
+        }
+
+        ancestors
+    }
+
+    /// Returns active projects that share this project's parent
+    pub fn siblings(&self) -> Result<Vec<Project>> {
+        let Some(ref parent) = self.metadata.parent else {
+            return Ok(Vec::new());
+        };
+        Ok(load_all_projects()?
+            .into_iter()
+            .filter(|p| {
+                p.metadata.name != self.metadata.name
+                    && p.metadata.parent.as_ref() == Some(parent)
+                    && p.metadata.status != "archived"
+            })
+            .collect())
+    }
+
+    /// Returns projects that declare this project as their parent
+    pub fn children(&self) -> Result<Vec<Project>> {
+        Ok(load_all_projects()?
+            .into_iter()
+            .filter(|p| p.metadata.parent.as_ref() == Some(&self.metadata.name))
+            .collect())
+    }
+
+    /// Adds spend that isn't a task of its own, such as a `/plan` pass
+    pub fn record_cost(&mut self, cost_usd: f64) -> Result<()> {
+        self.metadata.stats.total_cost_usd += cost_usd;
+        self.save_metadata()
+    }
+
+    /// Updates the last_task timestamp, task count, cost, and token totals
+    pub fn record_task(&mut self, cost_usd: Option<f64>, usage: Option<&TokenUsage>) -> Result<()> {
+        let stats = &mut self.metadata.stats;
         self.metadata.last_task = Some(Utc::now());
-        self.metadata.stats.total_tasks += 1;
+        stats.total_tasks += 1;
+        stats.total_cost_usd += cost_usd.unwrap_or(0.0);
+        if let Some(usage) = usage {
+            stats.input_tokens += usage.input_tokens;
+            stats.cache_read_tokens += usage.cache_read_tokens.unwrap_or(0);
+            stats.cache_creation_tokens += usage.cache_creation_tokens.unwrap_or(0);
+        }
         self.save_metadata()
     }
 
     /// Increments session count
+    ///
+    /// Like task numbers, the count is re-read under the counter lock so a
+    /// headless run alongside an open REPL gets a session of its own.
     pub fn record_session_start(&mut self) -> Result<()> {
-        self.metadata.stats.total_sessions += 1;
+        let _lock = self.lock_counter()?;
+        let saved = self.saved_stats().map_or(0, |stats| stats.total_sessions);
+        let stats = &mut self.metadata.stats;
+        stats.total_sessions = stats.total_sessions.max(saved) + 1;
         self.save_metadata()
     }
 
-    /// Returns the next task number
-    pub fn next_task_number(&self) -> Result<u32> {
+    /// Reserves and returns the next task number
+    ///
+    /// The counter lives in project metadata so large projects don't rescan
+    /// the tasks directory per task; it is seeded from a scan the first time.
+    ///
+    /// Reservation holds a lock file and re-reads the saved counter, so
+    /// parallel sessions, the daemon, and imports never get the same number.
+    pub fn next_task_number(&mut self) -> Result<u32> {
+        let _lock = self.lock_counter()?;
+        let saved = self.saved_task_number();
+        let stats = &mut self.metadata.stats;
+        stats.last_task_number = stats.last_task_number.max(saved);
+        if self.metadata.stats.last_task_number == 0 {
+            self.metadata.stats.last_task_number = self.scan_last_task_number()?;
+        }
+        self.metadata.stats.last_task_number += 1;
+        self.save_metadata()?;
+        Ok(self.metadata.stats.last_task_number)
+    }
+
+    /// The task counter as last saved by any process
+    fn saved_task_number(&self) -> u32 {
+        self.saved_stats().map_or(0, |stats| stats.last_task_number)
+    }
+
+    /// Project stats as last saved by any process
+    fn saved_stats(&self) -> Option<ProjectStats> {
+        std::fs::read_to_string(self.path.join(METADATA_FILE))
+            .ok()
+            .and_then(|content| toml::from_str::<ProjectMetadata>(&content).ok())
+            .map(|metadata| metadata.stats)
+    }
+
+    /// Takes the task counter lock
+    fn lock_counter(&self) -> Result<FileLock> {
+        lock_file(self.path.join(COUNTER_LOCK))
+    }
+
+    /// Highest task number among files in the tasks directory
+    fn scan_last_task_number(&self) -> Result<u32> {
         let tasks_dir = self.tasks_path();
         if !tasks_dir.exists() {
-            return Ok(1);
+            return Ok(0);
         }
 
         let mut max_num = 0;
@@ -212,89 +703,616 @@ impl Project {
             let entry = entry?;
             let name = entry.file_name();
             let name_str = name.to_string_lossy();
-            // Task files are named like 001-description.json
-            if let Some(num_str) = name_str.split('-').next() {
+            // Task files are named like 001-description.json or 001.stream.jsonl
+            if let Some(num_str) = name_str.split(['-', '.']).next() {
                 if let Ok(num) = num_str.parse::<u32>() {
                     max_num = max_num.max(num);
                 }
             }
         }
 
-        Ok(max_num + 1)
+        Ok(max_num)
     }
 }
 
-/// Lists all projects
-pub fn list_projects() -> Result<()> {
-    config::ensure_config_dir()?;
+/// Loads every project in the projects directory, sorted by name
+///
+/// Directories whose metadata can't be read are skipped with a warning.
+pub fn load_all_projects() -> Result<Vec<Project>> {
     let projects_dir = config::projects_dir()?;
-
     if !projects_dir.exists() {
-        println!("No projects found.");
-        return Ok(());
+        return Ok(Vec::new());
     }
 
-    let mut projects: Vec<_> = std::fs::read_dir(&projects_dir)?
+    let mut names: Vec<String> = std::fs::read_dir(&projects_dir)?
         .filter_map(|e| e.ok())
         .filter(|e| e.path().is_dir())
+        .map(|e| e.file_name().to_string_lossy().to_string())
         .collect();
+    names.sort();
+
+    Ok(names
+        .iter()
+        .filter_map(|name| match Project::open(name) {
+            Ok(project) => Some(project),
+            Err(e) => {
+                eprintln!(
+                    "{}",
+                    i18n::tf(
+                        "project.load_failed",
+                        &[("name", name), ("error", &format!("{:#}", e))]
+                    )
+                );
+                None
+            }
+        })
+        .collect())
+}
 
+/// Most projects shown at once by the picker
+const PICKER_ROWS: usize = 15;
+
+/// Lets the user choose a project for `clancy start` with no name
+///
+/// Projects are listed by last activity. Typing text narrows the list by
+/// fuzzy match (picking the project when one is left), a number picks from
+/// the list, and `+name` starts a new project. Returns None when cancelled.
+pub fn pick_project() -> Result<Option<String>> {
+    let mut projects = load_all_projects()?;
+    projects.sort_by_key(|p| std::cmp::Reverse(p.metadata.last_task.unwrap_or(p.metadata.created)));
     if projects.is_empty() {
-        println!("No projects found.");
-        return Ok(());
+        println!("{}", i18n::t("project.picker_empty"));
     }
 
-    // Sort by name
-    projects.sort_by_key(|a| a.file_name());
+    let mut matches: Vec<&Project> = projects.iter().collect();
+    loop {
+        for (i, project) in matches.iter().take(PICKER_ROWS).enumerate() {
+            let meta = &project.metadata;
+            println!(
+                "{}",
+                i18n::tf(
+                    "project.picker_row",
+                    &[
+                        ("num", &format!("{:>2}", i + 1)),
+                        ("name", &format!("{:<24}", meta.name)),
+                        (
+                            "date",
+                            &(meta.last_task.unwrap_or(meta.created).format("%Y-%m-%d"))
+                        ),
+                        ("tasks", &meta.stats.total_tasks)
+                    ]
+                )
+            );
+        }
+        if matches.len() > PICKER_ROWS {
+            println!(
+                "{}",
+                i18n::tf(
+                    "project.picker_more",
+                    &[("count", &(matches.len() - PICKER_ROWS))]
+                )
+            );
+        }
+        print!("{}", i18n::t("project.picker_prompt"));
+        std::io::stdout().flush()?;
 
-    println!("Projects:\n");
-    for entry in projects {
-        let name = entry.file_name();
-        let name_str = name.to_string_lossy();
+        let mut input = String::new();
+        if std::io::stdin().read_line(&mut input)? == 0 {
+            return Ok(None);
+        }
+        let input = input.trim();
+        if input.is_empty() {
+            return Ok(None);
+        }
+        if let Some(name) = input.strip_prefix('+') {
+            let name = name.trim();
+            if let Err(e) = validate_project_name(name) {
+                println!("{}", e);
+                continue;
+            }
+            return Ok(Some(name.to_string()));
+        }
+        if let Ok(number) = input.parse::<usize>() {
+            match matches.get(number.wrapping_sub(1)) {
+                Some(project) if number <= PICKER_ROWS => {
+                    return Ok(Some(project.metadata.name.clone()));
+                }
+                _ => println!("{}", i18n::tf("project.no_number", &[("num", &number)])),
+            }
+            continue;
+        }
 
-        // Try to load metadata for status info
-        if let Ok(project) = Project::open(&name_str) {
-            let status_marker = if project.metadata.status == "archived" {
-                " (archived)"
-            } else {
-                ""
-            };
-            let task_info = format!(
-                "{} sessions, {} tasks",
-                project.metadata.stats.total_sessions, project.metadata.stats.total_tasks
+        let filtered: Vec<&Project> = projects
+            .iter()
+            .filter(|p| fuzzy_match(input, &p.metadata.name))
+            .collect();
+        if let Some(exact) = filtered.iter().find(|p| p.metadata.name == input) {
+            return Ok(Some(exact.metadata.name.clone()));
+        }
+        match filtered.len() {
+            0 => println!("{}", i18n::tf("project.no_match", &[("input", &input)])),
+            1 => return Ok(Some(filtered[0].metadata.name.clone())),
+            _ => matches = filtered,
+        }
+    }
+}
+
+/// Checks a `clancy start` name that doesn't exist yet against existing
+/// projects, so a typo doesn't silently create an empty project
+///
+/// Returns the name to start (the suggestion if the user accepts it), or
+/// None when cancelled. Names with no close match pass through unchanged.
+pub fn confirm_new_project(name: &str) -> Result<Option<String>> {
+    if !is_unsafe_path_component(name) && config::projects_dir()?.join(name).exists() {
+        return Ok(Some(name.to_string()));
+    }
+    validate_project_name(name)?;
+    let names: Vec<String> = load_all_projects()?
+        .into_iter()
+        .map(|p| p.metadata.name)
+        .collect();
+    let Some(suggestion) = closest_name(name, &names) else {
+        return Ok(Some(name.to_string()));
+    };
+
+    print!(
+        "{}",
+        i18n::tf(
+            "project.did_you_mean",
+            &[("name", &name), ("suggestion", &suggestion)]
+        )
+    );
+    std::io::stdout().flush()?;
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    match input.trim().to_lowercase().as_str() {
+        "" | "y" | "yes" => Ok(Some(suggestion.to_string())),
+        "c" | "create" => Ok(Some(name.to_string())),
+        _ => {
+            println!(
+                "{}",
+                i18n::tf("project.create_cancelled", &[("name", &name)])
             );
-            println!("  {}{} - {}", name_str, status_marker, task_info);
-        } else {
-            println!("  {}", name_str);
+            Ok(None)
+        }
+    }
+}
+
+/// The existing name closest to `name` by edit distance, if close enough to
+/// likely be a typo: within 2 edits, or 1 for names under 5 characters
+fn closest_name<'a>(name: &str, names: &'a [String]) -> Option<&'a str> {
+    let limit = if name.chars().count() < 5 { 1 } else { 2 };
+    names
+        .iter()
+        .map(|candidate| {
+            (
+                edit_distance(&name.to_lowercase(), &candidate.to_lowercase()),
+                candidate,
+            )
+        })
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate.as_str())
+}
+
+/// Edit distance between two strings, by chars, counting a swap of two
+/// adjacent characters as one edit (optimal string alignment)
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    d[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
         }
     }
+    d[a.len()][b.len()]
+}
+
+/// Whether the query's characters appear in order in the name, ignoring case
+fn fuzzy_match(query: &str, name: &str) -> bool {
+    let mut chars = name.chars().flat_map(char::to_lowercase);
+    query
+        .chars()
+        .flat_map(char::to_lowercase)
+        .filter(|c| !c.is_whitespace())
+        .all(|q| chars.any(|c| c == q))
+}
+
+/// Returns the first meaningful line of a plan, without markdown markers
+pub fn plan_headline(plan: &str) -> Option<String> {
+    plan.lines()
+        .map(|line| {
+            line.trim()
+                .trim_start_matches('#')
+                .trim_start_matches(['-', '*'])
+                .trim()
+        })
+        .find(|line| !line.is_empty())
+        .map(String::from)
+}
+
+/// Columns available to `clancy list`
+pub const LIST_COLUMNS: &[&str] = &[
+    "name", "status", "parent", "sessions", "tasks", "last", "cost", "plan",
+];
+
+/// Orders accepted by `clancy list --sort`
+pub const LIST_SORTS: &[&str] = &["name", "last"];
+
+/// Which projects `clancy list` shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListFilter {
+    /// Everything not archived (the default)
+    Active,
+    Archived,
+    All,
+}
+
+/// Columns shown by `clancy list` when none are selected
+pub const DEFAULT_LIST_COLUMNS: &[&str] =
+    &["name", "status", "parent", "tasks", "last", "cost", "plan"];
 
+/// Lists all projects
+pub fn list_projects(columns: Option<&[String]>, filter: ListFilter, sort: &str) -> Result<()> {
+    config::ensure_config_dir()?;
+    if !LIST_SORTS.contains(&sort) {
+        bail!("Unknown sort '{}'. Valid: {}", sort, LIST_SORTS.join(", "));
+    }
+
+    let columns: Vec<&str> = match columns {
+        Some(cols) => cols.iter().map(String::as_str).collect(),
+        None => DEFAULT_LIST_COLUMNS.to_vec(),
+    };
+    for column in &columns {
+        if !LIST_COLUMNS.contains(column) {
+            bail!(
+                "Unknown column '{}'. Valid: {}",
+                column,
+                LIST_COLUMNS.join(", ")
+            );
+        }
+    }
+
+    let all = load_all_projects()?;
+    let total = all.len();
+    let projects = select_projects(all, filter, sort);
+    if projects.is_empty() {
+        println!("{}", i18n::t("project.none"));
+    } else {
+        let rows: Vec<(&ProjectMetadata, Option<String>)> = projects
+            .iter()
+            .map(|p| {
+                let plan = p.read_notes("plan").ok().and_then(|n| plan_headline(&n));
+                (&p.metadata, plan)
+            })
+            .collect();
+        print!("{}", render_list(&rows, &columns));
+    }
+
+    let hidden = total - projects.len();
+    if filter == ListFilter::Active && hidden > 0 {
+        println!(
+            "{}",
+            i18n::tf("project.archived_hidden", &[("count", &hidden)])
+        );
+    }
     Ok(())
 }
 
+/// Projects passing `filter`, ordered by name or by last activity (newest
+/// first)
+fn select_projects(projects: Vec<Project>, filter: ListFilter, sort: &str) -> Vec<Project> {
+    let mut selected: Vec<Project> = projects
+        .into_iter()
+        .filter(|p| {
+            let archived = p.metadata.status == "archived";
+            match filter {
+                ListFilter::Active => !archived,
+                ListFilter::Archived => archived,
+                ListFilter::All => true,
+            }
+        })
+        .collect();
+    if sort == "last" {
+        selected
+            .sort_by_key(|p| std::cmp::Reverse(p.metadata.last_task.unwrap_or(p.metadata.created)));
+    }
+    selected
+}
+
+/// Renders projects as an aligned table with the chosen columns
+fn render_list(rows: &[(&ProjectMetadata, Option<String>)], columns: &[&str]) -> String {
+    let cell = |meta: &ProjectMetadata, plan: &Option<String>, column: &str| -> String {
+        match column {
+            "name" => meta.name.clone(),
+            "status" => meta.status.clone(),
+            "parent" => meta.parent.clone().unwrap_or_else(|| "-".to_string()),
+            "sessions" => meta.stats.total_sessions.to_string(),
+            "tasks" => meta.stats.total_tasks.to_string(),
+            "last" => meta
+                .last_task
+                .unwrap_or(meta.created)
+                .format("%Y-%m-%d")
+                .to_string(),
+            "cost" if meta.stats.total_cost_usd > 0.0 => {
+                format!("${:.2}", meta.stats.total_cost_usd)
+            }
+            "cost" => "-".to_string(),
+            "plan" => {
+                let plan = plan.as_deref().unwrap_or("-");
+                if plan.chars().count() > 50 {
+                    format!("{}…", text::truncate_chars(plan, 49))
+                } else {
+                    plan.to_string()
+                }
+            }
+            _ => String::new(),
+        }
+    };
+
+    let mut table: Vec<Vec<String>> = vec![columns.iter().map(|c| c.to_uppercase()).collect()];
+    for (meta, plan) in rows {
+        table.push(columns.iter().map(|c| cell(meta, plan, c)).collect());
+    }
+
+    let widths: Vec<usize> = (0..columns.len())
+        .map(|i| {
+            table
+                .iter()
+                .map(|row| row[i].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let mut out = String::new();
+    for row in &table {
+        let line: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(value, width)| format!("{:<width$}", value, width = width))
+            .collect();
+        out.push_str(line.join("  ").trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// Prints the parent/child project hierarchy
+pub fn show_tree() -> Result<()> {
+    let projects: Vec<ProjectMetadata> = load_all_projects()?
+        .into_iter()
+        .map(|p| p.metadata)
+        .collect();
+
+    if projects.is_empty() {
+        println!("{}", i18n::t("project.none"));
+        return Ok(());
+    }
+
+    print!("{}", render_tree(&projects));
+    Ok(())
+}
+
+/// Renders projects as an indented tree rooted at projects without a parent
+///
+/// Projects whose parent doesn't exist are shown as roots with a marker so
+/// broken links stay visible, and projects whose parent links form a cycle
+/// (which no root reaches) are shown as a detached group with a warning.
+fn render_tree(projects: &[ProjectMetadata]) -> String {
+    let exists = |name: &str| projects.iter().any(|p| p.name == name);
+    let mut output = String::new();
+    let mut visited: Vec<&str> = Vec::new();
+
+    for root in projects
+        .iter()
+        .filter(|p| p.parent.as_deref().is_none_or(|parent| !exists(parent)))
+    {
+        output.push_str(&tree_line(root));
+        if let Some(ref parent) = root.parent {
+            output.push_str(&format!(" (missing parent: {})", parent));
+        }
+        output.push('\n');
+        visited.push(&root.name);
+        render_children(projects, &root.name, "", &mut visited, &mut output);
+    }
+
+    while let Some(start) = projects
+        .iter()
+        .find(|p| !visited.contains(&p.name.as_str()))
+    {
+        let cycle = parent_cycle(projects, start);
+        let root = projects
+            .iter()
+            .find(|p| p.name == cycle[0])
+            .unwrap_or(start);
+        output.push_str(&format!(
+            "{} (parent cycle: {})\n",
+            tree_line(root),
+            cycle.join(" -> ")
+        ));
+        visited.push(&root.name);
+        render_children(projects, &root.name, "", &mut visited, &mut output);
+    }
+
+    output
+}
+
+/// The parent cycle `start`'s ancestors run into, from its alphabetically
+/// first member back to that member
+fn parent_cycle<'a>(projects: &'a [ProjectMetadata], start: &'a ProjectMetadata) -> Vec<&'a str> {
+    let mut chain: Vec<&str> = vec![&start.name];
+    let mut current = start;
+    while let Some(parent) = current
+        .parent
+        .as_deref()
+        .and_then(|name| projects.iter().find(|p| p.name == name))
+    {
+        if let Some(i) = chain.iter().position(|name| *name == parent.name) {
+            let mut cycle = chain.split_off(i);
+            let first = (0..cycle.len()).min_by_key(|&j| cycle[j]).unwrap_or(0);
+            cycle.rotate_left(first);
+            cycle.push(cycle[0]);
+            return cycle;
+        }
+        chain.push(&parent.name);
+        current = parent;
+    }
+    chain
+}
+
+fn render_children<'a>(
+    projects: &'a [ProjectMetadata],
+    parent: &str,
+    prefix: &str,
+    visited: &mut Vec<&'a str>,
+    output: &mut String,
+) {
+    let children: Vec<&ProjectMetadata> = projects
+        .iter()
+        .filter(|p| p.parent.as_deref() == Some(parent))
+        .collect();
+
+    for (i, child) in children.iter().enumerate() {
+        let last = i == children.len() - 1;
+        let branch = if last { "└── " } else { "├── " };
+        output.push_str(&format!("{}{}{}\n", prefix, branch, tree_line(child)));
+
+        // Guard against cycles from hand-edited project.toml files
+        if visited.contains(&child.name.as_str()) {
+            continue;
+        }
+        visited.push(&child.name);
+        let child_prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+        render_children(projects, &child.name, &child_prefix, visited, output);
+    }
+}
+
+/// Formats one project for the tree: name, status, task count, last activity
+fn tree_line(project: &ProjectMetadata) -> String {
+    let last = project
+        .last_task
+        .map(|t| format!(", last {}", t.format("%Y-%m-%d")))
+        .unwrap_or_default();
+    format!(
+        "{} [{}] {} tasks{}",
+        project.name, project.status, project.stats.total_tasks, last
+    )
+}
+
 /// Shows project status
-pub fn show_status(project_name: Option<&str>) -> Result<()> {
+pub fn show_status(project_name: Option<&str>, recent_tasks: usize) -> Result<()> {
     let name = project_name.ok_or_else(|| anyhow::anyhow!("Project name required"))?;
     let project = Project::open(name)?;
 
-    println!("Project: {}", project.metadata.name);
-    println!("Status: {}", project.metadata.status);
     println!(
-        "Created: {}",
-        project.metadata.created.format("%Y-%m-%d %H:%M")
+        "{}",
+        i18n::tf("project.name", &[("name", &project.metadata.name)])
+    );
+    println!(
+        "{}",
+        i18n::tf("project.status", &[("status", &project.metadata.status)])
+    );
+    if let Some(ref parent) = project.metadata.parent {
+        let line = if project.metadata.inherit.is_empty() {
+            i18n::tf("project.parent_bare", &[("parent", parent)])
+        } else {
+            i18n::tf(
+                "project.parent",
+                &[
+                    ("parent", parent),
+                    ("categories", &project.metadata.inherit.join(", ")),
+                ],
+            )
+        };
+        println!("{}", line);
+    }
+    println!(
+        "{}",
+        i18n::tf(
+            "project.created",
+            &[("date", &project.metadata.created.format("%Y-%m-%d %H:%M"))]
+        )
     );
     if let Some(last) = project.metadata.last_task {
-        println!("Last task: {}", last.format("%Y-%m-%d %H:%M"));
+        println!(
+            "{}",
+            i18n::tf(
+                "project.last_task",
+                &[("date", &last.format("%Y-%m-%d %H:%M"))]
+            )
+        );
     }
     println!(
-        "Stats: {} sessions, {} tasks",
-        project.metadata.stats.total_sessions, project.metadata.stats.total_tasks
+        "{}",
+        i18n::tf(
+            "project.stats",
+            &[
+                ("sessions", &project.metadata.stats.total_sessions),
+                ("tasks", &project.metadata.stats.total_tasks)
+            ]
+        )
     );
+    let stats = &project.metadata.stats;
+    if let Some(rate) = crate::pricing::cache_hit_rate(
+        stats.input_tokens,
+        stats.cache_read_tokens,
+        stats.cache_creation_tokens,
+    ) {
+        println!(
+            "{}",
+            i18n::tf(
+                "project.cache_rate",
+                &[
+                    ("rate", &format!("{:.0}", rate * 100.0)),
+                    ("read", &stats.cache_read_tokens),
+                    ("written", &stats.cache_creation_tokens)
+                ]
+            )
+        );
+    }
+
+    // Only the child records the link, so find children by scanning
+    let children = project.children()?;
+    if !children.is_empty() {
+        println!("{}", i18n::t("project.children"));
+        for child in children {
+            let last = child
+                .metadata
+                .last_task
+                .map(|t| format!(", last task {}", t.format("%Y-%m-%d %H:%M")))
+                .unwrap_or_default();
+            println!(
+                "  {} [{}]{}",
+                child.metadata.name, child.metadata.status, last
+            );
+        }
+    }
+
+    // Show what happened in the last few tasks
+    let recent = crate::tasklog::recent_task_logs(&project, recent_tasks)?;
+    if !recent.is_empty() {
+        println!("{}", i18n::t("project.recent_tasks"));
+        for log in &recent {
+            println!("{}", crate::tasklog::task_summary_line(log));
+        }
+    }
 
     // Show plan if it exists
     let plan = project.read_notes("plan")?;
     if !plan.trim().is_empty() {
-        println!("\n## Current Plan\n");
+        println!("{}", i18n::t("project.current_plan"));
         println!("{}", plan);
     }
 
@@ -304,7 +1322,7 @@ pub fn show_status(project_name: Option<&str>) -> Result<()> {
         let lines: Vec<&str> = decisions.lines().collect();
         let recent: Vec<&str> = lines.iter().rev().take(5).copied().collect();
         if !recent.is_empty() {
-            println!("\n## Recent Decisions\n");
+            println!("{}", i18n::t("project.recent_decisions"));
             for line in recent.iter().rev() {
                 println!("{}", line);
             }
@@ -314,17 +1332,43 @@ pub fn show_status(project_name: Option<&str>) -> Result<()> {
     Ok(())
 }
 
+/// Appends an entry to a project's notes, reading stdin when no text is given
+pub fn add_note(project_name: &str, category: &str, text: Option<&str>) -> Result<()> {
+    let project = Project::open(project_name)?;
+    let text = match text {
+        Some(text) if text != "-" => text.to_string(),
+        _ => {
+            let mut input = String::new();
+            std::io::stdin()
+                .read_to_string(&mut input)
+                .context("Failed to read note from stdin")?;
+            input
+        }
+    };
+
+    project.add_note(category, &text)?;
+    println!(
+        "{}",
+        i18n::tf(
+            "project.note_added",
+            &[("category", &category), ("project", &project_name)]
+        )
+    );
+    Ok(())
+}
+
 /// Opens editor for notes
 pub fn edit_notes(project_name: &str, category: Option<&str>) -> Result<()> {
     let project = Project::open(project_name)?;
     let config = config::load_config()?;
 
     let path = if let Some(cat) = category {
-        if !NOTE_CATEGORIES.contains(&cat) {
+        if !NOTE_CATEGORIES.contains(&cat) && !EXTRA_NOTES.contains(&cat) {
             bail!(
-                "Invalid category '{}'. Valid: {}",
+                "Invalid category '{}'. Valid: {}, {}",
                 cat,
-                NOTE_CATEGORIES.join(", ")
+                NOTE_CATEGORIES.join(", "),
+                EXTRA_NOTES.join(", ")
             );
         }
         project.notes_path(cat)
@@ -351,12 +1395,142 @@ pub fn archive_project(project_name: &str) -> Result<()> {
     let mut project = Project::open(project_name)?;
     project.metadata.status = "archived".to_string();
     project.save_metadata()?;
-    println!("Project '{}' archived.", project_name);
+    println!(
+        "{}",
+        i18n::tf("project.archived", &[("name", &project_name)])
+    );
+    Ok(())
+}
+
+/// Makes an archived project active again
+pub fn unarchive_project(project_name: &str) -> Result<()> {
+    let mut project = Project::open(project_name)?;
+    if project.metadata.status != "archived" {
+        println!(
+            "{}",
+            i18n::tf("project.not_archived", &[("name", &project_name)])
+        );
+        return Ok(());
+    }
+    project.metadata.status = default_status();
+    project.save_metadata()?;
+    println!(
+        "{}",
+        i18n::tf("project.unarchived", &[("name", &project_name)])
+    );
+    Ok(())
+}
+
+/// Creates a new project, optionally linked to an existing parent
+pub fn create_project(
+    name: &str,
+    parent_name: Option<&str>,
+    description: Option<&str>,
+) -> Result<Project> {
+    if let Some(parent_name) = parent_name {
+        Project::open(parent_name)
+            .with_context(|| format!("Parent project '{}' not found", parent_name))?;
+    }
+
+    let mut project = Project::create(name)?;
+    project.metadata.parent = parent_name.map(String::from);
+    project.metadata.description = description.map(String::from);
+    project.save_metadata()?;
+
+    Ok(project)
+}
+
+/// Creates a new project from the CLI (`clancy new`)
+pub fn new_project(name: &str, parent_name: Option<&str>, description: Option<&str>) -> Result<()> {
+    create_project(name, parent_name, description)?;
+    match parent_name {
+        Some(parent_name) => println!(
+            "{}",
+            i18n::tf(
+                "project.new_child",
+                &[("name", &name), ("parent", &parent_name)]
+            )
+        ),
+        None => println!("{}", i18n::tf("project.new", &[("name", &name)])),
+    }
+    Ok(())
+}
+
+/// Opens a project for a session, creating it if needed, and ensures it is
+/// linked to the given parent
+///
+/// A project already linked to a different parent is never relinked here;
+/// that takes an explicit `clancy link`.
+pub fn open_or_create_with_parent(name: &str, parent_name: &str) -> Result<Project> {
+    config::ensure_config_dir()?;
+    if !config::projects_dir()?.join(name).exists() {
+        return create_project(name, Some(parent_name), None);
+    }
+
+    let project = Project::open(name)?;
+    match project.metadata.parent.as_deref() {
+        Some(parent) if parent == parent_name => return Ok(project),
+        Some(parent) => bail!(
+            "Project '{}' is already linked to '{}'. Use `clancy link {} {}` to relink it",
+            name,
+            parent,
+            name,
+            parent_name
+        ),
+        None => {}
+    }
+
+    link_projects(name, parent_name, None)?;
+    Project::open(name)
+}
+
+/// Formats free text as a bullet entry, indenting continuation lines
+pub fn note_entry(text: &str) -> Option<String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    let mut lines = text.lines();
+    let first = lines.next()?.trim();
+    let mut entry = if first.starts_with("- ") {
+        first.to_string()
+    } else {
+        format!("- {}", first)
+    };
+    for line in lines {
+        entry.push_str("\n  ");
+        entry.push_str(line.trim());
+    }
+    Some(entry)
+}
+
+/// Checks that every requested inherit category is a known note category
+pub fn validate_categories(categories: &[String]) -> Result<()> {
+    for category in categories {
+        if !NOTE_CATEGORIES.contains(&category.as_str()) {
+            bail!(
+                "Invalid category '{}'. Valid: {}",
+                category,
+                NOTE_CATEGORIES.join(", ")
+            );
+        }
+    }
     Ok(())
 }
 
 /// Links a child project to a parent for note inheritance
-pub fn link_projects(child_name: &str, parent_name: &str) -> Result<()> {
+///
+/// `inherit` selects which note categories flow from parent to child; when
+/// `None`, the child's existing selection (architecture by default) is kept.
+pub fn link_projects(
+    child_name: &str,
+    parent_name: &str,
+    inherit: Option<&[String]>,
+) -> Result<()> {
+    if let Some(categories) = inherit {
+        validate_categories(categories)?;
+    }
+
     // Verify parent exists
     let _parent = Project::open(parent_name)
         .with_context(|| format!("Parent project '{}' not found", parent_name))?;
@@ -388,11 +1562,21 @@ pub fn link_projects(child_name: &str, parent_name: &str) -> Result<()> {
     }
 
     child.metadata.parent = Some(parent_name.to_string());
+    if let Some(categories) = inherit {
+        child.metadata.inherit = categories.to_vec();
+    }
     child.save_metadata()?;
 
     println!(
-        "Linked '{}' -> '{}'. Child will inherit parent's architecture notes.",
-        child_name, parent_name
+        "{}",
+        i18n::tf(
+            "project.linked",
+            &[
+                ("child", &child_name),
+                ("parent", &parent_name),
+                ("inherit", &(child.metadata.inherit.join(", ")))
+            ]
+        )
     );
     Ok(())
 }
@@ -402,7 +1586,10 @@ pub fn unlink_project(project_name: &str) -> Result<()> {
     let mut project = Project::open(project_name)?;
 
     if project.metadata.parent.is_none() {
-        println!("Project '{}' has no parent link.", project_name);
+        println!(
+            "{}",
+            i18n::tf("project.no_parent", &[("name", &project_name)])
+        );
         return Ok(());
     }
 
@@ -410,13 +1597,174 @@ pub fn unlink_project(project_name: &str) -> Result<()> {
     project.save_metadata()?;
 
     println!(
-        "Unlinked '{}' from '{}'.",
-        project_name,
-        parent_name.unwrap_or_default()
+        "{}",
+        i18n::tf(
+            "project.unlinked",
+            &[
+                ("name", &project_name),
+                ("parent", &parent_name.unwrap_or_default())
+            ]
+        )
+    );
+    Ok(())
+}
+
+/// Deletes a project, or moves it to the trash directory with `trash`
+///
+/// Refuses while other projects name it as their parent. Asks first unless
+/// `force` is set.
+pub fn delete_project(project_name: &str, force: bool, trash: bool) -> Result<()> {
+    let project = Project::open(project_name)?;
+
+    let children: Vec<String> = project
+        .children()?
+        .into_iter()
+        .map(|p| p.metadata.name)
+        .collect();
+    if !children.is_empty() {
+        bail!(
+            "Project '{}' is the parent of {}; unlink them first (clancy unlink <project>)",
+            project_name,
+            children.join(", ")
+        );
+    }
+
+    if !force {
+        let key = if trash {
+            "project.trash_confirm"
+        } else {
+            "project.delete_confirm"
+        };
+        print!(
+            "{}",
+            i18n::tf(
+                key,
+                &[
+                    ("name", &project_name),
+                    ("tasks", &project.metadata.stats.total_tasks)
+                ]
+            )
+        );
+        std::io::stdout().flush()?;
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        if !matches!(input.trim().to_lowercase().as_str(), "y" | "yes") {
+            println!("{}", i18n::t("prompt.cancelled"));
+            return Ok(());
+        }
+    }
+
+    if trash {
+        let trash_dir = config::trash_dir()?;
+        std::fs::create_dir_all(&trash_dir)
+            .with_context(|| format!("Failed to create {}", trash_dir.display()))?;
+        let dest = trash_path(&trash_dir, project_name, Utc::now());
+        std::fs::rename(&project.path, &dest)
+            .with_context(|| format!("Failed to move project to {}", dest.display()))?;
+        println!(
+            "{}",
+            i18n::tf(
+                "project.trashed",
+                &[("name", &project_name), ("path", &dest.display())]
+            )
+        );
+        println!(
+            "{}",
+            i18n::tf(
+                "project.restore_hint",
+                &[("dest", &dest.display()), ("path", &project.path.display())]
+            )
+        );
+    } else {
+        std::fs::remove_dir_all(&project.path)
+            .with_context(|| format!("Failed to delete {}", project.path.display()))?;
+        println!(
+            "{}",
+            i18n::tf("project.deleted", &[("name", &project_name)])
+        );
+    }
+    Ok(())
+}
+
+/// Renames a project, repoints child projects' parent links at the new
+/// name, and updates daemon queue and schedule entries that name it
+pub fn rename_project(old_name: &str, new_name: &str) -> Result<()> {
+    validate_project_name(new_name)?;
+    let mut project = Project::open(old_name)?;
+    let dest = config::projects_dir()?.join(new_name);
+    if dest.exists() {
+        bail!("Project '{}' already exists", new_name);
+    }
+
+    move_project(&mut project, dest, new_name)?;
+    let relinked = relink_children(load_all_projects()?, old_name, new_name)?;
+    let schedules = crate::daemon::rename_project(old_name, new_name)?;
+
+    println!(
+        "{}",
+        i18n::tf("project.renamed", &[("old", &old_name), ("new", &new_name)])
     );
+    if !relinked.is_empty() {
+        println!(
+            "{}",
+            i18n::tf("project.relinked", &[("children", &relinked.join(", "))])
+        );
+    }
+    if schedules > 0 {
+        println!(
+            "{}",
+            i18n::tf("project.schedules_renamed", &[("count", &schedules)])
+        );
+    }
+    Ok(())
+}
+
+/// Moves a project's directory to `dest` and records its new name; if the
+/// metadata can't be saved the directory is moved back
+fn move_project(project: &mut Project, dest: PathBuf, new_name: &str) -> Result<()> {
+    let source = project.path.clone();
+    std::fs::rename(&source, &dest)
+        .with_context(|| format!("Failed to move project to {}", dest.display()))?;
+    project.path = dest;
+    let old_name = std::mem::replace(&mut project.metadata.name, new_name.to_string());
+    if let Err(e) = project.save_metadata() {
+        let _ = std::fs::rename(&project.path, &source);
+        project.path = source;
+        project.metadata.name = old_name;
+        return Err(e.context("Failed to save the renamed project; the move was undone"));
+    }
     Ok(())
 }
 
+/// Points every project whose parent is `old_name` at `new_name`; returns
+/// the relinked projects' names
+fn relink_children(projects: Vec<Project>, old_name: &str, new_name: &str) -> Result<Vec<String>> {
+    let mut relinked = Vec::new();
+    for mut other in projects {
+        if other.metadata.parent.as_deref() == Some(old_name) {
+            other.metadata.parent = Some(new_name.to_string());
+            other
+                .save_metadata()
+                .with_context(|| format!("Failed to relink '{}'", other.metadata.name))?;
+            relinked.push(other.metadata.name);
+        }
+    }
+    Ok(relinked)
+}
+
+/// Trash location for a project, `<name>-<timestamp>` so repeated deletes of
+/// the same name don't collide
+fn trash_path(trash_dir: &Path, name: &str, now: DateTime<Utc>) -> PathBuf {
+    let base = format!("{}-{}", name, now.format("%Y%m%d-%H%M%S"));
+    let mut path = trash_dir.join(&base);
+    let mut n = 2;
+    while path.exists() {
+        path = trash_dir.join(format!("{}-{}", base, n));
+        n += 1;
+    }
+    path
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -429,20 +1777,473 @@ mod tests {
         assert!(NOTE_CATEGORIES.contains(&"plan"));
     }
 
+    #[test]
+    fn test_write_atomic_leaves_no_pending_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.md");
+        write_atomic(&path, "one").unwrap();
+        write_atomic(&path, "two").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "two");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+        assert!(pending_writes(&path).is_empty());
+    }
+
     #[test]
     fn test_project_metadata_serialization() {
         let metadata = ProjectMetadata {
-            name: "test".to_string(),
-            created: Utc::now(),
-            last_task: None,
-            parent: None,
             branch: Some("main".to_string()),
-            status: "active".to_string(),
-            stats: ProjectStats::default(),
+            ..ProjectMetadata::new("test")
         };
 
         let serialized = toml::to_string_pretty(&metadata).unwrap();
         let deserialized: ProjectMetadata = toml::from_str(&serialized).unwrap();
         assert_eq!(deserialized.name, "test");
     }
+
+    #[test]
+    fn test_metadata_without_inherit_defaults_to_architecture() {
+        let content = r#"
+name = "legacy"
+created = "2025-01-15T09:00:00Z"
+status = "active"
+"#;
+        let metadata: ProjectMetadata = toml::from_str(content).unwrap();
+        assert_eq!(metadata.inherit, vec!["architecture"]);
+    }
+
+    #[test]
+    fn test_validate_categories_rejects_unknown() {
+        let categories = vec!["architecture".to_string(), "todos".to_string()];
+        assert!(validate_categories(&categories).is_err());
+    }
+
+    #[test]
+    fn test_validate_categories_accepts_known() {
+        let categories = vec!["architecture".to_string(), "failures".to_string()];
+        assert!(validate_categories(&categories).is_ok());
+    }
+
+    #[test]
+    fn test_plan_headline_skips_blank_and_heading_markers() {
+        let plan = "\n## Auth endpoint done\n- [ ] Add rate limiting\n";
+        assert_eq!(plan_headline(plan), Some("Auth endpoint done".to_string()));
+    }
+
+    #[test]
+    fn test_plan_headline_strips_bullets() {
+        assert_eq!(
+            plan_headline("- Write integration tests"),
+            Some("Write integration tests".to_string())
+        );
+    }
+
+    #[test]
+    fn test_plan_headline_empty() {
+        assert_eq!(plan_headline("  \n\n"), None);
+    }
+
+    fn tree_metadata(name: &str, parent: Option<&str>) -> ProjectMetadata {
+        ProjectMetadata {
+            parent: parent.map(String::from),
+            ..ProjectMetadata::new(name)
+        }
+    }
+
+    #[test]
+    fn test_render_tree_nests_children() {
+        let projects = vec![
+            tree_metadata("auth", None),
+            tree_metadata("feature-x", Some("auth")),
+            tree_metadata("feature-y", Some("auth")),
+            tree_metadata("fix-z", Some("feature-x")),
+        ];
+        let tree = render_tree(&projects);
+        assert_eq!(
+            tree,
+            "auth [active] 0 tasks\n\
+             ├── feature-x [active] 0 tasks\n\
+             │   └── fix-z [active] 0 tasks\n\
+             └── feature-y [active] 0 tasks\n"
+        );
+    }
+
+    #[test]
+    fn test_render_tree_marks_missing_parent() {
+        let projects = vec![tree_metadata("orphan", Some("gone"))];
+        assert_eq!(
+            render_tree(&projects),
+            "orphan [active] 0 tasks (missing parent: gone)\n"
+        );
+    }
+
+    #[test]
+    fn test_render_tree_shows_parent_cycle() {
+        let projects = vec![
+            tree_metadata("auth", None),
+            tree_metadata("loop-a", Some("loop-b")),
+            tree_metadata("loop-b", Some("loop-a")),
+            tree_metadata("spur", Some("loop-b")),
+        ];
+        assert_eq!(
+            render_tree(&projects),
+            "auth [active] 0 tasks\n\
+             loop-a [active] 0 tasks (parent cycle: loop-a -> loop-b -> loop-a)\n\
+             └── loop-b [active] 0 tasks\n    \
+                 ├── loop-a [active] 0 tasks\n    \
+                 └── spur [active] 0 tasks\n"
+        );
+    }
+
+    /// Builds a project rooted in a temp directory without touching the config dir
+    fn temp_project(dir: &std::path::Path, name: &str) -> Project {
+        let path = dir.join(name);
+        std::fs::create_dir_all(path.join("notes")).unwrap();
+        std::fs::create_dir_all(path.join("tasks")).unwrap();
+        Project::new(ProjectMetadata::new(name), path)
+    }
+
+    #[test]
+    fn test_render_list_columns() {
+        let mut meta = ProjectMetadata::new("api");
+        meta.created = "2025-01-10T09:00:00Z".parse().unwrap();
+        meta.last_task = Some("2025-02-01T12:00:00Z".parse().unwrap());
+        meta.stats.total_tasks = 12;
+        meta.stats.total_cost_usd = 3.456;
+        let other = ProjectMetadata::new("web-frontend");
+
+        let rows = vec![(&meta, Some("Finish auth".to_string())), (&other, None)];
+        let out = render_list(&rows, &["name", "tasks", "cost", "plan"]);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "NAME          TASKS  COST   PLAN");
+        assert_eq!(lines[1], "api           12     $3.46  Finish auth");
+        assert_eq!(lines[2], "web-frontend  0      -      -");
+
+        let out = render_list(&rows[..1], &["last"]);
+        assert_eq!(out, "LAST\n2025-02-01\n");
+
+        let mut child = ProjectMetadata::new("child");
+        child.parent = Some("api".to_string());
+        let rows = vec![(&child, None), (&other, None)];
+        let out = render_list(&rows, &["name", "parent"]);
+        assert_eq!(
+            out,
+            "NAME          PARENT\nchild         api\nweb-frontend  -\n"
+        );
+    }
+
+    #[test]
+    fn test_select_projects_filters_and_sorts() {
+        let project = |name: &str, status: &str, last: Option<&str>| {
+            let mut meta = ProjectMetadata::new(name);
+            meta.created = "2025-01-01T00:00:00Z".parse().unwrap();
+            meta.status = status.to_string();
+            meta.last_task = last.map(|t| t.parse().unwrap());
+            Project::new(meta, PathBuf::from(name))
+        };
+        let all = || {
+            vec![
+                project("api", "active", Some("2025-02-01T00:00:00Z")),
+                project("old", "archived", None),
+                project("web", "active", Some("2025-03-01T00:00:00Z")),
+            ]
+        };
+        let names = |projects: Vec<Project>| -> Vec<String> {
+            projects.into_iter().map(|p| p.metadata.name).collect()
+        };
+
+        assert_eq!(
+            names(select_projects(all(), ListFilter::Active, "name")),
+            ["api", "web"]
+        );
+        assert_eq!(
+            names(select_projects(all(), ListFilter::Archived, "name")),
+            ["old"]
+        );
+        assert_eq!(
+            names(select_projects(all(), ListFilter::All, "last")),
+            ["web", "api", "old"]
+        );
+    }
+
+    #[test]
+    fn test_templates_parse() {
+        let meta: ProjectMetadata = toml::from_str(
+            r#"
+name = "api"
+created = "2025-01-15T09:00:00Z"
+
+[templates.dependency-audit]
+prompt = "Audit dependencies"
+model = "haiku"
+verify = "cargo test"
+tags = ["maintenance"]
+"#,
+        )
+        .unwrap();
+        let template = &meta.templates["dependency-audit"];
+        assert_eq!(template.prompt, "Audit dependencies");
+        assert_eq!(template.verify.as_deref(), Some("cargo test"));
+        assert_eq!(template.tags, vec!["maintenance"]);
+
+        // Projects without templates don't grow an empty table
+        let plain = toml::to_string_pretty(&ProjectMetadata::new("web")).unwrap();
+        assert!(!plain.contains("templates"));
+    }
+
+    #[test]
+    fn test_claude_cli_options_parse() {
+        let toml_str = r#"
+name = "api"
+created = "2025-01-15T00:00:00Z"
+
+[claude_cli]
+add_dirs = ["../shared-lib"]
+mcp_config = ".clancy/mcp.json"
+
+[stats]
+total_sessions = 0
+total_tasks = 0
+"#;
+        let meta: ProjectMetadata = toml::from_str(toml_str).unwrap();
+        assert_eq!(meta.claude_cli.add_dirs, vec!["../shared-lib"]);
+        assert_eq!(
+            meta.claude_cli.mcp_config.as_deref(),
+            Some(".clancy/mcp.json")
+        );
+
+        let meta: ProjectMetadata =
+            toml::from_str("name = \"x\"\ncreated = \"2025-01-15T00:00:00Z\"").unwrap();
+        assert!(meta.claude_cli.add_dirs.is_empty());
+        assert!(meta.claude_cli.mcp_config.is_none());
+    }
+
+    #[test]
+    fn test_note_entry() {
+        assert_eq!(note_entry("  use sqlx "), Some("- use sqlx".to_string()));
+        assert_eq!(
+            note_entry("- already\nwrapped"),
+            Some("- already\n  wrapped".to_string())
+        );
+        assert_eq!(note_entry("  \n "), None);
+    }
+
+    #[test]
+    fn test_add_note_appends_to_plan() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let project = temp_project(temp_dir.path(), "demo");
+        project.write_notes("plan", "Phase 1: auth\n").unwrap();
+
+        project.add_note("plan", "check token expiry").unwrap();
+        assert_eq!(
+            project.read_notes("plan").unwrap(),
+            "Phase 1: auth\n- check token expiry"
+        );
+        assert!(project.add_note("bogus", "x").is_err());
+    }
+
+    #[test]
+    fn test_copy_notes_to() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let source = temp_project(temp_dir.path(), "source");
+        let target = temp_project(temp_dir.path(), "target");
+        source
+            .write_notes("architecture", "- Uses repository pattern")
+            .unwrap();
+        source.write_notes("plan", "Finish auth").unwrap();
+        source
+            .write_notes(INSTRUCTIONS_NOTE, "Use British spelling.\n")
+            .unwrap();
+        source
+            .write_notes(SCRATCH_NOTE, "ask about the retry budget")
+            .unwrap();
+        target.write_notes("failures", "- stale entry").unwrap();
+        assert_eq!(target.instructions().unwrap(), None);
+
+        source.copy_notes_to(&target).unwrap();
+
+        assert_eq!(
+            target.read_notes("architecture").unwrap(),
+            "- Uses repository pattern"
+        );
+        assert_eq!(target.read_notes("plan").unwrap(), "Finish auth");
+        assert_eq!(target.read_notes("failures").unwrap(), "");
+        assert_eq!(
+            target.instructions().unwrap().as_deref(),
+            Some("Use British spelling.")
+        );
+        assert_eq!(
+            target.read_notes(SCRATCH_NOTE).unwrap(),
+            "ask about the retry budget"
+        );
+    }
+    #[test]
+    fn test_validate_project_name() {
+        assert!(validate_project_name("api-server_v2.1").is_ok());
+        assert!(validate_project_name("").is_err());
+        assert!(validate_project_name("../etc").is_err());
+        assert!(validate_project_name("a/b").is_err());
+        assert!(validate_project_name(".hidden").is_err());
+        assert!(validate_project_name("a..b").is_err());
+        assert!(validate_project_name("naïve").is_err());
+        assert!(validate_project_name(&"x".repeat(65)).is_err());
+
+        assert!(is_unsafe_path_component("../etc"));
+        assert!(!is_unsafe_path_component("légacy name"));
+    }
+
+    #[test]
+    fn test_closest_name() {
+        assert_eq!(edit_distance("my-projct", "my-project"), 1);
+        assert_eq!(edit_distance("dmeo", "demo"), 1);
+        assert_eq!(edit_distance("abc", "abc"), 0);
+        assert_eq!(edit_distance("", "ab"), 2);
+
+        let names = vec!["my-project".to_string(), "api".to_string()];
+        assert_eq!(closest_name("my-projct", &names), Some("my-project"));
+        assert_eq!(closest_name("My-Project", &names), Some("my-project"));
+        assert_eq!(closest_name("apx", &names), Some("api"));
+        // Short names only tolerate one edit
+        assert_eq!(closest_name("abx", &names), None);
+        assert_eq!(closest_name("billing", &names), None);
+    }
+
+    #[test]
+    fn test_fuzzy_match() {
+        assert!(fuzzy_match("apisv", "api-server"));
+        assert!(fuzzy_match("API", "api-server"));
+        assert!(fuzzy_match("", "anything"));
+        assert!(!fuzzy_match("srvapi", "api-server"));
+        assert!(!fuzzy_match("apix", "api-server"));
+    }
+
+    #[test]
+    fn test_read_notes_sees_outside_edits() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let project = temp_project(temp_dir.path(), "api");
+        project.write_notes("decisions", "- Use SQLite").unwrap();
+        assert_eq!(project.read_notes("decisions").unwrap(), "- Use SQLite");
+
+        // Written directly, as an editor would; the size change invalidates
+        std::fs::write(project.notes_path("decisions"), "- Use Postgres instead").unwrap();
+        assert_eq!(
+            project.read_notes("decisions").unwrap(),
+            "- Use Postgres instead"
+        );
+        std::fs::remove_file(project.notes_path("decisions")).unwrap();
+        assert_eq!(project.read_notes("decisions").unwrap(), "");
+    }
+
+    #[test]
+    fn test_next_task_number_counter() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut project = temp_project(temp_dir.path(), "api");
+        std::fs::write(project.tasks_path().join("007-old-task.json"), "{}").unwrap();
+        assert_eq!(project.next_task_number().unwrap(), 8);
+        assert_eq!(project.next_task_number().unwrap(), 9);
+
+        let saved = std::fs::read_to_string(project.path.join("project.toml")).unwrap();
+        let metadata: ProjectMetadata = toml::from_str(&saved).unwrap();
+        assert_eq!(metadata.stats.last_task_number, 9);
+
+        // Another process reserved numbers since this one loaded metadata
+        let mut other = Project::new(metadata, project.path.clone());
+        assert_eq!(other.next_task_number().unwrap(), 10);
+        assert_eq!(other.next_task_number().unwrap(), 11);
+        assert_eq!(project.next_task_number().unwrap(), 12);
+        // Saving stale stats doesn't roll the counter back
+        other.save_metadata().unwrap();
+        assert_eq!(other.metadata.stats.last_task_number, 12);
+        assert!(!project.path.join(COUNTER_LOCK).exists());
+    }
+
+    #[test]
+    fn test_record_session_start_gives_concurrent_sessions_own_numbers() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut repl = temp_project(temp_dir.path(), "api");
+        repl.record_session_start().unwrap();
+        // A headless run opens the project while the REPL session is open
+        let saved = std::fs::read_to_string(repl.path.join("project.toml")).unwrap();
+        let mut headless = Project::new(toml::from_str(&saved).unwrap(), repl.path.clone());
+        headless.record_session_start().unwrap();
+        repl.record_session_start().unwrap();
+        assert_eq!(headless.metadata.stats.total_sessions, 2);
+        assert_eq!(repl.metadata.stats.total_sessions, 3);
+    }
+
+    #[test]
+    fn test_scan_counts_stream_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let project = temp_project(temp_dir.path(), "api");
+        std::fs::write(project.tasks_path().join("003-done.json"), "{}").unwrap();
+        std::fs::write(project.tasks_path().join("004.stream.jsonl"), "").unwrap();
+        assert_eq!(project.scan_last_task_number().unwrap(), 4);
+    }
+
+    #[test]
+    fn test_trash_path_avoids_collisions() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let now: DateTime<Utc> = "2025-03-04T05:06:07Z".parse().unwrap();
+        let first = trash_path(temp_dir.path(), "api", now);
+        assert_eq!(first, temp_dir.path().join("api-20250304-050607"));
+        std::fs::create_dir(&first).unwrap();
+        assert_eq!(
+            trash_path(temp_dir.path(), "api", now),
+            temp_dir.path().join("api-20250304-050607-2")
+        );
+    }
+
+    #[test]
+    fn test_move_project_renames_dir_and_metadata() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut project = Project::new(ProjectMetadata::new("api"), temp_dir.path().join("api"));
+        std::fs::create_dir_all(&project.path).unwrap();
+        project.save_metadata().unwrap();
+
+        let dest = temp_dir.path().join("backend");
+        move_project(&mut project, dest.clone(), "backend").unwrap();
+        assert!(!temp_dir.path().join("api").exists());
+        assert_eq!(project.path, dest);
+        let saved: ProjectMetadata =
+            toml::from_str(&std::fs::read_to_string(dest.join(METADATA_FILE)).unwrap()).unwrap();
+        assert_eq!(saved.name, "backend");
+    }
+
+    #[test]
+    fn test_move_project_undoes_move_when_save_fails() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let source = temp_dir.path().join("api");
+        // A directory where project.toml should be makes the save fail
+        std::fs::create_dir_all(source.join(METADATA_FILE)).unwrap();
+        let mut project = Project::new(ProjectMetadata::new("api"), source.clone());
+
+        let dest = temp_dir.path().join("backend");
+        assert!(move_project(&mut project, dest.clone(), "backend").is_err());
+        assert!(source.exists());
+        assert!(!dest.exists());
+        assert_eq!(project.metadata.name, "api");
+    }
+
+    #[test]
+    fn test_relink_children() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let projects: Vec<Project> = [("web", Some("api")), ("cli", Some("other")), ("api", None)]
+            .into_iter()
+            .map(|(name, parent)| {
+                let mut project =
+                    Project::new(ProjectMetadata::new(name), temp_dir.path().join(name));
+                project.metadata.parent = parent.map(str::to_string);
+                std::fs::create_dir_all(&project.path).unwrap();
+                project
+            })
+            .collect();
+
+        let relinked = relink_children(projects, "api", "backend").unwrap();
+        assert_eq!(relinked, vec!["web"]);
+        let saved: ProjectMetadata = toml::from_str(
+            &std::fs::read_to_string(temp_dir.path().join("web").join(METADATA_FILE)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(saved.parent.as_deref(), Some("backend"));
+        assert!(!temp_dir.path().join("cli").join(METADATA_FILE).exists());
+    }
 }
@@ -0,0 +1,513 @@
+//! Rendering stored task logs for humans
+//!
+//! Backs `clancy show` (alias `show-task`), which prints a task transcript as
+//! plain text or exports it as Markdown or a standalone HTML page for sharing
+//! in reviews. `--raw` prints the original stream-json instead.
+
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+
+use crate::diff::file_diffs;
+use crate::highlight::highlight_html;
+use crate::project::Project;
+use crate::tasklog::{find_task_log, TaskLog};
+use crate::transcript::{tool_input_label, Message};
+
+/// Output format for `clancy show`
+#[derive(Clone, Copy, PartialEq)]
+pub enum ShowFormat {
+    Text,
+    Markdown,
+    Html,
+    /// The stream-json claude printed, as captured
+    Raw,
+}
+
+/// Renders a stored task and prints it or writes it to a file
+pub fn show_task(
+    project_name: &str,
+    number: u32,
+    format: ShowFormat,
+    output: Option<&Path>,
+) -> Result<()> {
+    let project = Project::open(project_name)?;
+    let path = find_task_log(&project, number)?;
+
+    let rendered = match format {
+        ShowFormat::Text => render_text(&TaskLog::load(&path)?),
+        ShowFormat::Markdown => render_markdown(&TaskLog::load(&path)?),
+        ShowFormat::Html => render_html(&project.metadata.name, &TaskLog::load(&path)?),
+        ShowFormat::Raw => raw_output(&path, number)?,
+    };
+
+    match output {
+        Some(path) => {
+            std::fs::write(path, rendered)
+                .with_context(|| format!("Failed to write output: {}", path.display()))?;
+            println!("Wrote task {} to {}", number, path.display());
+        }
+        None => print!("{}", rendered),
+    }
+
+    Ok(())
+}
+
+/// The captured stream-json of a task log, which `TaskLog` doesn't load
+fn raw_output(path: &Path, number: u32) -> Result<String> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read task log: {:?}", path))?;
+    let log: serde_json::Value = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse task log: {:?}", path))?;
+    match log["raw_output"].as_str() {
+        Some(raw) if !raw.is_empty() => Ok(format!("{}\n", raw.trim_end())),
+        _ => bail!("Task {} has no raw output recorded", number),
+    }
+}
+
+/// Formats the one-line cost/duration/outcome summary for a task
+fn metadata_line(log: &TaskLog) -> String {
+    let outcome = if log.interrupted {
+        "interrupted"
+    } else if log.success {
+        "succeeded"
+    } else {
+        "failed"
+    };
+    let mut parts = vec![outcome.to_string()];
+    if log.starred {
+        parts.insert(0, "★ starred".to_string());
+    }
+    if let Some(timestamp) = log.timestamp {
+        parts.push(timestamp.format("%Y-%m-%d %H:%M").to_string());
+    }
+    if let Some(ms) = log.duration_ms {
+        parts.push(format!("{:.1}s", ms as f64 / 1000.0));
+    }
+    if let Some(cost) = log.cost_usd {
+        parts.push(format!("${:.4}", cost));
+    }
+    if let Some(usage) = log
+        .transcript
+        .result
+        .as_ref()
+        .and_then(|r| r.usage.as_ref())
+    {
+        parts.push(format!(
+            "{} in / {} out tokens",
+            usage.input_tokens, usage.output_tokens
+        ));
+    }
+    if let Some(model) = log.transcript.init.as_ref().and_then(|i| i.model.as_ref()) {
+        parts.push(model.clone());
+    }
+    if let Some(mode) = log.permission_mode.as_deref().filter(|m| *m != "default") {
+        parts.push(format!("{} mode", mode));
+    }
+    if let Some(dir) = &log.working_dir {
+        parts.push(format!("in {}", dir));
+    }
+    parts.join(" | ")
+}
+
+/// Renders a task as plain text for the terminal
+fn render_text(log: &TaskLog) -> String {
+    let mut out = String::new();
+    out.push_str(&format!("Task {}: {}\n", log.task_number, log.prompt));
+    out.push_str(&format!("{}\n", metadata_line(log)));
+    if !log.tools_used.is_empty() {
+        out.push_str(&format!("Tools: {}\n", log.tools_used.join(", ")));
+    }
+    for annotation in &log.annotations {
+        out.push_str(&format!(
+            "Note ({}): {}\n",
+            annotation.timestamp.format("%Y-%m-%d %H:%M"),
+            annotation.text
+        ));
+    }
+    out.push('\n');
+
+    for msg in &log.transcript.messages {
+        match msg {
+            Message::Text { text } => {
+                out.push_str(text);
+                out.push_str("\n\n");
+            }
+            Message::ToolUse {
+                tool_name, input, ..
+            } => {
+                out.push_str(&format!("[{}] {}\n", tool_name, tool_input_label(input)));
+            }
+            Message::ToolResult {
+                output, is_error, ..
+            } => {
+                if *is_error {
+                    out.push_str(&format!("  error: {}\n", output.trim()));
+                }
+            }
+        }
+    }
+
+    if let Some(text) = log
+        .transcript
+        .result
+        .as_ref()
+        .and_then(|r| r.result_text.as_ref())
+    {
+        out.push_str(&format!("\n---\n{}\n", text));
+    }
+
+    let diffs = file_diffs(&log.transcript);
+    if !diffs.is_empty() {
+        out.push_str("\n## Changes\n\n");
+        for diff in diffs {
+            out.push_str(&diff.to_unified());
+            out.push('\n');
+        }
+    }
+
+    out
+}
+
+/// Renders a task as Markdown: a heading with the prompt and metadata,
+/// followed by the transcript
+fn render_markdown(log: &TaskLog) -> String {
+    let mut md = format!("## Task {}: {}\n\n", log.task_number, log.prompt);
+    md.push_str(&format!("_{}_\n\n", metadata_line(log)));
+    for annotation in &log.annotations {
+        md.push_str(&format!(
+            "> **Note ({}):** {}\n\n",
+            annotation.timestamp.format("%Y-%m-%d %H:%M"),
+            annotation.text
+        ));
+    }
+    md.push_str(&log.transcript.to_markdown());
+
+    let diffs = file_diffs(&log.transcript);
+    if !diffs.is_empty() {
+        md.push_str("\n### Changes\n\n");
+        for diff in diffs {
+            md.push_str(&format!("```diff\n{}```\n\n", diff.to_unified()));
+        }
+    }
+    md
+}
+
+/// Escapes text for inclusion in HTML
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Converts assistant prose to HTML: fenced code blocks become highlighted
+/// `<pre><code>` blocks tagged with their language, everything else becomes
+/// paragraphs
+fn prose_to_html(text: &str) -> String {
+    let mut html = String::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut code: Option<(String, Vec<&str>)> = None;
+
+    let flush_paragraph = |paragraph: &mut Vec<&str>, html: &mut String| {
+        if !paragraph.is_empty() {
+            let lines: Vec<String> = paragraph.iter().map(|l| escape_html(l)).collect();
+            html.push_str(&format!("<p>{}</p>\n", lines.join("<br>\n")));
+            paragraph.clear();
+        }
+    };
+
+    for line in text.lines() {
+        if let Some(fence) = line.trim_start().strip_prefix("```") {
+            match code.take() {
+                Some((lang, lines)) => {
+                    let class = if lang.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" class=\"language-{}\"", escape_html(&lang))
+                    };
+                    html.push_str(&format!(
+                        "<pre><code{}>{}</code></pre>\n",
+                        class,
+                        highlight_html(&lines.join("\n"), &lang)
+                    ));
+                }
+                None => {
+                    flush_paragraph(&mut paragraph, &mut html);
+                    code = Some((fence.trim().to_string(), Vec::new()));
+                }
+            }
+        } else if let Some((_, ref mut lines)) = code {
+            lines.push(line);
+        } else if line.trim().is_empty() {
+            flush_paragraph(&mut paragraph, &mut html);
+        } else {
+            paragraph.push(line);
+        }
+    }
+
+    // Unterminated fence: still show the code
+    if let Some((lang, lines)) = code {
+        html.push_str(&format!(
+            "<pre><code>{}</code></pre>\n",
+            highlight_html(&lines.join("\n"), &lang)
+        ));
+    }
+    flush_paragraph(&mut paragraph, &mut html);
+
+    html
+}
+
+const HTML_STYLE: &str = r#"
+body { font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", sans-serif; max-width: 960px; margin: 2em auto; padding: 0 1em; color: #1f2328; line-height: 1.5; }
+header { border-bottom: 1px solid #d0d7de; margin-bottom: 1.5em; }
+.meta { color: #59636e; font-size: 0.9em; }
+.prompt { background: #f6f8fa; border-left: 4px solid #0969da; padding: 0.5em 1em; white-space: pre-wrap; }
+.annotation { background: #fff8c5; border-left: 4px solid #d4a72c; padding: 0.5em 1em; margin: 0.5em 0; white-space: pre-wrap; }
+.assistant { margin: 1em 0; }
+details { border: 1px solid #d0d7de; border-radius: 6px; margin: 0.5em 0; padding: 0.25em 0.75em; }
+details.error { border-color: #cf222e; }
+summary { cursor: pointer; font-family: ui-monospace, SFMono-Regular, Menlo, monospace; font-size: 0.9em; }
+pre { background: #f6f8fa; padding: 0.75em; overflow-x: auto; border-radius: 6px; }
+.result { border-top: 1px solid #d0d7de; margin-top: 2em; padding-top: 1em; }
+"#;
+
+/// Renders a task as a standalone HTML page
+///
+/// Tool calls and results are collapsible `<details>` blocks so the prose
+/// reads cleanly. The page loads nothing external, so it works offline:
+/// code blocks, tool inputs, and diffs are highlighted at render time with
+/// inline styles.
+fn render_html(project_name: &str, log: &TaskLog) -> String {
+    let mut body = String::new();
+
+    for annotation in &log.annotations {
+        body.push_str(&format!(
+            "<div class=\"annotation\"><strong>Note ({}):</strong> {}</div>\n",
+            annotation.timestamp.format("%Y-%m-%d %H:%M"),
+            escape_html(&annotation.text)
+        ));
+    }
+
+    for msg in &log.transcript.messages {
+        match msg {
+            Message::Text { text } => {
+                body.push_str(&format!(
+                    "<div class=\"assistant\">\n{}</div>\n",
+                    prose_to_html(text)
+                ));
+            }
+            Message::ToolUse {
+                tool_name, input, ..
+            } => {
+                let input_json = serde_json::to_string_pretty(input).unwrap_or_default();
+                body.push_str(&format!(
+                    "<details class=\"tool-use\"><summary>{} {}</summary>\n<pre><code class=\"language-json\">{}</code></pre></details>\n",
+                    escape_html(tool_name),
+                    escape_html(&tool_input_label(input)),
+                    highlight_html(&input_json, "json")
+                ));
+            }
+            Message::ToolResult {
+                output, is_error, ..
+            } => {
+                let (class, label) = if *is_error {
+                    ("tool-result error", "Error")
+                } else {
+                    ("tool-result", "Result")
+                };
+                body.push_str(&format!(
+                    "<details class=\"{}\"><summary>{} ({} lines)</summary>\n<pre>{}</pre></details>\n",
+                    class,
+                    label,
+                    output.lines().count(),
+                    escape_html(output)
+                ));
+            }
+        }
+    }
+
+    if let Some(text) = log
+        .transcript
+        .result
+        .as_ref()
+        .and_then(|r| r.result_text.as_ref())
+    {
+        body.push_str(&format!(
+            "<div class=\"result\">\n<h2>Result</h2>\n{}</div>\n",
+            prose_to_html(text)
+        ));
+    }
+
+    let diffs = file_diffs(&log.transcript);
+    if !diffs.is_empty() {
+        body.push_str("<div class=\"changes\">\n<h2>Changes</h2>\n");
+        for diff in diffs {
+            let (added, removed) = diff.line_counts();
+            body.push_str(&format!(
+                "<details class=\"diff\"><summary>{} (+{} -{})</summary>\n<pre><code class=\"language-diff\">{}</code></pre></details>\n",
+                escape_html(&diff.path),
+                added,
+                removed,
+                highlight_html(&diff.to_unified(), "diff")
+            ));
+        }
+        body.push_str("</div>\n");
+    }
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{project} — Task {number}</title>
+<style>{style}</style>
+</head>
+<body>
+<header>
+<h1>{project} — Task {number}</h1>
+<p class="meta">{meta}</p>
+<div class="prompt">{prompt}</div>
+</header>
+{body}</body>
+</html>
+"#,
+        project = escape_html(project_name),
+        number = log.task_number,
+        style = HTML_STYLE,
+        meta = escape_html(&metadata_line(log)),
+        prompt = escape_html(&log.prompt),
+        body = body,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcript::Transcript;
+
+    fn sample_log() -> TaskLog {
+        let output = r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Reading <config>"},{"type":"tool_use","name":"Read","id":"t1","input":{"file_path":"/src/main.rs"}}]}}
+{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1","content":"fn main() {}","is_error":true}]}}
+{"type":"result","subtype":"success","result":"Done","duration_ms":2000,"total_cost_usd":0.05}"#;
+        let json = serde_json::json!({
+            "task_number": 7,
+            "prompt": "inspect main",
+            "success": true,
+            "duration_ms": 2000,
+            "cost_usd": 0.05,
+            "transcript": Transcript::parse(output),
+        });
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(
+            escape_html("<a href=\"x\">&</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn test_prose_to_html_code_block() {
+        let html = prose_to_html("Intro line\n\n```rust\nlet x = 1 < 2;\n```\nAfter");
+        assert!(html.contains("<p>Intro line</p>"));
+        assert!(html.contains("<pre><code class=\"language-rust\"><span style="));
+        assert!(html.contains(">let</span> x = <span"));
+        assert!(html.contains("</span> &lt; <span"));
+        assert!(html.contains("<p>After</p>"));
+    }
+
+    #[test]
+    fn test_render_html_collapses_tool_calls() {
+        let html = render_html("demo", &sample_log());
+        assert!(html.contains("<details class=\"tool-use\"><summary>Read /src/main.rs</summary>"));
+        assert!(html.contains("<details class=\"tool-result error\"><summary>Error (1 lines)"));
+        assert!(html.contains("Reading &lt;config&gt;"));
+        assert!(html.contains("$0.0500"));
+    }
+
+    #[test]
+    fn test_render_html_loads_nothing_external() {
+        let html = render_html("demo", &sample_log());
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("<link"));
+    }
+
+    #[test]
+    fn test_render_markdown_header() {
+        let md = render_markdown(&sample_log());
+        assert!(md.starts_with("## Task 7: inspect main\n\n_succeeded | 2.0s | $0.0500_\n\n"));
+        assert!(md.contains("#### Tool: Read `/src/main.rs`"));
+    }
+
+    #[test]
+    fn test_render_html_includes_edit_diffs() {
+        let output = r#"{"type":"assistant","message":{"content":[{"type":"tool_use","name":"Edit","id":"t1","input":{"file_path":"src/a.rs","old_string":"a < b","new_string":"a > b"}}]}}"#;
+        let log: TaskLog = serde_json::from_value(serde_json::json!({
+            "task_number": 1,
+            "transcript": Transcript::parse(output),
+        }))
+        .unwrap();
+
+        let html = render_html("demo", &log);
+        assert!(html.contains("<summary>src/a.rs (+1 -1)</summary>"));
+        assert!(html.contains(">-a &lt; b</span>\n<span"));
+        assert!(html.contains(">+a &gt; b</span>"));
+    }
+
+    #[test]
+    fn test_annotations_rendered() {
+        let mut log = sample_log();
+        log.annotations.push(crate::tasklog::Annotation {
+            timestamp: "2025-01-15T14:30:00Z".parse().unwrap(),
+            text: "Check <this> later".to_string(),
+        });
+        assert!(render_text(&log).contains("Note (2025-01-15 14:30): Check <this> later\n"));
+        assert!(render_markdown(&log).contains("> **Note (2025-01-15 14:30):** Check <this>"));
+        assert!(render_html("demo", &log).contains("Check &lt;this&gt; later"));
+    }
+
+    #[test]
+    fn test_metadata_line_includes_permission_mode() {
+        let mut log = sample_log();
+        log.permission_mode = Some("plan".to_string());
+        assert_eq!(
+            metadata_line(&log),
+            "succeeded | 2.0s | $0.0500 | plan mode"
+        );
+        log.permission_mode = Some("default".to_string());
+        assert_eq!(metadata_line(&log), "succeeded | 2.0s | $0.0500");
+    }
+
+    #[test]
+    fn test_render_text_includes_tool_label() {
+        let text = render_text(&sample_log());
+        assert!(text.starts_with("Task 7: inspect main\n"));
+        assert!(text.contains("[Read] /src/main.rs"));
+        assert!(text.contains("  error: fn main() {}"));
+    }
+
+    #[test]
+    fn test_raw_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("007-inspect.json");
+        std::fs::write(
+            &path,
+            r#"{"task_number":7,"raw_output":"{\"type\":\"result\"}\n"}"#,
+        )
+        .unwrap();
+        assert_eq!(raw_output(&path, 7).unwrap(), "{\"type\":\"result\"}\n");
+
+        std::fs::write(&path, r#"{"task_number":7}"#).unwrap();
+        assert!(raw_output(&path, 7).is_err());
+    }
+}
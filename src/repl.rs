@@ -1,18 +1,42 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
 use rustyline::error::ReadlineError;
-use rustyline::DefaultEditor;
+use rustyline::{Cmd, DefaultEditor, EditMode, KeyEvent};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 use std::fs::OpenOptions;
 use std::io::{BufRead, BufReader, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
-
-use crate::config::{self, load_config};
-use crate::extraction::{apply_extraction, extract_notes};
-use crate::project::{Project, NOTE_CATEGORIES};
-use crate::transcript::Transcript;
-
-/// Conversation continuity mode
-#[derive(Clone, Copy, PartialEq)]
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
+
+use crate::clipboard;
+use crate::config::{self, load_config, PricingConfig};
+use crate::deps;
+use crate::diff::file_diffs;
+use crate::environment::{self, Environment};
+use crate::extraction::{
+    apply_extraction, extract_notes, parse_category_selection, HEURISTIC_MODEL,
+};
+use crate::handoff;
+use crate::i18n;
+use crate::notes;
+use crate::policy;
+use crate::pricing;
+use crate::processors;
+use crate::project::{self, Project, TaskTemplate, EXTRA_NOTES, NOTE_CATEGORIES};
+use crate::render::{self, ShowFormat};
+use crate::review;
+use crate::routing::{self, Route};
+use crate::settings;
+use crate::summarizer::{self, Summarizer};
+use crate::tasklog::{self, record_extraction};
+use crate::text;
+use crate::transcript::{Message, Transcript};
+
+/// Conversation continuity mode, ordered from cheapest to richest
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 enum ConversationMode {
     /// Fresh context each task (only notes, no history)
     Fresh,
@@ -22,26 +46,210 @@ enum ConversationMode {
     Full,
 }
 
+impl ConversationMode {
+    fn name(self) -> &'static str {
+        match self {
+            ConversationMode::Fresh => "fresh",
+            ConversationMode::Summary => "summary",
+            ConversationMode::Full => "full",
+        }
+    }
+
+    /// The next cheaper mode, if any
+    fn lower(self) -> Option<Self> {
+        match self {
+            ConversationMode::Full => Some(ConversationMode::Summary),
+            ConversationMode::Summary => Some(ConversationMode::Fresh),
+            ConversationMode::Fresh => None,
+        }
+    }
+
+    /// The richest mode allowed after `session_usd` of spend, one step down
+    /// per `threshold` passed
+    fn spend_cap(session_usd: f64, threshold: f64) -> Self {
+        if session_usd > threshold * 2.0 {
+            ConversationMode::Fresh
+        } else if session_usd > threshold {
+            ConversationMode::Summary
+        } else {
+            ConversationMode::Full
+        }
+    }
+}
+
+/// Claude Code permission modes selectable with `/mode`
+const PERMISSION_MODES: &[&str] = &["default", "plan", "acceptEdits"];
+
+/// Suffix of the raw stream file written while a task runs (`NNN.stream.jsonl`)
+const STREAM_SUFFIX: &str = ".stream.jsonl";
+
+/// Set while a claude child is running, so Ctrl+C cancels the task instead
+/// of exiting clancy
+static TASK_RUNNING: AtomicBool = AtomicBool::new(false);
+/// Set when Ctrl+C arrives during a task; cleared when the task is logged
+static TASK_INTERRUPTED: AtomicBool = AtomicBool::new(false);
+/// Set when a task is stopped at `pricing.max_task_usd`; cleared when the
+/// task is logged
+static COST_LIMIT_HIT: AtomicBool = AtomicBool::new(false);
+
+/// How a call to `run_task` ended
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum TaskOutcome {
+    /// Claude ran to the end, whether or not the task succeeded
+    Completed,
+    /// Declined at a confirmation; nothing ran
+    Skipped,
+    /// Stopped early by Ctrl+C or the cost ceiling
+    Interrupted,
+}
+
 /// Task record for conversation continuity
 struct TaskRecord {
     number: u32,
     prompt: String,
     summary: String,
-    /// Full raw output for /continue mode
-    raw_output: String,
+    /// Parsed transcript, kept so full mode and metrics don't re-parse the
+    /// output on every compile
+    transcript: Transcript,
+    /// Processor artifacts shown with the task in summary mode
+    artifacts: Vec<String>,
+}
+
+/// Aggregate stats for the tasks run this session
+#[derive(Debug, Default, PartialEq)]
+struct SessionMetrics {
+    tasks: usize,
+    succeeded: usize,
+    cost_usd: f64,
+    input_tokens: u64,
+    output_tokens: u64,
+    cache_read_tokens: u64,
+    cache_creation_tokens: u64,
+    /// Estimated prompt cache savings, when the model's price is known
+    cache_savings_usd: Option<f64>,
+    tool_counts: Vec<(String, usize)>,
+    files_touched: Vec<String>,
+}
+
+impl SessionMetrics {
+    /// Computes metrics from the transcripts of this session's tasks,
+    /// skipping the placeholder left by /compact
+    fn from_records(records: &[TaskRecord], pricing: &config::PricingConfig) -> Self {
+        let mut metrics = Self::default();
+        for record in records.iter().filter(|r| r.number != 0) {
+            let transcript = &record.transcript;
+            metrics.tasks += 1;
+            if transcript.succeeded() {
+                metrics.succeeded += 1;
+            }
+            metrics.cost_usd += transcript.total_cost().unwrap_or(0.0);
+            if let Some(usage) = transcript.usage() {
+                metrics.input_tokens += usage.input_tokens;
+                metrics.output_tokens += usage.output_tokens;
+                metrics.cache_read_tokens += usage.cache_read_tokens.unwrap_or(0);
+                metrics.cache_creation_tokens += usage.cache_creation_tokens.unwrap_or(0);
+                if let Some(saved) = pricing::cache_savings(pricing, transcript.model(), usage) {
+                    *metrics.cache_savings_usd.get_or_insert(0.0) += saved;
+                }
+            }
+            for (tool, count) in transcript.tool_counts() {
+                match metrics.tool_counts.iter_mut().find(|(t, _)| *t == tool) {
+                    Some((_, total)) => *total += count,
+                    None => metrics.tool_counts.push((tool, count)),
+                }
+            }
+            for diff in file_diffs(transcript) {
+                if !metrics.files_touched.contains(&diff.path) {
+                    metrics.files_touched.push(diff.path);
+                }
+            }
+        }
+        metrics
+            .tool_counts
+            .sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        metrics
+    }
+
+    fn format(&self) -> String {
+        let mut out = format!(
+            "Succeeded: {}/{} | Cost: ${:.4} | Tokens: {} in / {} out\n",
+            self.succeeded, self.tasks, self.cost_usd, self.input_tokens, self.output_tokens
+        );
+        if let Some(rate) = pricing::cache_hit_rate(
+            self.input_tokens,
+            self.cache_read_tokens,
+            self.cache_creation_tokens,
+        ) {
+            out.push_str(&format!(
+                "Cache: {} read / {} written ({:.0}% hit rate)",
+                self.cache_read_tokens,
+                self.cache_creation_tokens,
+                rate * 100.0
+            ));
+            if let Some(saved) = self.cache_savings_usd {
+                out.push_str(&format!(" | Saved: ~${:.4}", saved));
+            }
+            out.push('\n');
+        }
+        if !self.tool_counts.is_empty() {
+            let tools: Vec<String> = self
+                .tool_counts
+                .iter()
+                .map(|(name, n)| format!("{} {}", name, n))
+                .collect();
+            out.push_str(&format!("Tools: {}\n", tools.join(", ")));
+        }
+        if !self.files_touched.is_empty() {
+            out.push_str(&format!(
+                "Files touched ({}): {}\n",
+                self.files_touched.len(),
+                self.files_touched.join(", ")
+            ));
+        }
+        out
+    }
 }
 
 /// REPL session state
-struct Session {
+pub(crate) struct Session {
     project: Project,
     task_history: Vec<TaskRecord>,
     working_dir: PathBuf,
     /// Current conversation mode
     conversation_mode: ConversationMode,
+    /// Whether notes are extracted after each task (`/extract on|off`)
+    extraction_enabled: bool,
+    /// Plan every task in read-only mode before executing it
+    plan_first: bool,
+    /// Claude Code `--permission-mode` for tasks (`/mode`); None uses the default
+    permission_mode: Option<String>,
+    /// Run tasks inside the configured container (`/sandbox on|off`)
+    sandboxed: bool,
+    /// Subdirectory of the working dir tasks run in (`/cd`)
+    task_dir: Option<PathBuf>,
+    /// Environment section detected at session start, if enabled
+    environment: Option<String>,
+    /// Files added to the next task's context (`/attach`), with whether to
+    /// keep the start (`--head`) rather than the end when capped
+    attachments: Vec<(PathBuf, bool)>,
+    /// Text the next prompt starts with, for editing before it runs (`/paste`)
+    pending_input: Option<String>,
+    /// Whether a user is at the terminal to answer confirmations; false for
+    /// watch and daemon runs
+    interactive: bool,
+    /// Writes each task's line in session history (project.toml `[summarizer]`)
+    summarizer: Box<dyn Summarizer>,
+    /// Project session number new task logs are recorded under
+    session_number: u32,
+    /// Spend on `/plan` passes, which aren't tasks of their own
+    planning_cost: f64,
+    /// Cost of the approved plan the running task follows, for its log
+    pending_plan_cost: Option<f64>,
 }
 
 impl Session {
-    fn new(project: Project) -> Result<Self> {
+    pub(crate) fn new(project: Project) -> Result<Self> {
+        install_interrupt_handler();
         let working_dir = std::env::current_dir()?;
         // Load conversation mode from config
         let config = load_config()?;
@@ -50,14 +258,220 @@ impl Session {
             "full" => ConversationMode::Full,
             _ => ConversationMode::Summary,
         };
+        let environment = config
+            .context
+            .include_environment
+            .then(|| Environment::detect().format());
+        prepare_project(&project, &working_dir, &config)?;
+        let summarizer = summarizer::for_project(project.metadata.summarizer.as_ref())?;
         Ok(Self {
+            session_number: project.metadata.stats.total_sessions,
             project,
             task_history: Vec::new(),
             working_dir,
             conversation_mode,
+            extraction_enabled: true,
+            plan_first: config.repl.plan_first,
+            permission_mode: None,
+            sandboxed: config.sandbox.enabled,
+            task_dir: None,
+            environment,
+            attachments: Vec::new(),
+            pending_input: None,
+            interactive: false,
+            summarizer,
+            planning_cost: 0.0,
+            pending_plan_cost: None,
         })
     }
 
+    /// Total cost of this session's tasks and plan passes
+    fn session_cost(&self) -> f64 {
+        self.task_history
+            .iter()
+            .filter_map(|record| record.transcript.total_cost())
+            // sum() of no floats is -0.0, which would print as "$-0.00"
+            .fold(self.planning_cost, |total, cost| total + cost)
+    }
+
+    /// Renders the REPL prompt for `repl.prompt_style`
+    fn prompt(&self, repl: &config::ReplConfig) -> String {
+        let indicator =
+            || prompt_indicator(self.conversation_mode, self.session_cost(), repl.budget_usd);
+        match repl.prompt_style.as_str() {
+            "minimal" => format!("{}> ", indicator()),
+            "template" => {
+                let template = &repl.prompt_template;
+                // Only shell out to git when the template asks for it
+                let branch = if template.contains("{branch}") {
+                    git_branch(&self.working_dir).unwrap_or_else(|| "-".to_string())
+                } else {
+                    String::new()
+                };
+                let tasks = self.task_history.iter().filter(|r| r.number != 0).count();
+                i18n::fill(
+                    template,
+                    &[
+                        ("project", &self.project.metadata.name),
+                        ("mode", &self.conversation_mode.name()),
+                        ("cost", &format!("{:.2}", self.session_cost())),
+                        ("tasks", &tasks),
+                        ("branch", &branch),
+                        ("indicator", &indicator()),
+                    ],
+                )
+            }
+            _ => format!("{}{}> ", self.project.metadata.name, indicator()),
+        }
+    }
+
+    /// Prints the compiled context size per section, the headroom under
+    /// `max_context_tokens`, and what the next task's history will add
+    fn show_tokens(&self) -> Result<()> {
+        let config = load_config()?;
+        let content = self.build_context(&config)?;
+        let max_tokens = config.context.max_context_tokens;
+        let total = content.len() / 4;
+
+        println!("{}", i18n::t("tokens.header"));
+        for (title, tokens) in context_sections(&content) {
+            println!(
+                "{}",
+                i18n::tf(
+                    "tokens.section",
+                    &[("tokens", &format!("{:>7}", tokens)), ("title", &title)]
+                )
+            );
+        }
+        println!(
+            "{}",
+            i18n::tf("tokens.total", &[("tokens", &format!("{:>7}", total))])
+        );
+        if total > max_tokens {
+            println!(
+                "{}",
+                i18n::tf(
+                    "tokens.over",
+                    &[("max", &max_tokens), ("over", &(total - max_tokens))]
+                )
+            );
+        } else {
+            println!(
+                "{}",
+                i18n::tf(
+                    "tokens.headroom",
+                    &[("free", &(max_tokens - total)), ("max", &max_tokens)]
+                )
+            );
+        }
+
+        let records: Vec<&TaskRecord> =
+            self.task_history.iter().filter(|r| r.number != 0).collect();
+        let next = match self.conversation_mode {
+            ConversationMode::Fresh => Some(0),
+            // One "N. prompt — summary" line per task
+            ConversationMode::Summary => Some(
+                records
+                    .iter()
+                    .map(|t| format!("{}. {} — {}\n", t.number, t.prompt, t.summary).len() / 4)
+                    .sum::<usize>()
+                    .checked_div(records.len())
+                    .unwrap_or(35),
+            ),
+            // The whole transcript, estimated from this session's average
+            ConversationMode::Full => records
+                .iter()
+                .map(|t| full_history_entry(t).len() / 4)
+                .sum::<usize>()
+                .checked_div(records.len()),
+        };
+        match next {
+            Some(tokens) => println!(
+                "{}",
+                i18n::tf(
+                    "tokens.next_history",
+                    &[
+                        ("tokens", &tokens),
+                        ("mode", &self.conversation_mode.name())
+                    ]
+                )
+            ),
+            None => println!("{}", i18n::t("tokens.next_full")),
+        }
+        println!();
+        Ok(())
+    }
+
+    /// Cost of the most recent task this session, if it reported one
+    pub(crate) fn last_task_cost(&self) -> Option<f64> {
+        let record = self.task_history.last()?;
+        record.transcript.total_cost()
+    }
+
+    /// True if this session's last task finished successfully
+    fn last_task_succeeded(&self) -> bool {
+        self.task_history
+            .last()
+            .is_some_and(|record| record.transcript.succeeded())
+    }
+
+    /// Steps the conversation mode down before a task when session spend or
+    /// the projected context size is over its configured threshold,
+    /// printing why
+    fn auto_downgrade_mode(&mut self) -> Result<()> {
+        let config = load_config()?;
+        let context = &config.context;
+        if let Some(threshold) = context.downgrade_above_session_usd {
+            let spent = self.session_cost();
+            let cap = ConversationMode::spend_cap(spent, threshold);
+            if cap < self.conversation_mode {
+                self.announce_downgrade(
+                    cap,
+                    &format!(
+                        "session spend ${:.2} passed ${:.2}",
+                        spent,
+                        if cap == ConversationMode::Fresh {
+                            threshold * 2.0
+                        } else {
+                            threshold
+                        }
+                    ),
+                );
+            }
+        }
+        if let Some(max_tokens) = context.downgrade_above_tokens {
+            while let Some(lower) = self.conversation_mode.lower() {
+                let projected = self.build_context(&config)?.len() / 4;
+                if projected <= max_tokens {
+                    break;
+                }
+                self.announce_downgrade(
+                    lower,
+                    &format!(
+                        "context would be ~{} tokens, over {}",
+                        projected, max_tokens
+                    ),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn announce_downgrade(&mut self, mode: ConversationMode, reason: &str) {
+        println!(
+            "{}",
+            i18n::tf(
+                "mode.downgraded",
+                &[
+                    ("from", &self.conversation_mode.name()),
+                    ("to", &mode.name()),
+                    ("reason", &reason)
+                ]
+            )
+        );
+        self.conversation_mode = mode;
+    }
+
     /// Compiles all notes into .claude/context.md
     /// Returns estimated token count
     fn compile_context(&self) -> Result<usize> {
@@ -66,109 +480,194 @@ impl Session {
         std::fs::create_dir_all(&claude_dir)?;
 
         let context_path = claude_dir.join("context.md");
-        let mut content = String::new();
         let max_tokens = config.context.max_context_tokens;
+        let mut content = self.build_context(&config)?;
+        let built = content.clone();
+
+        // Apply token budget (rough estimate: 4 chars per token)
+        let estimated_tokens = content.len() / 4;
+        if estimated_tokens > max_tokens {
+            // Truncate content, keeping header and footer
+            let max_chars = max_tokens * 4;
+            if content.len() > max_chars {
+                let truncated = text::truncate(&content, max_chars);
+                // Find last complete section
+                if let Some(pos) = truncated.rfind("\n## ") {
+                    content = format!(
+                        "{}\n\n[Context truncated due to token limit]\n",
+                        &content[..pos]
+                    );
+                }
+            }
+        }
+
+        let final_tokens = content.len() / 4;
+
+        std::fs::write(&context_path, &content)
+            .with_context(|| format!("Failed to write context file: {:?}", context_path))?;
+        if let Err(e) = notes::record_usage(&self.project, &built, &content) {
+            eprintln!(
+                "{}",
+                i18n::tf("warn.note_usage", &[("error", &format!("{:#}", e))])
+            );
+        }
+
+        Ok(final_tokens)
+    }
+
+    /// Builds the full context before the token budget is applied
+    ///
+    /// With `context.stable`, everything before the session history stays
+    /// byte-identical between tasks (unless notes change) and the history
+    /// only grows at the end, so Claude's prompt cache can reuse the prefix.
+    fn build_context(&self, config: &config::Config) -> Result<String> {
+        let stable = config.context.stable;
+        let mut content = String::new();
 
         // Header
         content.push_str("<!-- CLANCY CONTEXT — AUTO-GENERATED -->\n");
-        content.push_str(&format!(
-            "<!-- Project: {} | Task: {} -->\n\n",
-            self.project.metadata.name,
-            self.task_history.len() + 1
-        ));
+        if stable {
+            content.push_str(&format!(
+                "<!-- Project: {} -->\n\n",
+                self.project.metadata.name
+            ));
+        } else {
+            content.push_str(&format!(
+                "<!-- Project: {} | Task: {} -->\n\n",
+                self.project.metadata.name,
+                self.task_history.len() + 1
+            ));
+        }
+        let add_dirs = &self.project.metadata.claude_cli.add_dirs;
+        if !add_dirs.is_empty() {
+            content.push_str(&format!(
+                "Additional directories available to this task: {}\n\n",
+                add_dirs.join(", ")
+            ));
+        }
+
+        if let Some(ref environment) = self.environment {
+            content.push_str(environment);
+        }
+        if config.context.include_build_commands {
+            content.push_str(&environment::format_build_commands(
+                &environment::detect_build_commands(&self.run_dir()),
+            ));
+        }
+
+        // Log tails, so "fix the CI failure" starts from the actual error text
+        let logs = config
+            .context
+            .failure_log
+            .iter()
+            .map(|path| self.working_dir.join(path));
+        for path in logs {
+            match std::fs::read_to_string(&path) {
+                Ok(log) => content.push_str(&log_tail_section(
+                    &path.display().to_string(),
+                    &log,
+                    config.context.failure_log_lines,
+                )),
+                // A missing log just means there's nothing to report yet
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => eprintln!(
+                    "{}",
+                    i18n::tf(
+                        "warn.read_file",
+                        &[("path", &path.display()), ("error", &e)]
+                    )
+                ),
+            }
+        }
+
+        for (path, head) in &self.attachments {
+            match std::fs::read(path) {
+                Ok(bytes) => content.push_str(&attachment_section(
+                    &path.display().to_string(),
+                    &String::from_utf8_lossy(&bytes),
+                    config.context.attach_max_bytes,
+                    *head,
+                )),
+                Err(e) => eprintln!(
+                    "{}",
+                    i18n::tf(
+                        "warn.read_file",
+                        &[("path", &path.display()), ("error", &e)]
+                    )
+                ),
+            }
+        }
 
         // Session context based on conversation mode
-        if !self.task_history.is_empty() {
-            match self.conversation_mode {
-                ConversationMode::Fresh => {
-                    // No session history included
-                }
-                ConversationMode::Summary => {
-                    content.push_str("## Session Context\n\n");
-                    content.push_str(&format!(
-                        "This is task {} of an ongoing session. Prior tasks:\n",
-                        self.task_history.len() + 1
-                    ));
-                    for task in &self.task_history {
+        let history = self.session_history(stable);
+        if !stable {
+            content.push_str(&history);
+        }
+
+        // Own notes are read up front so inherited sections can skip entries
+        // the project already has (e.g. copied in by /fork)
+        let mut seen = HashSet::new();
+        let mut own_notes = Vec::new();
+        for category in NOTE_CATEGORIES {
+            let notes = self.project.read_notes(category)?;
+            dedup_lines(&notes, &mut seen);
+            own_notes.push((category, notes));
+        }
+
+        // Include ancestor notes if configured, nearest parent first
+        if config.context.include_parent_notes {
+            for (ancestor, categories) in self.project.ancestors(config.context.inheritance_depth) {
+                for category in &categories {
+                    let notes = dedup_lines(&ancestor.read_notes(category)?, &mut seen);
+                    if !notes.trim().is_empty() {
                         content.push_str(&format!(
-                            "{}. {} — {}\n",
-                            task.number, task.prompt, task.summary
+                            "## Inherited {} (from {})\n\n",
+                            context_title(category),
+                            ancestor.metadata.name
                         ));
-                    }
-                    content.push('\n');
-                }
-                ConversationMode::Full => {
-                    content.push_str("## Full Conversation History\n\n");
-                    content.push_str(&format!(
-                        "This is task {} of an ongoing session. Full prior conversation:\n\n",
-                        self.task_history.len() + 1
-                    ));
-                    for task in &self.task_history {
-                        content.push_str(&format!("### Task {}: {}\n\n", task.number, task.prompt));
-                        // Include the full transcript, parsed for readability
-                        let transcript = Transcript::parse(&task.raw_output);
-                        for msg in &transcript.messages {
-                            match msg {
-                                crate::transcript::Message::Text { text } => {
-                                    content.push_str(text);
-                                    content.push_str("\n\n");
-                                }
-                                crate::transcript::Message::ToolUse { tool_name, .. } => {
-                                    content.push_str(&format!("[Used tool: {}]\n\n", tool_name));
-                                }
-                                _ => {}
-                            }
-                        }
+                        content.push_str(&notes);
+                        content.push_str("\n\n");
                     }
                 }
             }
         }
 
-        // Include parent project notes if configured and parent exists
-        if config.context.include_parent_notes {
-            if let Some(ref parent_name) = self.project.metadata.parent {
-                if let Ok(parent) = Project::open(parent_name) {
-                    let parent_arch = parent.read_notes("architecture")?;
-                    if !parent_arch.trim().is_empty() {
-                        content
-                            .push_str(&format!("## Inherited Context (from {})\n\n", parent_name));
-                        content.push_str(&parent_arch);
-                        content.push_str("\n\n");
+        // Sibling projects, so work on one service knows related efforts exist
+        if config.context.include_siblings {
+            let siblings = self.project.siblings()?;
+            if !siblings.is_empty() {
+                content.push_str("## Related Projects\n\n");
+                for sibling in siblings {
+                    let mut line = format!("- {}", sibling.metadata.name);
+                    if let Some(ref description) = sibling.metadata.description {
+                        line.push_str(&format!(" — {}", description));
+                    }
+                    if let Some(headline) = project::plan_headline(&sibling.read_notes("plan")?) {
+                        line.push_str(&format!(" (plan: {})", truncate_string(&headline, 80)));
                     }
+                    content.push_str(&line);
+                    content.push('\n');
                 }
+                content.push('\n');
             }
         }
 
-        // Architecture notes
-        let arch = self.project.read_notes("architecture")?;
-        if !arch.trim().is_empty() {
-            content.push_str("## Architectural Context\n\n");
-            content.push_str(&arch);
-            content.push_str("\n\n");
-        }
-
-        // Decisions
-        let decisions = self.project.read_notes("decisions")?;
-        if !decisions.trim().is_empty() {
-            content.push_str("## Key Decisions\n\n");
-            content.push_str(&decisions);
-            content.push_str("\n\n");
-        }
-
-        // Failures (critical for avoiding repeated mistakes)
-        let failures = self.project.read_notes("failures")?;
-        if !failures.trim().is_empty() {
-            content.push_str("## Known Pitfalls\n\n");
-            content.push_str(&failures);
-            content.push_str("\n\n");
+        // Project notes: architecture, decisions, failures (critical for
+        // avoiding repeated mistakes), then the current plan
+        if config.context.include_decisions_archive {
+            let archive = self.project.read_notes(notes::DECISIONS_ARCHIVE)?;
+            let position = own_notes.iter().position(|(c, _)| **c == "decisions");
+            own_notes.insert(
+                position.map_or(0, |p| p + 1),
+                (&notes::DECISIONS_ARCHIVE, archive),
+            );
         }
-
-        // Current plan
-        let plan = self.project.read_notes("plan")?;
-        if !plan.trim().is_empty() {
-            content.push_str("## Current Plan\n\n");
-            content.push_str(&plan);
-            content.push_str("\n\n");
+        for (category, notes) in own_notes {
+            if !notes.trim().is_empty() {
+                content.push_str(&format!("## {}\n\n", context_title(category)));
+                content.push_str(&notes);
+                content.push_str("\n\n");
+            }
         }
 
         // Footer
@@ -176,70 +675,564 @@ impl Session {
         content.push_str(
             "When you complete work or encounter a problem, state it clearly for continuity.\n",
         );
+        if stable && !history.is_empty() {
+            content.push('\n');
+            content.push_str(&history);
+        }
 
-        // Apply token budget (rough estimate: 4 chars per token)
-        let estimated_tokens = content.len() / 4;
-        if estimated_tokens > max_tokens {
-            // Truncate content, keeping header and footer
-            let max_chars = max_tokens * 4;
-            if content.len() > max_chars {
-                let truncated = &content[..max_chars];
-                // Find last complete section
-                if let Some(pos) = truncated.rfind("\n## ") {
-                    content = format!(
-                        "{}\n\n[Context truncated due to token limit]\n",
-                        &content[..pos]
-                    );
+        Ok(content)
+    }
+
+    /// Renders this session's prior tasks for the conversation mode
+    ///
+    /// The stable form leaves out the task count so earlier lines never change.
+    fn session_history(&self, stable: bool) -> String {
+        let mut content = String::new();
+        if self.task_history.is_empty() {
+            return content;
+        }
+        let next = self.task_history.len() + 1;
+        match self.conversation_mode {
+            ConversationMode::Fresh => {
+                // No session history included
+            }
+            ConversationMode::Summary => {
+                content.push_str("## Session Context\n\n");
+                if stable {
+                    content.push_str("Prior tasks in this session:\n");
+                } else {
+                    content.push_str(&format!(
+                        "This is task {} of an ongoing session. Prior tasks:\n",
+                        next
+                    ));
+                }
+                for task in &self.task_history {
+                    content.push_str(&format!(
+                        "{}. {} — {}\n",
+                        task.number, task.prompt, task.summary
+                    ));
+                    for artifact in &task.artifacts {
+                        content.push_str(&format!("   {}\n", artifact));
+                    }
+                }
+                content.push('\n');
+            }
+            ConversationMode::Full => {
+                content.push_str("## Full Conversation History\n\n");
+                if stable {
+                    content.push_str("Full prior conversation in this session:\n\n");
+                } else {
+                    content.push_str(&format!(
+                        "This is task {} of an ongoing session. Full prior conversation:\n\n",
+                        next
+                    ));
+                }
+                for task in &self.task_history {
+                    content.push_str(&full_history_entry(task));
                 }
             }
         }
+        content
+    }
 
-        let final_tokens = content.len() / 4;
-
-        std::fs::write(&context_path, &content)
-            .with_context(|| format!("Failed to write context file: {:?}", context_path))?;
-
-        Ok(final_tokens)
+    /// Restores the most recent session's tasks from their logs, so
+    /// conversation continuity survives a restart
+    fn resume_history(&mut self) -> Result<()> {
+        let logs = tasklog::last_session_logs(&self.project)?;
+        if logs.is_empty() {
+            println!("{}", i18n::t("resume.none"));
+            return Ok(());
+        }
+        // New tasks join the restored session, even if other sessions
+        // started since
+        if let Some(session) = logs.last().and_then(|log| log.session) {
+            self.session_number = session;
+        }
+        let config = load_config()?;
+        let processors = processors::registered(&config.processors, &self.task_run_dir(None));
+        self.task_history = logs
+            .into_iter()
+            .map(|log| TaskRecord {
+                number: log.task_number,
+                prompt: truncate_string(&log.prompt, 60),
+                // Logs from before summarizers were recorded get the heuristic
+                // summary rather than a fresh API call per task
+                summary: log.history_summary.unwrap_or_else(|| {
+                    summarizer::task_summary(
+                        &summarizer::Heuristic,
+                        &log.prompt,
+                        &log.transcript,
+                        log.interrupted,
+                    )
+                }),
+                artifacts: processors::stored_context(&processors, &log.artifacts),
+                transcript: log.transcript,
+            })
+            .collect();
+        let first = self.task_history.first().map_or(0, |t| t.number);
+        let last = self.task_history.last().map_or(0, |t| t.number);
+        if first == last {
+            println!("{}", i18n::tf("resume.one", &[("num", &first)]));
+        } else {
+            println!(
+                "{}",
+                i18n::tf(
+                    "resume.many",
+                    &[
+                        ("count", &self.task_history.len()),
+                        ("first", &first),
+                        ("last", &last)
+                    ]
+                )
+            );
+        }
+        Ok(())
     }
 
     /// Runs a task via claude -p
-    fn run_task(&mut self, prompt: &str) -> Result<()> {
-        // Compile context before task
+    pub(crate) fn run_task(&mut self, prompt: &str) -> Result<TaskOutcome> {
+        let (directives, prompt) = parse_directives(prompt);
+        let skip_extraction = directives.no_extract;
+        let task_dir = self.resolve_task_dir(directives.dir.as_deref())?;
+        // Logs keep the prompt as typed; claude gets the referenced files inlined
+        let expanded = expand_file_refs(
+            prompt,
+            &self.working_dir,
+            load_config()?.context.attach_max_bytes,
+        )?;
+        // Compile context before task, in a cheaper mode if over budget
+        self.auto_downgrade_mode()?;
         let token_count = self.compile_context()?;
-
         let task_num = self.project.next_task_number()?;
+
+        let config = load_config()?;
+        let route = routing::route_task(
+            &config.routing.rules,
+            prompt,
+            directives.model.as_deref(),
+            directives.agent.as_deref(),
+        );
+        let track_dependencies = config.policy.track_dependencies;
+        let mut pricing_config = config.pricing;
+        if let Some(model) = route.as_ref().and_then(|r| r.model.clone()) {
+            pricing_config.task_model = model;
+        }
+        let estimate =
+            pricing::estimate_input_cost(&pricing_config, token_count + expanded.len() / 4);
+        let over_threshold = estimate
+            .zip(pricing_config.confirm_above_usd)
+            .filter(|(cost, threshold)| cost > threshold);
+        if let (true, Some((cost, threshold))) = (self.interactive, over_threshold) {
+            print!(
+                "{}",
+                i18n::tf(
+                    "task.cost_confirm",
+                    &[
+                        ("num", &task_num),
+                        ("cost", &format!("{:.4}", cost)),
+                        ("threshold", &format!("{:.2}", threshold))
+                    ]
+                )
+            );
+            std::io::stdout().flush()?;
+            let mut answer = String::new();
+            std::io::stdin().read_line(&mut answer)?;
+            if !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+                println!("{}", i18n::t("task.skipped"));
+                return Ok(TaskOutcome::Skipped);
+            }
+        }
+        // Attachments apply to one task only
+        self.attachments.clear();
+        // Questions run read-only, whatever /mode is set to
+        let permission_mode = if directives.ask {
+            Some("plan")
+        } else {
+            self.permission_mode.as_deref()
+        };
+
+        let estimate_str = estimate
+            .map(|cost| i18n::tf("task.estimate", &[("cost", &format!("{:.4}", cost))]))
+            .unwrap_or_default();
+        println!(
+            "{}",
+            i18n::tf(
+                "task.injecting",
+                &[
+                    ("num", &task_num),
+                    ("tokens", &token_count),
+                    ("estimate", &estimate_str)
+                ]
+            )
+        );
+
+        if let Some(ref route) = route {
+            println!(
+                "{}",
+                i18n::tf(
+                    "task.routed",
+                    &[("target", &route_target(route)), ("reason", &route.reason)]
+                )
+            );
+        }
+
+        // Compared after the task to record dependency changes
+        let deps_before =
+            track_dependencies.then(|| deps::snapshot(&self.task_run_dir(task_dir.as_deref())));
+
+        // Output is also streamed to disk so other terminals can follow the
+        // run and a crash mid-task can be recovered
+        let inflight = self.start_stream_file(task_num, prompt, task_dir.as_deref())?;
+        let captured_output = self
+            .run_claude(
+                &expanded,
+                permission_mode,
+                route.as_ref(),
+                pricing_config
+                    .max_task_usd
+                    .map(|max| (max, &pricing_config)),
+                task_dir.as_deref(),
+                Some(&inflight),
+            )
+            .inspect_err(|_| {
+                TASK_INTERRUPTED.store(false, Ordering::SeqCst);
+                COST_LIMIT_HIT.store(false, Ordering::SeqCst);
+                let _ = std::fs::remove_file(&inflight);
+            })?;
+        let cost_limited = COST_LIMIT_HIT.swap(false, Ordering::SeqCst);
+        let interrupted = TASK_INTERRUPTED.swap(false, Ordering::SeqCst) || cost_limited;
+
+        // Parse the captured output into a structured transcript
+        let transcript = Transcript::parse(&captured_output);
+        let artifacts = processors::run_all(
+            &processors::registered(&config.processors, &self.task_run_dir(task_dir.as_deref())),
+            &transcript,
+        );
+
+        let summary =
+            summarizer::task_summary(self.summarizer.as_ref(), prompt, &transcript, interrupted);
+
+        // Record task with its transcript for full mode
+        self.task_history.push(TaskRecord {
+            number: task_num,
+            prompt: truncate_string(prompt, 60),
+            summary: summary.clone(),
+            transcript: transcript.clone(),
+            artifacts: artifacts.context.clone(),
+        });
+
+        // A task stopped at the cost ceiling never reports its final cost,
+        // so the running estimate stands in
+        let stopped_cost = cost_limited.then(|| {
+            let mut meter = pricing::CostMeter::new(&pricing_config);
+            for line in captured_output.lines() {
+                if let Ok(event) = serde_json::from_str(line) {
+                    meter.observe(&event);
+                }
+            }
+            meter.total()
+        });
+
+        // Update project stats
+        self.project
+            .record_task(transcript.total_cost().or(stopped_cost), transcript.usage())?;
+
+        // Fields beyond the transcript, recorded in the same write as the log
+        let mut fields = working_dir_field(task_dir.as_deref());
+        fields.insert("history_summary".to_string(), summary.into());
+        if interrupted {
+            fields.insert("interrupted".to_string(), true.into());
+            fields.insert("success".to_string(), false.into());
+        }
+        if let (Some(estimate), Some(limit)) = (stopped_cost, pricing_config.max_task_usd) {
+            fields.insert("cost_limit_usd".to_string(), serde_json::json!(limit));
+            fields.insert(
+                "estimated_cost_usd".to_string(),
+                serde_json::json!(estimate),
+            );
+        }
+        if let Some(plan_cost) = self.pending_plan_cost.take() {
+            fields.insert("plan_cost_usd".to_string(), serde_json::json!(plan_cost));
+        }
+        if directives.ask {
+            fields.insert("ask".to_string(), true.into());
+            fields.insert("permission_mode".to_string(), "plan".into());
+        }
+        if let Some(ref route) = route {
+            fields.insert("route".to_string(), serde_json::to_value(route)?);
+        }
+        // Discouraged tool calls are flagged, not blocked
+        let config = load_config().ok();
+        let violations = config
+            .as_ref()
+            .map(|c| policy::check_transcript(&c.policy.discouraged, &transcript))
+            .unwrap_or_default();
+        if !violations.is_empty() {
+            fields.insert(
+                "policy_violations".to_string(),
+                serde_json::to_value(&violations)?,
+            );
+        }
+        let dependency_changes = deps_before
+            .map(|before| {
+                deps::diff(
+                    &before,
+                    &deps::snapshot(&self.task_run_dir(task_dir.as_deref())),
+                )
+            })
+            .unwrap_or_default();
+        let unexpected_dependencies =
+            !dependency_changes.is_empty() && !deps::expected(prompt, &dependency_changes);
+        if !dependency_changes.is_empty() {
+            fields.insert(
+                "dependency_changes".to_string(),
+                serde_json::to_value(&dependency_changes)?,
+            );
+            if unexpected_dependencies {
+                fields.insert("unexpected_dependency_changes".to_string(), true.into());
+            }
+        }
+
+        // Save task log with parsed transcript
+        let log_path = self.save_task_log(
+            task_num,
+            prompt,
+            &captured_output,
+            &transcript,
+            &artifacts,
+            fields,
+        )?;
+        let _ = std::fs::remove_file(&inflight);
+
+        // Print task completion summary
+        let cache_str = transcript
+            .usage()
+            .and_then(|usage| pricing::cache_savings(&pricing_config, transcript.model(), usage))
+            .filter(|saved| *saved > 0.0)
+            .map(|saved| i18n::tf("task.cache_saved", &[("saved", &format!("{:.4}", saved))]))
+            .unwrap_or_default();
+        let cost_str = match (transcript.total_cost(), stopped_cost) {
+            (Some(c), _) => i18n::tf(
+                "task.cost",
+                &[("cost", &format!("{:.4}", c)), ("cache", &cache_str)],
+            ),
+            (None, Some(c)) => i18n::tf("task.cost_limited", &[("cost", &format!("{:.4}", c))]),
+            (None, None) => String::new(),
+        };
+        let duration_str = transcript
+            .duration_ms()
+            .map(|d| {
+                i18n::tf(
+                    "task.duration",
+                    &[("secs", &format!("{:.1}", d as f64 / 1000.0))],
+                )
+            })
+            .unwrap_or_default();
+        let outcome = i18n::t(if interrupted {
+            "task.interrupted"
+        } else {
+            "task.complete"
+        });
         println!(
-            "\n[Task {}] Injecting context (~{} tokens)...\n",
-            task_num, token_count
+            "{}",
+            i18n::tf(
+                "task.done",
+                &[
+                    ("num", &task_num),
+                    ("outcome", &outcome),
+                    ("duration", &duration_str),
+                    ("cost", &cost_str)
+                ]
+            )
         );
+        print_violations(&violations);
+        print_dependency_changes(&dependency_changes, unexpected_dependencies);
+
+        // Partial runs are only worth extracting from when configured
+        let extract_interrupted = config
+            .as_ref()
+            .is_some_and(|c| c.extraction.extract_interrupted);
+        if skip_extraction || !self.extraction_enabled || (interrupted && !extract_interrupted) {
+            println!("{}", i18n::t("task.extraction_skipped"));
+        } else {
+            self.run_extraction(&transcript, prompt, &log_path, directives.ask);
+        }
+
+        // The reviewer adds plan items, which a question shouldn't
+        if !interrupted && !directives.ask && config.is_some_and(|c| c.review.enabled) {
+            self.run_review(&transcript, prompt, &log_path);
+        }
+
+        println!();
+        Ok(if interrupted {
+            TaskOutcome::Interrupted
+        } else {
+            TaskOutcome::Completed
+        })
+    }
 
+    /// Runs `claude -p`, streaming its text to the terminal, and returns the
+    /// raw stream-json output
+    ///
+    /// `task_dir` is a subdirectory of the working dir to run in. Each output
+    /// line is also appended to `inflight` when given. With a `cost_limit`,
+    /// the child is killed once the running cost estimate crosses it.
+    fn run_claude(
+        &self,
+        prompt: &str,
+        permission_mode: Option<&str>,
+        route: Option<&Route>,
+        cost_limit: Option<(f64, &PricingConfig)>,
+        task_dir: Option<&Path>,
+        inflight: Option<&Path>,
+    ) -> Result<String> {
         // Build the command
-        let mut cmd = Command::new("claude");
-        cmd.arg("-p")
-            .arg(prompt)
-            .arg("--output-format")
-            .arg("stream-json")
-            .arg("--verbose")
-            .current_dir(&self.working_dir)
+        let mut claude_args: Vec<String> = vec![
+            "-p".to_string(),
+            prompt.to_string(),
+            "--output-format".to_string(),
+            "stream-json".to_string(),
+            "--verbose".to_string(),
+        ];
+        if let Some(mode) = permission_mode {
+            claude_args.extend(["--permission-mode".to_string(), mode.to_string()]);
+        }
+        if let Some(model) = route.and_then(|r| r.model.as_ref()) {
+            claude_args.extend(["--model".to_string(), model.clone()]);
+        }
+        if let Some(agent) = route.and_then(|r| r.agent.as_ref()) {
+            claude_args.extend(["--agent".to_string(), agent.clone()]);
+        }
+        // Standing instructions (coding standards, tone) go in the system
+        // prompt instead of mixing with the notes in context.md
+        if let Some(instructions) = self.project.instructions()? {
+            claude_args.extend(["--append-system-prompt".to_string(), instructions]);
+        }
+        // Paths from project.toml are relative to the project root, not the task dir
+        let cli_options = &self.project.metadata.claude_cli;
+        let mut mounts = Vec::new();
+        for dir in &cli_options.add_dirs {
+            let dir = self.working_dir.join(dir);
+            claude_args.extend(["--add-dir".to_string(), dir.display().to_string()]);
+            mounts.push(dir);
+        }
+        if let Some(mcp_config) = &cli_options.mcp_config {
+            let path = self.working_dir.join(mcp_config);
+            if !path.is_file() {
+                bail!(
+                    "{}",
+                    i18n::tf("error.mcp_missing", &[("path", &path.display())])
+                );
+            }
+            claude_args.extend(["--mcp-config".to_string(), path.display().to_string()]);
+            mounts.push(path);
+        }
+
+        // Container runtime and name, so a cost kill can stop the container
+        // itself; killing the `run` client would leave it running
+        let mut container = None;
+        let mut cmd = if self.sandboxed {
+            let config = load_config()?;
+            if config.sandbox.image.is_empty() {
+                bail!("{}", i18n::t("sandbox.no_image"));
+            }
+            println!(
+                "{}",
+                i18n::tf(
+                    "sandbox.running",
+                    &[
+                        ("runtime", &config.sandbox.runtime),
+                        ("image", &config.sandbox.image)
+                    ]
+                )
+            );
+            let name = format!("clancy-{}-{}", std::process::id(), unique_suffix());
+            let mut cmd = Command::new(&config.sandbox.runtime);
+            cmd.args(container_args(
+                &config.sandbox,
+                &name,
+                &self.working_dir,
+                task_dir,
+                &mounts,
+                &claude_args,
+            ));
+            container = Some((config.sandbox.runtime, name));
+            cmd
+        } else {
+            let mut cmd = Command::new("claude");
+            cmd.args(&claude_args);
+            cmd
+        };
+        cmd.current_dir(self.task_run_dir(task_dir))
             .stdout(Stdio::piped())
             .stderr(Stdio::inherit());
 
-        let mut child = cmd
-            .spawn()
-            .context("Failed to start claude. Is it installed and in PATH?")?;
+        let mut child = cmd.spawn().with_context(|| {
+            format!(
+                "Failed to start {}. Is it installed and in PATH?",
+                cmd.get_program().to_string_lossy()
+            )
+        })?;
+        // Ctrl+C also reaches the child (same process group), which exits and
+        // closes its stdout, so the loop below ends with what streamed so far
+        let _running = TaskRunning::start();
 
         // Stream output while capturing for later
         let stdout = child.stdout.take().expect("Failed to capture stdout");
         let reader = BufReader::new(stdout);
         let mut captured_output = String::new();
-
-        for line in reader.lines() {
-            let line = line?;
+        let mut lossy_lines = 0;
+        let mut inflight_file = match inflight {
+            Some(path) => Some(
+                OpenOptions::new()
+                    .append(true)
+                    .open(path)
+                    .with_context(|| format!("Failed to open {}", path.display()))?,
+            ),
+            None => None,
+        };
+        let mut meter =
+            cost_limit.map(|(limit, pricing)| (limit, pricing::CostMeter::new(pricing)));
+
+        // Read raw bytes so invalid UTF-8 (e.g. binary tool output) can't abort the task
+        for line in reader.split(b'\n') {
+            let (line, lossy) = decode_output_line(&line?);
+            if lossy {
+                lossy_lines += 1;
+            }
             captured_output.push_str(&line);
             captured_output.push('\n');
+            if let Some(ref mut file) = inflight_file {
+                writeln!(file, "{}", line)?;
+            }
 
             // Parse stream-json format and display relevant content
             if let Ok(json) = serde_json::from_str::<serde_json::Value>(&line) {
+                if let Some((limit, ref mut meter)) = meter {
+                    let spent = meter.observe(&json);
+                    if spent > limit {
+                        println!(
+                            "{}",
+                            i18n::tf(
+                                "task.cost_limit_hit",
+                                &[
+                                    ("spent", &format!("{:.4}", spent)),
+                                    ("limit", &format!("{:.2}", limit))
+                                ]
+                            )
+                        );
+                        COST_LIMIT_HIT.store(true, Ordering::SeqCst);
+                        if let Some((runtime, name)) = &container {
+                            let _ = Command::new(runtime)
+                                .args(["kill", name])
+                                .stdout(Stdio::null())
+                                .stderr(Stdio::null())
+                                .status();
+                        }
+                        let _ = child.kill();
+                        break;
+                    }
+                }
                 // Handle different message types
                 if let Some(msg_type) = json.get("type").and_then(|t| t.as_str()) {
                     match msg_type {
@@ -279,113 +1272,380 @@ impl Session {
             }
         }
 
-        let status = child.wait()?;
-        println!();
+        let status = child.wait()?;
+        println!();
+
+        if lossy_lines > 0 {
+            println!("{}", i18n::tf("task.lossy", &[("lines", &lossy_lines)]));
+        }
+
+        if TASK_INTERRUPTED.load(Ordering::SeqCst) || COST_LIMIT_HIT.load(Ordering::SeqCst) {
+            println!("{}", i18n::t("task.cancelled"));
+        } else if !status.success() {
+            println!(
+                "{}",
+                i18n::tf(
+                    "task.exit_code",
+                    &[("code", &format!("{:?}", status.code()))]
+                )
+            );
+        }
+
+        Ok(captured_output)
+    }
 
-        if !status.success() {
-            println!("[Task failed with exit code: {:?}]", status.code());
+    /// Absolute directory for a task run in `task_dir`
+    fn task_run_dir(&self, task_dir: Option<&Path>) -> PathBuf {
+        match task_dir {
+            Some(dir) => self.working_dir.join(dir),
+            None => self.working_dir.clone(),
         }
+    }
 
-        // Parse the captured output into a structured transcript
-        let transcript = Transcript::parse(&captured_output);
+    /// Picks the directory a task runs in: an `@dir=` override, else the
+    /// `/cd` directory. Returns a path relative to the working dir.
+    fn resolve_task_dir(&self, dir: Option<&str>) -> Result<Option<PathBuf>> {
+        let Some(dir) = dir else {
+            return Ok(self.task_dir.clone());
+        };
+        subdirectory(&self.working_dir, dir).map(Some)
+    }
 
-        // Generate summary from transcript (better than just truncating prompt)
-        let summary = if transcript.succeeded() {
-            let auto_summary = transcript.generate_summary();
-            // Prefer transcript summary if meaningful, fall back to prompt
-            if auto_summary.len() > 20 && auto_summary != "(no summary available)" {
-                truncate_string(&auto_summary, 80)
-            } else {
-                self.generate_basic_summary(prompt)
-            }
-        } else {
-            format!("(failed) {}", truncate_string(prompt, 70))
+    /// Plans a task in read-only plan mode, asks for approval, then runs
+    /// the approved plan as the real task
+    fn run_plan_task(&mut self, prompt: &str) -> Result<TaskOutcome> {
+        let (directives, task) = parse_directives(prompt);
+        if task.trim().is_empty() {
+            bail!("{}", i18n::t("usage.plan_task"));
+        }
+        // Directives are passed on to the real task unchanged
+        let prefix = &prompt[..prompt.len() - task.len()];
+        let task_dir = self.resolve_task_dir(directives.dir.as_deref())?;
+        let expanded = expand_file_refs(
+            task,
+            &self.working_dir,
+            load_config()?.context.attach_max_bytes,
+        )?;
+
+        let token_count = self.compile_context()?;
+        println!(
+            "{}",
+            i18n::tf("plan.injecting", &[("tokens", &token_count)])
+        );
+        let output = self.run_claude(
+            &expanded,
+            Some("plan"),
+            None,
+            None,
+            task_dir.as_deref(),
+            None,
+        )?;
+        let transcript = Transcript::parse(&output);
+        let Some(plan) = transcript
+            .result
+            .as_ref()
+            .and_then(|r| r.result_text.clone())
+            .or_else(|| last_assistant_paragraph(&transcript))
+        else {
+            bail!("{}", i18n::t("plan.empty"));
         };
+        let plan_cost = transcript.total_cost();
+        if let Some(cost) = plan_cost {
+            println!(
+                "{}",
+                i18n::tf("plan.ready", &[("cost", &format!("{:.4}", cost))])
+            );
+            // The plan is paid for whether or not it's approved
+            self.planning_cost += cost;
+            self.project.record_cost(cost)?;
+        }
 
-        // Record task with full output for /continue mode
-        self.task_history.push(TaskRecord {
-            number: task_num,
-            prompt: truncate_string(prompt, 60),
-            summary,
-            raw_output: captured_output.clone(),
-        });
+        print!("{}", i18n::t("plan.confirm"));
+        std::io::stdout().flush()?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+            println!("{}", i18n::t("plan.discarded"));
+            return Ok(TaskOutcome::Skipped);
+        }
 
-        // Update project stats
-        self.project.record_task()?;
+        self.pending_plan_cost = plan_cost;
+        let outcome = self.run_task(&format!(
+            "{}{}\n\nFollow this approved plan:\n\n{}",
+            prefix, task, plan
+        ));
+        self.pending_plan_cost = None;
+        outcome
+    }
 
-        // Save task log with parsed transcript
-        self.save_task_log(task_num, prompt, &captured_output, &transcript)?;
+    /// Runs the reviewer pass and applies what it flags
+    fn run_review(&self, transcript: &Transcript, prompt: &str, log_path: &Path) {
+        print!("{}", i18n::t("review.running"));
+        std::io::stdout().flush().ok();
 
-        // Print task completion summary
-        let cost_str = transcript
-            .total_cost()
-            .map(|c| format!(" (${:.4})", c))
-            .unwrap_or_default();
-        let duration_str = transcript
-            .duration_ms()
-            .map(|d| format!(" in {:.1}s", d as f64 / 1000.0))
-            .unwrap_or_default();
-        println!("[Task {} complete{}{}]", task_num, duration_str, cost_str);
+        let rt = match tokio::runtime::Runtime::new() {
+            Ok(rt) => rt,
+            Err(e) => {
+                println!("{}", i18n::tf("review.runtime_error", &[("error", &e)]));
+                return;
+            }
+        };
 
-        // Run note extraction
-        self.run_extraction(&transcript, prompt);
+        let review = match rt.block_on(review::review_task(&self.project, transcript, prompt)) {
+            Ok(review) => review,
+            Err(e) => {
+                // Don't fail the task if the review fails
+                println!("{}", i18n::tf("review.error", &[("error", &e)]));
+                return;
+            }
+        };
 
-        println!();
-        Ok(())
-    }
+        println!(
+            "{}",
+            i18n::tf("review.verdict", &[("verdict", &review.verdict)])
+        );
+        for (label, items) in [("failures", &review.failures), ("plan", &review.plan)] {
+            if let Some(items) = items {
+                println!(
+                    "{}",
+                    i18n::tf("review.added", &[("label", &label), ("items", &items)])
+                );
+            }
+        }
+        if let Err(e) = review::apply_review(&self.project, &review) {
+            println!("{}", i18n::tf("review.apply_failed", &[("error", &e)]));
+        }
 
-    /// Generates a basic summary (placeholder for Phase 3 extraction)
-    fn generate_basic_summary(&self, prompt: &str) -> String {
-        // For Phase 1, just use a truncated version of the prompt
-        // Phase 3 will use Claude API for proper extraction
-        truncate_string(prompt, 80)
+        let recorded = serde_json::to_value(&review)
+            .map_err(anyhow::Error::from)
+            .and_then(|value| {
+                tasklog::update_task_log(log_path, |log| {
+                    log.insert("review".to_string(), value);
+                })
+            });
+        if let Err(e) = recorded {
+            println!("{}", i18n::tf("review.record_failed", &[("error", &e)]));
+        }
     }
 
     /// Saves the task log to disk with parsed transcript
+    ///
+    /// `fields` are added last, so they can override the defaults (such as
+    /// `success` for an interrupted task) and fill in `working_dir`.
     fn save_task_log(
         &self,
         task_num: u32,
         prompt: &str,
         output: &str,
         transcript: &Transcript,
-    ) -> Result<()> {
+        artifacts: &processors::Artifacts,
+        fields: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<PathBuf> {
         let tasks_dir = self.project.tasks_path();
         std::fs::create_dir_all(&tasks_dir)?;
 
-        // Create a sanitized filename from the prompt
+        // Create a sanitized filename from the prompt; the suffix keeps logs
+        // apart even if two ever share a number
         let slug = create_slug(prompt);
-        let filename = format!("{:03}-{}.json", task_num, slug);
+        let filename = format!("{:03}-{}-{}.json", task_num, slug, unique_suffix());
         let path = tasks_dir.join(filename);
 
-        let log = serde_json::json!({
+        let mut log = serde_json::json!({
             "task_number": task_num,
             "prompt": prompt,
             "timestamp": chrono::Utc::now().to_rfc3339(),
             "success": transcript.succeeded(),
             "duration_ms": transcript.duration_ms(),
             "cost_usd": transcript.total_cost(),
-            "tools_used": transcript.tools_used(),
-            "summary": transcript.generate_summary(),
+            "permission_mode": self.permission_mode.as_deref().unwrap_or("default"),
+            "working_dir": null,
+            "session": self.session_number,
             "transcript": transcript,
             "raw_output": output,
         });
+        if let Some(log) = log.as_object_mut() {
+            // Artifacts live under their own key so a processor's name can't
+            // clobber core fields; summary and tools_used also keep their
+            // original top-level keys for older readers
+            let values: serde_json::Map<String, serde_json::Value> =
+                artifacts.values.iter().cloned().collect();
+            for key in ["summary", "tools_used"] {
+                if let Some(value) = values.get(key) {
+                    log.insert(key.to_string(), value.clone());
+                }
+            }
+            log.insert("artifacts".to_string(), values.into());
+            log.extend(fields);
+        }
 
         let content = serde_json::to_string_pretty(&log)?;
         std::fs::write(&path, content)?;
 
+        Ok(path)
+    }
+
+    /// Records a session run outside Clancy as a task, optionally extracting
+    /// notes from it, and returns its task number
+    pub(crate) fn import_task(
+        &mut self,
+        prompt: &str,
+        output: &str,
+        started: DateTime<Utc>,
+        import_id: &str,
+        extract: bool,
+    ) -> Result<u32> {
+        let task_num = self.project.next_task_number()?;
+        let transcript = Transcript::parse(output);
+        let processors = processors::registered(&load_config()?.processors, &self.working_dir);
+        let artifacts = processors::run_all(&processors, &transcript);
+        let log_path = self.save_task_log(
+            task_num,
+            prompt,
+            output,
+            &transcript,
+            &artifacts,
+            Default::default(),
+        )?;
+        tasklog::update_task_log(&log_path, |log| {
+            log.insert("timestamp".to_string(), started.to_rfc3339().into());
+            log.insert("import_id".to_string(), import_id.into());
+            // Imported work has no result line; count it as done
+            log.insert("success".to_string(), true.into());
+            // Imported work belongs to no clancy session
+            log.remove("permission_mode");
+            log.remove("session");
+        })?;
+        self.project
+            .record_task(transcript.total_cost(), transcript.usage())?;
+        if extract {
+            self.run_extraction(&transcript, prompt, &log_path, false);
+        }
+        Ok(task_num)
+    }
+
+    /// Creates the raw stream file for a task, starting with a header line
+    /// that identifies the task and the process running it
+    fn start_stream_file(
+        &self,
+        task_num: u32,
+        prompt: &str,
+        task_dir: Option<&Path>,
+    ) -> Result<PathBuf> {
+        let tasks_dir = self.project.tasks_path();
+        std::fs::create_dir_all(&tasks_dir)?;
+        let path = tasks_dir.join(format!("{:03}{}", task_num, STREAM_SUFFIX));
+        let header = serde_json::json!({
+            "type": "clancy_task",
+            "task_number": task_num,
+            "prompt": prompt,
+            "pid": std::process::id(),
+            "started": chrono::Utc::now().to_rfc3339(),
+            "permission_mode": self.permission_mode.as_deref().unwrap_or("default"),
+            "working_dir": task_dir.map(|d| d.display().to_string()),
+            "session": self.session_number,
+        });
+        std::fs::write(&path, format!("{}\n", header))
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// Turns stream files left by crashed runs into task logs marked
+    /// `interrupted`
+    ///
+    /// Files whose process is still running belong to another live session
+    /// and are left alone.
+    pub(crate) fn recover_interrupted_tasks(&mut self) -> Result<()> {
+        let tasks_dir = self.project.tasks_path();
+        if !tasks_dir.exists() {
+            return Ok(());
+        }
+        let mut orphans: Vec<PathBuf> = std::fs::read_dir(&tasks_dir)?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| {
+                p.file_name()
+                    .is_some_and(|n| n.to_string_lossy().ends_with(STREAM_SUFFIX))
+            })
+            .collect();
+        orphans.sort();
+
+        for path in orphans {
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let header: serde_json::Value = content
+                .lines()
+                .next()
+                .and_then(|line| serde_json::from_str(line).ok())
+                .unwrap_or_default();
+            if header["pid"]
+                .as_u64()
+                .is_some_and(|pid| pid != u64::from(std::process::id()) && process_alive(pid))
+            {
+                continue;
+            }
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+            let task_num = header["task_number"]
+                .as_u64()
+                .map(|n| n as u32)
+                .or_else(|| file_name.split(['-', '.']).next()?.parse().ok())
+                .unwrap_or(0);
+            let prompt = header["prompt"].as_str().unwrap_or("(unknown prompt)");
+            let task_dir = header["working_dir"].as_str().map(PathBuf::from);
+
+            let transcript = Transcript::parse(&content);
+            let artifacts = processors::run_all(
+                &processors::registered(
+                    &load_config()?.processors,
+                    &self.task_run_dir(task_dir.as_deref()),
+                ),
+                &transcript,
+            );
+            let mut fields = working_dir_field(task_dir.as_deref());
+            fields.insert("interrupted".to_string(), true.into());
+            fields.insert("success".to_string(), false.into());
+            if let Some(started) = header.get("started") {
+                fields.insert("timestamp".to_string(), started.clone());
+            }
+            if let Some(mode) = header.get("permission_mode") {
+                fields.insert("permission_mode".to_string(), mode.clone());
+            }
+            // Recovery happens in a later session than the task ran in
+            if let Some(session) = header.get("session") {
+                fields.insert("session".to_string(), session.clone());
+            }
+            self.save_task_log(task_num, prompt, &content, &transcript, &artifacts, fields)?;
+            self.project
+                .record_task(transcript.total_cost(), transcript.usage())?;
+            std::fs::remove_file(&path)?;
+            println!(
+                "{}",
+                i18n::tf(
+                    "task.recovered",
+                    &[("num", &task_num), ("messages", &transcript.messages.len())]
+                )
+            );
+        }
         Ok(())
     }
 
     /// Runs note extraction on the transcript
-    fn run_extraction(&self, transcript: &Transcript, prompt: &str) {
-        print!("Extracting notes...");
+    ///
+    /// A `question` (from `/ask`) never updates the plan.
+    fn run_extraction(
+        &self,
+        transcript: &Transcript,
+        prompt: &str,
+        log_path: &Path,
+        question: bool,
+    ) {
+        print!("{}", i18n::t("extract.running"));
         std::io::stdout().flush().ok();
 
         // Create a tokio runtime for the async extraction
         let rt = match tokio::runtime::Runtime::new() {
             Ok(rt) => rt,
             Err(e) => {
-                println!(" error creating runtime: {}", e);
+                println!("{}", i18n::tf("extract.runtime_error", &[("error", &e)]));
                 return;
             }
         };
@@ -394,21 +1654,55 @@ impl Session {
         let result = rt.block_on(extract_notes(&self.project, transcript, prompt));
 
         match result {
-            Ok(extraction) => {
+            Ok(mut extraction) => {
+                if question {
+                    extraction.retain_categories(&["architecture", "decisions", "failures"]);
+                }
+                let config = load_config().ok();
+                let primary = config.as_ref().map(|c| c.claude.model.as_str());
+                match extraction.model.as_deref() {
+                    Some(HEURISTIC_MODEL) => print!("{}", i18n::t("extract.offline")),
+                    Some(model) if Some(model) != primary => {
+                        print!("{}", i18n::tf("extract.fell_back", &[("model", &model)]))
+                    }
+                    _ => {}
+                }
+                let confirm = config
+                    .as_ref()
+                    .is_some_and(|c| c.extraction.confirm_categories);
+                if confirm && extraction.has_updates() {
+                    let available = extraction.categories();
+                    println!(
+                        "{}",
+                        i18n::tf("extract.found", &[("summary", &extraction.summary())])
+                    );
+                    let keep = prompt_category_selection(&available);
+                    extraction.retain_categories(&keep);
+                    print!("{}", i18n::t("extract.applying"));
+                }
+
                 if extraction.has_updates() {
                     // Apply the extracted notes
                     if let Err(e) = apply_extraction(&self.project, &extraction) {
-                        println!(" error applying notes: {}", e);
+                        println!("{}", i18n::tf("extract.apply_error", &[("error", &e)]));
                     } else {
-                        println!(" updated: {}", extraction.summary());
+                        println!(
+                            "{}",
+                            i18n::tf("extract.updated", &[("summary", &extraction.summary())])
+                        );
                     }
                 } else {
-                    println!(" no updates");
+                    println!("{}", i18n::t("extract.no_updates"));
+                }
+
+                // Keep what this task contributed alongside its log
+                if let Err(e) = record_extraction(log_path, &extraction) {
+                    println!("{}", i18n::tf("extract.record_failed", &[("error", &e)]));
                 }
             }
             Err(e) => {
                 // Don't fail the task if extraction fails
-                println!(" error: {}", e);
+                println!("{}", i18n::tf("extract.error", &[("error", &e)]));
             }
         }
     }
@@ -416,11 +1710,14 @@ impl Session {
     /// Compacts the session history into a single summary
     fn run_compact(&mut self) {
         if self.task_history.is_empty() {
-            println!("No tasks to compact.");
+            println!("{}", i18n::t("compact.none"));
             return;
         }
 
-        print!("Compacting {} tasks...", self.task_history.len());
+        print!(
+            "{}",
+            i18n::tf("compact.running", &[("count", &self.task_history.len())])
+        );
         std::io::stdout().flush().ok();
 
         // Create a summary of all tasks
@@ -440,13 +1737,14 @@ impl Session {
             number: 0, // Special marker for compacted history
             prompt: format!("(compacted {} tasks)", task_count),
             summary: combined_summary,
-            raw_output: String::new(),
+            transcript: Transcript::parse(""),
+            artifacts: Vec::new(),
         });
 
         // Switch to summary mode
         self.conversation_mode = ConversationMode::Summary;
 
-        println!(" done. Session history compacted.");
+        println!("{}", i18n::t("compact.done"));
     }
 
     /// Runs phases from a plan file automatically
@@ -456,8 +1754,8 @@ impl Session {
 
         if !path.exists() {
             anyhow::bail!(
-                "Plan file not found: {}\nUsage: /auto [file.md]  (defaults to PLAN.md)",
-                path.display()
+                "{}",
+                i18n::tf("auto.plan_missing", &[("path", &path.display())])
             );
         }
 
@@ -467,59 +1765,414 @@ impl Session {
         let phases = parse_plan_phases(&content);
 
         if phases.is_empty() {
-            anyhow::bail!(
-                "No phases found in {}.\nExpected format:\n\n## Phase 1: Title\nDescription of what to do.\n\n## Phase 2: Title\n...",
-                file_path
-            );
+            anyhow::bail!("{}", i18n::tf("auto.no_phases", &[("file", &file_path)]));
         }
 
-        println!("\nFound {} phases in {}:\n", phases.len(), file_path);
+        println!(
+            "{}",
+            i18n::tf(
+                "auto.found",
+                &[("count", &phases.len()), ("file", &file_path)]
+            )
+        );
         for (i, phase) in phases.iter().enumerate() {
-            println!("  {}. {}", i + 1, phase.title);
+            println!(
+                "{}",
+                i18n::tf(
+                    "auto.phase_item",
+                    &[("num", &(i + 1)), ("title", &phase.title)]
+                )
+            );
         }
-        println!("\nPress Enter to start, or Ctrl+C to cancel...");
+        println!("{}", i18n::t("auto.start_prompt"));
 
         // Wait for user confirmation
         let mut input = String::new();
         std::io::stdin().read_line(&mut input)?;
 
-        for (i, phase) in phases.iter().enumerate() {
+        let mut checkpoint = AutoCheckpoint {
+            plan_file: path,
+            total: phases.len(),
+            completed: 0,
+            phase_costs: Vec::new(),
+            started: Utc::now(),
+        };
+        self.run_phases(&phases, &mut checkpoint)
+    }
+
+    /// Runs the phases not yet completed in `checkpoint`, saving progress
+    /// after each so an interrupted run can be resumed
+    fn run_phases(&mut self, phases: &[Phase], checkpoint: &mut AutoCheckpoint) -> Result<()> {
+        let checkpoint_path = self.project.path.join(AUTO_CHECKPOINT_FILE);
+        checkpoint.save(&checkpoint_path)?;
+
+        for (i, phase) in phases.iter().enumerate().skip(checkpoint.completed) {
             println!("\n{}", "=".repeat(60));
-            println!("Phase {}/{}: {}", i + 1, phases.len(), phase.title);
+            println!(
+                "{}",
+                i18n::tf(
+                    "auto.phase_header",
+                    &[
+                        ("num", &(i + 1)),
+                        ("total", &phases.len()),
+                        ("title", &phase.title)
+                    ]
+                )
+            );
             println!("{}\n", "=".repeat(60));
 
             // Build the task prompt
             let prompt = format!("{}\n\n{}", phase.title, phase.description);
 
-            // Run the task
-            if let Err(e) = self.run_task(&prompt) {
-                println!("\nPhase {} failed: {}", i + 1, e);
-                println!("Stopping auto mode. Use /history to see completed phases.");
-                return Ok(());
+            // Run the task; only a phase that ran to the end counts as done,
+            // so a resumed run picks up a skipped or interrupted one again
+            match self.run_task(&prompt) {
+                Ok(TaskOutcome::Completed) => {}
+                Ok(outcome) => {
+                    let key = if outcome == TaskOutcome::Skipped {
+                        "auto.phase_skipped"
+                    } else {
+                        "auto.phase_interrupted"
+                    };
+                    println!("{}", i18n::tf(key, &[("num", &(i + 1))]));
+                    println!("{}", i18n::t("auto.resume_later"));
+                    return Ok(());
+                }
+                Err(e) => {
+                    println!(
+                        "{}",
+                        i18n::tf("auto.phase_failed", &[("num", &(i + 1)), ("error", &e)])
+                    );
+                    println!("{}", i18n::t("auto.stopping"));
+                    return Ok(());
+                }
             }
+            checkpoint.complete_phase(i, self.last_task_cost());
+            checkpoint.save(&checkpoint_path)?;
 
             // If there are more phases, ask to continue
             if i < phases.len() - 1 {
-                println!(
-                    "\nPhase {} complete. Press Enter for next phase, or 'q' to stop...",
-                    i + 1
-                );
+                println!("{}", i18n::tf("auto.phase_done", &[("num", &(i + 1))]));
                 let mut input = String::new();
                 std::io::stdin().read_line(&mut input)?;
                 if input.trim().eq_ignore_ascii_case("q") {
-                    println!("Stopped. {} of {} phases complete.", i + 1, phases.len());
+                    println!(
+                        "{}",
+                        i18n::tf(
+                            "auto.stopped",
+                            &[("done", &(i + 1)), ("total", &phases.len())]
+                        )
+                    );
                     return Ok(());
                 }
             }
         }
 
+        let _ = std::fs::remove_file(&checkpoint_path);
         println!("\n{}", "=".repeat(60));
-        println!("All {} phases complete!", phases.len());
+        println!("{}", i18n::tf("auto.all_done", &[("count", &phases.len())]));
         println!("{}\n", "=".repeat(60));
 
         Ok(())
     }
 
+    /// Offers to resume an `/auto` run whose checkpoint shows unfinished phases
+    fn offer_auto_resume(&mut self) -> Result<()> {
+        let checkpoint_path = self.project.path.join(AUTO_CHECKPOINT_FILE);
+        let Some(mut checkpoint) = AutoCheckpoint::load(&checkpoint_path) else {
+            return Ok(());
+        };
+        let phases = std::fs::read_to_string(&checkpoint.plan_file)
+            .map(|content| parse_plan_phases(&content))
+            .unwrap_or_default();
+        if phases.len() != checkpoint.total || checkpoint.completed >= phases.len() {
+            println!(
+                "{}",
+                i18n::tf(
+                    "auto.checkpoint_stale",
+                    &[("file", &checkpoint.plan_file.display())]
+                )
+            );
+            std::fs::remove_file(&checkpoint_path)?;
+            return Ok(());
+        }
+
+        let started = checkpoint
+            .started
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M");
+        println!(
+            "{}",
+            i18n::tf(
+                "auto.unfinished",
+                &[
+                    ("started", &started),
+                    ("done", &checkpoint.completed),
+                    ("total", &checkpoint.total),
+                    ("file", &checkpoint.plan_file.display())
+                ]
+            )
+        );
+        println!("{}", i18n::t("auto.remaining"));
+        for (i, phase) in phases.iter().enumerate().skip(checkpoint.completed) {
+            println!(
+                "{}",
+                i18n::tf(
+                    "auto.phase_item",
+                    &[("num", &(i + 1)), ("title", &phase.title)]
+                )
+            );
+        }
+        let stats = &self.project.metadata.stats;
+        let project_average =
+            (stats.total_tasks > 0).then(|| stats.total_cost_usd / f64::from(stats.total_tasks));
+        if let Some(estimate) = checkpoint.remaining_cost(project_average) {
+            println!(
+                "{}",
+                i18n::tf("auto.estimate", &[("cost", &format!("{:.2}", estimate))])
+            );
+        }
+        print!("{}", i18n::t("auto.resume_prompt"));
+        std::io::stdout().flush()?;
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        match input.trim().to_lowercase().as_str() {
+            "" | "y" | "yes" => self.run_phases(&phases, &mut checkpoint),
+            "d" | "discard" => {
+                std::fs::remove_file(&checkpoint_path)?;
+                println!("{}", i18n::t("auto.discarded"));
+                Ok(())
+            }
+            _ => {
+                println!("{}", i18n::t("auto.kept"));
+                Ok(())
+            }
+        }
+    }
+
+    /// Forks the current project into a new child project with copied notes
+    fn run_fork(&mut self, args: &[&str]) -> Result<()> {
+        let switch = args.contains(&"--switch");
+        let name = args
+            .iter()
+            .find(|a| !a.starts_with("--"))
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("Usage: /fork <name> [--switch]"))?;
+
+        let forked = self.project.fork(name)?;
+        println!(
+            "{}",
+            i18n::tf(
+                "fork.done",
+                &[("from", &self.project.metadata.name), ("to", &name)]
+            )
+        );
+
+        if switch {
+            self.project = forked;
+            self.project.record_session_start()?;
+            self.session_number = self.project.metadata.stats.total_sessions;
+            let token_count = self.compile_context()?;
+            println!(
+                "{}",
+                i18n::tf(
+                    "fork.switched",
+                    &[("name", &name), ("tokens", &token_count)]
+                )
+            );
+        } else {
+            println!("{}", i18n::tf("fork.switch_hint", &[("name", &name)]));
+        }
+
+        Ok(())
+    }
+
+    /// Closes out the current project's session and continues in another
+    /// existing project, with its own notes, history, and context
+    fn switch_project(&mut self, name: Option<&str>) -> Result<()> {
+        let Some(name) = name else {
+            bail!("{}", i18n::t("usage.switch"));
+        };
+        if name == self.project.metadata.name {
+            bail!("{}", i18n::tf("switch.same_project", &[("name", &name)]));
+        }
+        let mut project = Project::open(name)?;
+        prepare_project(&project, &self.working_dir, &load_config()?)?;
+
+        println!(
+            "{}",
+            i18n::tf(
+                "session.complete",
+                &[
+                    ("name", &self.project.metadata.name),
+                    ("tasks", &self.task_history.len())
+                ]
+            )
+        );
+        project.record_session_start()?;
+        println!(
+            "{}",
+            i18n::tf(
+                "session.loading",
+                &[
+                    ("name", &project.metadata.name),
+                    ("sessions", &project.metadata.stats.total_sessions),
+                    ("tasks", &project.metadata.stats.total_tasks)
+                ]
+            )
+        );
+        self.session_number = project.metadata.stats.total_sessions;
+        self.project = project;
+        // Session history belongs to the project it was run in
+        self.task_history.clear();
+        self.recover_interrupted_tasks()?;
+        let token_count = self.compile_context()?;
+        println!(
+            "{}",
+            i18n::tf("session.context", &[("tokens", &token_count)])
+        );
+        if self.interactive {
+            if let Err(e) = self.offer_auto_resume() {
+                println!("{}", i18n::tf("error.auto", &[("error", &e)]));
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of the most recent task run this session
+    fn last_task_number(&self) -> Result<u32> {
+        self.task_history
+            .iter()
+            .rev()
+            .find(|t| t.number != 0)
+            .map(|t| t.number)
+            .ok_or_else(|| anyhow::anyhow!("No tasks this session; pass a task number"))
+    }
+
+    /// Attaches a note to a task log: `/annotate [task] <text>`, defaulting
+    /// to the last task of this session
+    fn annotate(&self, args: &[&str]) -> Result<()> {
+        let (number, text) = match args.split_first() {
+            Some((first, rest)) if !rest.is_empty() && first.parse::<u32>().is_ok() => {
+                (first.parse::<u32>()?, rest.join(" "))
+            }
+            _ => (self.last_task_number()?, args.join(" ")),
+        };
+
+        let path = tasklog::find_task_log(&self.project, number)?;
+        tasklog::add_annotation(&path, &text)?;
+        println!("{}", i18n::tf("annotate.done", &[("num", &number)]));
+        Ok(())
+    }
+
+    /// Writes a "state of the work" blurb for this session, copies it to the
+    /// clipboard, and appends it to the project journal
+    fn handoff(&self) -> Result<()> {
+        let tasks: Vec<(u32, &str, &str)> = self
+            .task_history
+            .iter()
+            .map(|t| (t.number, t.prompt.as_str(), t.summary.as_str()))
+            .collect();
+        if tasks.is_empty() {
+            bail!("{}", i18n::t("session.no_tasks"));
+        }
+
+        print!("{}", i18n::t("handoff.writing"));
+        std::io::stdout().flush().ok();
+        let rt = tokio::runtime::Runtime::new()?;
+        let blurb = rt.block_on(handoff::generate_handoff(&self.project, &tasks))?;
+        println!("\n\n{}\n", blurb);
+
+        handoff::append_journal(&self.project, &blurb)?;
+        match clipboard::copy(&blurb) {
+            Ok(()) => println!(
+                "{}",
+                i18n::tf("handoff.copied", &[("file", &handoff::JOURNAL_FILE)])
+            ),
+            Err(e) => println!(
+                "{}",
+                i18n::tf(
+                    "handoff.not_copied",
+                    &[("file", &handoff::JOURNAL_FILE), ("error", &e)]
+                )
+            ),
+        }
+        Ok(())
+    }
+
+    /// Appends text, or the last assistant message's final paragraph, to a
+    /// note category without waiting for extraction
+    fn pin(&self, args: &[&str]) -> Result<()> {
+        let Some((category, rest)) = args.split_first() else {
+            bail!("{}", i18n::t("usage.pin"));
+        };
+        let text = if rest.is_empty() {
+            self.task_history
+                .iter()
+                .rev()
+                .find_map(|t| last_assistant_paragraph(&t.transcript))
+                .ok_or_else(|| anyhow::anyhow!("No assistant output to pin"))?
+        } else {
+            rest.join(" ")
+        };
+
+        self.project.add_note(category, &text)?;
+        println!("{}", i18n::tf("pin.done", &[("category", &category)]));
+        Ok(())
+    }
+
+    /// Appends a timestamped entry to a note category: `/note <category> <text>`
+    fn note(&self, args: &[&str]) -> Result<()> {
+        let (Some(category), true) = (args.first(), args.len() > 1) else {
+            bail!("{}", i18n::t("usage.note"));
+        };
+        let text = strip_quotes(&args[1..].join(" ")).to_string();
+        let stamped = format!("[{}] {}", chrono::Utc::now().format("%Y-%m-%d %H:%M"), text);
+
+        self.project.add_note(category, &stamped)?;
+        println!("{}", i18n::tf("pin.added", &[("category", &category)]));
+        Ok(())
+    }
+
+    /// Stars or unstars a task: `/star [task]`, defaulting to the last task
+    fn star(&self, arg: Option<&str>, starred: bool) -> Result<()> {
+        let number = match arg {
+            Some(n) => n
+                .parse()
+                .with_context(|| format!("Invalid task number: {}", n))?,
+            None => self.last_task_number()?,
+        };
+        let path = tasklog::find_task_log(&self.project, number)?;
+        tasklog::set_starred(&path, starred)?;
+        if starred {
+            println!("{}", i18n::tf("star.starred", &[("num", &number)]));
+        } else {
+            println!("{}", i18n::tf("star.unstarred", &[("num", &number)]));
+        }
+        Ok(())
+    }
+
+    /// Lists the project's stored tasks, optionally only starred ones
+    fn show_tasks(&self, starred_only: bool) -> Result<()> {
+        let mut shown = 0;
+        for (_, path) in tasklog::task_log_paths(&self.project)? {
+            let log = tasklog::TaskLog::load(&path)?;
+            if starred_only && !log.starred {
+                continue;
+            }
+            println!("{}", tasklog::task_list_line(&log));
+            shown += 1;
+        }
+        if shown == 0 {
+            if starred_only {
+                println!("{}", i18n::t("tasks.no_starred"));
+            } else {
+                println!("{}", i18n::t("tasks.none_stored"));
+            }
+        }
+        Ok(())
+    }
+
     /// Handles REPL commands (those starting with /)
     fn handle_command(&mut self, cmd: &str) -> Result<bool> {
         let parts: Vec<&str> = cmd.split_whitespace().collect();
@@ -528,54 +2181,309 @@ impl Session {
         match command {
             "/done" | "/quit" | "/q" => {
                 println!(
-                    "Session complete. {} tasks, notes updated.",
-                    self.task_history.len()
+                    "{}",
+                    i18n::tf("session.done", &[("tasks", &self.task_history.len())])
                 );
                 return Ok(true); // Signal to exit
             }
             "/status" => {
                 self.show_status()?;
             }
+            "/notes" if parts.get(1) == Some(&"show") => {
+                if let Err(e) = notes::show_entries(&self.project, parts.get(2).copied()) {
+                    println!("{}", i18n::tf("error.notes", &[("error", &e)]));
+                }
+            }
             "/notes" => {
                 let category = parts.get(1).copied();
                 self.edit_notes(category)?;
             }
-            "/history" => {
-                self.show_history();
+            "/note" if parts.get(1) == Some(&"rm") => match (parts.get(2), parts.get(3)) {
+                (Some(category), Some(id)) => {
+                    if let Err(e) = notes::remove_note(&self.project, category, id) {
+                        println!("{}", i18n::tf("error.note", &[("error", &e)]));
+                    }
+                }
+                _ => println!("{}", i18n::t("usage.note_rm")),
+            },
+            "/why" => match parts.get(1) {
+                Some(id) => {
+                    if let Err(e) = notes::why(&self.project, id) {
+                        println!("{}", i18n::tf("error.note", &[("error", &e)]));
+                    }
+                }
+                None => println!("{}", i18n::t("usage.why")),
+            },
+            "/history" => {
+                self.show_history();
+            }
+            "/last" => {
+                self.show_last();
+            }
+            "/continue" => {
+                self.conversation_mode = ConversationMode::Full;
+                println!("{}", i18n::t("mode.full"));
+            }
+            "/compact" => {
+                self.run_compact();
+            }
+            "/fresh" => {
+                self.conversation_mode = ConversationMode::Fresh;
+                println!("{}", i18n::t("mode.fresh"));
+            }
+            "/summary" => {
+                self.conversation_mode = ConversationMode::Summary;
+                println!("{}", i18n::t("mode.summary"));
+            }
+            "/fork" => {
+                self.run_fork(&parts[1..])?;
+            }
+            "/switch" => {
+                if let Err(e) = self.switch_project(parts.get(1).copied()) {
+                    println!("{}", i18n::tf("error.switch", &[("error", &e)]));
+                }
+            }
+            "/ask" => {
+                let question = cmd.trim_start().trim_start_matches("/ask").trim();
+                if question.is_empty() {
+                    println!("{}", i18n::t("usage.ask"));
+                } else if let Err(e) = self.run_task(&format!("@ask {}", question)) {
+                    println!("{}", i18n::tf("task.error", &[("error", &e)]));
+                }
+            }
+            "/run-template" => match parts.get(1).copied() {
+                Some(name) => {
+                    if let Err(e) = self.run_template(name) {
+                        println!("{}", i18n::tf("task.error", &[("error", &e)]));
+                    }
+                }
+                None => self.show_templates(),
+            },
+            "/plan-task" => {
+                let prompt = cmd.trim_start().trim_start_matches("/plan-task").trim();
+                if let Err(e) = self.run_plan_task(prompt) {
+                    println!("{}", i18n::tf("error.plan", &[("error", &e)]));
+                }
+            }
+            "/tasks" => {
+                let starred_only = parts.contains(&"--starred");
+                if let Err(e) = self.show_tasks(starred_only) {
+                    println!("{}", i18n::tf("error.tasks", &[("error", &e)]));
+                }
+            }
+            "/star" | "/unstar" => {
+                if let Err(e) = self.star(parts.get(1).copied(), command == "/star") {
+                    println!("{}", i18n::tf("error.star", &[("error", &e)]));
+                }
+            }
+            "/mode" => match parts.get(1).copied() {
+                Some(mode) if PERMISSION_MODES.contains(&mode) => {
+                    self.permission_mode = (mode != "default").then(|| mode.to_string());
+                    println!("{}", i18n::tf("mode.permission", &[("mode", &mode)]));
+                }
+                _ => println!(
+                    "{}",
+                    i18n::tf(
+                        "mode.usage",
+                        &[
+                            (
+                                "mode",
+                                &self.permission_mode.as_deref().unwrap_or("default")
+                            ),
+                            ("modes", &PERMISSION_MODES.join("|"))
+                        ]
+                    )
+                ),
+            },
+            "/sandbox" => match parts.get(1).copied() {
+                Some("on") => {
+                    self.sandboxed = true;
+                    println!("{}", i18n::t("sandbox.on"));
+                }
+                Some("off") => {
+                    self.sandboxed = false;
+                    println!("{}", i18n::t("sandbox.off"));
+                }
+                _ => println!(
+                    "{}",
+                    i18n::tf(
+                        "sandbox.usage",
+                        &[("state", &if self.sandboxed { "on" } else { "off" })]
+                    )
+                ),
+            },
+            "/cd" => match parts.get(1).copied() {
+                None | Some(".") | Some("/") => {
+                    self.task_dir = None;
+                    println!(
+                        "{}",
+                        i18n::tf("cd.current", &[("dir", &self.working_dir.display())])
+                    );
+                }
+                Some(dir) => match self.resolve_task_dir(Some(dir)) {
+                    Ok(task_dir) => {
+                        self.task_dir = task_dir;
+                        println!("{}", i18n::tf("cd.current", &[("dir", &dir)]));
+                    }
+                    Err(e) => println!("{}", i18n::tf("error.cd", &[("error", &e)])),
+                },
+            },
+            "/tokens" => {
+                self.show_tokens()?;
+            }
+            "/config" => match parts.get(1).copied() {
+                None => {
+                    let config = load_config()?;
+                    println!("{}", i18n::t("config.header"));
+                    println!("{}", toml::to_string_pretty(&config)?);
+                }
+                Some("set") if parts.len() >= 4 => {
+                    let key = parts[2];
+                    // Taken from the raw line so quoted values keep their spacing
+                    let rest = cmd.trim_end();
+                    let rest = rest[rest.find(key).unwrap_or(0) + key.len()..].trim_start();
+                    let (value, persist) = match rest.strip_suffix("--save") {
+                        Some(value) => (value.trim_end(), true),
+                        None => (rest, false),
+                    };
+                    match config::set_setting(key, value, persist) {
+                        Ok(()) if persist => println!(
+                            "{}",
+                            i18n::tf("config.saved", &[("key", &key), ("value", &value)])
+                        ),
+                        Ok(()) => println!(
+                            "{}",
+                            i18n::tf("config.session", &[("key", &key), ("value", &value)])
+                        ),
+                        Err(e) => println!("{}", i18n::tf("error.config", &[("error", &e)])),
+                    }
+                }
+                _ => println!("{}", i18n::t("usage.config")),
+            },
+            "/verify" => {
+                let given = cmd.strip_prefix("/verify").unwrap_or("").trim();
+                if let Err(e) = self.verify((!given.is_empty()).then_some(given)) {
+                    println!("{}", i18n::tf("error.verify", &[("error", &e)]));
+                }
+            }
+            "/attach" => match cmd.trim_start().strip_prefix("/attach").map(str::trim) {
+                // The rest of the line, so paths with spaces work
+                Some(arg) if !arg.is_empty() => {
+                    let (file, head) = match arg.strip_prefix("--head") {
+                        Some(rest) if rest.starts_with(char::is_whitespace) => {
+                            (rest.trim_start(), true)
+                        }
+                        _ => (arg, false),
+                    };
+                    let path = resolve_user_path(&self.working_dir, file);
+                    if path.is_file() {
+                        println!("{}", i18n::tf("attach.added", &[("file", &file)]));
+                        self.attachments.push((path, head));
+                    } else {
+                        println!(
+                            "{}",
+                            i18n::tf("attach.not_file", &[("path", &path.display())])
+                        );
+                    }
+                }
+                _ => println!("{}", i18n::t("usage.attach")),
+            },
+            "/paste" => {
+                let prefix = cmd.trim_start().trim_start_matches("/paste").trim();
+                let pasted = clipboard::paste()?;
+                if pasted.trim().is_empty() {
+                    println!("{}", i18n::t("clipboard.empty"));
+                } else {
+                    let text = if prefix.is_empty() {
+                        pasted.trim_end().to_string()
+                    } else {
+                        format!("{} {}", prefix, pasted.trim_end())
+                    };
+                    println!(
+                        "{}",
+                        i18n::tf("clipboard.pasted", &[("lines", &text.lines().count())])
+                    );
+                    self.pending_input = Some(text);
+                }
+            }
+            "/copy" => {
+                let what = parts.get(1).copied().unwrap_or("result");
+                let Some(task) = self.task_history.iter().rev().find(|t| t.number != 0) else {
+                    println!("{}", i18n::t("session.no_tasks"));
+                    return Ok(false);
+                };
+                match copy_text(&task.transcript, what) {
+                    Some(text) => {
+                        clipboard::copy(&text)?;
+                        println!(
+                            "{}",
+                            i18n::tf(
+                                "copy.done",
+                                &[
+                                    ("num", &task.number),
+                                    ("what", &what),
+                                    ("chars", &text.chars().count())
+                                ]
+                            )
+                        );
+                    }
+                    None if matches!(what, "result" | "message") => {
+                        println!(
+                            "{}",
+                            i18n::tf("copy.missing", &[("num", &task.number), ("what", &what)])
+                        )
+                    }
+                    None => println!("{}", i18n::t("usage.copy")),
+                }
             }
-            "/continue" => {
-                self.conversation_mode = ConversationMode::Full;
-                println!(
-                    "Switched to full conversation mode. Next task will include complete prior context."
-                );
+            "/extract" => match parts.get(1).copied() {
+                Some("on") => {
+                    self.extraction_enabled = true;
+                    println!("{}", i18n::t("extract.enabled"));
+                }
+                Some("off") => {
+                    self.extraction_enabled = false;
+                    println!("{}", i18n::t("extract.disabled"));
+                }
+                _ => println!(
+                    "{}",
+                    i18n::tf(
+                        "extract.usage",
+                        &[("state", &if self.extraction_enabled { "on" } else { "off" })]
+                    )
+                ),
+            },
+            "/note" => {
+                if let Err(e) = self.note(&parts[1..]) {
+                    println!("{}", i18n::tf("error.note", &[("error", &e)]));
+                }
             }
-            "/compact" => {
-                self.run_compact();
+            "/handoff" => {
+                if let Err(e) = self.handoff() {
+                    println!("{}", i18n::tf("error.handoff", &[("error", &e)]));
+                }
             }
-            "/fresh" => {
-                self.conversation_mode = ConversationMode::Fresh;
-                println!("Switched to fresh mode. Next task will only include notes, no session history.");
+            "/pin" => {
+                if let Err(e) = self.pin(&parts[1..]) {
+                    println!("{}", i18n::tf("error.pin", &[("error", &e)]));
+                }
             }
-            "/summary" => {
-                self.conversation_mode = ConversationMode::Summary;
-                println!(
-                    "Switched to summary mode (default). Next task will include task summaries."
-                );
+            "/annotate" => {
+                if let Err(e) = self.annotate(&parts[1..]) {
+                    println!("{}", i18n::tf("error.annotate", &[("error", &e)]));
+                }
             }
             "/auto" => {
                 let file = parts.get(1).copied();
                 if let Err(e) = self.run_auto(file) {
-                    println!("Auto error: {}", e);
+                    println!("{}", i18n::tf("error.auto", &[("error", &e)]));
                 }
             }
             "/help" => {
                 self.show_help();
             }
             _ => {
-                println!(
-                    "Unknown command: {}. Type /help for available commands.",
-                    command
-                );
+                println!("{}", i18n::tf("command.unknown", &[("command", &command)]));
             }
         }
 
@@ -583,17 +2491,36 @@ impl Session {
     }
 
     fn show_status(&self) -> Result<()> {
-        println!("\n## Project: {}", self.project.metadata.name);
         println!(
-            "Session tasks: {} | Total tasks: {}",
-            self.task_history.len(),
-            self.project.metadata.stats.total_tasks
+            "{}",
+            i18n::tf("status.project", &[("name", &self.project.metadata.name)])
         );
+        println!(
+            "{}",
+            i18n::tf(
+                "status.tasks",
+                &[
+                    ("session", &self.task_history.len()),
+                    ("total", &self.project.metadata.stats.total_tasks)
+                ]
+            )
+        );
+
+        let metrics = SessionMetrics::from_records(&self.task_history, &load_config()?.pricing);
+        if metrics.tasks > 0 {
+            println!(
+                "{}",
+                i18n::tf(
+                    "status.session",
+                    &[("metrics", &metrics.format().trim_end())]
+                )
+            );
+        }
 
         // Show plan
         let plan = self.project.read_notes("plan")?;
         if !plan.trim().is_empty() {
-            println!("\n## Current Plan\n{}", plan);
+            println!("{}", i18n::tf("status.plan", &[("plan", &plan)]));
         }
 
         // Show recent decisions
@@ -601,7 +2528,7 @@ impl Session {
         if !decisions.trim().is_empty() {
             let lines: Vec<&str> = decisions.lines().take(5).collect();
             if !lines.is_empty() {
-                println!("\n## Recent Decisions");
+                println!("{}", i18n::t("status.decisions"));
                 for line in lines {
                     println!("{}", line);
                 }
@@ -617,11 +2544,17 @@ impl Session {
         let editor = &config.repl.editor;
 
         let path = if let Some(cat) = category {
-            if !NOTE_CATEGORIES.contains(&cat) {
+            if !NOTE_CATEGORIES.contains(&cat) && !EXTRA_NOTES.contains(&cat) {
                 println!(
-                    "Invalid category '{}'. Valid: {}",
-                    cat,
-                    NOTE_CATEGORIES.join(", ")
+                    "{}",
+                    i18n::tf(
+                        "notes.invalid_category",
+                        &[
+                            ("category", &cat),
+                            ("valid", &NOTE_CATEGORIES.join(", ")),
+                            ("extra", &EXTRA_NOTES.join(", "))
+                        ]
+                    )
                 );
                 return Ok(());
             }
@@ -636,58 +2569,401 @@ impl Session {
             .with_context(|| format!("Failed to open editor: {}", editor))?;
 
         if !status.success() {
-            println!("Editor exited with error");
+            println!("{}", i18n::t("editor.failed"));
         }
 
         Ok(())
     }
 
+    /// Shows the last task's summary and the file changes it made
+    fn show_last(&self) {
+        let Some(task) = self.task_history.iter().rev().find(|t| t.number != 0) else {
+            println!("{}", i18n::t("session.no_tasks"));
+            return;
+        };
+
+        println!(
+            "{}",
+            i18n::tf(
+                "last.header",
+                &[("num", &task.number), ("prompt", &task.prompt)]
+            )
+        );
+        println!("{}\n", task.summary);
+
+        let diffs = file_diffs(&task.transcript);
+        if diffs.is_empty() {
+            println!("{}", i18n::t("last.no_edits"));
+            return;
+        }
+        for diff in diffs {
+            println!("{}", diff.to_unified());
+        }
+    }
+
+    /// Warns when a prompt repeats a recent successful task in this project
+    /// and asks whether to run it anyway
+    fn confirm_repeat(&self, prompt: &str) -> Result<bool> {
+        let lookback = load_config()?.repl.duplicate_lookback;
+        if lookback == 0 {
+            return Ok(true);
+        }
+        let (_, prompt) = parse_directives(prompt);
+        // A broken log shouldn't block new work
+        let Ok(logs) = tasklog::recent_task_logs(&self.project, lookback) else {
+            return Ok(true);
+        };
+        let Some(log) = tasklog::find_similar_task(&logs, prompt) else {
+            return Ok(true);
+        };
+
+        let when = log
+            .timestamp
+            .map(|t| tasklog::days_ago(t, Utc::now()))
+            .unwrap_or_else(|| "before".to_string());
+        println!(
+            "{}",
+            i18n::tf("similar.ran", &[("num", &log.task_number), ("when", &when)])
+        );
+        if let Some(summary) = log.summary.lines().next().filter(|l| !l.trim().is_empty()) {
+            println!("  {}", text::truncate_chars(summary.trim(), 100));
+        }
+        loop {
+            print!("{}", i18n::t("similar.prompt"));
+            std::io::stdout().flush()?;
+            let mut input = String::new();
+            std::io::stdin().read_line(&mut input)?;
+            match input.trim().to_lowercase().as_str() {
+                "v" | "view" => render::show_task(
+                    &self.project.metadata.name,
+                    log.task_number,
+                    ShowFormat::Text,
+                    None,
+                )?,
+                "r" | "run" => return Ok(true),
+                _ => return Ok(false),
+            }
+        }
+    }
+
     fn show_history(&self) {
         if self.task_history.is_empty() {
-            println!("No tasks this session.");
+            println!("{}", i18n::t("session.no_tasks"));
             return;
         }
 
-        println!("\n## Task History\n");
+        println!("{}", i18n::t("history.header"));
         for task in &self.task_history {
             println!("{}. {} — {}", task.number, task.prompt, task.summary);
         }
         println!();
     }
 
-    fn show_help(&self) {
-        let mode_str = match self.conversation_mode {
-            ConversationMode::Fresh => "fresh",
-            ConversationMode::Summary => "summary",
-            ConversationMode::Full => "full",
+    /// Directory tasks run in: the working dir, or the `/cd` subdirectory
+    fn run_dir(&self) -> PathBuf {
+        match self.task_dir {
+            Some(ref dir) => self.working_dir.join(dir),
+            None => self.working_dir.clone(),
+        }
+    }
+
+    /// Runs a verification command, defaulting to the detected test command,
+    /// and returns whether it passed
+    fn verify(&self, command: Option<&str>) -> Result<bool> {
+        let run_dir = self.run_dir();
+        let command = match command {
+            Some(command) => command.to_string(),
+            None => match environment::detected_test_command(&run_dir) {
+                Some(command) => command,
+                None => bail!("{}", i18n::t("verify.no_command")),
+            },
         };
-        println!(
-            r#"
-## Clancy REPL Commands
+        println!("{}", i18n::tf("verify.running", &[("command", &command)]));
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(&command)
+            .current_dir(&run_dir)
+            .status()
+            .with_context(|| format!("Failed to run: {}", command))?;
+        if status.success() {
+            println!("{}", i18n::t("verify.passed"));
+        } else {
+            match status.code() {
+                Some(code) => println!("{}", i18n::tf("verify.failed_code", &[("code", &code)])),
+                None => println!("{}", i18n::t("verify.failed")),
+            }
+        }
+        Ok(status.success())
+    }
+
+    /// Runs a task template from project.toml, then its verify command, and
+    /// records both in the task log
+    ///
+    /// Returns whether the task succeeded and verification (if any) passed.
+    pub(crate) fn run_template(&mut self, name: &str) -> Result<bool> {
+        let template = find_template(&self.project.metadata.templates, name)?.clone();
+
+        let prompt = match template.model {
+            Some(ref model) => format!("@model={} {}", model, template.prompt),
+            None => template.prompt.clone(),
+        };
+        if self.run_task(&prompt)? == TaskOutcome::Skipped {
+            return Ok(false);
+        }
+        let succeeded = self.last_task_succeeded();
+        let verified = match template.verify {
+            Some(ref command) => Some(self.verify(Some(command))?),
+            None => None,
+        };
+
+        let task_num = self.task_history.last().map_or(0, |record| record.number);
+        let log_path = tasklog::find_task_log(&self.project, task_num)?;
+        tasklog::update_task_log(&log_path, |log| {
+            log.insert("template".to_string(), name.into());
+            if !template.tags.is_empty() {
+                log.insert("tags".to_string(), serde_json::json!(template.tags));
+            }
+            if let Some(passed) = verified {
+                log.insert("verified".to_string(), passed.into());
+            }
+        })?;
+        Ok(succeeded && verified.unwrap_or(true))
+    }
+
+    /// Lists the project's task templates
+    fn show_templates(&self) {
+        let templates = &self.project.metadata.templates;
+        if templates.is_empty() {
+            println!("{}", i18n::t("template.none"));
+            return;
+        }
+        for (name, template) in templates {
+            println!("  {:<20} {}", name, truncate_string(&template.prompt, 60));
+        }
+    }
 
-  <task description>   Run a task via Claude
-  /status              Show current notes summary
-  /notes [category]    Edit notes (architecture|decisions|failures|plan)
-  /history             Show task history this session
-  /auto [file]         Run phases from PLAN.md (or specified file)
+    fn show_help(&self) {
+        println!(
+            "{}",
+            i18n::tf("help.text", &[("mode", &self.conversation_mode.name())])
+        );
+    }
+}
 
-## Conversation Modes (current: {})
+/// Asks which extracted categories to apply, e.g. `[a]rchitecture [p]lan`
+fn prompt_category_selection(available: &[&'static str]) -> Vec<&'static str> {
+    let choices: Vec<String> = available
+        .iter()
+        .map(|c| format!("[{}]{}", &c[..1], &c[1..]))
+        .collect();
+    print!(
+        "{}",
+        i18n::tf("extract.accept_prompt", &[("choices", &choices.join(" "))])
+    );
+    std::io::stdout().flush().ok();
 
-  /continue            Switch to full mode (include complete prior context)
-  /compact             Summarize history and start fresh
-  /fresh               Switch to fresh mode (only notes, no history)
-  /summary             Switch to summary mode (default)
+    let mut input = String::new();
+    if std::io::stdin().read_line(&mut input).is_err() {
+        return Vec::new();
+    }
+    parse_category_selection(&input, available)
+}
 
-## Session
+/// Builds `docker|podman run` arguments that run claude in a container
+/// called `name`, with the working directory mounted at the sandbox workdir
+fn container_args(
+    sandbox: &config::SandboxConfig,
+    name: &str,
+    working_dir: &Path,
+    task_dir: Option<&Path>,
+    mounts: &[PathBuf],
+    claude_args: &[String],
+) -> Vec<String> {
+    let workdir = match task_dir {
+        Some(dir) => format!(
+            "{}/{}",
+            sandbox.workdir.trim_end_matches('/'),
+            dir.display()
+        ),
+        None => sandbox.workdir.clone(),
+    };
+    let mut args: Vec<String> = vec![
+        "run".to_string(),
+        "--rm".to_string(),
+        "-i".to_string(),
+        "--name".to_string(),
+        name.to_string(),
+        "-v".to_string(),
+        format!("{}:{}", working_dir.display(), sandbox.workdir),
+        "-w".to_string(),
+        workdir,
+    ];
+    for path in mounts {
+        // Mounted at the same path so --add-dir/--mcp-config resolve inside
+        args.extend([
+            "-v".to_string(),
+            format!("{}:{}", path.display(), path.display()),
+        ]);
+    }
+    for var in &sandbox.env {
+        // `-e NAME` forwards the host value without exposing it in argv
+        args.extend(["-e".to_string(), var.clone()]);
+    }
+    args.extend(sandbox.extra_args.iter().cloned());
+    args.push(sandbox.image.clone());
+    args.push("claude".to_string());
+    args.extend(claude_args.iter().cloned());
+    args
+}
 
-  /done or /quit       Exit the session
-  /help                Show this help
-"#,
-            mode_str
+/// Prints policy violations loudly enough to notice in scrollback
+fn print_violations(violations: &[policy::Violation]) {
+    if violations.is_empty() {
+        return;
+    }
+    let key = if violations.len() == 1 {
+        "policy.header_one"
+    } else {
+        "policy.header_many"
+    };
+    println!("{}", i18n::tf(key, &[("count", &violations.len())]));
+    for violation in violations {
+        println!(
+            "{}",
+            i18n::tf(
+                "policy.violation",
+                &[
+                    ("tool", &violation.tool),
+                    ("input", &truncate_string(&violation.input, 80)),
+                    ("rule", &violation.rule)
+                ]
+            )
         );
     }
 }
 
+/// Lists a task's dependency changes, flagged like policy violations when
+/// the prompt didn't ask for them
+fn print_dependency_changes(changes: &[deps::DependencyChange], unexpected: bool) {
+    if changes.is_empty() {
+        return;
+    }
+    if unexpected {
+        println!("{}", i18n::t("deps.unrequested"));
+        for line in deps::summary_lines(changes) {
+            println!("!!   {}", line);
+        }
+    } else {
+        println!("{}", i18n::t("deps.changed"));
+        for line in deps::summary_lines(changes) {
+            println!("  {}", line);
+        }
+    }
+}
+
+/// Describes a route's model and agent for the `[Routed to ...]` line
+fn route_target(route: &Route) -> String {
+    match (&route.model, &route.agent) {
+        (Some(model), Some(agent)) => format!("{} (agent {})", model, agent),
+        (Some(model), None) => model.clone(),
+        (None, Some(agent)) => format!("agent {}", agent),
+        (None, None) => "defaults".to_string(),
+    }
+}
+
+/// Looks up a task template by name, listing the available ones if missing
+fn find_template<'a>(
+    templates: &'a BTreeMap<String, TaskTemplate>,
+    name: &str,
+) -> Result<&'a TaskTemplate> {
+    if let Some(template) = templates.get(name) {
+        return Ok(template);
+    }
+    if templates.is_empty() {
+        bail!("{}", i18n::tf("template.unknown_none", &[("name", &name)]));
+    }
+    let names: Vec<&str> = templates.keys().map(String::as_str).collect();
+    bail!(
+        "{}",
+        i18n::tf(
+            "template.unknown",
+            &[("name", &name), ("available", &names.join(", "))]
+        )
+    )
+}
+
+/// Per-task options given as `@` directives at the start of a prompt
+#[derive(Debug, Default, PartialEq)]
+struct TaskDirectives {
+    /// `@noextract`: skip note extraction for this task
+    no_extract: bool,
+    /// `@dir=<path>`: run in a subdirectory of the project root
+    dir: Option<String>,
+    /// `@model=<model>`: override routing rules (`default` for none)
+    model: Option<String>,
+    /// `@agent=<agent>`: override routing rules (`default` for none)
+    agent: Option<String>,
+    /// `@ask` (or `/ask`): a read-only question that leaves the plan alone
+    ask: bool,
+}
+
+/// Splits leading `@noextract`, `@ask`, `@dir=`, `@model=`, and `@agent=`
+/// directives from a prompt
+fn parse_directives(prompt: &str) -> (TaskDirectives, &str) {
+    let mut directives = TaskDirectives::default();
+    let mut rest = prompt.trim_start();
+    loop {
+        let token = rest.split_whitespace().next().unwrap_or("");
+        if token == "@noextract" {
+            directives.no_extract = true;
+        } else if token == "@ask" {
+            directives.ask = true;
+        } else if let Some(dir) = token.strip_prefix("@dir=").filter(|d| !d.is_empty()) {
+            directives.dir = Some(dir.to_string());
+        } else if let Some(model) = token.strip_prefix("@model=").filter(|m| !m.is_empty()) {
+            directives.model = Some(model.to_string());
+        } else if let Some(agent) = token.strip_prefix("@agent=").filter(|a| !a.is_empty()) {
+            directives.agent = Some(agent.to_string());
+        } else {
+            return (directives, rest);
+        }
+        rest = rest[token.len()..].trim_start();
+    }
+}
+
+/// Removes one pair of matching surrounding quotes
+fn strip_quotes(text: &str) -> &str {
+    let text = text.trim();
+    for quote in ['"', '\''] {
+        if let Some(inner) = text.strip_prefix(quote).and_then(|t| t.strip_suffix(quote)) {
+            return inner;
+        }
+    }
+    text
+}
+
+/// Returns the final paragraph of the last assistant message in a transcript
+fn last_assistant_paragraph(transcript: &Transcript) -> Option<String> {
+    let text = transcript
+        .messages
+        .iter()
+        .rev()
+        .find_map(|msg| match msg {
+            Message::Text { text } if !text.trim().is_empty() => Some(text.as_str()),
+            _ => None,
+        })
+        .or_else(|| {
+            transcript
+                .result
+                .as_ref()
+                .and_then(|r| r.result_text.as_deref())
+        })?;
+    text.rsplit("\n\n")
+        .map(str::trim)
+        .find(|p| !p.is_empty())
+        .map(str::to_string)
+}
+
 /// Checks if .gitignore content already contains a .claude entry
 fn gitignore_has_claude_entry(content: &str) -> bool {
     content.lines().any(|line| {
@@ -722,8 +2998,8 @@ fn check_gitignore(working_dir: &std::path::Path) -> Result<()> {
     }
 
     // Ask user if they want to add the entry
-    println!("The .claude/ directory (used for context injection) is not in .gitignore.");
-    print!("Add '.claude/' to .gitignore? [Y/n] ");
+    println!("{}", i18n::t("gitignore.missing"));
+    print!("{}", i18n::t("gitignore.prompt"));
     std::io::stdout().flush()?;
 
     let mut input = String::new();
@@ -734,94 +3010,595 @@ fn check_gitignore(working_dir: &std::path::Path) -> Result<()> {
         // Append .claude/ to .gitignore
         let mut file = OpenOptions::new().append(true).open(&gitignore_path)?;
         write!(file, "{}", format_gitignore_append(&content))?;
-        println!("Added '.claude/' to .gitignore\n");
+        println!("{}", i18n::t("gitignore.added"));
     } else {
         println!();
     }
 
-    Ok(())
+    Ok(())
+}
+
+/// Runs one task for an existing project without the REPL, for scripts and
+/// cron jobs
+///
+/// Context is compiled, the task log saved, and notes extracted just as in a
+/// session. Returns whether the task succeeded.
+pub fn run_single_task(project_name: &str, prompt: &str) -> Result<bool> {
+    let mut session = headless_session(project_name)?;
+    session.run_task(prompt)?;
+    Ok(session.last_task_succeeded())
+}
+
+/// Runs a project's task template without the REPL; see `Session::run_template`
+pub fn run_template_task(project_name: &str, template: &str) -> Result<bool> {
+    // Check the name before a session is recorded for it
+    find_template(&Project::open(project_name)?.metadata.templates, template)?;
+    headless_session(project_name)?.run_template(template)
+}
+
+/// A session for one scripted task in an existing project
+fn headless_session(project_name: &str) -> Result<Session> {
+    let mut project = Project::open(project_name)?;
+    project.record_session_start()?;
+    let mut session = Session::new(project)?;
+    session.recover_interrupted_tasks()?;
+    Ok(session)
+}
+
+/// Starts the REPL session for a project
+///
+/// With `resume`, the previous session continues: its tasks are restored
+/// into the session history and new tasks are logged under it.
+pub fn start_session(project_name: &str, parent: Option<&str>, resume: bool) -> Result<()> {
+    let mut project = match parent {
+        Some(parent) => project::open_or_create_with_parent(project_name, parent)?,
+        None => Project::open_or_create(project_name)?,
+    };
+    if !resume || project.metadata.stats.total_sessions == 0 {
+        project.record_session_start()?;
+    }
+
+    println!(
+        "{}",
+        i18n::tf(
+            "session.loading",
+            &[
+                ("name", &project.metadata.name),
+                ("sessions", &project.metadata.stats.total_sessions),
+                ("tasks", &project.metadata.stats.total_tasks)
+            ]
+        )
+    );
+
+    let mut session = Session::new(project)?;
+    session.interactive = true;
+    session.recover_interrupted_tasks()?;
+    if resume {
+        session.resume_history()?;
+    }
+
+    // Check .gitignore and offer to add .claude/ if needed
+    check_gitignore(&session.working_dir)?;
+
+    let token_count = session.compile_context()?;
+    println!(
+        "{}",
+        i18n::tf("session.context", &[("tokens", &token_count)])
+    );
+
+    if let Err(e) = session.offer_auto_resume() {
+        println!("{}", i18n::tf("error.auto", &[("error", &e)]));
+    }
+
+    // Set up readline with history kept per project
+    let repl_config = load_config()?.repl;
+    let mut rl = DefaultEditor::with_config(readline_config(&repl_config)?)?;
+    if !repl_config.incremental_search {
+        rl.bind_sequence(KeyEvent::ctrl('R'), Cmd::Noop);
+    }
+    let mut history_path = project_history(&session.project);
+    if repl_config.history_dedup {
+        if let Ok(content) = std::fs::read_to_string(&history_path) {
+            let _ = std::fs::write(&history_path, dedup_history(&content));
+        }
+    }
+    let _ = rl.load_history(&history_path);
+
+    loop {
+        // Rebuilt each iteration since /switch and /fork --switch change the project
+        // and /config set can change the prompt settings
+        let prompt = match load_config() {
+            Ok(config) => session.prompt(&config.repl),
+            Err(_) => session.prompt(&repl_config),
+        };
+        let input = match session.pending_input.take() {
+            Some(text) => rl.readline_with_initial(&prompt, (&text, "")),
+            None => rl.readline(&prompt),
+        };
+        match input {
+            Ok(line) => {
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+
+                rl.add_history_entry(line)?;
+
+                if line.starts_with('/') {
+                    match session.handle_command(line) {
+                        Ok(should_exit) => {
+                            if should_exit {
+                                break;
+                            }
+                        }
+                        Err(e) => println!("{}", i18n::tf("error.generic", &[("error", &e)])),
+                    }
+                    // /switch and /fork --switch move to another project's history
+                    let project_history = project_history(&session.project);
+                    if project_history != history_path {
+                        let _ = rl.save_history(&history_path);
+                        rl.clear_history()?;
+                        let _ = rl.load_history(&project_history);
+                        history_path = project_history;
+                    }
+                } else {
+                    match session.confirm_repeat(line) {
+                        Ok(true) => {}
+                        Ok(false) => continue,
+                        Err(e) => {
+                            println!("{}", i18n::tf("error.generic", &[("error", &e)]));
+                            continue;
+                        }
+                    }
+                    // Run as a task
+                    // Questions have nothing to plan
+                    let result = if session.plan_first && !parse_directives(line).0.ask {
+                        session.run_plan_task(line)
+                    } else {
+                        session.run_task(line)
+                    };
+                    if let Err(e) = result {
+                        println!("{}", i18n::tf("task.error", &[("error", &e)]));
+                    }
+                }
+            }
+            Err(ReadlineError::Interrupted) => {
+                println!("{}", i18n::t("session.exit_hint"));
+            }
+            Err(ReadlineError::Eof) => {
+                println!(
+                    "{}",
+                    i18n::tf("session.ended", &[("tasks", &session.task_history.len())])
+                );
+                break;
+            }
+            Err(err) => {
+                println!(
+                    "{}",
+                    i18n::tf("error.generic", &[("error", &format!("{:?}", err))])
+                );
+                break;
+            }
+        }
+    }
+
+    // Save history
+    let _ = rl.save_history(&history_path);
+
+    Ok(())
+}
+
+/// Per-project setup done whenever a session starts in a project: syncing
+/// Claude Code settings and archiving old decisions
+fn prepare_project(project: &Project, working_dir: &Path, config: &config::Config) -> Result<()> {
+    if settings::sync_claude_settings(project, working_dir)? {
+        println!("{}", i18n::t("settings.updated"));
+    }
+    if let Some(months) = config.context.archive_decisions_after_months {
+        let moved = notes::archive_old_decisions(project, months)?;
+        if moved > 0 {
+            println!(
+                "{}",
+                i18n::tf(
+                    "decisions.archived",
+                    &[
+                        ("count", &moved),
+                        ("months", &months),
+                        ("file", &notes::DECISIONS_ARCHIVE)
+                    ]
+                )
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Flags costly or unusual state for the prompt, e.g. `[full! $4.20]`
+///
+/// Summary mode under 80% of the budget shows nothing.
+fn prompt_indicator(mode: ConversationMode, cost: f64, budget: Option<f64>) -> String {
+    let mut flags = Vec::new();
+    match mode {
+        ConversationMode::Full => flags.push("full!".to_string()),
+        ConversationMode::Fresh => flags.push("fresh".to_string()),
+        ConversationMode::Summary => {}
+    }
+    if budget.is_some_and(|budget| cost >= budget * 0.8) {
+        flags.push(format!("${:.2}", cost));
+    }
+    if flags.is_empty() {
+        String::new()
+    } else {
+        format!("[{}]", flags.join(" "))
+    }
+}
+
+/// Current git branch of `dir`, if it is inside a repository
+fn git_branch(dir: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--abbrev-ref", "HEAD"])
+        .current_dir(dir)
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !branch.is_empty()).then_some(branch)
+}
+
+/// Builds rustyline settings from `[repl]` config
+fn readline_config(repl: &config::ReplConfig) -> Result<rustyline::Config> {
+    let edit_mode = match repl.edit_mode.as_str() {
+        "emacs" => EditMode::Emacs,
+        "vi" => EditMode::Vi,
+        other => bail!("{}", i18n::tf("config.bad_edit_mode", &[("mode", &other)])),
+    };
+    Ok(rustyline::Config::builder()
+        .edit_mode(edit_mode)
+        .max_history_size(repl.history_size)?
+        .history_ignore_dups(repl.history_dedup)?
+        .history_ignore_space(repl.history_ignore_space)
+        .build())
+}
+
+/// The project's REPL history file
+///
+/// History used to be one global `history.txt`; the first session after
+/// upgrading hands a copy to each project that existed back then, so
+/// upgrading doesn't lose recall.
+fn project_history(project: &Project) -> PathBuf {
+    if let (Ok(dir), Ok(projects_dir)) = (config::config_dir(), config::projects_dir()) {
+        migrate_history(&dir.join("history.txt"), &projects_dir);
+    }
+    project.path.join("history.txt")
+}
+
+/// Copies the old global history into every project created before it was
+/// last written and lacking its own, then renames it to
+/// `history.txt.migrated` so later projects never inherit it
+fn migrate_history(global: &Path, projects_dir: &Path) {
+    let Ok(modified) = std::fs::metadata(global).and_then(|m| m.modified()) else {
+        return;
+    };
+    let last_used: DateTime<Utc> = modified.into();
+    let dirs = std::fs::read_dir(projects_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path());
+    for dir in dirs {
+        let created = std::fs::read_to_string(dir.join(project::METADATA_FILE))
+            .ok()
+            .and_then(|content| toml::from_str::<project::ProjectMetadata>(&content).ok())
+            .map(|metadata| metadata.created);
+        let path = dir.join("history.txt");
+        if created.is_some_and(|created| created <= last_used) && !path.exists() {
+            // Best effort: without the copy the project just starts empty
+            let _ = std::fs::copy(global, path);
+        }
+    }
+    let _ = std::fs::rename(global, global.with_extension("txt.migrated"));
+}
+
+/// Checks that `dir` names a directory inside `root`, returning it relative
+///
+/// The path is resolved before the check, so a symlink inside `root` that
+/// points elsewhere is refused too.
+fn subdirectory(root: &Path, dir: &str) -> Result<PathBuf> {
+    let relative = PathBuf::from(dir);
+    if relative.is_absolute() || relative.components().any(|c| c.as_os_str() == "..") {
+        bail!("{}", i18n::tf("task.dir_outside", &[("dir", &dir)]));
+    }
+    let full = root.join(&relative);
+    if !full.is_dir() {
+        bail!("{}", i18n::tf("task.no_such_dir", &[("dir", &dir)]));
+    }
+    let inside = full
+        .canonicalize()
+        .ok()
+        .zip(root.canonicalize().ok())
+        .is_some_and(|(full, root)| full.starts_with(root));
+    if !inside {
+        bail!("{}", i18n::tf("task.dir_outside", &[("dir", &dir)]));
+    }
+    Ok(relative)
+}
+
+/// Removes repeated history entries, keeping each one's most recent position
+///
+/// Entries are one per line in rustyline's format (multi-line input is
+/// escaped), so lines can be compared directly.
+fn dedup_history(content: &str) -> String {
+    let mut seen = HashSet::new();
+    let mut kept: Vec<&str> = content
+        .lines()
+        .rev()
+        .filter(|line| line.starts_with("#V") || seen.insert(*line))
+        .collect();
+    kept.reverse();
+    kept.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+/// Marks a claude child as running until dropped
+struct TaskRunning;
+
+impl TaskRunning {
+    fn start() -> Self {
+        TASK_INTERRUPTED.store(false, Ordering::SeqCst);
+        TASK_RUNNING.store(true, Ordering::SeqCst);
+        Self
+    }
+}
+
+impl Drop for TaskRunning {
+    fn drop(&mut self) {
+        TASK_RUNNING.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Installs the Ctrl+C handler once per process
+///
+/// During a task, Ctrl+C cancels just that task; elsewhere it exits as usual
+/// (the REPL prompt reads Ctrl+C as a key, so this doesn't affect it).
+fn install_interrupt_handler() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let result = ctrlc::set_handler(|| {
+            if TASK_RUNNING.load(Ordering::SeqCst) {
+                TASK_INTERRUPTED.store(true, Ordering::SeqCst);
+            } else {
+                std::process::exit(130);
+            }
+        });
+        if let Err(e) = result {
+            eprintln!("{}", i18n::tf("warn.ctrlc", &[("error", &e)]));
+        }
+    });
+}
+
+/// Whether a process with this ID is still running
+fn process_alive(pid: u64) -> bool {
+    Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Decodes one line of child output, replacing invalid UTF-8
+///
+/// Lossy lines are marked with `"lossy_utf8": true` so the transcript can
+/// count them; lines that aren't JSON objects are wrapped in one.
+fn decode_output_line(bytes: &[u8]) -> (String, bool) {
+    let bytes = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(line) => (line.to_string(), false),
+        Err(_) => {
+            let line = String::from_utf8_lossy(bytes);
+            let marked = match serde_json::from_str::<serde_json::Value>(&line) {
+                Ok(serde_json::Value::Object(mut map)) => {
+                    map.insert("lossy_utf8".to_string(), serde_json::Value::Bool(true));
+                    serde_json::Value::Object(map)
+                }
+                _ => serde_json::json!({"type": "lossy_line", "text": line, "lossy_utf8": true}),
+            };
+            (marked.to_string(), true)
+        }
+    }
+}
+
+/// Renders one task for the full conversation history section
+fn full_history_entry(task: &TaskRecord) -> String {
+    let mut content = format!("### Task {}: {}\n\n", task.number, task.prompt);
+    // Include the full transcript, rendered for readability
+    for msg in &task.transcript.messages {
+        match msg {
+            Message::Text { text } => {
+                content.push_str(text);
+                content.push_str("\n\n");
+            }
+            Message::ToolUse { tool_name, .. } => {
+                content.push_str(&format!("[Used tool: {}]\n\n", tool_name));
+            }
+            _ => {}
+        }
+    }
+    content
 }
 
-/// Starts the REPL session for a project
-pub fn start_session(project_name: &str) -> Result<()> {
-    let mut project = Project::open_or_create(project_name)?;
-    project.record_session_start()?;
+/// Splits compiled context into `(section, estimated tokens)`
+///
+/// Text before the first `## ` heading is the header; the closing `---`
+/// block is the footer.
+fn context_sections(content: &str) -> Vec<(String, usize)> {
+    let mut sections: Vec<(String, usize)> = vec![("(header)".to_string(), 0)];
+    for line in content.split_inclusive('\n') {
+        if let Some(title) = line.strip_prefix("## ") {
+            sections.push((title.trim().to_string(), 0));
+        } else if line.trim_end() == "---" {
+            sections.push(("(footer)".to_string(), 0));
+        }
+        if let Some((_, chars)) = sections.last_mut() {
+            *chars += line.len();
+        }
+    }
+    sections
+        .into_iter()
+        .filter(|(_, chars)| *chars > 0)
+        .map(|(title, chars)| (title, chars / 4))
+        .collect()
+}
 
-    println!(
-        "Loading project: {} ({} prior sessions, {} tasks)",
-        project.metadata.name,
-        project.metadata.stats.total_sessions,
-        project.metadata.stats.total_tasks
-    );
+/// Formats the last `max_lines` lines of a log as a fenced context section
+fn log_tail_section(label: &str, log: &str, max_lines: usize) -> String {
+    let lines: Vec<&str> = log.lines().collect();
+    if lines.iter().all(|line| line.trim().is_empty()) {
+        return String::new();
+    }
+    let start = lines.len().saturating_sub(max_lines);
+    let heading = if start > 0 {
+        format!("## Log: {} (last {} lines)", label, max_lines)
+    } else {
+        format!("## Log: {}", label)
+    };
+    format!("{}\n\n```\n{}\n```\n\n", heading, lines[start..].join("\n"))
+}
 
-    let mut session = Session::new(project)?;
+/// Text `/copy` takes from a transcript: the final `result`, or the last
+/// assistant `message`
+fn copy_text(transcript: &Transcript, what: &str) -> Option<String> {
+    let text = match what {
+        "result" => transcript.result.as_ref()?.result_text.clone(),
+        "message" => transcript.messages.iter().rev().find_map(|m| match m {
+            Message::Text { text } if !text.trim().is_empty() => Some(text.clone()),
+            _ => None,
+        }),
+        _ => None,
+    }?;
+    (!text.trim().is_empty()).then_some(text)
+}
 
-    // Check .gitignore and offer to add .claude/ if needed
-    check_gitignore(&session.working_dir)?;
+/// A backtick fence longer than any backtick run in `content`, so the
+/// content can't close it early
+fn code_fence(content: &str) -> String {
+    let longest = content.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
 
-    let token_count = session.compile_context()?;
-    println!("Injected context (~{} tokens)\n", token_count);
+/// Formats an /attach file as a fenced context section
+fn attachment_section(label: &str, content: &str, max_bytes: usize, head: bool) -> String {
+    let (shown, part) = if head {
+        (text::truncate(content, max_bytes), "first")
+    } else {
+        (text::tail(content, max_bytes), "last")
+    };
+    let truncated = shown.len() < content.len();
+    let heading = if truncated {
+        format!(
+            "## Attached: {} ({} {} of {} bytes)",
+            label,
+            part,
+            shown.len(),
+            content.len()
+        )
+    } else {
+        format!("## Attached: {}", label)
+    };
+    let fence = code_fence(shown);
+    format!(
+        "{}\n\n{}\n{}\n{}\n\n",
+        heading,
+        fence,
+        shown.trim_end(),
+        fence
+    )
+}
 
-    // Set up readline
-    let mut rl = DefaultEditor::new()?;
-    let history_path = config::config_dir()?.join("history.txt");
-    let _ = rl.load_history(&history_path);
+/// Resolves a user-typed path: `~/` is the home directory, other relative
+/// paths are taken from `base`
+fn resolve_user_path(base: &Path, raw: &str) -> PathBuf {
+    match raw.strip_prefix("~/").zip(dirs::home_dir()) {
+        Some((rest, home)) => home.join(rest),
+        None => base.join(raw),
+    }
+}
 
-    let prompt = format!("{}> ", project_name);
+/// Replaces `{{file:path}}` references in a prompt with the file's contents
+/// in a fenced block, each capped at `max_bytes`
+fn expand_file_refs(prompt: &str, base: &Path, max_bytes: usize) -> Result<String> {
+    let mut out = String::new();
+    let mut rest = prompt;
+    while let Some(start) = rest.find("{{file:") {
+        let Some(len) = rest[start..].find("}}") else {
+            break;
+        };
+        let raw = rest[start + "{{file:".len()..start + len].trim();
+        let path = resolve_user_path(base, raw);
+        let bytes = std::fs::read(&path).with_context(|| {
+            format!(
+                "Failed to read {} (from {{{{file:{}}}}})",
+                path.display(),
+                raw
+            )
+        })?;
+        let content = String::from_utf8_lossy(&bytes);
+        let shown = text::truncate(&content, max_bytes);
+        let note = if shown.len() < content.len() {
+            format!(", first {} of {} bytes", shown.len(), content.len())
+        } else {
+            String::new()
+        };
+        out.push_str(&rest[..start]);
+        let fence = code_fence(shown);
+        out.push_str(&format!(
+            "\n\n({}{}):\n{}\n{}\n{}\n",
+            raw,
+            note,
+            fence,
+            shown.trim_end(),
+            fence
+        ));
+        rest = &rest[start + len + "}}".len()..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
 
-    loop {
-        match rl.readline(&prompt) {
-            Ok(line) => {
-                let line = line.trim();
-                if line.is_empty() {
-                    continue;
-                }
+/// The section title for a note category in compiled context, translated
+/// only when `i18n.localize_context` is set
+fn context_title(category: &str) -> String {
+    i18n::context_text(&format!("context.{}", category), section_title(category))
+}
 
-                rl.add_history_entry(line)?;
+/// Returns the context section title used for a note category
+pub(crate) fn section_title(category: &str) -> &str {
+    match category {
+        "architecture" => "Architectural Context",
+        "decisions" => "Key Decisions",
+        "failures" => "Known Pitfalls",
+        "plan" => "Current Plan",
+        notes::DECISIONS_ARCHIVE => "Archived Decisions",
+        other => other,
+    }
+}
 
-                if line.starts_with('/') {
-                    match session.handle_command(line) {
-                        Ok(should_exit) => {
-                            if should_exit {
-                                break;
-                            }
-                        }
-                        Err(e) => println!("Error: {}", e),
-                    }
-                } else {
-                    // Run as a task
-                    if let Err(e) = session.run_task(line) {
-                        println!("Task error: {}", e);
-                    }
-                }
-            }
-            Err(ReadlineError::Interrupted) => {
-                println!("Use /done or /quit to exit");
-            }
-            Err(ReadlineError::Eof) => {
-                println!("Session complete. {} tasks.", session.task_history.len());
-                break;
-            }
-            Err(err) => {
-                println!("Error: {:?}", err);
-                break;
-            }
+/// Drops lines already present in `seen` and records the rest
+///
+/// Blank lines are kept so paragraph structure survives. Used to keep
+/// inherited sections from repeating entries that appear closer to the project.
+fn dedup_lines(notes: &str, seen: &mut HashSet<String>) -> String {
+    let mut kept = Vec::new();
+    for line in notes.lines() {
+        let key = line.trim();
+        if key.is_empty() || seen.insert(key.to_string()) {
+            kept.push(line);
         }
     }
-
-    // Save history
-    let _ = rl.save_history(&history_path);
-
-    Ok(())
+    kept.join("\n")
 }
 
 /// Truncates a string to max length, adding ... if truncated
 fn truncate_string(s: &str, max_len: usize) -> String {
-    if s.len() <= max_len {
-        s.to_string()
-    } else {
-        format!("{}...", &s[..max_len - 3])
-    }
+    text::truncate_ellipsis(s, max_len)
 }
 
 /// A phase parsed from a plan file
@@ -830,6 +3607,51 @@ struct Phase {
     description: String,
 }
 
+/// Progress of an `/auto` run, kept in the project dir until all phases finish
+const AUTO_CHECKPOINT_FILE: &str = "auto.json";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct AutoCheckpoint {
+    plan_file: PathBuf,
+    total: usize,
+    completed: usize,
+    /// Reported cost of each completed phase's task, for estimating the rest
+    phase_costs: Vec<f64>,
+    started: DateTime<Utc>,
+}
+
+impl AutoCheckpoint {
+    fn load(path: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Marks phase `index` done, recording the cost of the task that ran it
+    ///
+    /// A phase whose task reported no cost adds nothing, so it doesn't pull
+    /// the average towards zero.
+    fn complete_phase(&mut self, index: usize, cost: Option<f64>) {
+        self.completed = index + 1;
+        self.phase_costs.extend(cost);
+    }
+
+    /// Estimated cost of the remaining phases: the average of this run's
+    /// phases, else the given per-task average
+    fn remaining_cost(&self, fallback_average: Option<f64>) -> Option<f64> {
+        let average = if self.phase_costs.is_empty() {
+            fallback_average?
+        } else {
+            self.phase_costs.iter().sum::<f64>() / self.phase_costs.len() as f64
+        };
+        Some(average * self.total.saturating_sub(self.completed) as f64)
+    }
+}
+
 /// Parses phases from a markdown plan file
 /// Looks for ## headers with "Phase" or numbered sections
 fn parse_plan_phases(content: &str) -> Vec<Phase> {
@@ -896,6 +3718,16 @@ fn parse_plan_phases(content: &str) -> Vec<Phase> {
     phases
 }
 
+/// Starts a task log's extra fields with the directory the task ran in
+fn working_dir_field(task_dir: Option<&Path>) -> serde_json::Map<String, serde_json::Value> {
+    let mut fields = serde_json::Map::new();
+    fields.insert(
+        "working_dir".to_string(),
+        task_dir.map(|d| d.display().to_string()).into(),
+    );
+    fields
+}
+
 /// Creates a URL-safe slug from text
 fn create_slug(text: &str) -> String {
     text.chars()
@@ -912,16 +3744,432 @@ fn create_slug(text: &str) -> String {
         .to_string()
 }
 
+/// Short base-36 tag from the clock and process ID, distinct per call in
+/// practice
+fn unique_suffix() -> String {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64);
+    let mut n = (nanos ^ (u64::from(std::process::id()) << 32)) % 36u64.pow(5);
+    let mut suffix = String::new();
+    for _ in 0..5 {
+        suffix.push(std::char::from_digit((n % 36) as u32, 36).unwrap_or('0'));
+        n /= 36;
+    }
+    suffix
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_session_metrics() {
+        let record = |number, raw_output: &str| TaskRecord {
+            number,
+            prompt: String::new(),
+            summary: String::new(),
+            transcript: Transcript::parse(raw_output),
+            artifacts: Vec::new(),
+        };
+        let ok = r#"{"type":"assistant","message":{"content":[{"type":"tool_use","name":"Edit","id":"t1","input":{"file_path":"src/a.rs","old_string":"a","new_string":"b"}}]}}
+{"type":"result","subtype":"success","result":"ok","total_cost_usd":0.25,"usage":{"input_tokens":100,"output_tokens":20,"cache_read_input_tokens":900}}"#;
+        let failed = r#"{"type":"assistant","message":{"content":[{"type":"tool_use","name":"Edit","id":"t2","input":{"file_path":"src/a.rs","old_string":"b","new_string":"c"}}]}}
+{"type":"result","subtype":"error_during_execution","total_cost_usd":0.5}"#;
+
+        let metrics = SessionMetrics::from_records(
+            &[record(0, ""), record(1, ok), record(2, failed)],
+            &config::PricingConfig::default(),
+        );
+        assert_eq!(metrics.tasks, 2);
+        assert_eq!(metrics.succeeded, 1);
+        assert_eq!(metrics.input_tokens, 100);
+        assert_eq!(metrics.tool_counts, vec![("Edit".to_string(), 2)]);
+        assert_eq!(metrics.files_touched, vec!["src/a.rs"]);
+        assert!(metrics
+            .format()
+            .starts_with("Succeeded: 1/2 | Cost: $0.7500 | Tokens: 100 in / 20 out\n"));
+        assert!(metrics
+            .format()
+            .contains("Cache: 900 read / 0 written (90% hit rate) | Saved: ~$0.0024\n"));
+    }
+
+    #[test]
+    fn test_container_args() {
+        let sandbox = config::SandboxConfig {
+            image: "claude-sandbox:latest".to_string(),
+            extra_args: vec!["--network".to_string(), "none".to_string()],
+            ..Default::default()
+        };
+        let args = container_args(
+            &sandbox,
+            "clancy-1-abcde",
+            Path::new("/home/me/app"),
+            None,
+            &[],
+            &["-p".to_string(), "fix it".to_string()],
+        );
+        assert_eq!(
+            args.join(" "),
+            "run --rm -i --name clancy-1-abcde -v /home/me/app:/workspace -w /workspace \
+             -e ANTHROPIC_API_KEY \
+             --network none claude-sandbox:latest claude -p fix it"
+        );
+
+        let args = container_args(
+            &sandbox,
+            "clancy-1-abcde",
+            Path::new("/home/me/app"),
+            Some(Path::new("api")),
+            &[PathBuf::from("/opt/shared")],
+            &[],
+        );
+        assert_eq!(args[8], "/workspace/api");
+        assert_eq!(args[10], "/opt/shared:/opt/shared");
+    }
+
+    #[test]
+    fn test_prompt_indicator() {
+        assert_eq!(prompt_indicator(ConversationMode::Summary, 1.0, None), "");
+        assert_eq!(
+            prompt_indicator(ConversationMode::Full, 4.2, Some(5.0)),
+            "[full! $4.20]"
+        );
+        assert_eq!(
+            prompt_indicator(ConversationMode::Summary, 3.0, Some(5.0)),
+            ""
+        );
+        assert_eq!(
+            prompt_indicator(ConversationMode::Fresh, 0.0, None),
+            "[fresh]"
+        );
+    }
+
+    #[test]
+    fn test_mode_downgrade_steps() {
+        assert!(ConversationMode::Fresh < ConversationMode::Summary);
+        assert_eq!(
+            ConversationMode::Full.lower(),
+            Some(ConversationMode::Summary)
+        );
+        assert_eq!(ConversationMode::Fresh.lower(), None);
+
+        let cap = |spent| ConversationMode::spend_cap(spent, 2.0).name();
+        assert_eq!(cap(1.5), "full");
+        assert_eq!(cap(2.5), "summary");
+        assert_eq!(cap(4.5), "fresh");
+    }
+
+    #[test]
+    fn test_prompt_template_fill() {
+        assert_eq!(
+            i18n::fill(
+                "{project} ({branch}) [{mode}, ${cost}]> ",
+                &[
+                    ("project", &"api"),
+                    ("branch", &"main"),
+                    ("mode", &"full"),
+                    ("cost", &"4.20")
+                ],
+            ),
+            "api (main) [full, $4.20]> "
+        );
+        assert_eq!(i18n::fill("{unknown}> ", &[]), "{unknown}> ");
+        // A branch named like a placeholder is shown as is
+        assert_eq!(
+            i18n::fill(
+                "({branch}) {mode}> ",
+                &[("branch", &"{mode}"), ("mode", &"full")]
+            ),
+            "({mode}) full> "
+        );
+    }
+
+    #[test]
+    fn test_readline_config() {
+        let mut repl = config::ReplConfig {
+            edit_mode: "vi".to_string(),
+            history_size: 50,
+            ..Default::default()
+        };
+        let rl_config = readline_config(&repl).unwrap();
+        assert_eq!(rl_config.edit_mode(), EditMode::Vi);
+        assert_eq!(rl_config.max_history_size(), 50);
+
+        repl.edit_mode = "nano".to_string();
+        assert!(readline_config(&repl).is_err());
+    }
+
+    #[test]
+    fn test_dedup_history() {
+        assert_eq!(
+            dedup_history("#V2\n/status\nfix the bug\n/status\nrun tests\n"),
+            "#V2\nfix the bug\n/status\nrun tests\n"
+        );
+    }
+
+    /// Writes a project dir with the given creation time under `projects`
+    fn history_project(projects: &Path, name: &str, created: &str) -> PathBuf {
+        let dir = projects.join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut metadata = project::ProjectMetadata::new(name);
+        metadata.created = created.parse().unwrap();
+        std::fs::write(
+            dir.join(project::METADATA_FILE),
+            toml::to_string(&metadata).unwrap(),
+        )
+        .unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_migrate_history_copies_global_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let global = dir.path().join("history.txt");
+        std::fs::write(&global, "#V2\nfix the bug\n").unwrap();
+        let old = history_project(dir.path(), "old", "2020-01-01T00:00:00Z");
+
+        migrate_history(&global, dir.path());
+        assert_eq!(
+            std::fs::read_to_string(old.join("history.txt")).unwrap(),
+            "#V2\nfix the bug\n"
+        );
+        assert!(!global.exists());
+        assert!(dir.path().join("history.txt.migrated").exists());
+
+        // Projects created after the migration never get a copy
+        let later = history_project(dir.path(), "later", "2020-01-01T00:00:00Z");
+        migrate_history(&global, dir.path());
+        assert!(!later.join("history.txt").exists());
+    }
+
+    #[test]
+    fn test_migrate_history_skips_newer_and_existing_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let global = dir.path().join("history.txt");
+        std::fs::write(&global, "#V2\nfix the bug\n").unwrap();
+        let newer = history_project(dir.path(), "newer", "2999-01-01T00:00:00Z");
+        let own = history_project(dir.path(), "own", "2020-01-01T00:00:00Z");
+        std::fs::write(own.join("history.txt"), "#V2\nrun tests\n").unwrap();
+
+        migrate_history(&global, dir.path());
+        assert!(!newer.join("history.txt").exists());
+        assert_eq!(
+            std::fs::read_to_string(own.join("history.txt")).unwrap(),
+            "#V2\nrun tests\n"
+        );
+    }
+
+    #[test]
+    fn test_subdirectory_accepts_nested_dir() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join("crates/api")).unwrap();
+        assert_eq!(
+            subdirectory(root.path(), "crates/api").unwrap(),
+            PathBuf::from("crates/api")
+        );
+    }
+
+    #[test]
+    fn test_subdirectory_refuses_symlink_out() {
+        let root = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::os::unix::fs::symlink(outside.path(), root.path().join("escape")).unwrap();
+        assert!(subdirectory(root.path(), "escape").is_err());
+    }
+
+    #[test]
+    fn test_process_alive() {
+        assert!(process_alive(u64::from(std::process::id())));
+        let mut child = Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+        assert!(!process_alive(u64::from(pid)));
+    }
+
+    #[test]
+    fn test_decode_output_line() {
+        assert_eq!(
+            decode_output_line(b"{\"type\":\"result\"}"),
+            ("{\"type\":\"result\"}".to_string(), false)
+        );
+
+        let (line, lossy) = decode_output_line(b"{\"type\":\"assistant\",\"text\":\"bin \xff\"}\r");
+        assert!(lossy);
+        let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(json["lossy_utf8"], true);
+        assert_eq!(json["text"], "bin \u{fffd}");
+
+        let (line, _) = decode_output_line(b"plain \xfe");
+        assert_eq!(Transcript::parse(&line).lossy_lines, 1);
+    }
+
+    #[test]
+    fn test_context_sections() {
+        let content =
+            "<!-- header -->\n\n## Plan\n\n12345678\n\n## Failures\n\nx\n---\nfooter text\n";
+        let sections = context_sections(content);
+        let titles: Vec<&str> = sections.iter().map(|(t, _)| t.as_str()).collect();
+        assert_eq!(titles, vec!["(header)", "Plan", "Failures", "(footer)"]);
+        assert_eq!(sections[1].1, "## Plan\n\n12345678\n\n".len() / 4);
+    }
+
+    #[test]
+    fn test_log_tail_section() {
+        assert_eq!(
+            log_tail_section("ci.log", "a\nb\nc\n", 2),
+            "## Log: ci.log (last 2 lines)\n\n```\nb\nc\n```\n\n"
+        );
+        assert_eq!(
+            log_tail_section("ci.log", "error: boom\n", 80),
+            "## Log: ci.log\n\n```\nerror: boom\n```\n\n"
+        );
+        assert_eq!(log_tail_section("ci.log", "\n  \n", 80), "");
+    }
+
+    #[test]
+    fn test_attachment_section_caps_bytes() {
+        assert_eq!(
+            attachment_section("spec.json", "{\"a\": 1}\n", 100, true),
+            "## Attached: spec.json\n\n```\n{\"a\": 1}\n```\n\n"
+        );
+        assert_eq!(
+            attachment_section("x.txt", "abcdef", 4, true),
+            "## Attached: x.txt (first 4 of 6 bytes)\n\n```\nabcd\n```\n\n"
+        );
+    }
+
+    #[test]
+    fn test_attachment_section_keeps_log_tail() {
+        assert_eq!(
+            attachment_section("ci.log", "setup ok\nerror: boom\n", 12, false),
+            "## Attached: ci.log (last 12 of 21 bytes)\n\n```\nerror: boom\n```\n\n"
+        );
+    }
+
+    #[test]
+    fn test_attachment_section_fence_outlasts_backticks() {
+        assert_eq!(
+            attachment_section("README.md", "Run:\n```\nmake\n```\n", 100, false),
+            "## Attached: README.md\n\n````\nRun:\n```\nmake\n```\n````\n\n"
+        );
+    }
+
+    #[test]
+    fn test_expand_file_refs() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(temp_dir.path().join("err.log"), "boom\n").unwrap();
+        let out =
+            expand_file_refs("Fix this: {{file:err.log}} please", temp_dir.path(), 100).unwrap();
+        assert_eq!(out, "Fix this: \n\n(err.log):\n```\nboom\n```\n please");
+        assert_eq!(
+            expand_file_refs("no refs {{here}}", temp_dir.path(), 100).unwrap(),
+            "no refs {{here}}"
+        );
+        assert!(expand_file_refs("{{file:missing.txt}}", temp_dir.path(), 100).is_err());
+    }
+
+    #[test]
+    fn test_parse_directives() {
+        let (directives, prompt) = parse_directives("@noextract run the tests again");
+        assert!(directives.no_extract);
+        assert_eq!(prompt, "run the tests again");
+
+        let (directives, prompt) = parse_directives("@dir=services/api @noextract fix auth");
+        assert_eq!(
+            directives,
+            TaskDirectives {
+                no_extract: true,
+                dir: Some("services/api".to_string()),
+                ..TaskDirectives::default()
+            }
+        );
+        assert_eq!(prompt, "fix auth");
+
+        let (directives, prompt) = parse_directives("@model=opus @agent=reviewer check it");
+        assert_eq!(directives.model.as_deref(), Some("opus"));
+        assert_eq!(directives.agent.as_deref(), Some("reviewer"));
+        assert_eq!(prompt, "check it");
+
+        let (directives, prompt) = parse_directives("@ask how does auth work?");
+        assert!(directives.ask);
+        assert_eq!(prompt, "how does auth work?");
+
+        let (directives, prompt) = parse_directives("@noextractor x");
+        assert_eq!(directives, TaskDirectives::default());
+        assert_eq!(prompt, "@noextractor x");
+    }
+
+    #[test]
+    fn test_strip_quotes() {
+        assert_eq!(strip_quotes("\"rustls 0.22 breaks\""), "rustls 0.22 breaks");
+        assert_eq!(strip_quotes("'x'"), "x");
+        assert_eq!(strip_quotes("don't"), "don't");
+    }
+
+    #[test]
+    fn test_last_assistant_paragraph() {
+        let output = r#"{"type":"assistant","message":{"content":[{"type":"text","text":"First reply"}]}}
+{"type":"assistant","message":{"content":[{"type":"text","text":"Done.\n\nThe cache must be cleared after migrations.\n\n"}]}}"#;
+        assert_eq!(
+            last_assistant_paragraph(&Transcript::parse(output)).as_deref(),
+            Some("The cache must be cleared after migrations.")
+        );
+        assert_eq!(last_assistant_paragraph(&Transcript::parse("")), None);
+    }
+
+    #[test]
+    fn test_copy_text() {
+        let output = r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Fixed the retry loop."}]}}
+{"type":"result","subtype":"success","result":"All tests pass."}"#;
+        let transcript = Transcript::parse(output);
+        assert_eq!(
+            copy_text(&transcript, "result").as_deref(),
+            Some("All tests pass.")
+        );
+        assert_eq!(
+            copy_text(&transcript, "message").as_deref(),
+            Some("Fixed the retry loop.")
+        );
+        assert_eq!(copy_text(&transcript, "diff"), None);
+        assert_eq!(copy_text(&Transcript::parse(""), "result"), None);
+    }
+
     #[test]
     fn test_truncate_string() {
         assert_eq!(truncate_string("hello", 10), "hello");
         assert_eq!(truncate_string("hello world", 8), "hello...");
     }
 
+    #[test]
+    fn test_dedup_lines_skips_seen_entries() {
+        let mut seen = HashSet::new();
+        dedup_lines("- Uses repository pattern\n- Async handlers", &mut seen);
+        let inherited = dedup_lines(
+            "- Uses repository pattern\n\n- Errors via anyhow",
+            &mut seen,
+        );
+        assert_eq!(inherited, "\n- Errors via anyhow");
+    }
+
+    #[test]
+    fn test_dedup_lines_ignores_indentation() {
+        let mut seen = HashSet::new();
+        dedup_lines("- Shared entry", &mut seen);
+        assert_eq!(dedup_lines("  - Shared entry  ", &mut seen), "");
+    }
+
+    #[test]
+    fn test_unique_suffix() {
+        let suffix = unique_suffix();
+        assert_eq!(suffix.len(), 5);
+        assert!(suffix.chars().all(|c| c.is_ascii_alphanumeric()));
+        assert_eq!(
+            tasklog::task_number_from_filename(&format!("042-fix-auth-{}.json", suffix)),
+            Some(42)
+        );
+    }
+
     #[test]
     fn test_create_slug() {
         assert_eq!(create_slug("Fix the auth bug"), "fix-the-auth-bug");
@@ -977,6 +4225,38 @@ Do the second thing.
         assert_eq!(phases[1].title, "Second Step");
     }
 
+    #[test]
+    fn test_auto_checkpoint_remaining_cost() {
+        let mut checkpoint = AutoCheckpoint {
+            plan_file: PathBuf::from("/tmp/PLAN.md"),
+            total: 4,
+            completed: 0,
+            phase_costs: Vec::new(),
+            started: Utc::now(),
+        };
+        assert_eq!(checkpoint.remaining_cost(None), None);
+        assert_eq!(checkpoint.remaining_cost(Some(0.5)), Some(2.0));
+
+        checkpoint.completed = 2;
+        checkpoint.phase_costs = vec![0.25, 0.75];
+        assert_eq!(checkpoint.remaining_cost(Some(5.0)), Some(1.0));
+    }
+
+    #[test]
+    fn test_complete_phase_skips_unknown_cost() {
+        let mut checkpoint = AutoCheckpoint {
+            plan_file: PathBuf::from("PLAN.md"),
+            total: 3,
+            completed: 0,
+            phase_costs: Vec::new(),
+            started: Utc::now(),
+        };
+        checkpoint.complete_phase(0, Some(0.5));
+        checkpoint.complete_phase(1, None);
+        assert_eq!(checkpoint.completed, 2);
+        assert_eq!(checkpoint.phase_costs, vec![0.5]);
+    }
+
     #[test]
     fn test_gitignore_has_claude_entry_with_trailing_slash() {
         assert!(gitignore_has_claude_entry(".claude/"));
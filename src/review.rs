@@ -0,0 +1,166 @@
+//! Automated reviewer pass after each task
+//!
+//! When `review.enabled` is set, a second Claude call reads the task prompt,
+//! the changes it made, and the project's known failures, then returns a
+//! short verdict. Problems it flags are appended to the failures notes and
+//! follow-ups to the plan.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::config::load_config;
+use crate::diff::file_diffs;
+use crate::extraction::complete;
+use crate::project::Project;
+use crate::text;
+use crate::transcript::Transcript;
+
+/// Max bytes of diff text sent to the reviewer
+const MAX_DIFF_BYTES: usize = 12000;
+
+/// Reviewer output for one task
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Review {
+    /// One-line verdict, e.g. "PASS — change is minimal and tested"
+    pub verdict: String,
+    /// Problems to remember, as failures-style bullets
+    pub failures: Option<String>,
+    /// Follow-up items for the plan
+    pub plan: Option<String>,
+}
+
+/// Asks Claude to critique a finished task
+pub async fn review_task(
+    project: &Project,
+    transcript: &Transcript,
+    prompt: &str,
+) -> Result<Review> {
+    let config = load_config()?;
+    let review_prompt = build_review_prompt(project, transcript, prompt)?;
+    let (response, _model) = complete(&config, &review_prompt).await?;
+    Ok(parse_review_response(&response))
+}
+
+fn build_review_prompt(project: &Project, transcript: &Transcript, prompt: &str) -> Result<String> {
+    let failures = project.read_notes("failures")?;
+
+    let mut diffs = String::new();
+    for diff in file_diffs(transcript) {
+        diffs.push_str(&diff.to_unified());
+    }
+    if diffs.len() > MAX_DIFF_BYTES {
+        diffs.truncate(text::truncate(&diffs, MAX_DIFF_BYTES).len());
+        diffs.push_str("\n[... diff truncated ...]\n");
+    }
+
+    Ok(format!(
+        r#"You are reviewing the work of a coding agent. Be brief and concrete.
+
+## Task
+{prompt}
+
+## Agent's Summary
+{summary}
+
+## Changes
+{diffs}
+
+## Known Failures (mistakes this project has hit before)
+{failures}
+
+---
+
+Check whether the changes accomplish the task, repeat a known failure, or
+introduce an obvious bug, missing test, or unfinished step.
+
+Output format (use exactly these headers):
+
+VERDICT: PASS|CONCERNS|FAIL — one sentence
+
+### FAILURES
+[problems worth remembering, as "- Don't ..." bullets, or NO_UPDATES]
+
+### PLAN
+[follow-up work as "- " bullets, or NO_UPDATES]"#,
+        prompt = prompt,
+        summary = transcript.generate_summary(),
+        diffs = if diffs.is_empty() {
+            "(no file edits)"
+        } else {
+            &diffs
+        },
+        failures = if failures.trim().is_empty() {
+            "(none)"
+        } else {
+            &failures
+        },
+    ))
+}
+
+/// Parses the reviewer's response; missing pieces are left empty
+fn parse_review_response(response: &str) -> Review {
+    let verdict = response
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("VERDICT:"))
+        .map(|v| v.trim().to_string())
+        .unwrap_or_else(|| "(no verdict)".to_string());
+
+    let section = |header: &str, next: Option<&str>| -> Option<String> {
+        let start = response.find(header)? + header.len();
+        let end = next
+            .and_then(|n| response[start..].find(n))
+            .map(|pos| start + pos)
+            .unwrap_or(response.len());
+        let content = response[start..end].trim();
+        (!content.is_empty() && !content.starts_with("NO_UPDATES")).then(|| content.to_string())
+    };
+
+    Review {
+        verdict,
+        failures: section("### FAILURES", Some("### PLAN")),
+        plan: section("### PLAN", None),
+    }
+}
+
+/// Appends flagged problems to failures and follow-ups to the plan
+///
+/// Unlike extraction, the plan is appended to so the reviewer can't wipe it.
+pub fn apply_review(project: &Project, review: &Review) -> Result<()> {
+    if let Some(ref failures) = review.failures {
+        project.append_notes("failures", failures)?;
+    }
+    if let Some(ref plan) = review.plan {
+        let existing = project.read_notes("plan")?;
+        let content = if existing.trim().is_empty() {
+            plan.clone()
+        } else {
+            format!("{}\n{}", existing.trim_end(), plan)
+        };
+        project.write_notes("plan", &content)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_review_response() {
+        let response = "VERDICT: CONCERNS — retry loop has no backoff\n\n### FAILURES\n- Don't retry without backoff — hammers the API\n\n### PLAN\nNO_UPDATES\n";
+        let review = parse_review_response(response);
+        assert_eq!(review.verdict, "CONCERNS — retry loop has no backoff");
+        assert_eq!(
+            review.failures.as_deref(),
+            Some("- Don't retry without backoff — hammers the API")
+        );
+        assert!(review.plan.is_none());
+    }
+
+    #[test]
+    fn test_parse_review_response_without_verdict() {
+        let review = parse_review_response("looks fine");
+        assert_eq!(review.verdict, "(no verdict)");
+        assert!(review.failures.is_none());
+    }
+}
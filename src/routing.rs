@@ -0,0 +1,141 @@
+//! Per-task model and agent routing
+//!
+//! `[[routing.rules]]` in config.toml send tasks to a model or agent by tag
+//! or prompt length, e.g. `#docs` and short prompts to haiku, `#arch` to
+//! opus. A task's `@model=` or `@agent=` directive overrides the rules.
+
+use serde::Serialize;
+
+use crate::config::RouteRule;
+
+/// The model and agent chosen for a task, recorded in its log
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct Route {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    /// What picked this route, e.g. "#docs", "max_chars=200", or "@model"
+    pub reason: String,
+}
+
+/// Picks the route for a prompt: the first matching rule, with any
+/// directive overrides applied on top
+///
+/// Returns None when no rule matches and nothing is overridden, so the
+/// claude CLI's own defaults apply.
+pub fn route_task(
+    rules: &[RouteRule],
+    prompt: &str,
+    model_override: Option<&str>,
+    agent_override: Option<&str>,
+) -> Option<Route> {
+    let mut route = rules
+        .iter()
+        .enumerate()
+        .find(|(_, rule)| rule_matches(rule, prompt))
+        .map(|(index, rule)| Route {
+            model: rule.model.clone(),
+            agent: rule.agent.clone(),
+            reason: rule_label(rule, index),
+        });
+
+    let mut overrides = Vec::new();
+    if let Some(model) = model_override {
+        overrides.push("@model");
+        route.get_or_insert_with(Route::default).model =
+            (model != "default").then(|| model.to_string());
+    }
+    if let Some(agent) = agent_override {
+        overrides.push("@agent");
+        route.get_or_insert_with(Route::default).agent =
+            (agent != "default").then(|| agent.to_string());
+    }
+    if let Some(route) = route.as_mut().filter(|_| !overrides.is_empty()) {
+        route.reason = overrides.join(", ");
+    }
+    route.filter(|r| r.model.is_some() || r.agent.is_some())
+}
+
+fn rule_matches(rule: &RouteRule, prompt: &str) -> bool {
+    let tagged = rule.tag.as_deref().is_none_or(|tag| {
+        prompt
+            .split_whitespace()
+            .any(|word| word.eq_ignore_ascii_case(tag))
+    });
+    let short = rule
+        .max_chars
+        .is_none_or(|max| prompt.chars().count() <= max);
+    tagged && short
+}
+
+/// Describes a rule by its conditions for the task log
+fn rule_label(rule: &RouteRule, index: usize) -> String {
+    let mut parts = Vec::new();
+    if let Some(ref tag) = rule.tag {
+        parts.push(tag.clone());
+    }
+    if let Some(max) = rule.max_chars {
+        parts.push(format!("max_chars={}", max));
+    }
+    if parts.is_empty() {
+        format!("rule {}", index + 1)
+    } else {
+        parts.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(tag: Option<&str>, max_chars: Option<usize>, model: &str) -> RouteRule {
+        RouteRule {
+            tag: tag.map(String::from),
+            max_chars,
+            model: Some(model.to_string()),
+            agent: None,
+        }
+    }
+
+    #[test]
+    fn test_route_task_rules() {
+        let rules = vec![
+            rule(Some("#arch"), None, "opus"),
+            rule(Some("#docs"), None, "haiku"),
+            rule(None, Some(20), "haiku"),
+        ];
+        let route = route_task(&rules, "#arch redesign the storage layer", None, None).unwrap();
+        assert_eq!(route.model.as_deref(), Some("opus"));
+        assert_eq!(route.reason, "#arch");
+
+        let route = route_task(&rules, "fix typo", None, None).unwrap();
+        assert_eq!(route.model.as_deref(), Some("haiku"));
+        assert_eq!(route.reason, "max_chars=20");
+
+        // Tags match whole words only
+        assert_eq!(
+            route_task(&rules, "update the #docsite build config", None, None),
+            None
+        );
+    }
+
+    #[test]
+    fn test_route_task_overrides() {
+        let rules = vec![rule(Some("#docs"), None, "haiku")];
+        let route = route_task(&rules, "#docs tidy", Some("sonnet"), Some("writer")).unwrap();
+        assert_eq!(route.model.as_deref(), Some("sonnet"));
+        assert_eq!(route.agent.as_deref(), Some("writer"));
+        assert_eq!(route.reason, "@model, @agent");
+
+        // "default" drops the rule's choice entirely
+        assert_eq!(
+            route_task(&rules, "#docs tidy", Some("default"), None),
+            None
+        );
+        assert_eq!(
+            route_task(&[], "anything", Some("opus"), None).map(|r| r.model),
+            Some(Some("opus".to_string()))
+        );
+    }
+}
@@ -0,0 +1,230 @@
+//! Full-text search over notes and task logs
+//!
+//! `clancy search` looks for a phrase (case-insensitive) in every note file
+//! and in each task log's prompt, summary, assistant replies, result, and
+//! annotations, and prints grep-style matches with surrounding lines.
+
+use anyhow::Result;
+
+use crate::project::{self, Project};
+use crate::tasklog::{self, TaskLog};
+use crate::transcript::Message;
+
+/// Characters of a long line shown around the match
+const SNIPPET_CHARS: usize = 160;
+
+/// A run of lines around one or more matches, as `(line number, text,
+/// is_match)`
+type Block<'a> = Vec<(usize, &'a str, bool)>;
+
+/// Searches notes and task logs of one project, or all projects
+pub fn search(query: &str, project_name: Option<&str>, context: usize, limit: usize) -> Result<()> {
+    let query = query.trim();
+    if query.is_empty() {
+        anyhow::bail!("Usage: clancy search <text>");
+    }
+    let projects = match project_name {
+        Some(name) => vec![Project::open(name)?],
+        None => project::load_all_projects()?,
+    };
+
+    let mut shown = 0;
+    let mut total = 0;
+    for project in &projects {
+        for (label, text) in searchable_texts(project)? {
+            for block in match_blocks(&text, query, context) {
+                total += block.iter().filter(|(_, _, is_match)| *is_match).count();
+                if shown >= limit {
+                    continue;
+                }
+                if shown > 0 && context > 0 {
+                    println!("--");
+                }
+                for (line_no, line, is_match) in &block {
+                    let sep = if *is_match { ':' } else { '-' };
+                    println!(
+                        "{}/{}{}{}{} {}",
+                        project.metadata.name,
+                        label,
+                        sep,
+                        line_no,
+                        sep,
+                        snippet(line, query)
+                    );
+                }
+                shown += block.iter().filter(|(_, _, is_match)| *is_match).count();
+            }
+        }
+    }
+
+    if total == 0 {
+        println!("No matches for \"{}\".", query);
+    } else if total > shown {
+        println!(
+            "\n{} of {} matches shown; raise --limit to see more.",
+            shown, total
+        );
+    }
+    Ok(())
+}
+
+/// Every searchable text in a project with its label: note files by path,
+/// task log fields as `task N <field>`
+fn searchable_texts(project: &Project) -> Result<Vec<(String, String)>> {
+    let mut texts = Vec::new();
+
+    let notes_dir = project.path.join("notes");
+    if notes_dir.exists() {
+        let mut paths: Vec<_> = std::fs::read_dir(&notes_dir)?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "md"))
+            .collect();
+        paths.sort();
+        for path in paths {
+            if let Ok(content) = std::fs::read_to_string(&path) {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                texts.push((format!("notes/{}", name), content));
+            }
+        }
+    }
+
+    for (_, path) in tasklog::task_log_paths(project)? {
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        // Damaged logs are fsck's business, not search's
+        let Ok(log) = serde_json::from_str::<TaskLog>(&content) else {
+            continue;
+        };
+        texts.extend(task_texts(&log));
+    }
+    Ok(texts)
+}
+
+/// The searchable fields of one task log
+fn task_texts(log: &TaskLog) -> Vec<(String, String)> {
+    let label = |field: &str| format!("task {} {}", log.task_number, field);
+    let mut texts = vec![(label("prompt"), log.prompt.clone())];
+    if !log.summary.is_empty() {
+        texts.push((label("summary"), log.summary.clone()));
+    }
+    let replies: Vec<&str> = log
+        .transcript
+        .messages
+        .iter()
+        .filter_map(|msg| match msg {
+            Message::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    if !replies.is_empty() {
+        texts.push((label("reply"), replies.join("\n")));
+    }
+    if let Some(result) = log
+        .transcript
+        .result
+        .as_ref()
+        .and_then(|r| r.result_text.clone())
+    {
+        texts.push((label("result"), result));
+    }
+    for annotation in &log.annotations {
+        texts.push((label("annotation"), annotation.text.clone()));
+    }
+    texts
+}
+
+/// Lines matching `query` (case-insensitive) with `context` lines either
+/// side, overlapping windows merged into one block
+fn match_blocks<'a>(text: &'a str, query: &str, context: usize) -> Vec<Block<'a>> {
+    let query = query.to_lowercase();
+    let lines: Vec<&str> = text.lines().collect();
+    let matches: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.to_lowercase().contains(&query))
+        .map(|(i, _)| i)
+        .collect();
+
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut end = 0;
+    for &i in &matches {
+        let start = i.saturating_sub(context);
+        let stop = (i + context + 1).min(lines.len());
+        let block_start = match blocks.last_mut() {
+            Some(_) if start <= end => end,
+            _ => {
+                blocks.push(Vec::new());
+                start
+            }
+        };
+        let block = blocks.last_mut().expect("block pushed above");
+        for (j, line) in lines.iter().enumerate().take(stop).skip(block_start) {
+            block.push((j + 1, line, matches.binary_search(&j).is_ok()));
+        }
+        end = end.max(stop);
+    }
+    blocks
+}
+
+/// A long line cut down to the part around the first match
+fn snippet(line: &str, query: &str) -> String {
+    let chars: Vec<char> = line.trim_end().chars().collect();
+    if chars.len() <= SNIPPET_CHARS {
+        return chars.into_iter().collect();
+    }
+    let lower: Vec<char> = line.to_lowercase().chars().collect();
+    let query: Vec<char> = query.to_lowercase().chars().collect();
+    let at = lower
+        .windows(query.len().max(1))
+        .position(|w| w == query.as_slice())
+        .unwrap_or(0)
+        .min(chars.len());
+    let start = at.saturating_sub(SNIPPET_CHARS / 3);
+    let end = (start + SNIPPET_CHARS).min(chars.len());
+    let mut out = String::new();
+    if start > 0 {
+        out.push('…');
+    }
+    out.extend(&chars[start..end]);
+    if end < chars.len() {
+        out.push('…');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_blocks_merges_context() {
+        let text = "a\nuse SQLx here\nb\nc\nsqlx again\nd\ne\nf\nsqlx last";
+        let blocks = match_blocks(text, "sqlx", 1);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(
+            blocks[0],
+            vec![
+                (1, "a", false),
+                (2, "use SQLx here", true),
+                (3, "b", false),
+                (4, "c", false),
+                (5, "sqlx again", true),
+                (6, "d", false),
+            ]
+        );
+        assert_eq!(blocks[1], vec![(8, "f", false), (9, "sqlx last", true)]);
+        assert!(match_blocks(text, "postgres", 1).is_empty());
+    }
+
+    #[test]
+    fn test_snippet_centers_long_lines() {
+        assert_eq!(snippet("short line", "line"), "short line");
+        let long = format!("{}decided on sqlx{}", "x".repeat(300), "y".repeat(300));
+        let out = snippet(&long, "SQLX");
+        assert!(out.starts_with('…') && out.ends_with('…'));
+        assert!(out.contains("decided on sqlx"));
+        assert_eq!(out.chars().count(), SNIPPET_CHARS + 2);
+    }
+}
@@ -0,0 +1,113 @@
+//! Claude Code settings managed from project.toml
+//!
+//! A project's `[claude_settings]` table mirrors `.claude/settings.json`
+//! (permissions, hooks, env, ...). At session start it's merged into the
+//! working dir's settings file so guardrails live with the project's notes.
+//! Keys not set in project.toml are left as they are.
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::path::Path;
+
+use crate::project::Project;
+
+/// Merges the project's `[claude_settings]` into `.claude/settings.json`
+/// under `working_dir`, returning whether the file changed
+pub fn sync_claude_settings(project: &Project, working_dir: &Path) -> Result<bool> {
+    let Some(managed) = &project.metadata.claude_settings else {
+        return Ok(false);
+    };
+    let path = working_dir.join(".claude").join("settings.json");
+    let existing = match std::fs::read_to_string(&path) {
+        Ok(content) => Some(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    let mut settings = match &existing {
+        Some(content) => serde_json::from_str(content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?,
+        None => Value::Object(Default::default()),
+    };
+    merge_json(&mut settings, &serde_json::to_value(managed)?);
+
+    let content = format!("{}\n", serde_json::to_string_pretty(&settings)?);
+    if existing.as_deref() == Some(content.as_str()) {
+        return Ok(false);
+    }
+    std::fs::create_dir_all(working_dir.join(".claude"))?;
+    std::fs::write(&path, content)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(true)
+}
+
+/// Recursively merges `overlay` into `base`: objects merge key by key, any
+/// other value (including arrays) replaces what was there
+fn merge_json(base: &mut Value, overlay: &Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_merge_json() {
+        let mut settings = json!({
+            "permissions": {"allow": ["Bash(ls)"], "deny": ["Read(.env)"]},
+            "model": "sonnet"
+        });
+        merge_json(
+            &mut settings,
+            &json!({"permissions": {"allow": ["Bash(cargo test:*)"]}, "env": {"CI": "1"}}),
+        );
+        assert_eq!(
+            settings,
+            json!({
+                "permissions": {"allow": ["Bash(cargo test:*)"], "deny": ["Read(.env)"]},
+                "model": "sonnet",
+                "env": {"CI": "1"}
+            })
+        );
+    }
+
+    #[test]
+    fn test_sync_claude_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut project = Project::new(
+            crate::project::ProjectMetadata::new("api"),
+            dir.path().join("project"),
+        );
+        assert!(!sync_claude_settings(&project, dir.path()).unwrap());
+
+        project.metadata.claude_settings =
+            Some(toml::from_str("[env]\nRUST_BACKTRACE = \"1\"").unwrap());
+        std::fs::create_dir(dir.path().join(".claude")).unwrap();
+        std::fs::write(
+            dir.path().join(".claude/settings.json"),
+            r#"{"env": {"FOO": "bar"}}"#,
+        )
+        .unwrap();
+        assert!(sync_claude_settings(&project, dir.path()).unwrap());
+        // Unchanged on the second run
+        assert!(!sync_claude_settings(&project, dir.path()).unwrap());
+
+        let written: Value = serde_json::from_str(
+            &std::fs::read_to_string(dir.path().join(".claude/settings.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(written["env"], json!({"FOO": "bar", "RUST_BACKTRACE": "1"}));
+    }
+}
@@ -0,0 +1,775 @@
+//! Reports aggregated across a project's history
+//!
+//! `clancy stats usage` (or plain `clancy stats`) totals spend, tokens, and
+//! durations recorded in task logs, per project and per day or week, with
+//! a "Key sessions" appendix listing starred tasks.
+//! `clancy stats failures` groups failure notes and the errors of failed
+//! tasks by word overlap, so recurring problems stand out from one-offs.
+//! `clancy stats templates` tracks task template runs over time.
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Datelike, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::notes;
+use crate::project::{self, Project};
+use crate::tasklog::{self, TaskLog};
+use crate::text;
+use crate::transcript::Message;
+
+/// Word-set overlap at which two failures count as the same problem
+const CLUSTER_SIMILARITY: f64 = 0.3;
+
+/// Words too common in failure text to say anything about the problem
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "can", "didn", "do", "don", "for",
+    "from", "has", "have", "in", "into", "is", "it", "its", "not", "of", "on", "or", "so", "t",
+    "that", "the", "then", "this", "to", "was", "when", "with", "without",
+];
+
+/// Where a failure came from
+#[derive(Debug, Clone, PartialEq)]
+enum Source {
+    Note { project: String },
+    Task { project: String, number: u32 },
+}
+
+/// A group of similar failures, led by the first one seen
+#[derive(Debug)]
+struct Cluster {
+    text: String,
+    words: BTreeSet<String>,
+    sources: Vec<Source>,
+}
+
+/// Prints the most frequent recurring failures for a project, or for every
+/// project when `project_name` is None
+pub fn failure_report(project_name: Option<&str>, limit: usize) -> Result<()> {
+    let projects = match project_name {
+        Some(name) => vec![Project::open(name)?],
+        None => project::load_all_projects()?,
+    };
+
+    let mut failures = Vec::new();
+    for project in &projects {
+        collect_failures(project, &mut failures)?;
+    }
+    if failures.is_empty() {
+        println!("No failure notes or failed tasks found.");
+        return Ok(());
+    }
+
+    let total = failures.len();
+    let clusters = cluster_failures(failures);
+    let recurring = clusters.iter().filter(|c| c.sources.len() > 1).count();
+    println!(
+        "{} failures in {} groups ({} recurring)\n",
+        total,
+        clusters.len(),
+        recurring
+    );
+    for cluster in clusters.iter().take(limit) {
+        println!(
+            "{:>3}x  {}",
+            cluster.sources.len(),
+            text::truncate_ellipsis(cluster.text.lines().next().unwrap_or(""), 100)
+        );
+        println!(
+            "      {}",
+            describe_sources(&cluster.sources, projects.len() > 1)
+        );
+    }
+    Ok(())
+}
+
+/// Gathers failure note entries and failed-task errors from one project
+fn collect_failures(project: &Project, out: &mut Vec<(String, Source)>) -> Result<()> {
+    let name = &project.metadata.name;
+    for entry in notes::parse_entries(&project.read_notes("failures")?) {
+        let text = entry.text.trim_start_matches(['-', '*']).trim();
+        out.push((
+            text.to_string(),
+            Source::Note {
+                project: name.clone(),
+            },
+        ));
+    }
+    for (number, path) in tasklog::task_log_paths(project)? {
+        // Unreadable logs are skipped rather than failing the whole report
+        let Ok(log) = TaskLog::load(&path) else {
+            continue;
+        };
+        if let Some(error) = task_error(&log) {
+            out.push((
+                error,
+                Source::Task {
+                    project: name.clone(),
+                    number,
+                },
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The error text of a failed task: its result text, or the last failing
+/// tool output
+fn task_error(log: &TaskLog) -> Option<String> {
+    if log.success || log.interrupted {
+        return None;
+    }
+    let result_text = log
+        .transcript
+        .result
+        .as_ref()
+        .and_then(|r| r.result_text.clone());
+    let tool_error = || {
+        log.transcript.messages.iter().rev().find_map(|m| match m {
+            Message::ToolResult {
+                output,
+                is_error: true,
+                ..
+            } => Some(output.clone()),
+            _ => None,
+        })
+    };
+    result_text.or_else(tool_error).and_then(|text| {
+        text.lines()
+            .map(str::trim)
+            .find(|l| !l.is_empty())
+            .map(String::from)
+    })
+}
+
+/// Lowercased content words, without stopwords and numbers
+fn signature(text: &str) -> BTreeSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|w| w.len() > 1 && !w.chars().all(|c| c.is_ascii_digit()))
+        .filter(|w| !STOPWORDS.contains(&w.as_str()))
+        .collect()
+}
+
+fn similarity(a: &BTreeSet<String>, b: &BTreeSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// Groups failures greedily by similarity to each group's first failure,
+/// largest groups first
+fn cluster_failures(failures: Vec<(String, Source)>) -> Vec<Cluster> {
+    let mut clusters: Vec<Cluster> = Vec::new();
+    for (text, source) in failures {
+        let words = signature(&text);
+        match clusters
+            .iter_mut()
+            .find(|c| similarity(&c.words, &words) >= CLUSTER_SIMILARITY)
+        {
+            Some(cluster) => cluster.sources.push(source),
+            None => clusters.push(Cluster {
+                text,
+                words,
+                sources: vec![source],
+            }),
+        }
+    }
+    // Stable sort keeps first-seen order among equal counts
+    clusters.sort_by_key(|c| std::cmp::Reverse(c.sources.len()));
+    clusters
+}
+
+/// Summarizes a cluster's sources, e.g. "2 notes; tasks #4, #9"
+fn describe_sources(sources: &[Source], with_project: bool) -> String {
+    let notes = sources
+        .iter()
+        .filter(|s| matches!(s, Source::Note { .. }))
+        .count();
+    let tasks: Vec<String> = sources
+        .iter()
+        .filter_map(|s| match s {
+            Source::Task { project, number } if with_project => {
+                Some(format!("{}#{}", project, number))
+            }
+            Source::Task { number, .. } => Some(format!("#{}", number)),
+            Source::Note { .. } => None,
+        })
+        .collect();
+
+    let mut parts = Vec::new();
+    if notes > 0 {
+        parts.push(format!(
+            "{} note{}",
+            notes,
+            if notes == 1 { "" } else { "s" }
+        ));
+    }
+    if !tasks.is_empty() {
+        parts.push(format!("tasks {}", tasks.join(", ")));
+    }
+    if with_project {
+        let projects: BTreeSet<&str> = sources
+            .iter()
+            .map(|s| match s {
+                Source::Note { project } | Source::Task { project, .. } => project.as_str(),
+            })
+            .collect();
+        parts.push(format!(
+            "projects: {}",
+            projects.into_iter().collect::<Vec<_>>().join(", ")
+        ));
+    }
+    parts.join("; ")
+}
+
+/// Periods shown in the text report; `--json` includes all of them
+const REPORT_PERIODS: usize = 14;
+
+/// Spend, tokens, and time totalled over a set of task logs
+#[derive(Debug, Default, Serialize)]
+pub struct UsageReport {
+    pub tasks: usize,
+    pub cost_usd: f64,
+    /// Tasks whose log records no cost (interrupted, or older logs)
+    pub tasks_without_cost: usize,
+    pub avg_duration_secs: Option<f64>,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cache_creation_tokens: u64,
+    /// Spend and task count per period (`YYYY-MM-DD` or `YYYY-Www`, UTC),
+    /// oldest first
+    pub periods: Vec<PeriodUsage>,
+    pub projects: Vec<ProjectUsage>,
+    /// Tool uses, most used first
+    pub tools: Vec<(String, usize)>,
+    /// Starred tasks, oldest first
+    pub key_sessions: Vec<KeySession>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct PeriodUsage {
+    pub period: String,
+    pub tasks: usize,
+    pub cost_usd: f64,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ProjectUsage {
+    pub project: String,
+    pub tasks: usize,
+    pub cost_usd: f64,
+}
+
+/// A starred task, listed so landmark runs are easy to find again
+#[derive(Debug, Serialize, PartialEq)]
+pub struct KeySession {
+    pub project: String,
+    pub task_number: u32,
+    pub prompt: String,
+    pub timestamp: Option<DateTime<Utc>>,
+    pub cost_usd: Option<f64>,
+}
+
+/// Prints spend and usage for one project, or all projects
+pub fn usage_report(project_name: Option<&str>, by: &str, json: bool) -> Result<()> {
+    if !matches!(by, "day" | "week") {
+        bail!("--by must be day or week");
+    }
+    let projects = match project_name {
+        Some(name) => vec![Project::open(name)?],
+        None => project::load_all_projects()?,
+    };
+    let mut logs = Vec::new();
+    for project in &projects {
+        for (_, path) in tasklog::task_log_paths(project)? {
+            // Unreadable logs are skipped rather than failing the whole report
+            if let Ok(log) = TaskLog::load(&path) {
+                logs.push((project.metadata.name.clone(), log));
+            }
+        }
+    }
+    let report = summarize_usage(&logs, by == "week");
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    if report.tasks == 0 {
+        println!("No task logs found.");
+        return Ok(());
+    }
+    print!("{}", format_usage(&report, by));
+    Ok(())
+}
+
+/// Totals `(project, log)` pairs; periods are ISO weeks when `weekly`
+fn summarize_usage(logs: &[(String, TaskLog)], weekly: bool) -> UsageReport {
+    let mut report = UsageReport::default();
+    let mut periods: BTreeMap<String, (usize, f64)> = BTreeMap::new();
+    let mut projects: BTreeMap<&str, (usize, f64)> = BTreeMap::new();
+    let mut tools: BTreeMap<String, usize> = BTreeMap::new();
+    let mut durations = Vec::new();
+
+    for (project, log) in logs {
+        let cost = log
+            .cost_usd
+            .or_else(|| log.transcript.total_cost())
+            .or(log.estimated_cost_usd)
+            .map(|cost| cost + log.plan_cost_usd.unwrap_or(0.0));
+        report.tasks += 1;
+        report.cost_usd += cost.unwrap_or(0.0);
+        if cost.is_none() {
+            report.tasks_without_cost += 1;
+        }
+        if let Some(ms) = log.duration_ms.or_else(|| log.transcript.duration_ms()) {
+            durations.push(ms);
+        }
+        if let Some(usage) = log.transcript.usage() {
+            report.input_tokens += usage.input_tokens;
+            report.output_tokens += usage.output_tokens;
+            report.cache_read_tokens += usage.cache_read_tokens.unwrap_or(0);
+            report.cache_creation_tokens += usage.cache_creation_tokens.unwrap_or(0);
+        }
+        if let Some(timestamp) = log.timestamp {
+            let period = if weekly {
+                let week = timestamp.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            } else {
+                timestamp.format("%Y-%m-%d").to_string()
+            };
+            let entry = periods.entry(period).or_default();
+            entry.0 += 1;
+            entry.1 += cost.unwrap_or(0.0);
+        }
+        let entry = projects.entry(project).or_default();
+        entry.0 += 1;
+        entry.1 += cost.unwrap_or(0.0);
+        // Every call counts; logs without a transcript only list tool names
+        let counts = log.transcript.tool_counts();
+        if counts.is_empty() {
+            for tool in &log.tools_used {
+                *tools.entry(tool.clone()).or_default() += 1;
+            }
+        }
+        for (tool, count) in counts {
+            *tools.entry(tool).or_default() += count;
+        }
+        if log.starred {
+            report.key_sessions.push(KeySession {
+                project: project.clone(),
+                task_number: log.task_number,
+                prompt: log.prompt.clone(),
+                timestamp: log.timestamp,
+                cost_usd: cost,
+            });
+        }
+    }
+
+    if !durations.is_empty() {
+        let total: u64 = durations.iter().sum();
+        report.avg_duration_secs = Some(total as f64 / durations.len() as f64 / 1000.0);
+    }
+    report.periods = periods
+        .into_iter()
+        .map(|(period, (tasks, cost_usd))| PeriodUsage {
+            period,
+            tasks,
+            cost_usd,
+        })
+        .collect();
+    report.projects = projects
+        .into_iter()
+        .map(|(project, (tasks, cost_usd))| ProjectUsage {
+            project: project.to_string(),
+            tasks,
+            cost_usd,
+        })
+        .collect();
+    report.tools = tools.into_iter().collect();
+    report
+        .tools
+        .sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    report.key_sessions.sort_by_key(|session| session.timestamp);
+    report
+}
+
+/// Renders a usage report as text
+fn format_usage(report: &UsageReport, by: &str) -> String {
+    let mut out = format!("Tasks: {}", report.tasks);
+    if report.projects.len() > 1 {
+        out.push_str(&format!(" across {} projects", report.projects.len()));
+    }
+    out.push_str(&format!("\nSpend: ${:.2}", report.cost_usd));
+    if report.tasks_without_cost > 0 {
+        out.push_str(&format!(
+            " ({} tasks without recorded cost)",
+            report.tasks_without_cost
+        ));
+    }
+    if let Some(secs) = report.avg_duration_secs {
+        out.push_str(&format!("\nAverage duration: {:.1}s", secs));
+    }
+    out.push_str(&format!(
+        "\nTokens: {} in, {} out, {} cache read, {} cache written\n",
+        report.input_tokens,
+        report.output_tokens,
+        report.cache_read_tokens,
+        report.cache_creation_tokens
+    ));
+
+    if !report.periods.is_empty() {
+        out.push_str(&format!("\nSpend per {} (UTC):\n", by));
+        let skip = report.periods.len().saturating_sub(REPORT_PERIODS);
+        for period in &report.periods[skip..] {
+            out.push_str(&format!(
+                "  {:<10}  {:>9}  {} tasks\n",
+                period.period,
+                format!("${:.2}", period.cost_usd),
+                period.tasks
+            ));
+        }
+    }
+    if report.projects.len() > 1 {
+        out.push_str("\nPer project:\n");
+        let width = report
+            .projects
+            .iter()
+            .map(|p| p.project.len())
+            .max()
+            .unwrap_or(0);
+        for project in &report.projects {
+            out.push_str(&format!(
+                "  {:<width$}  {:>9}  {} tasks\n",
+                project.project,
+                format!("${:.2}", project.cost_usd),
+                project.tasks,
+                width = width
+            ));
+        }
+    }
+    if !report.tools.is_empty() {
+        out.push_str("\nMost used tools (calls):\n");
+        for (tool, count) in report.tools.iter().take(10) {
+            out.push_str(&format!("  {:<12} {}\n", tool, count));
+        }
+    }
+    if !report.key_sessions.is_empty() {
+        out.push_str("\nKey sessions (starred):\n");
+        for session in &report.key_sessions {
+            let date = session
+                .timestamp
+                .map(|t| t.format("%Y-%m-%d").to_string())
+                .unwrap_or_else(|| "-".to_string());
+            let cost = session
+                .cost_usd
+                .map(|c| format!("${:.2}", c))
+                .unwrap_or_else(|| "-".to_string());
+            let line = format!(
+                "  {} #{:<4} {}  {:>7}  {}",
+                session.project,
+                session.task_number,
+                date,
+                cost,
+                text::truncate_ellipsis(&session.prompt, 60)
+            );
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Recent outcomes shown per template
+const TEMPLATE_HISTORY: usize = 10;
+
+/// Outcomes of one task template's runs
+#[derive(Debug, PartialEq)]
+struct TemplateRuns {
+    project: String,
+    template: String,
+    runs: usize,
+    /// Runs where the task succeeded and verification, if any, passed
+    passed: usize,
+    cost_usd: f64,
+    last_run: Option<String>,
+    /// ✓/✗ per run, oldest first, for the most recent runs
+    recent: String,
+}
+
+/// Prints how each task template's runs have gone
+pub fn template_report(project_name: Option<&str>) -> Result<()> {
+    let projects = match project_name {
+        Some(name) => vec![Project::open(name)?],
+        None => project::load_all_projects()?,
+    };
+    let mut logs = Vec::new();
+    for project in &projects {
+        for (_, path) in tasklog::task_log_paths(project)? {
+            if let Ok(log) = TaskLog::load(&path) {
+                logs.push((project.metadata.name.clone(), log));
+            }
+        }
+    }
+    let summary = summarize_templates(&logs);
+    if summary.is_empty() {
+        println!("No template runs found. Run one with `clancy run <project> --task <name>`.");
+        return Ok(());
+    }
+    let width = summary
+        .iter()
+        .map(|t| t.project.len() + t.template.len() + 1)
+        .max()
+        .unwrap_or(0);
+    for runs in &summary {
+        println!(
+            "{:<width$}  {:>3}/{:<3} passed  {:>9}  last {}  {}",
+            format!("{}/{}", runs.project, runs.template),
+            runs.passed,
+            runs.runs,
+            format!("${:.2}", runs.cost_usd),
+            runs.last_run.as_deref().unwrap_or("----------"),
+            runs.recent,
+            width = width
+        );
+    }
+    Ok(())
+}
+
+/// Groups `(project, log)` pairs by template, in task order
+fn summarize_templates(logs: &[(String, TaskLog)]) -> Vec<TemplateRuns> {
+    let mut groups: BTreeMap<(&str, &str), Vec<&TaskLog>> = BTreeMap::new();
+    for (project, log) in logs {
+        if let Some(ref template) = log.template {
+            groups.entry((project, template)).or_default().push(log);
+        }
+    }
+    groups
+        .into_iter()
+        .map(|((project, template), mut runs)| {
+            runs.sort_by_key(|log| log.task_number);
+            let outcomes: Vec<bool> = runs
+                .iter()
+                .map(|log| log.success && log.verified != Some(false))
+                .collect();
+            let skip = outcomes.len().saturating_sub(TEMPLATE_HISTORY);
+            TemplateRuns {
+                project: project.to_string(),
+                template: template.to_string(),
+                runs: runs.len(),
+                passed: outcomes.iter().filter(|ok| **ok).count(),
+                cost_usd: runs
+                    .iter()
+                    .filter_map(|log| log.cost_usd.or_else(|| log.transcript.total_cost()))
+                    .sum(),
+                last_run: runs
+                    .iter()
+                    .filter_map(|log| log.timestamp)
+                    .max()
+                    .map(|t| t.format("%Y-%m-%d").to_string()),
+                recent: outcomes[skip..]
+                    .iter()
+                    .map(|ok| if *ok { '✓' } else { '✗' })
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(text: &str) -> (String, Source) {
+        (
+            text.to_string(),
+            Source::Note {
+                project: "api".to_string(),
+            },
+        )
+    }
+
+    #[test]
+    fn test_cluster_failures() {
+        let task = (
+            "error: connection refused to postgres on port 5432".to_string(),
+            Source::Task {
+                project: "api".to_string(),
+                number: 7,
+            },
+        );
+        let clusters = cluster_failures(vec![
+            note("Don't forget to run migrations before tests"),
+            note("Postgres connection refused: start the container first"),
+            task,
+            note("Run migrations before the tests, or they fail"),
+            note("Tokio runtime panics inside block_on"),
+        ]);
+        assert_eq!(clusters.len(), 3);
+        assert_eq!(clusters[0].sources.len(), 2);
+        assert!(clusters[0].text.contains("migrations"));
+        assert!(clusters[1].text.contains("Postgres"));
+        assert_eq!(clusters[2].sources.len(), 1);
+        assert_eq!(
+            describe_sources(&clusters[1].sources, false),
+            "1 note; tasks #7"
+        );
+    }
+
+    #[test]
+    fn test_signature() {
+        let words = signature("Don't use the 2.0 API in tests");
+        assert_eq!(
+            words.into_iter().collect::<Vec<_>>(),
+            vec!["api", "tests", "use"]
+        );
+    }
+
+    fn usage_log(timestamp: &str, cost: Option<f64>, duration_ms: u64, tools: &[&str]) -> TaskLog {
+        let mut log: TaskLog = serde_json::from_str("{}").unwrap();
+        log.timestamp = Some(timestamp.parse().unwrap());
+        log.cost_usd = cost;
+        log.duration_ms = Some(duration_ms);
+        log.tools_used = tools.iter().map(|t| t.to_string()).collect();
+        log
+    }
+
+    #[test]
+    fn test_summarize_usage() {
+        let logs = vec![
+            (
+                "api".to_string(),
+                usage_log("2025-03-03T10:00:00Z", Some(1.0), 2000, &["Edit", "Bash"]),
+            ),
+            (
+                "api".to_string(),
+                usage_log("2025-03-03T15:00:00Z", Some(0.5), 4000, &["Edit"]),
+            ),
+            (
+                "web".to_string(),
+                usage_log("2025-03-10T09:00:00Z", None, 6000, &["Read"]),
+            ),
+        ];
+
+        let report = summarize_usage(&logs, false);
+        assert_eq!(report.tasks, 3);
+        assert_eq!(report.cost_usd, 1.5);
+        assert_eq!(report.tasks_without_cost, 1);
+        assert_eq!(report.avg_duration_secs, Some(4.0));
+        assert_eq!(
+            report.periods,
+            vec![
+                PeriodUsage {
+                    period: "2025-03-03".to_string(),
+                    tasks: 2,
+                    cost_usd: 1.5
+                },
+                PeriodUsage {
+                    period: "2025-03-10".to_string(),
+                    tasks: 1,
+                    cost_usd: 0.0
+                },
+            ]
+        );
+        assert_eq!(report.projects.len(), 2);
+        assert_eq!(report.tools[0], ("Edit".to_string(), 2));
+
+        let weekly = summarize_usage(&logs, true);
+        let weeks: Vec<&str> = weekly.periods.iter().map(|p| p.period.as_str()).collect();
+        assert_eq!(weeks, vec!["2025-W10", "2025-W11"]);
+    }
+
+    #[test]
+    fn test_summarize_usage_uses_estimated_cost() {
+        let mut log = usage_log("2025-03-03T10:00:00Z", None, 2000, &[]);
+        log.estimated_cost_usd = Some(0.75);
+        let report = summarize_usage(&[("api".to_string(), log)], false);
+        assert_eq!(report.cost_usd, 0.75);
+        assert_eq!(report.tasks_without_cost, 0);
+    }
+
+    #[test]
+    fn test_summarize_usage_counts_tool_calls() {
+        let mut log = usage_log("2025-03-03T10:00:00Z", None, 2000, &["Edit"]);
+        log.transcript = crate::transcript::Transcript::parse(
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","name":"Edit","id":"t1","input":{}},{"type":"tool_use","name":"Edit","id":"t2","input":{}}]}}"#,
+        );
+        let report = summarize_usage(&[("api".to_string(), log)], false);
+        assert_eq!(report.tools, vec![("Edit".to_string(), 2)]);
+    }
+
+    #[test]
+    fn test_key_sessions_list_starred_tasks() {
+        let mut later = usage_log("2025-03-05T10:00:00Z", Some(2.0), 2000, &[]);
+        later.task_number = 9;
+        later.prompt = "Refactor the auth middleware".to_string();
+        later.starred = true;
+        let mut earlier = usage_log("2025-03-03T10:00:00Z", None, 2000, &[]);
+        earlier.task_number = 4;
+        earlier.starred = true;
+        let plain = usage_log("2025-03-04T10:00:00Z", Some(1.0), 2000, &[]);
+        let logs = vec![
+            ("api".to_string(), later),
+            ("web".to_string(), earlier),
+            ("api".to_string(), plain),
+        ];
+
+        let report = summarize_usage(&logs, false);
+        let numbers: Vec<u32> = report.key_sessions.iter().map(|s| s.task_number).collect();
+        assert_eq!(numbers, vec![4, 9]);
+        let text = format_usage(&report, "day");
+        assert!(text.contains(
+            "\nKey sessions (starred):\n  web #4    2025-03-03        -\n  api #9    2025-03-05    $2.00  Refactor the auth middleware\n"
+        ));
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["key_sessions"][1]["project"], "api");
+        assert_eq!(json["key_sessions"][1]["task_number"], 9);
+    }
+
+    #[test]
+    fn test_summarize_usage_adds_plan_cost() {
+        let mut log = usage_log("2025-03-03T10:00:00Z", Some(1.0), 2000, &[]);
+        log.plan_cost_usd = Some(0.25);
+        let report = summarize_usage(&[("api".to_string(), log)], false);
+        assert_eq!(report.cost_usd, 1.25);
+    }
+
+    #[test]
+    fn test_summarize_templates() {
+        let log = |number: u32, success: bool, verified: Option<bool>| -> TaskLog {
+            serde_json::from_value(serde_json::json!({
+                "task_number": number,
+                "timestamp": format!("2025-03-0{}T10:00:00Z", number),
+                "success": success,
+                "cost_usd": 0.5,
+                "template": "dependency-audit",
+                "verified": verified,
+            }))
+            .unwrap()
+        };
+        let logs = vec![
+            ("api".to_string(), log(3, true, Some(false))),
+            ("api".to_string(), log(1, true, Some(true))),
+            ("api".to_string(), log(2, false, None)),
+            (
+                "api".to_string(),
+                usage_log("2025-03-04T10:00:00Z", None, 0, &[]),
+            ),
+        ];
+        assert_eq!(
+            summarize_templates(&logs),
+            vec![TemplateRuns {
+                project: "api".to_string(),
+                template: "dependency-audit".to_string(),
+                runs: 3,
+                passed: 1,
+                cost_usd: 1.5,
+                last_run: Some("2025-03-03".to_string()),
+                recent: "✓✗✗".to_string(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! One-line task summaries for session history
+//!
+//! Summary mode lists each earlier task as "N. prompt — summary". How that
+//! summary is produced is chosen per project in project.toml:
+//!
+//! - `heuristic` (default): the transcript's result text, cut to one line
+//! - `api`: the extraction model writes a sentence from the transcript
+//! - `template`: a format string filled from transcript stats, such as
+//!   "Edited {files} files, {tests}, result: {result}"
+
+use anyhow::{bail, Result};
+
+use crate::config::load_config;
+use crate::diff::file_diffs;
+use crate::extraction::complete;
+use crate::project::SummarizerOptions;
+use crate::text;
+use crate::transcript::{Message, Transcript};
+
+/// Longest summary kept for a task, in bytes
+const MAX_SUMMARY_BYTES: usize = 80;
+
+/// Template used when `kind = "template"` sets none
+pub const DEFAULT_TEMPLATE: &str = "Edited {files} files, {tests}, result: {result}";
+
+/// Names accepted by `[summarizer] kind`
+pub const SUMMARIZERS: &[&str] = &["heuristic", "api", "template"];
+
+/// Produces the history line for a finished task
+pub trait Summarizer {
+    /// The summary, or None to fall back to the prompt
+    fn summarize(&self, prompt: &str, transcript: &Transcript) -> Result<Option<String>>;
+}
+
+/// The transcript's own result text, when it says something
+pub struct Heuristic;
+
+impl Summarizer for Heuristic {
+    fn summarize(&self, _prompt: &str, transcript: &Transcript) -> Result<Option<String>> {
+        let summary = transcript.generate_summary();
+        Ok((summary.len() > 20 && summary != "(no summary available)").then_some(summary))
+    }
+}
+
+/// Asks the extraction model (or `model`) for a one-sentence summary
+pub struct Api {
+    model: Option<String>,
+}
+
+impl Summarizer for Api {
+    fn summarize(&self, prompt: &str, transcript: &Transcript) -> Result<Option<String>> {
+        let mut config = load_config()?;
+        if let Some(model) = &self.model {
+            config.claude.model = model.clone();
+            config.claude.fallback_models.clear();
+        }
+        let request = format!(
+            "Summarize what this coding task did in one sentence of at most 15 words. \
+             Reply with the sentence only.\n\nTask: {}\n\nOutcome:\n{}\n\nTools used: {}",
+            prompt,
+            transcript.generate_summary(),
+            transcript.tools_used().join(", "),
+        );
+        let rt = tokio::runtime::Runtime::new()?;
+        let (response, _model) = rt.block_on(complete(&config, &request))?;
+        let line = response.lines().next().unwrap_or_default().trim();
+        Ok((!line.is_empty()).then(|| line.to_string()))
+    }
+}
+
+/// Fills a format string from [`TranscriptStats`]
+pub struct Template {
+    template: String,
+}
+
+impl Summarizer for Template {
+    fn summarize(&self, _prompt: &str, transcript: &Transcript) -> Result<Option<String>> {
+        Ok(Some(
+            TranscriptStats::from_transcript(transcript).render(&self.template),
+        ))
+    }
+}
+
+/// Counts a template summary can refer to
+#[derive(Debug, Default, PartialEq)]
+pub struct TranscriptStats {
+    /// Files changed by edits and writes
+    pub files: usize,
+    /// Shell commands run
+    pub commands: usize,
+    /// Whether any shell command looked like a test run
+    pub ran_tests: bool,
+    /// Tool calls that returned an error
+    pub errors: usize,
+    /// First line of the final result text
+    pub result: String,
+}
+
+impl TranscriptStats {
+    pub fn from_transcript(transcript: &Transcript) -> Self {
+        let mut stats = TranscriptStats {
+            files: file_diffs(transcript).len(),
+            ..Default::default()
+        };
+        for message in &transcript.messages {
+            match message {
+                Message::ToolUse {
+                    tool_name, input, ..
+                } if tool_name == "Bash" => {
+                    stats.commands += 1;
+                    let command = input.get("command").and_then(|c| c.as_str());
+                    stats.ran_tests |= command.is_some_and(|c| c.contains("test"));
+                }
+                Message::ToolResult { is_error: true, .. } => stats.errors += 1,
+                _ => {}
+            }
+        }
+        stats.result = match &transcript.result {
+            Some(result) if !result.success => "failed".to_string(),
+            Some(result) => result
+                .result_text
+                .as_deref()
+                .and_then(|t| t.lines().find(|l| !l.trim().is_empty()))
+                .unwrap_or("done")
+                .trim()
+                .to_string(),
+            None => "unknown".to_string(),
+        };
+        stats
+    }
+
+    /// Replaces `{files}`, `{commands}`, `{errors}`, `{tests}` ("ran tests"
+    /// or "no tests run"), and `{result}` in `template`
+    pub fn render(&self, template: &str) -> String {
+        template
+            .replace("{files}", &self.files.to_string())
+            .replace("{commands}", &self.commands.to_string())
+            .replace("{errors}", &self.errors.to_string())
+            .replace(
+                "{tests}",
+                if self.ran_tests {
+                    "ran tests"
+                } else {
+                    "no tests run"
+                },
+            )
+            .replace("{result}", &self.result)
+    }
+}
+
+/// The summarizer configured for a project, the heuristic one by default
+pub fn for_project(options: Option<&SummarizerOptions>) -> Result<Box<dyn Summarizer>> {
+    let Some(options) = options else {
+        return Ok(Box::new(Heuristic));
+    };
+    Ok(match options.kind.as_str() {
+        "heuristic" => Box::new(Heuristic),
+        "api" => Box::new(Api {
+            model: options.model.clone(),
+        }),
+        "template" => Box::new(Template {
+            template: options
+                .template
+                .clone()
+                .unwrap_or_else(|| DEFAULT_TEMPLATE.to_string()),
+        }),
+        other => bail!(
+            "Unknown summarizer '{}' in project.toml (expected one of: {})",
+            other,
+            SUMMARIZERS.join(", ")
+        ),
+    })
+}
+
+/// History line for a task: interrupted and failed tasks are marked, and a
+/// summarizer that errors or has nothing to say falls back to the prompt
+pub fn task_summary(
+    summarizer: &dyn Summarizer,
+    prompt: &str,
+    transcript: &Transcript,
+    interrupted: bool,
+) -> String {
+    if interrupted {
+        return format!("(interrupted) {}", text::truncate_ellipsis(prompt, 65));
+    }
+    if !transcript.succeeded() {
+        return format!("(failed) {}", text::truncate_ellipsis(prompt, 70));
+    }
+    let summary = summarizer
+        .summarize(prompt, transcript)
+        .unwrap_or_else(|e| {
+            eprintln!("[Summary] Summarizer failed, using the prompt: {:#}", e);
+            None
+        })
+        .unwrap_or_else(|| prompt.to_string());
+    text::truncate_ellipsis(summary.trim(), MAX_SUMMARY_BYTES)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRANSCRIPT: &str = r#"{"type":"assistant","message":{"content":[{"type":"tool_use","name":"Edit","id":"t1","input":{"file_path":"src/a.rs","old_string":"a","new_string":"b"}},{"type":"tool_use","name":"Bash","id":"t2","input":{"command":"cargo test"}}]}}
+{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1","content":"ok"},{"type":"tool_result","tool_use_id":"t2","content":"1 failed","is_error":true}]}}
+{"type":"result","subtype":"success","result":"Fixed the parser\nDetails follow","duration_ms":10,"total_cost_usd":0.01}"#;
+
+    #[test]
+    fn test_transcript_stats() {
+        let stats = TranscriptStats::from_transcript(&Transcript::parse(TRANSCRIPT));
+        assert_eq!(
+            stats,
+            TranscriptStats {
+                files: 1,
+                commands: 1,
+                ran_tests: true,
+                errors: 1,
+                result: "Fixed the parser".to_string(),
+            }
+        );
+        assert_eq!(
+            stats.render(DEFAULT_TEMPLATE),
+            "Edited 1 files, ran tests, result: Fixed the parser"
+        );
+    }
+
+    #[test]
+    fn test_task_summary_falls_back_to_prompt() {
+        let transcript = Transcript::parse(
+            r#"{"type":"result","subtype":"success","result":"ok","total_cost_usd":0.01}"#,
+        );
+        assert_eq!(
+            task_summary(&Heuristic, "Tidy imports", &transcript, false),
+            "Tidy imports"
+        );
+        assert_eq!(
+            task_summary(&Heuristic, "Tidy imports", &transcript, true),
+            "(interrupted) Tidy imports"
+        );
+    }
+
+    #[test]
+    fn test_for_project_rejects_unknown_kind() {
+        let options = SummarizerOptions {
+            kind: "magic".to_string(),
+            ..Default::default()
+        };
+        assert!(for_project(Some(&options)).is_err());
+        assert!(for_project(None).is_ok());
+    }
+}
@@ -0,0 +1,624 @@
+//! Stored task logs
+//!
+//! Each task run is saved to `tasks/NNN-slug.json` in the project directory.
+//! This module reads those files back for viewers and reports.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::extraction::ExtractionResult;
+use crate::project::Project;
+use crate::text;
+use crate::transcript::Transcript;
+
+/// A task log as written by the REPL after each task
+///
+/// Every field is defaulted so logs from older versions still load.
+#[derive(Debug, Deserialize)]
+pub struct TaskLog {
+    #[serde(default)]
+    pub task_number: u32,
+    #[serde(default)]
+    pub prompt: String,
+    #[serde(default)]
+    pub timestamp: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub success: bool,
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
+    #[serde(default)]
+    pub cost_usd: Option<f64>,
+    #[serde(default)]
+    pub tools_used: Vec<String>,
+    #[serde(default)]
+    pub summary: String,
+    /// The task's line in session history, from the project's summarizer
+    #[serde(default)]
+    pub history_summary: Option<String>,
+    /// Claude Code permission mode the task ran under
+    #[serde(default)]
+    pub permission_mode: Option<String>,
+    /// Subdirectory the task ran in, relative to the session's working dir
+    #[serde(default)]
+    pub working_dir: Option<String>,
+    #[serde(default = "empty_transcript")]
+    pub transcript: Transcript,
+    /// Notes extracted from this task, recorded after extraction runs
+    #[serde(default)]
+    pub extracted_notes: Option<ExtractionResult>,
+    /// Reviewer comments added after the task ran
+    #[serde(default)]
+    pub annotations: Vec<Annotation>,
+    /// Marked as a landmark run worth finding later
+    #[serde(default)]
+    pub starred: bool,
+    /// Clancy exited mid-task; recovered from the in-flight capture
+    #[serde(default)]
+    pub interrupted: bool,
+    /// Project session the task ran in; missing in older logs
+    #[serde(default)]
+    pub session: Option<u32>,
+    /// Task template the task was run from
+    #[serde(default)]
+    pub template: Option<String>,
+    /// The template's tags
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Whether the template's verify command passed afterwards
+    #[serde(default)]
+    pub verified: Option<bool>,
+    /// Metered estimate for a task stopped at the cost limit, which never
+    /// reports a final cost
+    #[serde(default)]
+    pub estimated_cost_usd: Option<f64>,
+    /// Cost of the `/plan` pass whose approved plan the task followed
+    #[serde(default)]
+    pub plan_cost_usd: Option<f64>,
+    /// Transcript processor output by processor name
+    #[serde(default)]
+    pub artifacts: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A human note attached to a stored task
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Annotation {
+    pub timestamp: DateTime<Utc>,
+    pub text: String,
+}
+
+fn empty_transcript() -> Transcript {
+    Transcript::parse("")
+}
+
+impl TaskLog {
+    /// Reads and parses a task log file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read task log: {:?}", path))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse task log: {:?}", path))
+    }
+}
+
+/// Applies an in-place change to a task log's JSON, preserving unknown fields
+pub fn update_task_log(
+    path: &Path,
+    update: impl FnOnce(&mut serde_json::Map<String, serde_json::Value>),
+) -> Result<()> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read task log: {:?}", path))?;
+    let mut value: serde_json::Value = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse task log: {:?}", path))?;
+    let Some(object) = value.as_object_mut() else {
+        bail!("Task log is not a JSON object: {:?}", path);
+    };
+    update(object);
+    std::fs::write(path, serde_json::to_string_pretty(&value)?)
+        .with_context(|| format!("Failed to write task log: {:?}", path))
+}
+
+/// Records the notes a task's extraction produced in its log
+pub fn record_extraction(path: &Path, extraction: &ExtractionResult) -> Result<()> {
+    let notes = serde_json::to_value(extraction)?;
+    update_task_log(path, |log| {
+        log.insert("extracted_notes".to_string(), notes);
+    })
+}
+
+/// Appends an annotation to a task log
+pub fn add_annotation(path: &Path, text: &str) -> Result<()> {
+    let text = text.trim();
+    if text.is_empty() {
+        bail!("Annotation text is empty");
+    }
+    let annotation = serde_json::to_value(Annotation {
+        timestamp: Utc::now(),
+        text: text.to_string(),
+    })?;
+    update_task_log(path, |log| {
+        match log.get_mut("annotations").and_then(|a| a.as_array_mut()) {
+            Some(annotations) => annotations.push(annotation),
+            None => {
+                log.insert(
+                    "annotations".to_string(),
+                    serde_json::Value::Array(vec![annotation]),
+                );
+            }
+        }
+    })
+}
+
+/// Attaches a reviewer comment to a stored task
+pub fn annotate_task(project_name: &str, number: u32, text: &str) -> Result<()> {
+    let project = Project::open(project_name)?;
+    add_annotation(&find_task_log(&project, number)?, text)?;
+    println!("Annotated task {} in '{}'.", number, project.metadata.name);
+    Ok(())
+}
+
+/// Stars or unstars a task log
+pub fn set_starred(path: &Path, starred: bool) -> Result<()> {
+    update_task_log(path, |log| {
+        log.insert("starred".to_string(), serde_json::Value::Bool(starred));
+    })
+}
+
+/// Stars or unstars a stored task
+pub fn star_task(project_name: &str, number: u32, starred: bool) -> Result<()> {
+    let project = Project::open(project_name)?;
+    set_starred(&find_task_log(&project, number)?, starred)?;
+    let verb = if starred { "Starred" } else { "Unstarred" };
+    println!("{} task {} in '{}'.", verb, number, project.metadata.name);
+    Ok(())
+}
+
+/// Formats a one-line listing entry for a task
+pub fn task_list_line(log: &TaskLog) -> String {
+    let mut line = format!(
+        "{:>3}. {}",
+        log.task_number,
+        log.prompt.lines().next().unwrap_or("")
+    );
+    if let Some(timestamp) = log.timestamp {
+        line.push_str(&format!(" ({})", timestamp.format("%Y-%m-%d")));
+    }
+    if log.interrupted {
+        line.push_str(" [interrupted]");
+    } else if !log.success {
+        line.push_str(" [failed]");
+    }
+    if log.starred {
+        line.push_str(" ★");
+    }
+    line
+}
+
+/// Formats a task with its outcome, date, and summary for status views
+pub fn task_summary_line(log: &TaskLog) -> String {
+    let marker = if log.success { "✓" } else { "✗" };
+    let date = log
+        .timestamp
+        .map(|t| t.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| "----------".to_string());
+    let summary = log.summary.lines().next().unwrap_or("").trim();
+    let text = if summary.is_empty() {
+        log.prompt.lines().next().unwrap_or("")
+    } else {
+        summary
+    };
+    let mut line = format!("{} {:>3}. {} {}", marker, log.task_number, date, text);
+    if line.chars().count() > 100 {
+        line = format!("{}…", text::truncate_chars(&line, 99));
+    }
+    line
+}
+
+/// Prints a page of a project's tasks, newest first
+///
+/// Pages are 1-based. With `failed_only`, failed and interrupted tasks are
+/// listed and the rest skipped; unreadable logs are skipped either way.
+pub fn show_log(project_name: &str, limit: usize, page: usize, failed_only: bool) -> Result<()> {
+    let project = Project::open(project_name)?;
+    let limit = limit.max(1);
+    let skip = page.saturating_sub(1) * limit;
+
+    let mut matching = task_log_paths(&project)?
+        .into_iter()
+        .rev()
+        .filter_map(|(_, path)| TaskLog::load(&path).ok())
+        .filter(|log| !failed_only || !log.success)
+        .skip(skip);
+    let shown: Vec<TaskLog> = matching.by_ref().take(limit).collect();
+    let more = matching.next().is_some();
+
+    if shown.is_empty() {
+        match (skip, failed_only) {
+            (0, true) => println!("No failed tasks in '{}'.", project.metadata.name),
+            (0, false) => println!("No stored tasks in '{}'.", project.metadata.name),
+            _ => println!("No tasks on page {}.", page),
+        }
+        return Ok(());
+    }
+    for log in &shown {
+        println!("{}", task_log_line(log));
+    }
+    if more {
+        println!("\nOlder tasks: --page {}", page.max(1) + 1);
+    }
+    Ok(())
+}
+
+/// Formats a task for `clancy log`: number, time, outcome, duration, cost,
+/// and the prompt's first line
+fn task_log_line(log: &TaskLog) -> String {
+    let marker = if log.success { "✓" } else { "✗" };
+    let time = log
+        .timestamp
+        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "----------------".to_string());
+    let duration = log
+        .duration_ms
+        .or_else(|| log.transcript.duration_ms())
+        .map(|ms| format!("{:.1}s", ms as f64 / 1000.0))
+        .unwrap_or_else(|| "-".to_string());
+    let cost = log
+        .cost_usd
+        .or_else(|| log.transcript.total_cost())
+        .map(|c| format!("${:.4}", c))
+        .unwrap_or_else(|| "-".to_string());
+    let mut line = format!(
+        "{:>4}  {}  {}  {:>7}  {:>8}  {}",
+        log.task_number,
+        time,
+        marker,
+        duration,
+        cost,
+        log.prompt.lines().next().unwrap_or("")
+    );
+    if line.chars().count() > 110 {
+        line = format!("{}…", text::truncate_chars(&line, 109));
+    }
+    if log.interrupted {
+        line.push_str(" [interrupted]");
+    }
+    if log.starred {
+        line.push_str(" ★");
+    }
+    line
+}
+
+/// Prompts at least this similar to a past task's are treated as repeats
+const DUPLICATE_SIMILARITY: f64 = 0.9;
+
+/// Finds the most recent successful task whose prompt is near-identical to
+/// `prompt`
+pub fn find_similar_task<'a>(logs: &'a [TaskLog], prompt: &str) -> Option<&'a TaskLog> {
+    logs.iter()
+        .rev()
+        .filter(|log| log.success && !log.interrupted)
+        .find(|log| prompt_similarity(&log.prompt, prompt) >= DUPLICATE_SIMILARITY)
+}
+
+/// Overlap of two prompts' word sets, ignoring case and punctuation, from 0 to 1
+fn prompt_similarity(a: &str, b: &str) -> f64 {
+    fn words(s: &str) -> std::collections::BTreeSet<String> {
+        s.split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(|w| w.to_lowercase())
+            .collect()
+    }
+    let (a, b) = (words(a), words(b));
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+/// Describes how long ago a timestamp was, in days
+pub fn days_ago(timestamp: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    match (now - timestamp).num_days() {
+        0 => "earlier today".to_string(),
+        1 => "yesterday".to_string(),
+        days => format!("{} days ago", days),
+    }
+}
+
+/// Loads the most recent `count` task logs, oldest first
+///
+/// An unreadable log is skipped with a warning rather than hiding every
+/// other one.
+pub fn recent_task_logs(project: &Project, count: usize) -> Result<Vec<TaskLog>> {
+    let paths = task_log_paths(project)?;
+    let skip = paths.len().saturating_sub(count);
+    Ok(paths
+        .into_iter()
+        .skip(skip)
+        .filter_map(|(_, path)| match TaskLog::load(&path) {
+            Ok(log) => Some(log),
+            Err(e) => {
+                eprintln!("Warning: skipping {}: {:#}", path.display(), e);
+                None
+            }
+        })
+        .collect())
+}
+
+/// Longest pause between tasks still counted as one session, for logs
+/// written before task logs recorded their session
+const LEGACY_SESSION_GAP_MINUTES: i64 = 60;
+
+/// Loads the task logs of the project's most recent session, oldest first
+///
+/// Logs are walked newest first until the session changes; unreadable logs
+/// are skipped.
+pub fn last_session_logs(project: &Project) -> Result<Vec<TaskLog>> {
+    let mut logs: Vec<TaskLog> = Vec::new();
+    for (_, path) in task_log_paths(project)?.into_iter().rev() {
+        let Ok(log) = TaskLog::load(&path) else {
+            continue;
+        };
+        if logs.last().is_some_and(|newer| !same_session(&log, newer)) {
+            break;
+        }
+        logs.push(log);
+    }
+    logs.reverse();
+    Ok(logs)
+}
+
+/// Whether two logs come from the same session: by session number, or for
+/// older logs without one, by a short enough pause between them
+fn same_session(older: &TaskLog, newer: &TaskLog) -> bool {
+    match (older.session, newer.session) {
+        (Some(a), Some(b)) => a == b,
+        (None, None) => older
+            .timestamp
+            .zip(newer.timestamp)
+            .is_some_and(|(a, b)| b - a <= chrono::Duration::minutes(LEGACY_SESSION_GAP_MINUTES)),
+        _ => false,
+    }
+}
+
+/// Extracts the task number from a log filename like `001-fix-auth.json`
+pub fn task_number_from_filename(name: &str) -> Option<u32> {
+    if !name.ends_with(".json") {
+        return None;
+    }
+    name.split('-').next()?.parse().ok()
+}
+
+/// Returns the paths of all task logs in a project, ordered by task number
+pub fn task_log_paths(project: &Project) -> Result<Vec<(u32, PathBuf)>> {
+    let tasks_dir = project.tasks_path();
+    if !tasks_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut paths: Vec<(u32, PathBuf)> = std::fs::read_dir(&tasks_dir)?
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            task_number_from_filename(&name).map(|n| (n, e.path()))
+        })
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+/// Finds the log file for a task number
+pub fn find_task_log(project: &Project, number: u32) -> Result<PathBuf> {
+    match task_log_paths(project)?
+        .into_iter()
+        .find(|(n, _)| *n == number)
+    {
+        Some((_, path)) => Ok(path),
+        None => bail!(
+            "Task {} not found in project '{}'",
+            number,
+            project.metadata.name
+        ),
+    }
+}
+
+/// Loads the log for a task number
+pub fn load_task_log(project: &Project, number: u32) -> Result<TaskLog> {
+    TaskLog::load(&find_task_log(project, number)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_number_from_filename() {
+        assert_eq!(task_number_from_filename("001-fix-auth.json"), Some(1));
+        assert_eq!(task_number_from_filename("042-x.json"), Some(42));
+        assert_eq!(task_number_from_filename("notes.json"), None);
+        assert_eq!(task_number_from_filename("001-fix-auth.txt"), None);
+    }
+
+    #[test]
+    fn test_parse_task_log() {
+        let json = r#"{
+            "task_number": 3,
+            "prompt": "fix the auth bug",
+            "timestamp": "2025-01-15T14:30:00+00:00",
+            "success": true,
+            "duration_ms": 1500,
+            "cost_usd": 0.02,
+            "tools_used": ["Read", "Edit"],
+            "summary": "Fixed it",
+            "transcript": {"init": null, "messages": [], "result": null},
+            "raw_output": ""
+        }"#;
+        let log: TaskLog = serde_json::from_str(json).unwrap();
+        assert_eq!(log.task_number, 3);
+        assert_eq!(log.tools_used, vec!["Read", "Edit"]);
+        assert!(log.timestamp.is_some());
+    }
+
+    #[test]
+    fn test_record_extraction_preserves_fields() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("001-task.json");
+        std::fs::write(&path, r#"{"task_number": 1, "raw_output": "keep me"}"#).unwrap();
+
+        let extraction = ExtractionResult {
+            failures: Some("- Don't use blocking IO".to_string()),
+            ..Default::default()
+        };
+        record_extraction(&path, &extraction).unwrap();
+
+        let log = TaskLog::load(&path).unwrap();
+        let notes = log.extracted_notes.unwrap();
+        assert_eq!(notes.failures.as_deref(), Some("- Don't use blocking IO"));
+        let raw: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(raw["raw_output"], "keep me");
+    }
+
+    #[test]
+    fn test_recent_task_logs_skips_unreadable() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let project = Project::new(
+            crate::project::ProjectMetadata::new("api"),
+            temp_dir.path().to_path_buf(),
+        );
+        std::fs::create_dir_all(project.tasks_path()).unwrap();
+        for (name, content) in [
+            ("001-a.json", r#"{"task_number": 1}"#),
+            ("002-b.json", "{not json"),
+            ("003-c.json", r#"{"task_number": 3}"#),
+        ] {
+            std::fs::write(project.tasks_path().join(name), content).unwrap();
+        }
+
+        let logs = recent_task_logs(&project, 3).unwrap();
+        let numbers: Vec<u32> = logs.iter().map(|l| l.task_number).collect();
+        assert_eq!(numbers, vec![1, 3]);
+    }
+
+    #[test]
+    fn test_add_annotation_appends() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("001-task.json");
+        std::fs::write(&path, r#"{"task_number": 1}"#).unwrap();
+
+        add_annotation(&path, "Good approach").unwrap();
+        add_annotation(&path, "  Missed the retry case  ").unwrap();
+        assert!(add_annotation(&path, "   ").is_err());
+
+        let log = TaskLog::load(&path).unwrap();
+        let texts: Vec<&str> = log.annotations.iter().map(|a| a.text.as_str()).collect();
+        assert_eq!(texts, vec!["Good approach", "Missed the retry case"]);
+    }
+
+    #[test]
+    fn test_set_starred_and_list_line() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("004-task.json");
+        std::fs::write(
+            &path,
+            r#"{"task_number": 4, "prompt": "refactor auth\nmore", "success": true}"#,
+        )
+        .unwrap();
+
+        set_starred(&path, true).unwrap();
+        let log = TaskLog::load(&path).unwrap();
+        assert!(log.starred);
+        assert_eq!(task_list_line(&log), "  4. refactor auth ★");
+
+        set_starred(&path, false).unwrap();
+        assert!(!TaskLog::load(&path).unwrap().starred);
+    }
+
+    #[test]
+    fn test_find_similar_task() {
+        let logs: Vec<TaskLog> = [
+            r#"{"task_number": 3, "prompt": "Fix the flaky auth test", "success": true}"#,
+            r#"{"task_number": 5, "prompt": "add retry logic", "success": false}"#,
+        ]
+        .iter()
+        .map(|json| serde_json::from_str(json).unwrap())
+        .collect();
+
+        let found = find_similar_task(&logs, "fix the flaky auth test.").unwrap();
+        assert_eq!(found.task_number, 3);
+        assert!(find_similar_task(&logs, "fix the flaky login test").is_none());
+        // Failed runs are worth repeating
+        assert!(find_similar_task(&logs, "add retry logic").is_none());
+    }
+
+    #[test]
+    fn test_task_summary_line() {
+        let log: TaskLog = serde_json::from_str(
+            r#"{"task_number": 12, "prompt": "fix auth", "success": false,
+                "timestamp": "2025-01-15T14:30:00+00:00", "summary": "Tried a retry loop\nmore"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            task_summary_line(&log),
+            "✗  12. 2025-01-15 Tried a retry loop"
+        );
+
+        let log: TaskLog =
+            serde_json::from_str(r#"{"task_number": 1, "prompt": "look", "success": true}"#)
+                .unwrap();
+        assert_eq!(task_summary_line(&log), "✓   1. ---------- look");
+    }
+
+    #[test]
+    fn test_task_log_line() {
+        let mut log: TaskLog = serde_json::from_value(serde_json::json!({
+            "task_number": 7,
+            "prompt": "Fix the login bug\nwith details",
+            "timestamp": "2025-03-01T14:02:00Z",
+            "success": true,
+            "duration_ms": 12345,
+            "cost_usd": 0.0412,
+        }))
+        .unwrap();
+        assert_eq!(
+            task_log_line(&log),
+            "   7  2025-03-01 14:02  ✓    12.3s   $0.0412  Fix the login bug"
+        );
+
+        log.success = false;
+        log.interrupted = true;
+        log.duration_ms = None;
+        log.cost_usd = None;
+        assert_eq!(
+            task_log_line(&log),
+            "   7  2025-03-01 14:02  ✗        -         -  Fix the login bug [interrupted]"
+        );
+    }
+
+    #[test]
+    fn test_parse_task_log_missing_fields() {
+        let log: TaskLog = serde_json::from_str(r#"{"prompt": "old"}"#).unwrap();
+        assert_eq!(log.prompt, "old");
+        assert!(log.transcript.messages.is_empty());
+    }
+
+    #[test]
+    fn test_same_session() {
+        let log = |session: Option<u32>, timestamp: &str| -> TaskLog {
+            serde_json::from_value(serde_json::json!({
+                "session": session,
+                "timestamp": timestamp,
+            }))
+            .unwrap()
+        };
+        let first = log(Some(4), "2025-03-01T10:00:00Z");
+        assert!(same_session(&first, &log(Some(4), "2025-03-02T10:00:00Z")));
+        assert!(!same_session(&first, &log(Some(5), "2025-03-01T10:05:00Z")));
+
+        let legacy = log(None, "2025-03-01T10:00:00Z");
+        assert!(same_session(&legacy, &log(None, "2025-03-01T10:45:00Z")));
+        assert!(!same_session(&legacy, &log(None, "2025-03-01T12:00:00Z")));
+        assert!(!same_session(&legacy, &first));
+    }
+}
@@ -0,0 +1,117 @@
+//! Unicode-safe truncation helpers
+//!
+//! Byte-index slicing panics inside multi-byte characters, and char-based
+//! cuts can still split a user-perceived character (an emoji with a skin
+//! tone, a ZWJ family, a flag, a Hangul syllable, a letter plus combining
+//! accent). These helpers cut on extended grapheme cluster boundaries.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Moves a byte index back to the nearest grapheme cluster boundary
+fn boundary_floor(s: &str, end: usize) -> usize {
+    if end >= s.len() {
+        return s.len();
+    }
+    s.grapheme_indices(true)
+        .map(|(start, _)| start)
+        .take_while(|&start| start <= end)
+        .last()
+        .unwrap_or(0)
+}
+
+/// Returns the longest prefix of at most `max_bytes` bytes that doesn't split
+/// a character
+pub fn truncate(s: &str, max_bytes: usize) -> &str {
+    if s.len() <= max_bytes {
+        s
+    } else {
+        &s[..boundary_floor(s, max_bytes)]
+    }
+}
+
+/// Returns the longest suffix of at most `max_bytes` bytes that doesn't
+/// split a character
+pub fn tail(s: &str, max_bytes: usize) -> &str {
+    if s.len() <= max_bytes {
+        return s;
+    }
+    let min_start = s.len() - max_bytes;
+    let start = s
+        .grapheme_indices(true)
+        .map(|(start, _)| start)
+        .find(|&start| start >= min_start)
+        .unwrap_or(s.len());
+    &s[start..]
+}
+
+/// Truncates to at most `max_bytes` bytes including a trailing "..."
+pub fn truncate_ellipsis(s: &str, max_bytes: usize) -> String {
+    if s.len() <= max_bytes {
+        s.to_string()
+    } else {
+        format!("{}...", truncate(s, max_bytes.saturating_sub(3)))
+    }
+}
+
+/// Returns the longest prefix of at most `max_chars` chars that doesn't split
+/// a character
+pub fn truncate_chars(s: &str, max_chars: usize) -> &str {
+    match s.char_indices().nth(max_chars) {
+        Some((end, _)) => &s[..boundary_floor(s, end)],
+        None => s,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_multibyte() {
+        assert_eq!(truncate("héllo", 2), "h");
+        assert_eq!(truncate("日本語", 4), "日");
+        assert_eq!(truncate("short", 10), "short");
+        // Skin tone modifier stays with its emoji
+        assert_eq!(truncate("👍🏽ok", 5), "");
+        // Combining accent stays with its letter
+        assert_eq!(truncate("ae\u{0301}", 2), "a");
+    }
+
+    #[test]
+    fn test_tail() {
+        assert_eq!(tail("short", 10), "short");
+        assert_eq!(tail("abc\nerror: boom", 11), "error: boom");
+        assert_eq!(tail("a日本", 4), "本");
+        // Flag pair stays whole
+        assert_eq!(tail("🇯🇵a", 5), "a");
+    }
+
+    #[test]
+    fn test_truncate_ellipsis() {
+        assert_eq!(truncate_ellipsis("hello world", 8), "hello...");
+        assert_eq!(truncate_ellipsis("日本語テキスト", 8), "日...");
+        assert_eq!(truncate_ellipsis("hi", 8), "hi");
+    }
+
+    #[test]
+    fn test_truncate_chars() {
+        assert_eq!(truncate_chars("日本語", 2), "日本");
+        // Cutting inside a ZWJ sequence drops the whole sequence
+        assert_eq!(truncate_chars("a👩\u{200D}💻", 3), "a");
+        assert_eq!(truncate_chars("abc", 5), "abc");
+    }
+
+    #[test]
+    fn test_truncate_keeps_grapheme_clusters() {
+        // Regional indicator pair (flag)
+        assert_eq!(truncate("a🇯🇵", 6), "a");
+        // Emoji ZWJ sequence
+        assert_eq!(truncate("a👩\u{200D}💻", 9), "a");
+        // Hangul syllable spelled with conjoining jamo
+        assert_eq!(truncate("a\u{1100}\u{1161}\u{11A8}", 5), "a");
+        assert_eq!(
+            truncate("a\u{1100}\u{1161}\u{11A8}", 10),
+            "a\u{1100}\u{1161}\u{11A8}"
+        );
+    }
+}
@@ -5,6 +5,8 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::text::truncate;
+
 /// A complete parsed transcript from a Claude task execution
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transcript {
@@ -14,6 +16,13 @@ pub struct Transcript {
     pub messages: Vec<Message>,
     /// Final result of the task
     pub result: Option<TaskResult>,
+    /// Output lines that contained invalid UTF-8 (replaced with U+FFFD)
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub lossy_lines: usize,
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
 }
 
 /// System initialization message from Claude
@@ -74,6 +83,7 @@ impl Transcript {
             init: None,
             messages: Vec::new(),
             result: None,
+            lossy_lines: 0,
         };
 
         for line in output.lines() {
@@ -87,27 +97,30 @@ impl Transcript {
                 continue;
             };
 
+            // Lines marked by the reader when the child emitted invalid UTF-8
+            if json.get("lossy_utf8").and_then(|v| v.as_bool()) == Some(true) {
+                transcript.lossy_lines += 1;
+            }
+
             // Get the message type
             let Some(msg_type) = json.get("type").and_then(|t| t.as_str()) else {
                 continue;
             };
 
             match msg_type {
-                "system" => {
-                    if json.get("subtype").and_then(|s| s.as_str()) == Some("init") {
-                        transcript.init = Some(SystemInit {
-                            model: json.get("model").and_then(|v| v.as_str()).map(String::from),
-                            session_id: json
-                                .get("session_id")
-                                .and_then(|v| v.as_str())
-                                .map(String::from),
-                            claude_code_version: json
-                                .get("claude_code_version")
-                                .and_then(|v| v.as_str())
-                                .map(String::from),
-                            cwd: json.get("cwd").and_then(|v| v.as_str()).map(String::from),
-                        });
-                    }
+                "system" if json.get("subtype").and_then(|s| s.as_str()) == Some("init") => {
+                    transcript.init = Some(SystemInit {
+                        model: json.get("model").and_then(|v| v.as_str()).map(String::from),
+                        session_id: json
+                            .get("session_id")
+                            .and_then(|v| v.as_str())
+                            .map(String::from),
+                        claude_code_version: json
+                            .get("claude_code_version")
+                            .and_then(|v| v.as_str())
+                            .map(String::from),
+                        cwd: json.get("cwd").and_then(|v| v.as_str()).map(String::from),
+                    });
                 }
                 "assistant" => {
                     // Extract content from assistant messages
@@ -228,7 +241,7 @@ impl Transcript {
             if let Some(ref text) = result.result_text {
                 // Truncate long results
                 if text.len() > 200 {
-                    summary.push_str(&text[..200]);
+                    summary.push_str(truncate(text, 200));
                     summary.push_str("...");
                 } else {
                     summary.push_str(text);
@@ -242,7 +255,7 @@ impl Transcript {
                 if let Message::Text { text } = msg {
                     // Take the first text response as summary
                     if text.len() > 200 {
-                        summary.push_str(&text[..200]);
+                        summary.push_str(truncate(text, 200));
                         summary.push_str("...");
                     } else {
                         summary.push_str(text);
@@ -273,11 +286,34 @@ impl Transcript {
             .collect()
     }
 
+    /// Count tool invocations by name, most used first
+    pub fn tool_counts(&self) -> Vec<(String, usize)> {
+        let mut counts: Vec<(String, usize)> = Vec::new();
+        for tool in self.tools_used() {
+            match counts.iter_mut().find(|(name, _)| *name == tool) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((tool, 1)),
+            }
+        }
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+    }
+
     /// Get total cost in USD, if available
     pub fn total_cost(&self) -> Option<f64> {
         self.result.as_ref().and_then(|r| r.total_cost_usd)
     }
 
+    /// Get token usage, if reported
+    pub fn usage(&self) -> Option<&TokenUsage> {
+        self.result.as_ref().and_then(|r| r.usage.as_ref())
+    }
+
+    /// Get the model that ran the task, if reported
+    pub fn model(&self) -> Option<&str> {
+        self.init.as_ref().and_then(|i| i.model.as_deref())
+    }
+
     /// Get duration in milliseconds, if available
     pub fn duration_ms(&self) -> Option<u64> {
         self.result.as_ref().and_then(|r| r.duration_ms)
@@ -287,6 +323,89 @@ impl Transcript {
     pub fn succeeded(&self) -> bool {
         self.result.as_ref().map(|r| r.success).unwrap_or(false)
     }
+
+    /// Render the conversation as Markdown for pasting into PRs or wikis
+    ///
+    /// Assistant text is kept as-is, each tool call gets a header, tool
+    /// results are fenced and cut to the first few lines, and the final
+    /// result closes the document.
+    pub fn to_markdown(&self) -> String {
+        const MAX_RESULT_LINES: usize = 10;
+        let mut md = String::new();
+
+        for msg in &self.messages {
+            match msg {
+                Message::Text { text } => {
+                    md.push_str(text.trim());
+                    md.push_str("\n\n");
+                }
+                Message::ToolUse {
+                    tool_name, input, ..
+                } => {
+                    let label = tool_input_label(input);
+                    if label.is_empty() {
+                        md.push_str(&format!("#### Tool: {}\n\n", tool_name));
+                    } else {
+                        md.push_str(&format!("#### Tool: {} `{}`\n\n", tool_name, label));
+                    }
+                }
+                Message::ToolResult {
+                    output, is_error, ..
+                } => {
+                    if output.trim().is_empty() {
+                        continue;
+                    }
+                    let lines: Vec<&str> = output.lines().collect();
+                    let shown = lines.len().min(MAX_RESULT_LINES);
+                    let fence = code_fence(output);
+                    if *is_error {
+                        md.push_str("**Error:**\n\n");
+                    }
+                    md.push_str(&format!("{}\n{}\n", fence, lines[..shown].join("\n")));
+                    if lines.len() > shown {
+                        md.push_str(&format!("… ({} more lines)\n", lines.len() - shown));
+                    }
+                    md.push_str(&format!("{}\n\n", fence));
+                }
+            }
+        }
+
+        if let Some(ref result) = self.result {
+            if let Some(ref text) = result.result_text {
+                md.push_str("---\n\n**Result:**\n\n");
+                md.push_str(text.trim());
+                md.push('\n');
+            }
+        }
+
+        md
+    }
+}
+
+/// Picks the most descriptive input field of a tool call for a short label
+pub fn tool_input_label(input: &serde_json::Value) -> String {
+    [
+        "file_path",
+        "command",
+        "pattern",
+        "path",
+        "url",
+        "description",
+    ]
+    .iter()
+    .find_map(|key| input.get(key).and_then(|v| v.as_str()))
+    .map(|s| s.lines().next().unwrap_or("").to_string())
+    .unwrap_or_default()
+}
+
+/// Returns a backtick fence longer than any run of backticks in the text
+fn code_fence(text: &str) -> String {
+    let longest = text
+        .split(|c| c != '`')
+        .map(|run| run.len())
+        .max()
+        .unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
 }
 
 #[cfg(test)]
@@ -359,6 +478,16 @@ mod tests {
         assert_eq!(transcript.tools_used(), vec!["Read"]);
     }
 
+    #[test]
+    fn test_tool_counts() {
+        let output = r#"{"type":"assistant","message":{"content":[{"type":"tool_use","name":"Read","id":"1","input":{}},{"type":"tool_use","name":"Edit","id":"2","input":{}},{"type":"tool_use","name":"Read","id":"3","input":{}}]}}"#;
+        let transcript = Transcript::parse(output);
+        assert_eq!(
+            transcript.tool_counts(),
+            vec![("Read".to_string(), 2), ("Edit".to_string(), 1)]
+        );
+    }
+
     #[test]
     fn test_generate_summary() {
         let output =
@@ -381,6 +510,47 @@ mod tests {
         assert!(!transcript.succeeded());
     }
 
+    #[test]
+    fn test_to_markdown_renders_tool_headers_and_result() {
+        let output = r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Checking the file."},{"type":"tool_use","name":"Read","id":"t1","input":{"file_path":"src/lib.rs"}}]}}
+{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1","content":"pub fn lib() {}"}]}}
+{"type":"result","subtype":"success","result":"All good"}"#;
+
+        let md = Transcript::parse(output).to_markdown();
+        assert_eq!(
+            md,
+            "Checking the file.\n\n\
+             #### Tool: Read `src/lib.rs`\n\n\
+             ```\npub fn lib() {}\n```\n\n\
+             ---\n\n**Result:**\n\nAll good\n"
+        );
+    }
+
+    #[test]
+    fn test_to_markdown_truncates_long_results() {
+        let long_output: Vec<String> = (1..=15).map(|i| format!("line {}", i)).collect();
+        let transcript = Transcript {
+            init: None,
+            messages: vec![Message::ToolResult {
+                tool_id: "t1".to_string(),
+                output: long_output.join("\n"),
+                is_error: false,
+            }],
+            result: None,
+            lossy_lines: 0,
+        };
+
+        let md = transcript.to_markdown();
+        assert!(md.contains("line 10\n… (5 more lines)\n"));
+        assert!(!md.contains("line 11"));
+    }
+
+    #[test]
+    fn test_code_fence_outgrows_inner_backticks() {
+        assert_eq!(code_fence("plain"), "```");
+        assert_eq!(code_fence("has ``` inside"), "````");
+    }
+
     #[test]
     fn test_malformed_json_lines_skipped() {
         let output = r#"not json
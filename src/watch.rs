@@ -0,0 +1,167 @@
+//! Watch mode: a lightweight auto-repair loop outside the REPL
+//!
+//! Polls the working directory for changes, runs a check command on each
+//! change, and when it fails runs a task with the failure output. Polling
+//! keeps this dependency-free; the tree walk skips build output and VCS dirs.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+
+use crate::project::Project;
+use crate::repl::Session;
+
+/// Directories never scanned for changes
+const IGNORED_DIRS: &[&str] = &["target", "node_modules", "__pycache__", "dist", "build"];
+
+/// Lines of command output included in the repair prompt
+const OUTPUT_TAIL_LINES: usize = 80;
+
+/// Watches the current directory and runs repair tasks when `cmd` fails
+///
+/// After `max_attempts` consecutive repair tasks without the command passing,
+/// waits for the next change before trying again.
+pub fn watch(
+    project_name: &str,
+    cmd: &str,
+    prompt: &str,
+    interval_secs: u64,
+    max_attempts: usize,
+) -> Result<()> {
+    let mut project = Project::open(project_name)?;
+    project.record_session_start()?;
+    let mut session = Session::new(project)?;
+    session.recover_interrupted_tasks()?;
+    let dir = std::env::current_dir()?;
+    let interval = Duration::from_secs(interval_secs.max(1));
+
+    println!(
+        "Watching {} for project '{}' (Ctrl+C to stop)",
+        dir.display(),
+        project_name
+    );
+    let mut attempts = 0;
+    loop {
+        println!("\n[watch] {}", cmd);
+        let (passed, output) = run_check(cmd, &dir)?;
+        if passed {
+            println!("[watch] passed; waiting for changes...");
+        } else if attempts < max_attempts {
+            attempts += 1;
+            println!(
+                "[watch] failed; starting repair task ({}/{})",
+                attempts, max_attempts
+            );
+            // A failed task counts as an attempt; the watch keeps going
+            if let Err(e) = session.run_task(&repair_prompt(prompt, cmd, &output)) {
+                println!("[watch] repair task failed: {:#}", e);
+            }
+            continue;
+        } else {
+            println!(
+                "[watch] still failing after {} repair tasks; waiting for changes...",
+                max_attempts
+            );
+        }
+        wait_for_change(&dir, interval);
+        attempts = 0;
+    }
+}
+
+/// Runs the check command through the shell, returning success and combined output
+fn run_check(cmd: &str, dir: &Path) -> Result<(bool, String)> {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(cmd)
+        .current_dir(dir)
+        .output()
+        .with_context(|| format!("Failed to run: {}", cmd))?;
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    Ok((output.status.success(), text))
+}
+
+/// Builds the task prompt from the user's instruction and the failure output
+fn repair_prompt(prompt: &str, cmd: &str, output: &str) -> String {
+    let lines: Vec<&str> = output.lines().collect();
+    let tail = &lines[lines.len().saturating_sub(OUTPUT_TAIL_LINES)..];
+    format!(
+        "{}\n\n`{}` failed with this output:\n\n```\n{}\n```",
+        prompt,
+        cmd,
+        tail.join("\n")
+    )
+}
+
+/// Blocks until the directory's snapshot differs from its current one
+fn wait_for_change(dir: &Path, interval: Duration) {
+    let before = snapshot(dir);
+    loop {
+        std::thread::sleep(interval);
+        if snapshot(dir) != before {
+            return;
+        }
+    }
+}
+
+/// Paths, modification times, and sizes of all watched files, sorted by path
+fn snapshot(dir: &Path) -> Vec<(PathBuf, SystemTime, u64)> {
+    let mut files = Vec::new();
+    collect_files(dir, &mut files);
+    files.sort();
+    files
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<(PathBuf, SystemTime, u64)>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            // Hidden dirs cover .git and .claude (rewritten before every task)
+            if !name.starts_with('.') && !IGNORED_DIRS.contains(&name.as_ref()) {
+                collect_files(&path, files);
+            }
+        } else if let Ok(modified) = metadata.modified() {
+            files.push((path, modified, metadata.len()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_ignores_build_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("target")).unwrap();
+        std::fs::create_dir(dir.path().join(".claude")).unwrap();
+        std::fs::write(dir.path().join("main.rs"), "fn main() {}").unwrap();
+        let before = snapshot(dir.path());
+        assert_eq!(before.len(), 1);
+
+        std::fs::write(dir.path().join("target/out"), "bin").unwrap();
+        std::fs::write(dir.path().join(".claude/context.md"), "ctx").unwrap();
+        assert_eq!(snapshot(dir.path()), before);
+
+        std::fs::write(dir.path().join("lib.rs"), "").unwrap();
+        assert_ne!(snapshot(dir.path()), before);
+    }
+
+    #[test]
+    fn test_repair_prompt() {
+        let prompt = repair_prompt("fix the tests", "cargo test", "ok\nFAILED foo\n");
+        assert_eq!(
+            prompt,
+            "fix the tests\n\n`cargo test` failed with this output:\n\n```\nok\nFAILED foo\n```"
+        );
+    }
+}